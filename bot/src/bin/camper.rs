@@ -0,0 +1,71 @@
+// Camper: stays where it starts, shields when something is about to hit it,
+// and takes a shot whenever the opponent walks into its row or column.
+//
+// Shows: danger_zone to see what's coming, toggle_shield, player_status
+// for energy, and Actions::every_n_ticks to pace firing.
+//
+// Run with `cargo run --bin camper 1` (or 2).
+
+use std::env;
+
+use rs_sdk::{danger_zone, run_bot, Actions, Bot, Dir, Frame, Map, Player, ENERGY_SHIELD};
+
+// How many ticks ahead to look for danger
+const LOOKAHEAD: u32 = 2;
+
+// Fire at most once every this many ticks
+const FIRE_EVERY: u32 = 5;
+
+struct Camper {
+    me: Player,
+    opponent: Player,
+    map: Map,
+}
+
+impl Bot for Camper {
+    fn on_connect(&mut self, map: &Map) {
+        self.map = map.clone();
+    }
+
+    fn on_frame(&mut self, frame: &Frame, out: &mut Actions) {
+        let (me, op) = match (frame.player(self.me), frame.player(self.opponent)) {
+            (Some(me), Some(op)) => (me, op),
+            _ => return,
+        };
+
+        // Is anything going to hit us? Our own missiles fly away from us,
+        // but their explosions don't, so check everything.
+        let threatened = frame
+            .entities()
+            .iter()
+            .any(|e| danger_zone(e, &self.map, LOOKAHEAD).contains(&me.pos()));
+
+        // The shield uses energy every tick, so only have it on when needed
+        let energy = frame.player_status(self.me).energy() as u32;
+        if threatened != me.has_shield() && (!threatened || energy > ENERGY_SHIELD) {
+            out.toggle_shield();
+        }
+
+        // Lined up? Obstacles may be in the way, a camper doesn't care.
+        let (x, y) = me.pos();
+        let (ox, oy) = op.pos();
+        let aim = if x == ox {
+            if oy < y { Dir::Up } else { Dir::Down }
+        } else if y == oy {
+            if ox < x { Dir::Left } else { Dir::Right }
+        } else {
+            return;
+        };
+        if out.every_n_ticks(FIRE_EVERY) {
+            out.fire(aim);
+        }
+    }
+}
+
+fn main() -> Result<(), rs_sdk::SDKError> {
+    let (me, opponent) = match env::args().nth(1).as_deref() {
+        Some("2") => (Player::Two, Player::One),
+        _ => (Player::One, Player::Two),
+    };
+    run_bot(me, Camper { me, opponent, map: Map::default() })
+}
@@ -0,0 +1,56 @@
+// Random walker: wanders about and fires in random directions. Easy to beat,
+// useful as a first opponent for your own bot.
+//
+// Shows: driving the connection yourself with rs_sdk::connect instead of
+// run_bot, game_state, telling the game we're ready between rounds, and
+// heartbeats so the game can show how laggy our connection is.
+//
+// Run with `cargo run --bin random_walker 1` (or 2).
+
+use std::env;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rs_sdk::{Dir, GameState, Player, SDKError, TOKEN_ENV};
+
+const DIRS: [Dir; 5] = [Dir::None, Dir::Up, Dir::Down, Dir::Left, Dir::Right];
+
+// Pick a new direction about this often, in ticks
+const WANDER_EVERY: u32 = 10;
+
+// Chance of firing each tick
+const FIRE_CHANCE: f64 = 0.05;
+
+fn main() -> Result<(), SDKError> {
+    let me = match env::args().nth(1).as_deref() {
+        Some("2") => Player::Two,
+        _ => Player::One,
+    };
+    let token = env::var(TOKEN_ENV).ok();
+    let (mut b_in, mut b_out) = rs_sdk::connect(me, token.as_deref())?;
+    let mut rng = rand::thread_rng();
+
+    loop {
+        let frame = match b_in.next_frame() {
+            Ok(f) => f,
+            Err(SDKError::Disconnected) => return Ok(()), // game exited
+            Err(e) => return Err(e),
+        };
+        match frame.game_state() {
+            // run_bot does these two for you
+            GameState::Waiting => b_out.ready()?,
+            GameState::GameOver => b_out.rematch()?,
+
+            GameState::Playing => {
+                // and this, it's the ping next to our name
+                b_out.heartbeat(frame.tick())?;
+                if frame.tick() % WANDER_EVERY == 0 {
+                    b_out.dir(*DIRS.choose(&mut rng).unwrap())?;
+                }
+                if rng.gen_bool(FIRE_CHANCE) {
+                    b_out.fire(*DIRS[1..].choose(&mut rng).unwrap())?;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// Rusher: runs straight at the opponent and fires missiles as soon as
+// it's lined up. No dodging, no plan B.
+//
+// Shows: the smallest useful Bot, Frame::player to find both players,
+// and on_event to follow rounds and matches.
+//
+// Run with `cargo run --bin rusher 1` (or 2).
+
+use std::env;
+
+use rs_sdk::{run_bot, Actions, Bot, Dir, Event, Frame, Player};
+
+struct Rusher {
+    me: Player,
+    opponent: Player,
+    rounds: u32,
+}
+
+impl Bot for Rusher {
+    fn on_frame(&mut self, frame: &Frame, out: &mut Actions) {
+        let (me, op) = match (frame.player(self.me), frame.player(self.opponent)) {
+            (Some(me), Some(op)) => (me, op),
+            _ => return,
+        };
+        let (x, y) = me.pos();
+        let (ox, oy) = op.pos();
+
+        // In line, fire. Missiles are cheap so fire every frame we can.
+        if x == ox {
+            out.fire(if oy < y { Dir::Up } else { Dir::Down });
+        } else if y == oy {
+            out.fire(if ox < x { Dir::Left } else { Dir::Right });
+        }
+
+        // Close the biggest gap first, that gets us lined up soonest
+        let want = if x.abs_diff(ox) > y.abs_diff(oy) {
+            if ox < x { Dir::Left } else { Dir::Right }
+        } else if oy < y {
+            Dir::Up
+        } else {
+            Dir::Down
+        };
+        // Asking for the opposite of our direction stops us, so only
+        // send a direction when it changes.
+        if me.dir() != want {
+            out.dir(want);
+        }
+    }
+
+    fn on_event(&mut self, event: Event) {
+        match event {
+            Event::RoundStart => {
+                self.rounds += 1;
+                println!("Round {}, charge!", self.rounds);
+            }
+            Event::RoundOver => println!("Round over"),
+            Event::MatchOver => {
+                println!("Match over after {} rounds", self.rounds);
+                self.rounds = 0;
+            }
+        }
+    }
+}
+
+fn main() -> Result<(), rs_sdk::SDKError> {
+    let (me, opponent) = match env::args().nth(1).as_deref() {
+        Some("2") => (Player::Two, Player::One),
+        _ => (Player::One, Player::Two),
+    };
+    run_bot(me, Rusher { me, opponent, rounds: 0 })
+}
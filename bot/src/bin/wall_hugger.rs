@@ -0,0 +1,132 @@
+// Wall hugger: heads for the top wall then runs clockwise around the edge
+// of the arena, firing a ray when it has the energy and a clear shot.
+//
+// Shows: the Map from on_connect (bounds and obstacles), player_status
+// for energy and weapon, and change_weapon.
+//
+// Run with `cargo run --bin wall_hugger 1` (or 2).
+
+use std::env;
+
+use rs_sdk::{
+    run_bot, Actions, Bot, Dir, Frame, Map, Player, Weapon, ENERGY_MISSILE, ENERGY_RAY,
+};
+
+// If the way we want to go is blocked, try these in turn
+const CLOCKWISE: [Dir; 4] = [Dir::Up, Dir::Right, Dir::Down, Dir::Left];
+
+struct WallHugger {
+    me: Player,
+    opponent: Player,
+    map: Map,
+}
+
+impl WallHugger {
+    // Which way to go round the edge. Off the edge, go up until we hit it.
+    fn clockwise(&self, (x, y): (u32, u32)) -> Dir {
+        let ((min_x, min_y), (max_x, max_y)) = self.map.bounds();
+        let (right, bottom) = (max_x - 1, max_y - 1);
+        if y == min_y && x < right {
+            Dir::Right
+        } else if x == right && y < bottom {
+            Dir::Down
+        } else if y == bottom && x > min_x {
+            Dir::Left
+        } else {
+            Dir::Up // left wall, or not at a wall yet
+        }
+    }
+
+    // Can we move one square from p in direction d?
+    fn is_open(&self, p: (u32, u32), d: Dir) -> bool {
+        match step(p, d) {
+            Some((x, y)) => !self.map.is_blocked(x, y),
+            None => false,
+        }
+    }
+
+    // Direction of a clear straight shot from `from` to `to`, if there is one
+    fn shot(&self, from: (u32, u32), to: (u32, u32)) -> Option<Dir> {
+        let d = if from.0 == to.0 {
+            if to.1 < from.1 { Dir::Up } else { Dir::Down }
+        } else if from.1 == to.1 {
+            if to.0 < from.0 { Dir::Left } else { Dir::Right }
+        } else {
+            return None;
+        };
+        let mut p = from;
+        while let Some(next) = step(p, d) {
+            if next == to {
+                return Some(d);
+            }
+            if self.map.is_blocked(next.0, next.1) {
+                return None;
+            }
+            p = next;
+        }
+        None
+    }
+}
+
+impl Bot for WallHugger {
+    fn on_connect(&mut self, map: &Map) {
+        let ((min_x, min_y), (max_x, max_y)) = map.bounds();
+        println!(
+            "Arena is {}x{} with {} obstacles",
+            max_x - min_x,
+            max_y - min_y,
+            map.obstacles().count()
+        );
+        self.map = map.clone();
+    }
+
+    fn on_frame(&mut self, frame: &Frame, out: &mut Actions) {
+        let (me, op) = match (frame.player(self.me), frame.player(self.opponent)) {
+            (Some(me), Some(op)) => (me, op),
+            _ => return,
+        };
+
+        // Rays reach the far wall at once but cost a lot. Use one when we
+        // can afford it, otherwise fall back to missiles.
+        let status = frame.player_status(self.me);
+        let energy = status.energy() as u32;
+        let want = if energy >= ENERGY_RAY { Weapon::Ray } else { Weapon::Missile };
+        if status.weapon() != want {
+            out.change_weapon(); // happens before firing below
+        }
+        if let Some(d) = self.shot(me.pos(), op.pos()) {
+            if energy >= ENERGY_MISSILE {
+                out.fire(d);
+            }
+        }
+
+        // Keep going clockwise, turning away from obstacles
+        let ideal = self.clockwise(me.pos());
+        let start = CLOCKWISE.iter().position(|&d| d == ideal).unwrap();
+        let d = (0..CLOCKWISE.len())
+            .map(|i| CLOCKWISE[(start + i) % CLOCKWISE.len()])
+            .find(|&d| self.is_open(me.pos(), d))
+            .unwrap_or(Dir::None);
+        if me.dir() != d {
+            out.dir(d);
+        }
+    }
+}
+
+fn step(p: (u32, u32), d: Dir) -> Option<(u32, u32)> {
+    match d {
+        Dir::Up => Some((p.0, p.1.checked_sub(1)?)),
+        Dir::Down => Some((p.0, p.1 + 1)),
+        Dir::Left => Some((p.0.checked_sub(1)?, p.1)),
+        Dir::Right => Some((p.0 + 1, p.1)),
+        Dir::None => None,
+    }
+}
+
+fn main() -> Result<(), rs_sdk::SDKError> {
+    let (me, opponent) = match env::args().nth(1).as_deref() {
+        Some("2") => (Player::Two, Player::One),
+        _ => (Player::One, Player::Two),
+    };
+    run_bot(me, WallHugger { me, opponent, map: Map::default() })
+}
@@ -0,0 +1,311 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::seq::IteratorRandom;
+use rand::{Rng, SeedableRng};
+use rs_sdk::{
+    danger_zone, Actions, Bot, Dir, EntityKind, EntityState, Frame, Map, Player, Weapon,
+    ENERGY_MISSILE, ENERGY_RAY, ENERGY_SHIELD,
+};
+
+use crate::skill::Skill;
+
+// How many ticks ahead to look for incoming missiles. We move one square a tick.
+const LOOKAHEAD: u32 = 3;
+
+// Don't fire more often than this many ticks, missiles are cheap but not free
+const FIRE_EVERY: u32 = 4;
+
+// Missiles always fly at least this far before exploding, so don't
+// bother with them when the target is further away.
+const MISSILE_REACH: u32 = 12;
+
+// Energy to keep after firing a ray, so we can still shield
+const RAY_RESERVE: u32 = 10;
+
+// Only look this far for a way out of danger. Any further and it will have hit us.
+const DODGE_DIST: u32 = 2;
+
+const DIRS: [Dir; 4] = [Dir::Up, Dir::Down, Dir::Left, Dir::Right];
+
+type Square = (u32, u32);
+
+// Reference bot. Each frame, in order of priority:
+// - Dodge anything heading our way, or shield if we're cornered
+// - Fire if the opponent is in a straight clear line, with a ray if we can afford it
+// - Otherwise walk around obstacles to somewhere we can fire from
+// How quickly and accurately it does that depends on its Skill.
+pub struct Hunter {
+    me: Player,
+    opponent: Player,
+    map: Map,
+    last_fire: u32, // tick
+    skill: Skill,
+    rng: StdRng,
+}
+
+impl Hunter {
+    pub fn new(me: Player, opponent: Player, skill: Skill) -> Hunter {
+        Hunter {
+            me,
+            opponent,
+            map: Map::default(),
+            last_fire: 0,
+            skill,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    // Only decide every this many ticks, to simulate reaction time
+    fn decide_every(&self, tick_duration: Duration) -> u32 {
+        let ticks = self.skill.reaction.as_millis() / tick_duration.as_millis().max(1);
+        ticks.max(1) as u32
+    }
+
+    // Where we actually fire when we want to fire in direction d
+    fn aim(&mut self, d: Dir) -> Dir {
+        if !self.rng.gen_bool(self.skill.aim_error) {
+            return d;
+        }
+        *DIRS.iter().filter(|&&o| o != d).choose(&mut self.rng).unwrap()
+    }
+
+    // Squares which could be hit in the next few ticks. Missiles going away
+    // from us (including our own) can be ignored.
+    fn danger(&self, f: &Frame, me: &EntityState) -> HashSet<Square> {
+        f.entities()
+            .iter()
+            .filter(|e| match e.kind() {
+                EntityKind::Missile | EntityKind::Cluster => e.is_moving_toward(me.pos()),
+                EntityKind::Ray | EntityKind::Beam | EntityKind::Explosion | EntityKind::Marker => true,
+                EntityKind::Player | EntityKind::Obstacle | EntityKind::Pickup
+                | EntityKind::Supply
+                | EntityKind::Teleporter => false,
+            })
+            .flat_map(|e| danger_zone(e, &self.map, LOOKAHEAD))
+            .collect()
+    }
+
+    // Get out of danger. Returns false if there's no way out.
+    fn dodge(&self, me: &EntityState, danger: &HashSet<Square>, out: &mut Actions) -> bool {
+        let no_avoid = HashSet::new(); // running through danger is better than staying in it
+        match self.first_step(me.pos(), |p| !danger.contains(&p), &no_avoid, DODGE_DIST) {
+            Some(d) => {
+                steer(me, d, out);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Direction to fire from `from` to hit `to`, if there's a clear straight line
+    fn line_of_fire(&self, from: Square, to: Square) -> Option<Dir> {
+        let d = if from.0 == to.0 && from.1 > to.1 {
+            Dir::Up
+        } else if from.0 == to.0 && from.1 < to.1 {
+            Dir::Down
+        } else if from.1 == to.1 && from.0 > to.0 {
+            Dir::Left
+        } else if from.1 == to.1 && from.0 < to.0 {
+            Dir::Right
+        } else {
+            return None;
+        };
+        let mut p = from;
+        while let Some(next) = step(p, d) {
+            if next == to {
+                return Some(d);
+            }
+            if self.map.is_blocked(next.0, next.1) {
+                return None;
+            }
+            p = next;
+        }
+        None
+    }
+
+    // Squares with a clear straight line to `target`, found by walking out from it
+    fn firing_squares(&self, target: Square) -> HashSet<Square> {
+        let mut squares = HashSet::new();
+        for &d in DIRS.iter() {
+            let mut p = target;
+            while let Some(next) = step(p, d) {
+                if self.map.is_blocked(next.0, next.1) {
+                    break;
+                }
+                squares.insert(next);
+                p = next;
+            }
+        }
+        squares
+    }
+
+    // Ray if we can afford it and keep a reserve, missile if they're close enough
+    fn choose_weapon(&self, energy: u32, dist: u32) -> Option<Weapon> {
+        if energy > ENERGY_RAY + RAY_RESERVE {
+            Some(Weapon::Ray)
+        } else if energy > ENERGY_MISSILE && dist <= MISSILE_REACH {
+            Some(Weapon::Missile)
+        } else {
+            None
+        }
+    }
+
+    // Breadth first search from `from` to the nearest square where `goal` is true,
+    // not going through walls, obstacles or `avoid`. Returns the first step.
+    fn first_step(
+        &self,
+        from: Square,
+        goal: impl Fn(Square) -> bool,
+        avoid: &HashSet<Square>,
+        max_dist: u32,
+    ) -> Option<Dir> {
+        let mut first: HashMap<Square, (Dir, u32)> = HashMap::new(); // first step and distance
+        let mut queue = VecDeque::new();
+        for &d in DIRS.iter() {
+            if let Some(p) = self.open(from, d, avoid) {
+                first.insert(p, (d, 1));
+                queue.push_back(p);
+            }
+        }
+        while let Some(p) = queue.pop_front() {
+            let (d, dist) = first[&p];
+            if goal(p) {
+                return Some(d);
+            }
+            if dist >= max_dist {
+                continue;
+            }
+            for &next_d in DIRS.iter() {
+                if let Some(next) = self.open(p, next_d, avoid) {
+                    if next != from && !first.contains_key(&next) {
+                        first.insert(next, (d, dist + 1));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // The square one step in direction d, if we can stand there
+    fn open(&self, p: Square, d: Dir, avoid: &HashSet<Square>) -> Option<Square> {
+        let next = step(p, d)?;
+        if self.map.is_blocked(next.0, next.1) || avoid.contains(&next) {
+            None
+        } else {
+            Some(next)
+        }
+    }
+}
+
+impl Bot for Hunter {
+    fn on_connect(&mut self, map: &Map) {
+        self.map = map.clone();
+    }
+
+    fn on_frame(&mut self, frame: &Frame, out: &mut Actions) {
+        let (me, op) = match (frame.player(self.me), frame.player(self.opponent)) {
+            (Some(me), Some(op)) => (me, op),
+            _ => return,
+        };
+        if !out.every_n_ticks(self.decide_every(out.tick_duration())) {
+            return; // still reacting, carry on as we are
+        }
+        let status = frame.player_status(self.me);
+        let energy = status.energy() as u32;
+
+        let danger = self.danger(frame, me);
+        let in_danger = danger.contains(&me.pos());
+        if in_danger && self.rng.gen_bool(self.skill.dodge_chance) {
+            if !self.dodge(me, &danger, out) && !me.has_shield() && energy > ENERGY_SHIELD {
+                out.toggle_shield(); // cornered
+            }
+            return;
+        }
+        if me.has_shield() && !in_danger {
+            out.toggle_shield(); // safe again, save energy
+        }
+
+        if let Some(d) = self.line_of_fire(me.pos(), op.pos()) {
+            steer(me, Dir::None, out); // hold position while we have them lined up
+            if frame.tick() < self.last_fire + FIRE_EVERY {
+                return;
+            }
+            if let Some(w) = self.choose_weapon(energy, me.distance_to(op)) {
+                if status.weapon() != w {
+                    out.change_weapon();
+                }
+                out.fire(self.aim(d));
+                self.last_fire = frame.tick();
+            }
+            return;
+        }
+
+        let firing = self.firing_squares(op.pos());
+        let goal = |p: Square| firing.contains(&p) && !danger.contains(&p);
+        match self.first_step(me.pos(), goal, &danger, u32::MAX) {
+            Some(d) => steer(me, d, out),
+            None => steer(me, Dir::None, out),
+        }
+    }
+}
+
+// Move in direction d. The game stops us instead if d is the opposite of
+// our current direction, so reversing takes two frames.
+fn steer(me: &EntityState, d: Dir, out: &mut Actions) {
+    if me.dir() != d {
+        out.dir(d);
+    }
+}
+
+fn step(p: Square, d: Dir) -> Option<Square> {
+    match d {
+        Dir::Up => Some((p.0, p.1.checked_sub(1)?)),
+        Dir::Down => Some((p.0, p.1 + 1)),
+        Dir::Left => Some((p.0.checked_sub(1)?, p.1)),
+        Dir::Right => Some((p.0 + 1, p.1)),
+        Dir::None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rs_sdk::testing::{Command, MockServer};
+    use rs_sdk::{run_bot_with, GameState, PlayerStatus};
+
+    use super::*;
+    use crate::skill::Difficulty;
+
+    // What a hard Hunter playing one sends, in the one frame where it's at
+    // (5, 8) and two is at (5, 3)
+    fn commands(obstacles: &[Square]) -> Vec<Command> {
+        let mut server = MockServer::start(Map::new((1, 1), (20, 10), obstacles)).unwrap();
+        let status = PlayerStatus::new(3, 50, Weapon::Missile);
+        let players = vec![
+            EntityState::new(0, EntityKind::Player, (5, 8), Dir::None, 0),
+            EntityState::new(1, EntityKind::Player, (5, 3), Dir::None, 0),
+        ];
+        server.push_frame(Frame::new(10, GameState::Playing, [status, status], players));
+        server.hang_up();
+        let hunter = Hunter::new(Player::One, Player::Two, Difficulty::Hard.skill());
+        run_bot_with(server.connector(Player::One), hunter).unwrap();
+        server.finish().unwrap()
+    }
+
+    #[test]
+    fn it_fires_a_ray_down_a_clear_line() {
+        assert_eq!(
+            commands(&[]),
+            [Command::Heartbeat(10), Command::ChangeWeapon, Command::Fire(Dir::Up, 0)]
+        );
+    }
+
+    #[test]
+    fn it_walks_round_what_is_in_the_way() {
+        let sent = commands(&[(5, 5)]);
+        assert_eq!(sent[0], Command::Heartbeat(10));
+        assert!(matches!(sent[1..], [Command::Move(_, 0)]), "{:?}", sent);
+    }
+}
@@ -0,0 +1,3 @@
+// The reference bot, also used by the game for --vs-ai
+pub mod hunter;
+pub mod skill;
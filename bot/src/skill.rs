@@ -0,0 +1,52 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+// How well the bot plays, so a human can practice against something beatable
+#[derive(Clone, Copy, Debug)]
+pub struct Skill {
+    pub reaction: Duration, // only decide what to do this often
+    pub aim_error: f64,     // chance of firing the wrong way, 0 to 1
+    pub dodge_chance: f64,  // chance of noticing we're in danger, 0 to 1
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn skill(self) -> Skill {
+        match self {
+            Difficulty::Easy => Skill {
+                reaction: Duration::from_millis(400),
+                aim_error: 0.3,
+                dodge_chance: 0.3,
+            },
+            Difficulty::Normal => Skill {
+                reaction: Duration::from_millis(200),
+                aim_error: 0.1,
+                dodge_chance: 0.7,
+            },
+            Difficulty::Hard => Skill {
+                reaction: Duration::from_millis(0),
+                aim_error: 0.0,
+                dodge_chance: 1.0,
+            },
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!("unknown difficulty '{}', use easy, normal or hard", s)),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rs_sdk::Frame;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(f) = Frame::from_network(data) {
+        // everything the parser accepted is usable
+        for e in f.entities() {
+            let _ = (e.kind(), e.dir(), e.pos());
+        }
+        let _ = f.player_status(rs_sdk::Player::Two).weapon();
+    }
+});
@@ -0,0 +1,13 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use rust_console_game::bench::Arena;
+
+// One tick of the simulation plus building the frame sent to bots, as the
+// game loop does every frame_gap_ms
+fn tick(c: &mut Criterion) {
+    let mut arena = Arena::new(160, 48);
+    c.bench_function("tick 160x48", |b| b.iter(|| black_box(arena.tick())));
+}
+
+criterion_group!(benches, tick);
+criterion_main!(benches);
@@ -0,0 +1,328 @@
+// What happened in a replay, for bot authors tuning a strategy: each round's
+// shots, hits and energy, and where each player spent their time. For the
+// `analyze` subcommand.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use rs_sdk::GameState;
+
+use crate::playback;
+use crate::replay::{self, RECORD_EVENT, RECORD_STATE};
+use crate::victory::{self, Outcome, WinCondition};
+use crate::{GameError, InputEvent, World};
+
+// Energy graphs, lowest to highest
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Widest energy graph. Longer rounds are averaged down to fit.
+const SPARK_WIDTH: usize = 40;
+
+// Heatmaps, least visited to most. A space is never.
+const HEAT: &[u8] = b" .:-=+*#%@";
+
+// Biggest heatmap. Bigger arenas are scaled down to fit.
+const HEAT_WIDTH: u32 = 60;
+const HEAT_HEIGHT: u32 = 20;
+
+// One round, from the first Playing frame to the last
+struct Round {
+    start: u32, // ticks
+    end: u32,
+    fired: [u32; 2], // fire commands received, whether or not there was energy for them
+    hits: [u32; 2],  // on the other player
+    spent: [u32; 2], // energy, on shots, shields and pushes
+    energy: [Vec<u32>; 2], // every tick
+}
+
+impl Round {
+    fn new(tick: u32) -> Round {
+        Round {
+            start: tick,
+            end: tick,
+            fired: [0, 0],
+            hits: [0, 0],
+            spent: [0, 0],
+            energy: [Vec::new(), Vec::new()],
+        }
+    }
+}
+
+// Where the players have been, a count per square, player one then two
+struct Heat {
+    width: u32,
+    height: u32,
+    visits: [Vec<u32>; 2],
+}
+
+impl Heat {
+    fn new(width: u32, height: u32) -> Heat {
+        let size = (width * height) as usize;
+        Heat {
+            width,
+            height,
+            visits: [vec![0; size], vec![0; size]],
+        }
+    }
+
+    fn visit(&mut self, idx: usize, x: u32, y: u32) {
+        if x < self.width && y < self.height {
+            self.visits[idx][(y * self.width + x) as usize] += 1;
+        }
+    }
+
+    // Squares are added up into cells of the map, then shaded by how their
+    // count compares to the busiest cell's
+    fn map(&self, idx: usize) -> Vec<String> {
+        let (cols, rows) = (self.width.clamp(1, HEAT_WIDTH), self.height.clamp(1, HEAT_HEIGHT));
+        let mut cells = vec![0u32; (cols * rows) as usize];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let cell = (y * rows / self.height.max(1)) * cols + x * cols / self.width.max(1);
+                cells[cell as usize] += self.visits[idx][(y * self.width + x) as usize];
+            }
+        }
+        let busiest = cells.iter().copied().max().unwrap_or(0).max(1) as u64;
+        cells
+            .chunks(cols as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|&n| match n {
+                        0 => ' ',
+                        n => HEAT[(1 + n as u64 * (HEAT.len() as u64 - 2) / busiest) as usize] as char,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// Print the analysis of the replay at path to out
+pub fn run(path: &Path, out: &mut impl Write) -> Result<(), GameError> {
+    let mut reader = replay::Reader::open(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    let game = playback::read_game(&mut reader)?
+        .ok_or_else(|| GameError::Protocol(format!("{} is not a replay file", path.display())))?;
+    let names = [game.config.names.player1.clone(), game.config.names.player2.clone()];
+    writeln!(
+        out,
+        "{}: {}x{}, {} lives, {} vs {}",
+        path.display(),
+        game.width,
+        game.height,
+        game.config.lives,
+        names[0],
+        names[1]
+    )?;
+
+    // the menu's mode changes aren't in the replay, every match is judged
+    // by the mode it was recorded with
+    let win = victory::of(&game.config.victory);
+    let mut world = World::with_config(game.width, game.height, game.config.clone());
+    let mut heat = Heat::new(game.width, game.height);
+    let mut round: Option<Round> = None;
+    let mut rounds = 0;
+    let mut points = [0, 0]; // as of the frame before
+    let mut is_over = false; // this match
+    let mut matches = 0;
+    while let Some((kind, payload)) = reader.next_record()? {
+        if kind == RECORD_EVENT {
+            if let (Some(r), Some((_, InputEvent::Fire { entity_id: id @ 1..=2, .. }))) =
+                (round.as_mut(), replay::read_event(&payload))
+            {
+                r.fired[id as usize - 1] += 1;
+            }
+            continue;
+        }
+        if kind != RECORD_STATE {
+            continue;
+        }
+        // format 0 has no tick in front of the frame
+        let frame = match reader.version() {
+            0 => &payload[..],
+            _ => payload.get(4..).unwrap_or_default(),
+        };
+        world.load_state(frame, game.protocol)?;
+        // it loaded, so it has a header with a state in it
+        let state = GameState::from_num(frame[4]).unwrap_or(GameState::Waiting);
+        let players = [world.player1, world.player2];
+
+        // one GameOver frame ends each match
+        if matches == 0 || (is_over && state != GameState::GameOver) {
+            matches += 1;
+            rounds = 0;
+            is_over = false;
+            world.match_start = Some(world.tick);
+            writeln!(out, "\nMatch {}", matches)?;
+        }
+        let now = world.points();
+        if let Some(r) = round.as_mut() {
+            for idx in 0..2 {
+                r.hits[idx] += now[idx].saturating_sub(points[idx]);
+            }
+        }
+        points = now;
+
+        if state == GameState::Playing {
+            let r = round.get_or_insert_with(|| Round::new(world.tick));
+            r.end = world.tick;
+            for (idx, &id) in players.iter().enumerate() {
+                let e = world.energy[id];
+                if let Some(&last) = r.energy[idx].last() {
+                    r.spent[idx] += last.saturating_sub(e);
+                }
+                r.energy[idx].push(e);
+                let p = world.position[id][0];
+                if !p.invalid {
+                    heat.visit(idx, p.x, p.y);
+                }
+            }
+            continue;
+        }
+        if let Some(r) = round.take() {
+            rounds += 1;
+            show_round(out, &world, &names, rounds, &r)?;
+        }
+        if state == GameState::GameOver && !is_over {
+            is_over = true;
+            show_result(out, &world, &*win, &names)?;
+        }
+    }
+    // the replay ended mid-round
+    if let Some(r) = round.take() {
+        show_round(out, &world, &names, rounds + 1, &r)?;
+    }
+
+    for (idx, name) in names.iter().enumerate() {
+        writeln!(out, "\nWhere {} spent their time", name)?;
+        for line in heat.map(idx) {
+            writeln!(out, "|{}|", line)?;
+        }
+    }
+    Ok(())
+}
+
+// e.g. "Player 2 wins, 3 lives to 0", or in points mode "Draw, 4 hits to 4"
+fn show_result(out: &mut impl Write, w: &World, win: &dyn WinCondition, names: &[String; 2]) -> io::Result<()> {
+    let result = match win.outcome(w) {
+        Some(Outcome::Won(idx)) => format!("{} wins", names[idx]),
+        Some(Outcome::Draw) => "Draw".to_string(),
+        None => "No result".to_string(), // stopped part way through
+    };
+    let (what, [p1, p2]) = match win.takes_lives() {
+        true => ("lives", [w.p1_lives, w.p2_lives]),
+        false => ("hits", w.points()),
+    };
+    writeln!(out, "{}, {} {} to {}", result, p1, what, p2)
+}
+
+// e.g.
+//   Round 3, ticks 840 to 1161, 16.1s, Player 2 hit
+//     Player 1  fired 4  hits 1  spent 35  ▇▇▆▅▅▄▃▃▄▅
+fn show_round(out: &mut impl Write, w: &World, names: &[String; 2], n: u32, r: &Round) -> io::Result<()> {
+    let secs = (r.end - r.start + 1) as f64 * w.config.frame_gap_ms as f64 / 1000.0;
+    let what = match r.hits {
+        [0, 0] => "no hits".to_string(),
+        [0, _] => format!("{} hit", names[0]),
+        [_, 0] => format!("{} hit", names[1]),
+        _ => "both hit".to_string(),
+    };
+    writeln!(out, "Round {}, ticks {} to {}, {:.1}s, {}", n, r.start, r.end, secs, what)?;
+    let width = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+    for (idx, name) in names.iter().enumerate() {
+        writeln!(
+            out,
+            "  {:width$}  fired {:2}  hits {}  spent {:3}  {}",
+            name,
+            r.fired[idx],
+            r.hits[idx],
+            r.spent[idx],
+            sparkline(&r.energy[idx], w.config.energy.max),
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+// values as at most SPARK_WIDTH bars, each the average of its share of them,
+// scaled so max is a full bar
+fn sparkline(values: &[u32], max: u32) -> String {
+    let bars = values.len().min(SPARK_WIDTH);
+    (0..bars)
+        .map(|i| {
+            let chunk = &values[i * values.len() / bars..(i + 1) * values.len() / bars];
+            let avg = chunk.iter().map(|&v| v as u64).sum::<u64>() / chunk.len() as u64;
+            let level = avg * (SPARKS.len() as u64 - 1) / max.max(1) as u64;
+            SPARKS[level.min(SPARKS.len() as u64 - 1) as usize]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::config::{GameConfig, Mode, Victory};
+    use crate::maps::MapKind;
+    use crate::{end_round, new_match, start_round};
+
+    // Record a match of 10 tick rounds, in each of which player hit[n], 0 or
+    // 1, is hit. What analyze makes of it.
+    fn analyze(mode: Mode, hit: &[usize]) -> String {
+        let config = GameConfig {
+            lives: 3,
+            victory: Victory { mode, points: 2, seconds: 1 },
+            ..GameConfig::default()
+        };
+        let path = std::env::temp_dir().join(format!("rcg-analyze-{}-{:?}.rcgr", std::process::id(), mode));
+        let rec = replay::Recorder::create(&path, &replay::start(80, 24, MapKind::Empty, 1, &config)).unwrap();
+        let mut w = World::with_config(80, 24, config);
+        w.reset();
+        new_match(&mut w);
+        let mut frame = Vec::new();
+        let mut record = |w: &World, state: GameState| {
+            w.entity_state(state, &mut frame);
+            rec.state(w.tick, &frame);
+        };
+        for &idx in hit {
+            record(&w, GameState::Waiting);
+            start_round(&mut w);
+            for _ in 0..10 {
+                record(&w, GameState::Playing);
+                w.tick += 1;
+            }
+            w.alive[[w.player1, w.player2][idx]] = false;
+            end_round(&mut w);
+        }
+        record(&w, GameState::GameOver);
+        rec.finish();
+
+        let mut out = Vec::new();
+        let res = run(&path, &mut out);
+        fs::remove_file(&path).unwrap();
+        res.unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn the_last_with_lives_wins() {
+        let out = analyze(Mode::Lives, &[1, 0, 1, 1]);
+        assert!(out.contains("Round 2, ticks 10 to 19, 0.5s, Player 1 hit"), "{}", out);
+        assert!(out.contains("Player 1 wins, 2 lives to 0"), "{}", out);
+    }
+
+    #[test]
+    fn in_points_mode_hits_win() {
+        // nobody loses a life, so lives can't tell who won
+        let out = analyze(Mode::Points, &[0, 1, 0]);
+        assert!(out.contains("Player 2  fired  0  hits 1"), "{}", out);
+        assert!(out.contains("Player 2 wins, 1 hits to 2"), "{}", out);
+    }
+
+    #[test]
+    fn level_on_lives_when_time_is_up_is_a_draw() {
+        let out = analyze(Mode::Timed, &[1, 0]);
+        assert!(out.contains("Draw, 2 lives to 2"), "{}", out);
+    }
+}
@@ -0,0 +1,63 @@
+// The game loop's work for one tick, without a terminal, sockets or sleeping,
+// for benches/tick.rs. Not part of the API, it may change at any time.
+
+use rs_sdk::{Dir, GameState};
+
+use crate::input::InputEvent;
+use crate::{both_players_standing, handle_event, start_round, System, World};
+
+// Both players strafe and fire every few ticks, so there are always missiles,
+// rays and explosions around.
+const FIRE_EVERY: u32 = 4;
+
+// Dead entities stay in the World until the round ends, so start a new round
+// this often to keep every tick about the same amount of work.
+const ROUND_TICKS: u32 = 400;
+
+pub struct Arena {
+    w: World,
+    system: [System; 6],
+    frame: Vec<u8>,
+}
+
+impl Arena {
+    pub fn new(width: u32, height: u32) -> Arena {
+        let mut w = World::new(width, height);
+        w.add_players();
+        w.add_obstacles();
+        let system = start_round(&mut w);
+        Arena {
+            w,
+            system,
+            frame: Vec::new(),
+        }
+    }
+
+    // Returns the size of the state frame bots would be sent
+    pub fn tick(&mut self) -> usize {
+        let w = &mut self.w;
+        if w.tick.is_multiple_of(FIRE_EVERY) {
+            let n = w.tick / FIRE_EVERY;
+            for entity_id in 1..=2 {
+                let dir = [Dir::Up, Dir::Left, Dir::Down, Dir::Right][(n + entity_id as u32) as usize % 4];
+                handle_event(w, InputEvent::Move { entity_id, dir });
+                handle_event(w, InputEvent::Fire { entity_id, dir: dir.opposite() });
+                if n.is_multiple_of(5) {
+                    handle_event(w, InputEvent::ChangeWeapon { entity_id });
+                }
+            }
+        }
+        for s in self.system.iter_mut() {
+            s.step(w);
+        }
+        w.entity_state(GameState::Playing, &mut self.frame);
+        w.tick += 1;
+
+        // nobody loses lives, play forever
+        if !both_players_standing(w) || w.tick.is_multiple_of(ROUND_TICKS) {
+            w.reset();
+            self.system = start_round(w);
+        }
+        self.frame.len()
+    }
+}
@@ -0,0 +1,99 @@
+// Entities a system wants added or gone. Systems loop over the entity
+// vectors, so instead of changing them mid-loop they queue a command, and
+// System::step applies the queue once the system is done, before the next
+// one runs. The input handler and the code between rounds still change the
+// world directly.
+//
+//   w.commands.despawn(marker);
+//   w.commands.spawn(Spawn::Supply { pos: p, what });
+
+use rs_sdk::Dir;
+
+use crate::pos::Pos;
+use crate::{new_supply, projectile, World};
+
+// What a system can ask for, one for each new_ function that needs queueing
+pub enum Spawn {
+    Supply { pos: Pos, what: u8 },                  // a landed supply drop, see drop_supply
+    Fragment { pos: Pos, dir: Dir, owner: usize }, // one of a cluster's missiles, see projectile::split
+}
+
+enum Command {
+    Spawn(Spawn),
+    Despawn(usize), // by entity id
+}
+
+#[derive(Default)]
+pub struct Commands {
+    queue: Vec<Command>,
+}
+
+impl Commands {
+    pub fn spawn(&mut self, s: Spawn) {
+        self.queue.push(Command::Spawn(s));
+    }
+
+    pub fn despawn(&mut self, id: usize) {
+        self.queue.push(Command::Despawn(id));
+    }
+
+    // Forget them all, e.g. when the entities they refer to are cleared
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
+
+// In the order they were queued. Despawning what's already gone does
+// nothing.
+pub fn apply(w: &mut World) {
+    for c in std::mem::take(&mut w.commands.queue) {
+        match c {
+            Command::Spawn(Spawn::Supply { pos, what }) => new_supply(w, pos, what),
+            Command::Spawn(Spawn::Fragment { pos, dir, owner }) => projectile::fragment(w, pos, dir, owner),
+            Command::Despawn(id) => w.alive[id] = false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rs_sdk::EntityKind;
+
+    use super::*;
+    use crate::tests::arena;
+    use crate::{SUPPLY_ENERGY, SUPPLY_LIFE};
+
+    fn at(x: u32, y: u32) -> Pos {
+        Pos { x, y, invalid: false }
+    }
+
+    #[test]
+    fn commands_apply_in_the_order_queued() {
+        let mut w = arena();
+        let first = w.name.len();
+        w.commands.spawn(Spawn::Supply { pos: at(10, 10), what: SUPPLY_LIFE });
+        w.commands.despawn(first);
+        w.commands.spawn(Spawn::Fragment { pos: at(30, 10), dir: Dir::Up, owner: w.player1 });
+        w.commands.spawn(Spawn::Supply { pos: at(20, 10), what: SUPPLY_ENERGY });
+        apply(&mut w);
+
+        let made: Vec<_> = (first..w.name.len()).map(|id| (w.kind[id], w.position[id][0].x, w.alive[id])).collect();
+        assert_eq!(
+            made,
+            [(EntityKind::Supply, 10, false), (EntityKind::Missile, 30, true), (EntityKind::Supply, 20, true)]
+        );
+        assert_eq!(w.owner[first + 1], Some(w.player1));
+        assert!(w.commands.queue.is_empty());
+    }
+
+    #[test]
+    fn despawning_the_dead_changes_nothing() {
+        let mut w = arena();
+        w.alive[w.player2] = false;
+        w.commands.despawn(w.player2);
+        w.commands.despawn(w.player2);
+        apply(&mut w);
+        assert_eq!((w.alive[w.player1], w.alive[w.player2]), (true, false));
+        assert_eq!(w.name.len(), 2);
+    }
+}
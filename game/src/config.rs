@@ -0,0 +1,741 @@
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::Color;
+use serde::{Deserialize, Serialize};
+
+use rs_sdk::{
+    Dir, ENERGY_CLUSTER, ENERGY_EVERY, ENERGY_MISSILE, ENERGY_PUSH, ENERGY_RAY, ENERGY_SHIELD, ENERGY_WALL, MAX_ENERGY,
+};
+
+use crate::input::InputEvent;
+use crate::maps::{Density, MapKind};
+use crate::{
+    BEAM_CHANCE, FRAME_GAP_MS, IDLE_SECONDS, LIFETIME_RAY, MISSILE_MIN_RANGE, PICKUP_EVERY, PLAYER_LIVES, SNAPSHOT_TICKS,
+    SUPPLY_EVERY, SUPPLY_WARNING, TELEPORT_GRACE, VICTORY_POINTS, VICTORY_SECONDS, WALL_LIFETIME,
+};
+
+// Read from the current directory if it's there
+pub const CONFIG_FILE: &str = "config.toml";
+
+// Gameplay settings. Anything not in config.toml keeps its compiled in value.
+// Bots are built with the default energy costs (see rs_sdk::rules), so
+// changing those may confuse them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub lives: u32,
+    pub frame_gap_ms: u64,
+    pub palette: Palette, // see GameConfig::set_palette
+    pub momentum: bool,   // show the tug of war bar, see momentum.rs
+    pub labels: bool,     // players' names next to them, see labels.rs
+    pub map: MapKind,     // obstacle layout, see maps::layout
+    pub density: Density, // how much of the board the obstacles cover
+    pub teleporters: bool, // in the corners, see teleport
+    pub teleport_grace: Span, // nothing can hit what comes out of a teleporter
+    pub disabled_systems: Vec<String>, // by System::name, e.g. "explode" for missiles that just vanish
+    pub snapshot_ticks: u32, // of play kept for instant replays, see snapshots.rs
+    pub idle_seconds: u32, // a bot quiet this long loses its seat, 0 never
+    pub turn_ticks: u32, // played after each player's turn in hot-seat play, 0 for real time
+    pub opponent_energy: bool, // false keeps each player's energy from the other's HUD and bot
+    pub victory: Victory,
+    pub energy: Energy,
+    pub weapons: Weapons,
+    pub names: Names,
+    pub glyphs: Glyphs,
+    pub colors: Colors,
+    pub keys: Keys,
+    pub sound: Sound,
+    pub screen: Screen,
+}
+
+// How a match is won, see victory.rs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Victory {
+    pub mode: Mode,
+    pub points: u32,  // hits to win, in points mode
+    pub seconds: u32, // of play before the match ends, in timed mode
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Lives,  // the last player with lives left wins
+    Points, // the first to land `points` hits wins, nobody loses lives
+    Timed,  // the most lives after `seconds` wins
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Energy {
+    pub missile: u32,      // to fire one
+    pub ray: u32,          // to fire one
+    pub cluster: u32,      // to fire one
+    pub wall: u32,         // to put one up
+    pub shield: u32,       // per reload while it's on
+    pub push: u32,         // from each shielded player when they bump into each other
+    pub max: u32,          // players start with this much
+    pub reload_every: Span, // between gaining one energy
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Weapons {
+    pub missile_min_range: u32, // missiles go at least this far before exploding
+    pub ray_lifetime: Span,     // a ray stays on screen
+    pub pickup_every: Span,     // between weapon upgrades appearing, 0 for none
+    pub beam_chance: u32,       // percent of those which are a beam instead, see Weapon::Beam
+    pub supply_every: Span,     // between supply drops, 0 for none
+    pub supply_warning: Span,   // a drop's marker shows before it lands
+    pub wall_lifetime: Span,    // a player's wall stands
+}
+
+// Shown in the status bar and banners. Usually set with a profile, see profile.rs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Names {
+    pub player1: String,
+    pub player2: String,
+}
+
+// The single character each player is drawn as, and the lines for the rest
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Glyphs {
+    pub player1: String,
+    pub player2: String,
+    pub border: LineStyle,
+    pub obstacles: LineStyle,
+}
+
+// Lines for the board's border and obstacles. ascii is for terminals
+// without Unicode, the others are box drawing characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineStyle {
+    Ascii,
+    Single,
+    Double,
+    Heavy,
+}
+
+// See crossterm's Color for the names, e.g. "yellow" or "dark_cyan"
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Colors {
+    pub border: ColorName,
+    pub obstacles: ColorName,
+    pub player1: ColorName,
+    pub player2: ColorName,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Keys {
+    pub player1: PlayerKeys,
+    pub player2: PlayerKeys,
+    pub mute: Key, // sound on and off, for either player
+}
+
+// For players who can't rely on colors. Replaces [colors], and for
+// high-contrast also gives player 2's weapons glyphs of their own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    #[default]
+    Normal,       // whatever [colors] says
+    Colorblind,   // yellow and blue, safe for deuteranopia and protanopia
+    HighContrast, // the terminal's own foreground color, all bold
+}
+
+// Effects are only played with the sound feature, see sound.rs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sound {
+    pub mute: bool, // start with sound off
+    pub volume: f32, // 0 to 1
+}
+
+// Rows and columns at the terminal's edges to leave alone, e.g. where a
+// tmux status line or the terminal's tabs cover it. The game is drawn in
+// what's left.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Screen {
+    pub top: u16,
+    pub bottom: u16,
+    pub left: u16,
+    pub right: u16,
+}
+
+// Fire is the fire modifier held with a move key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlayerKeys {
+    pub up: Key,
+    pub down: Key,
+    pub left: Key,
+    pub right: Key,
+    pub shield: Key,
+    pub weapon: Key,
+    #[serde(default)]
+    pub wall: Option<Key>, // none if left out, for configs from before walls
+    pub fire: FireModifier,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FireModifier {
+    Shift,
+    Alt,
+}
+
+// How long something lasts, or how long between them, in config.toml as a
+// number of ticks, e.g. 10, or a time, e.g. "500ms" or "15s". A time is
+// turned into ticks using frame_gap_ms, so it stays the same length however
+// fast the game runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SpanValue", into = "SpanValue")]
+pub enum Span {
+    Ticks(u32),
+    Millis(u32),
+}
+
+// How a Span is written in config.toml
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SpanValue {
+    Ticks(u32),
+    Time(String),
+}
+
+// A key, in config.toml as a single character, a word for some punctuation
+// ("period", "comma", "space"), or an arrow ("up", "down", "left", "right")
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Key(pub KeyCode);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ColorName {
+    name: String,
+    color: Color,
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        GameConfig {
+            lives: PLAYER_LIVES,
+            frame_gap_ms: FRAME_GAP_MS,
+            palette: Palette::Normal,
+            momentum: false,
+            labels: false,
+            map: MapKind::Bar,
+            density: Density::Normal,
+            teleporters: false,
+            teleport_grace: Span::Ticks(TELEPORT_GRACE),
+            disabled_systems: Vec::new(),
+            snapshot_ticks: SNAPSHOT_TICKS,
+            idle_seconds: IDLE_SECONDS,
+            turn_ticks: 0,
+            opponent_energy: true,
+            victory: Victory::default(),
+            energy: Energy::default(),
+            weapons: Weapons::default(),
+            names: Names::default(),
+            glyphs: Glyphs::default(),
+            colors: Colors::default(),
+            keys: Keys::default(),
+            sound: Sound::default(),
+            screen: Screen::default(),
+        }
+    }
+}
+
+impl Default for Victory {
+    fn default() -> Victory {
+        Victory {
+            mode: Mode::Lives,
+            points: VICTORY_POINTS,
+            seconds: VICTORY_SECONDS,
+        }
+    }
+}
+
+impl Default for Energy {
+    fn default() -> Energy {
+        Energy {
+            missile: ENERGY_MISSILE,
+            ray: ENERGY_RAY,
+            cluster: ENERGY_CLUSTER,
+            wall: ENERGY_WALL,
+            shield: ENERGY_SHIELD,
+            push: ENERGY_PUSH,
+            max: MAX_ENERGY,
+            reload_every: Span::Ticks(ENERGY_EVERY),
+        }
+    }
+}
+
+impl Default for Weapons {
+    fn default() -> Weapons {
+        Weapons {
+            missile_min_range: MISSILE_MIN_RANGE,
+            ray_lifetime: Span::Ticks(LIFETIME_RAY),
+            pickup_every: Span::Ticks(PICKUP_EVERY),
+            beam_chance: BEAM_CHANCE,
+            supply_every: Span::Ticks(SUPPLY_EVERY),
+            supply_warning: Span::Ticks(SUPPLY_WARNING),
+            wall_lifetime: Span::Ticks(WALL_LIFETIME),
+        }
+    }
+}
+
+impl Default for Names {
+    fn default() -> Names {
+        Names {
+            player1: "Player 1".to_string(),
+            player2: "Player 2".to_string(),
+        }
+    }
+}
+
+impl Default for Glyphs {
+    fn default() -> Glyphs {
+        Glyphs {
+            player1: "1".to_string(),
+            player2: "2".to_string(),
+            border: LineStyle::Ascii,
+            obstacles: LineStyle::Heavy,
+        }
+    }
+}
+
+impl Default for Colors {
+    fn default() -> Colors {
+        let c = |name: &str| ColorName::try_from(name.to_string()).unwrap();
+        Colors {
+            border: c("reset"),
+            obstacles: c("grey"),
+            player1: c("yellow"),
+            player2: c("cyan"),
+        }
+    }
+}
+
+impl Default for Keys {
+    fn default() -> Keys {
+        let c = |ch| Key(KeyCode::Char(ch));
+        Keys {
+            player1: PlayerKeys {
+                up: c('w'),
+                down: c('s'),
+                left: c('a'),
+                right: c('d'),
+                shield: c('e'),
+                weapon: c('q'),
+                wall: Some(c('r')),
+                fire: FireModifier::Shift,
+            },
+            player2: PlayerKeys {
+                up: Key(KeyCode::Up),
+                down: Key(KeyCode::Down),
+                left: Key(KeyCode::Left),
+                right: Key(KeyCode::Right),
+                shield: c('.'),
+                weapon: c(','),
+                wall: Some(c('/')),
+                fire: FireModifier::Alt,
+            },
+            mute: c('m'),
+        }
+    }
+}
+
+impl Default for Sound {
+    fn default() -> Sound {
+        Sound {
+            mute: false,
+            volume: 0.5,
+        }
+    }
+}
+
+impl GameConfig {
+    // Missing settings keep their defaults, unknown ones are an error
+    pub fn load(path: &Path) -> Result<GameConfig, String> {
+        let s = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let c: GameConfig = toml::from_str(&s).map_err(|e| format!("{}: {}", path.display(), e))?;
+        c.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(c)
+    }
+
+    // Catch settings the game can't run with
+    pub fn validate(&self) -> Result<(), String> {
+        if self.lives == 0 || self.lives > 255 {
+            return Err(format!("lives must be 1 to 255, not {}", self.lives)); // u8 on the wire
+        }
+        if self.frame_gap_ms == 0 {
+            return Err("frame_gap_ms must be more than 0".to_string());
+        }
+        if self.victory.mode == Mode::Points && self.victory.points == 0 {
+            return Err("victory.points must be more than 0".to_string());
+        }
+        if self.victory.mode == Mode::Timed && self.victory.seconds == 0 {
+            return Err("victory.seconds must be more than 0".to_string());
+        }
+        let supply_every = self.weapons.supply_every.ticks(self.frame_gap_ms);
+        let supply_warning = self.weapons.supply_warning.ticks(self.frame_gap_ms);
+        if supply_every != 0 && supply_every <= supply_warning {
+            return Err(format!(
+                "weapons.supply_every must be 0 or more than weapons.supply_warning, {} ticks",
+                supply_warning
+            ));
+        }
+        if self.weapons.beam_chance > 100 {
+            return Err(format!("weapons.beam_chance is a percent, not {}", self.weapons.beam_chance));
+        }
+        if self.energy.reload_every.ticks(self.frame_gap_ms) == 0 {
+            return Err("energy.reload_every must be more than 0".to_string());
+        }
+        for name in [&self.names.player1, &self.names.player2] {
+            if name.trim().is_empty() {
+                return Err("player names can't be blank".to_string());
+            }
+        }
+        if self.names.player1 == self.names.player2 {
+            return Err(format!("both players are called {}", self.names.player1));
+        }
+        for g in [&self.glyphs.player1, &self.glyphs.player2] {
+            if g.chars().count() != 1 || g.trim().is_empty() {
+                return Err(format!("glyph '{}' must be one visible character", g));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.sound.volume) {
+            return Err(format!("sound.volume must be 0 to 1, not {}", self.sound.volume));
+        }
+        let names: Vec<&str> = crate::systems().iter().map(|s| s.name()).collect();
+        for name in &self.disabled_systems {
+            if !names.contains(&name.as_str()) {
+                return Err(format!("unknown system '{}', use {}", name, names.join(", ")));
+            }
+        }
+        let mut bound: Vec<Key> = vec![self.keys.mute];
+        for k in self.keys.player1.all().iter().chain(self.keys.player2.all().iter()) {
+            if bound.contains(k) {
+                return Err(format!("key {} is used twice", k));
+            }
+            bound.push(*k);
+        }
+        Ok(())
+    }
+
+    // Use a palette's colors, over any from [colors] or a profile, so call it
+    // after applying profiles
+    pub fn set_palette(&mut self, p: Palette) {
+        self.palette = p;
+        let [player1, player2, obstacles, border] = match p {
+            Palette::Normal => return,
+            Palette::Colorblind => ["yellow", "blue", "grey", "reset"],
+            Palette::HighContrast => ["reset", "reset", "reset", "reset"],
+        };
+        let c = |name: &str| ColorName::try_from(name.to_string()).unwrap();
+        self.colors = Colors {
+            border: c(border),
+            obstacles: c(obstacles),
+            player1: c(player1),
+            player2: c(player2),
+        };
+    }
+
+    // As it would be in config.toml
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).unwrap_or_else(|e| e.to_string())
+    }
+}
+
+impl Colors {
+    // Color for a Sprite's color_idx
+    pub fn by_index(&self, idx: usize) -> Color {
+        match idx {
+            1 => self.player1.color,
+            2 => self.player2.color,
+            _ => self.obstacles.color,
+        }
+    }
+
+    pub fn border(&self) -> Color {
+        self.border.color
+    }
+}
+
+impl LineStyle {
+    // Horizontal, vertical, then the corners: top left, top right, bottom
+    // left, bottom right
+    pub fn chars(self) -> [char; 6] {
+        match self {
+            LineStyle::Ascii => ['-', '|', '-', '-', '-', '-'],
+            LineStyle::Single => ['─', '│', '┌', '┐', '└', '┘'],
+            LineStyle::Double => ['═', '║', '╔', '╗', '╚', '╝'],
+            LineStyle::Heavy => ['━', '┃', '┏', '┓', '┗', '┛'],
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Palette, String> {
+        match s {
+            "normal" => Ok(Palette::Normal),
+            "colorblind" => Ok(Palette::Colorblind),
+            "high-contrast" => Ok(Palette::HighContrast),
+            _ => Err(format!("unknown palette '{}', use normal, colorblind or high-contrast", s)),
+        }
+    }
+}
+
+impl Mode {
+    // The one after, for the menu after a match
+    pub fn next(self) -> Mode {
+        match self {
+            Mode::Lives => Mode::Points,
+            Mode::Points => Mode::Timed,
+            Mode::Timed => Mode::Lives,
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mode, String> {
+        match s {
+            "lives" => Ok(Mode::Lives),
+            "points" => Ok(Mode::Points),
+            "timed" => Ok(Mode::Timed),
+            _ => Err(format!("unknown mode '{}', use lives, points or timed", s)),
+        }
+    }
+}
+
+impl Keys {
+    // The move, fire etc a key press means, if any
+    pub fn event(&self, e: &KeyEvent) -> Option<InputEvent> {
+        if e.code == self.mute.0 {
+            return Some(InputEvent::ToggleMute);
+        }
+        self.player1.event(1, e).or_else(|| self.player2.event(2, e))
+    }
+}
+
+impl PlayerKeys {
+    fn all(&self) -> Vec<Key> {
+        let keys = [self.up, self.down, self.left, self.right, self.shield, self.weapon];
+        keys.iter().copied().chain(self.wall).collect()
+    }
+
+    fn event(&self, entity_id: u8, e: &KeyEvent) -> Option<InputEvent> {
+        if e.code == self.shield.0 {
+            return Some(InputEvent::ToggleShield { entity_id });
+        }
+        if e.code == self.weapon.0 {
+            return Some(InputEvent::ChangeWeapon { entity_id });
+        }
+        if self.wall.is_some_and(|k| e.code == k.0) {
+            return Some(InputEvent::DropWall { entity_id });
+        }
+        let fire_held = match self.fire {
+            FireModifier::Shift => e.modifiers.contains(KeyModifiers::SHIFT),
+            FireModifier::Alt => e.modifiers.contains(KeyModifiers::ALT),
+        };
+        let moves = [
+            (self.up, Dir::Up),
+            (self.down, Dir::Down),
+            (self.left, Dir::Left),
+            (self.right, Dir::Right),
+        ];
+        for (k, dir) in moves.iter().copied() {
+            // terminals send shift + letter as the upper case letter
+            let shifted = match k.0 {
+                KeyCode::Char(c) => self.fire == FireModifier::Shift && e.code == KeyCode::Char(c.to_ascii_uppercase()),
+                _ => false,
+            };
+            if shifted || (e.code == k.0 && fire_held) {
+                return Some(InputEvent::Fire { entity_id, dir });
+            }
+            if e.code == k.0 {
+                return Some(InputEvent::Move { entity_id, dir });
+            }
+        }
+        None
+    }
+
+    // The move keys, short, e.g. "wasd" or "arrows"
+    fn moves(&self) -> String {
+        let arrows = [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right];
+        if [self.up.0, self.down.0, self.left.0, self.right.0] == arrows {
+            return "arrows".to_string();
+        }
+        // up left down right, the order of wasd
+        let keys = [self.up, self.left, self.down, self.right];
+        if keys.iter().all(|k| matches!(k.0, KeyCode::Char(c) if c.is_alphanumeric())) {
+            keys.iter().map(|k| k.to_string()).collect()
+        } else {
+            keys.iter().map(|k| k.to_string()).collect::<Vec<_>>().join("/")
+        }
+    }
+
+    // For the status bar, e.g. "wasd,shift+wasd,e,q,r"
+    pub fn summary(&self) -> String {
+        let moves = self.moves();
+        let wall = self.wall.map(|k| format!(",{}", k)).unwrap_or_default();
+        format!("{},{}+{},{},{}{}", moves, self.fire, moves, self.shield, self.weapon, wall)
+    }
+
+    // For the start screen
+    pub fn help(&self) -> String {
+        let wall = self.wall.map(|k| format!(". Wall: {}", k)).unwrap_or_default();
+        format!(
+            "Move: {}. Fire: {} + move direction. Toggle shield: {}. Change weapon: {}{}",
+            self.moves(),
+            self.fire,
+            self.shield,
+            self.weapon,
+            wall
+        )
+    }
+}
+
+impl Display for FireModifier {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            FireModifier::Shift => write!(f, "shift"),
+            FireModifier::Alt => write!(f, "alt"),
+        }
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Key, String> {
+        let code = match s.as_str() {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "period" => KeyCode::Char('.'),
+            "comma" => KeyCode::Char(','),
+            "space" => KeyCode::Char(' '),
+            _ => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c.to_ascii_lowercase()),
+                    _ => return Err(format!("unknown key '{}'", s)),
+                }
+            }
+        };
+        Ok(Key(code))
+    }
+}
+
+impl From<Key> for String {
+    fn from(k: Key) -> String {
+        k.to_string()
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.0 {
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Char('.') => write!(f, "period"),
+            KeyCode::Char(',') => write!(f, "comma"),
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            other => write!(f, "{:?}", other), // not something we parse
+        }
+    }
+}
+
+impl Span {
+    // At a tick every frame_gap_ms. A time of more than 0 is at least one tick.
+    pub fn ticks(self, frame_gap_ms: u64) -> u32 {
+        match self {
+            Span::Ticks(n) => n,
+            Span::Millis(0) => 0,
+            Span::Millis(ms) => {
+                let gap = frame_gap_ms.max(1);
+                ((u64::from(ms) + gap / 2) / gap).clamp(1, u64::from(u32::MAX)) as u32
+            }
+        }
+    }
+
+    // num / den as long, e.g. (1, 2) for half
+    pub fn scaled(self, num: u32, den: u32) -> Span {
+        let scale = |n: u32| (u64::from(n) * u64::from(num) / u64::from(den)).min(u64::from(u32::MAX)) as u32;
+        match self {
+            Span::Ticks(n) => Span::Ticks(scale(n)),
+            Span::Millis(ms) => Span::Millis(scale(ms)),
+        }
+    }
+}
+
+impl TryFrom<SpanValue> for Span {
+    type Error = String;
+
+    fn try_from(v: SpanValue) -> Result<Span, String> {
+        let s = match v {
+            SpanValue::Ticks(n) => return Ok(Span::Ticks(n)),
+            SpanValue::Time(s) => s,
+        };
+        let (n, per) = if let Some(n) = s.strip_suffix("ms") {
+            (n, 1)
+        } else if let Some(n) = s.strip_suffix('s') {
+            (n, 1000)
+        } else {
+            (s.as_str(), 0)
+        };
+        match n.trim().parse::<u32>().ok().and_then(|n| n.checked_mul(per)) {
+            Some(ms) if per > 0 => Ok(Span::Millis(ms)),
+            _ => Err(format!("'{}' isn't ticks or a time, e.g. 10, \"500ms\" or \"15s\"", s)),
+        }
+    }
+}
+
+impl From<Span> for SpanValue {
+    fn from(s: Span) -> SpanValue {
+        match s {
+            Span::Ticks(n) => SpanValue::Ticks(n),
+            Span::Millis(ms) if ms % 1000 == 0 && ms > 0 => SpanValue::Time(format!("{}s", ms / 1000)),
+            Span::Millis(ms) => SpanValue::Time(format!("{}ms", ms)),
+        }
+    }
+}
+
+impl TryFrom<String> for ColorName {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<ColorName, String> {
+        match Color::try_from(name.as_str()) {
+            Ok(color) => Ok(ColorName { name, color }),
+            Err(()) => Err(format!("unknown color '{}'", name)),
+        }
+    }
+}
+
+impl From<ColorName> for String {
+    fn from(c: ColorName) -> String {
+        c.name
+    }
+}
@@ -1,191 +1,381 @@
 use crossterm::{cursor, execute, queue, style, terminal};
 //use log::debug;
-use std::error::Error;
 use std::io::{stdout, Stdout, Write};
 
-const TITLE: &str = "Hash Bang";
+use crate::config::Screen;
+use crate::render::{clock_time, FeedLine, Inspector, View, WeaponWheel};
+use crate::sound::Sounds;
+use crate::GameError;
 
-lazy_static! {
-    static ref COLORS: Vec<style::Color> =
-        vec![style::Color::Grey, style::Color::Yellow, style::Color::Cyan];
-}
+const TITLE: &str = "Hash Bang";
+const MOMENTUM_WIDTH: u16 = 40; // at most, see draw_momentum
 
 pub struct ConsoleOutput {
-    w: u16,
+    w: u16, // the arena
     h: u16,
+    vw: u16, // how much of it is shown, less than w and h if the terminal is smaller
+    vh: u16,
+    screen: Screen,     // margins, everything is drawn inside them
+    origin: (u16, u16), // of the arena inside the margins, centering one smaller than the terminal
+    camera: (u16, u16), // top left of what's shown of a bigger one, see follow
     writer: Stdout,
+    sounds: Option<Sounds>, // None to draw in silence
 }
 
-pub fn new() -> ConsoleOutput {
-    let (w, h) = terminal::size().unwrap();
-    ConsoleOutput {
+pub fn new() -> Result<ConsoleOutput, GameError> {
+    let (w, h) = terminal::size().map_err(|e| GameError::Terminal(format!("no terminal: {}", e)))?;
+    Ok(ConsoleOutput {
         w,
         h,
+        vw: w,
+        vh: h,
+        screen: Screen::default(),
+        origin: (0, 0),
+        camera: (0, 0),
         writer: stdout(),
+        sounds: None,
+    })
+}
+
+// An arena of width x height, inside screen's margins. None means all of
+// what's left. One smaller than that is centered, a bigger one scrolls to
+// follow the players.
+pub fn with_size(width: Option<u16>, height: Option<u16>, screen: Screen) -> Result<ConsoleOutput, GameError> {
+    let mut out = new()?;
+    let (across, down) = (screen.left + screen.right, screen.top + screen.bottom);
+    if across >= out.w || down >= out.h {
+        return Err(GameError::Terminal(format!(
+            "the screen margins leave nothing of the terminal ({}x{}) to play on",
+            out.w, out.h
+        )));
     }
+    let (room_w, room_h) = (out.w - across, out.h - down);
+    out.w = width.unwrap_or(room_w);
+    out.h = height.unwrap_or(room_h);
+    out.vw = out.w.min(room_w);
+    out.vh = out.h.min(room_h);
+    out.origin = ((room_w - out.vw) / 2, (room_h - out.vh) / 2);
+    out.screen = screen;
+    Ok(out)
 }
 
 impl ConsoleOutput {
-    fn draw_board(&mut self, world: &crate::World) -> Result<(), Box<dyn Error>> {
+    // Play each frame's cues as it's drawn
+    pub fn with_sounds(mut self, s: Sounds) -> ConsoleOutput {
+        self.sounds = Some(s);
+        self
+    }
+
+    // Where x, y of what's shown is on the terminal. For the status lines,
+    // banners and the like, which stay put when the arena scrolls.
+    fn at(&self, x: u16, y: u16) -> cursor::MoveTo {
+        cursor::MoveTo(self.screen.left + self.origin.0 + x, self.screen.top + self.origin.1 + y)
+    }
+
+    // Where x, y in the arena is on the terminal, if it's shown. The top and
+    // bottom rows are kept for the status lines.
+    fn board_at(&self, x: u16, y: u16) -> Option<cursor::MoveTo> {
+        let sx = x.checked_sub(self.camera.0).filter(|&sx| sx < self.vw)?;
+        let sy = y.checked_sub(self.camera.1).filter(|&sy| sy >= 1 && sy + 1 < self.vh)?;
+        Some(self.at(sx, sy))
+    }
+
+    // Keep focus in the middle of what's shown, as far as the arena's edges allow
+    fn follow(&mut self, (x, y): (u16, u16)) {
+        let centre = |at: u16, shown: u16, size: u16| at.saturating_sub(shown / 2).min(size - shown);
+        self.camera = (centre(x, self.vw, self.w), centre(y, self.vh, self.h));
+    }
+
+    // As much of s, drawn from x, y in the arena, as is shown. One column a char.
+    fn board_print(&self, x: u16, y: u16, s: &str) -> Result<(), GameError> {
+        let mut shown = (x..).zip(s.chars()).filter(|&(cx, _)| self.board_at(cx, y).is_some());
+        if let Some((first, c)) = shown.next() {
+            let rest: String = std::iter::once(c).chain(shown.map(|(_, c)| c)).collect();
+            queue!(&self.writer, self.board_at(first, y).unwrap(), style::Print(rest))?;
+        }
+        Ok(())
+    }
+
+    // Blank the part of the terminal we draw in. Without margins that's all of it.
+    fn clear(&self) -> Result<(), GameError> {
+        let mut stdout = &self.writer;
+        if self.screen == Screen::default() {
+            queue!(stdout, terminal::Clear(terminal::ClearType::All))?;
+            return Ok(());
+        }
+        let blank = " ".repeat(self.vw as usize);
+        for y in 0..self.vh {
+            queue!(stdout, self.at(0, y), style::Print(&blank))?;
+        }
+        Ok(())
+    }
+
+    fn draw_board(&mut self, view: &View) -> Result<(), GameError> {
         let top = 1;
         let bottom = self.h - 2;
-        self.draw_status(world)?;
+        self.draw_status(view)?;
 
-        let mut stdout = &self.writer;
+        let [horizontal, vertical, top_left, top_right, bottom_left, bottom_right] = view.border.chars();
+        queue!(&self.writer, style::SetForegroundColor(view.border_color))?;
 
         // top border
-        queue!(stdout, cursor::MoveTo(0, top))?;
-        line(&mut stdout, self.w)?;
+        self.board_print(0, top, &line(self.w, top_left, horizontal, top_right))?;
 
         // side borders
+        let side = vertical.to_string();
         for i in top + 1..bottom {
-            queue!(
-                stdout,
-                cursor::MoveTo(0, i),
-                style::Print("|"),
-                cursor::MoveTo(self.w - 1, i),
-                style::Print("|"),
-            )?;
+            self.board_print(0, i, &side)?;
+            self.board_print(self.w - 1, i, &side)?;
         }
 
         // bottom border
-        queue!(stdout, cursor::MoveTo(0, bottom))?;
-        line(&mut stdout, self.w)?;
+        self.board_print(0, bottom, &line(self.w, bottom_left, horizontal, bottom_right))?;
+        if let Some(share) = view.momentum {
+            self.draw_momentum(share, (horizontal, vertical), view)?;
+        }
+        queue!(&self.writer, style::ResetColor)?;
 
-        stdout.flush()?;
+        self.writer.flush()?;
         Ok(())
     }
 
-    fn draw_status(&mut self, world: &crate::World) -> Result<(), Box<dyn Error>> {
-        let quarter_width = self.w / 4;
-
-        let mut player1 = format!(
-            "Lives: {} / {}. Nrg: {}. Wpn: {:7}. Keys: wasd,shift+wasd,e,q.",
-            world.p1_lives,
-            crate::PLAYER_LIVES,
-            world.energy[world.player1],
-            world.active_weapon[world.player1].as_ref().unwrap().name(),
-        );
-        if world.shield[world.player1] {
-            player1 += ". SHIELD ON.";
+    fn draw_status(&mut self, view: &View) -> Result<(), GameError> {
+        let quarter_width = self.vw / 4;
+        for (p, centre) in view.players.iter().zip([quarter_width, 3 * quarter_width]) {
+            let mut status = format!(
+                "{}. Lives: {} / {}. Nrg: {}. Wpn: {:7} {}. Keys: {}.",
+                p.name,
+                p.lives,
+                p.max_lives,
+                p.energy.map_or("?".to_string(), |e| e.to_string()),
+                p.weapon.name(),
+                p.tier,
+                p.keys,
+            );
+            if p.shield {
+                status += ". SHIELD ON.";
+            }
+            if let Some(ping) = p.ping {
+                status += &format!(" Ping: {:.1}ms.", ping.as_secs_f64() * 1000.0);
+            }
+            if p.idle {
+                status += " Bot idle, seat open.";
+            }
+            queue!(
+                &self.writer,
+                self.at(centre.saturating_sub(status.len() as u16 / 2), 0),
+                style::SetForegroundColor(p.color),
+                style::Print(status),
+            )?;
         }
-
-        let mut player2 = format!(
-            "Lives: {} / {}. Nrg: {}. Wpn: {:7}. Keys: arrows,alt+arrows,period,comma.",
-            world.p2_lives,
-            crate::PLAYER_LIVES,
-            world.energy[world.player2],
-            world.active_weapon[world.player2].as_ref().unwrap().name(),
-        );
-        if world.shield[world.player2] {
-            player2 += ". SHIELD ON.";
+        queue!(self.writer, style::ResetColor)?;
+        // under the board, whose turn it is while a player picks, the clock
+        // stands still meanwhile
+        if let Some(t) = &view.turn {
+            queue!(
+                &self.writer,
+                self.at((self.vw / 2).saturating_sub(t.text.len() as u16 / 2), self.vh - 1),
+                style::SetForegroundColor(t.color),
+                style::Print(&t.text),
+                style::ResetColor,
+            )?;
+        } else if let Some(c) = &view.clock {
+            let clock = format!("Round {}   Match {}", clock_time(c.round), clock_time(c.game));
+            queue!(
+                &self.writer,
+                self.at((self.vw / 2).saturating_sub(clock.len() as u16 / 2), self.vh - 1),
+                style::Print(clock),
+            )?;
         }
+        Ok(())
+    }
 
+    // The tug of war bar, over the middle of the top border, or where it would
+    // be when the arena scrolls. Player 1's color
+    // pulls from the left, player 2's from the right, and a mark shows where
+    // they meet for palettes where the colors are the same.
+    fn draw_momentum(&self, share: f32, (line, mark): (char, char), view: &View) -> Result<(), GameError> {
+        let width = (self.vw / 3).min(MOMENTUM_WIDTH);
+        if width < 3 {
+            return Ok(());
+        }
+        let left = ((share.clamp(0.0, 1.0) * (width - 1) as f32).round() as u16).min(width - 1);
+        let part = |n: u16| line.to_string().repeat(n as usize);
         queue!(
-            self.writer,
-            cursor::MoveTo(quarter_width - player1.len() as u16 / 2, 0),
-            style::SetForegroundColor(COLORS[1]),
-            style::Print(player1),
-            cursor::MoveTo(3 * quarter_width - player2.len() as u16 / 2, 0),
-            style::SetForegroundColor(COLORS[2]),
-            style::Print(player2),
-            style::ResetColor,
+            &self.writer,
+            self.at((self.vw - width) / 2, 1),
+            style::SetAttribute(style::Attribute::Bold),
+            style::SetForegroundColor(view.players[0].color),
+            style::Print(part(left)),
+            style::SetForegroundColor(view.border_color),
+            style::Print(mark),
+            style::SetForegroundColor(view.players[1].color),
+            style::Print(part(width - 1 - left)),
+            style::SetAttribute(style::Attribute::Reset),
+            style::SetForegroundColor(view.border_color),
         )?;
         Ok(())
     }
+
+    // Top right corner of what's shown, newest at the bottom
+    fn draw_feed(&mut self, feed: &[FeedLine]) -> Result<(), GameError> {
+        let width = self.vw.saturating_sub(2) as usize;
+        for (y, line) in (2..).zip(feed.iter()) {
+            let text: String = line.text.chars().take(width).collect();
+            let x = self.vw.saturating_sub(1 + text.chars().count() as u16);
+            queue!(
+                &self.writer,
+                self.at(x, y),
+                style::SetForegroundColor(line.color),
+                style::Print(text),
+            )?;
+        }
+        queue!(self.writer, style::ResetColor)?;
+        Ok(())
+    }
+
+    // On the line above the player, or below when they're at the top, e.g.
+    // " Missile [Ray] ". Kept inside the border.
+    fn draw_wheel(&self, wheel: &WeaponWheel) -> Result<(), GameError> {
+        let names: Vec<String> = (0..)
+            .zip(wheel.weapons.iter())
+            .map(|(i, name)| if i == wheel.selected { format!("[{}]", name) } else { format!(" {} ", name) })
+            .collect();
+        let width = names.iter().map(|n| n.chars().count() as u16).sum::<u16>();
+        let y = if wheel.y > 2 { wheel.y - 1 } else { wheel.y + 1 };
+        let mut x = wheel.x.saturating_sub(width / 2).min(self.w.saturating_sub(width + 1)).max(1);
+        for (i, name) in (0..).zip(names.iter()) {
+            let color = if i == wheel.selected { wheel.color } else { wheel.faded };
+            queue!(&self.writer, style::SetForegroundColor(color))?;
+            self.board_print(x, y, name)?;
+            x += name.chars().count() as u16;
+        }
+        queue!(&self.writer, style::ResetColor)?;
+        Ok(())
+    }
+
+    // Over the board, as many lines as fit, blanking what's behind them
+    fn draw_inspector(&mut self, ins: &Inspector) -> Result<(), GameError> {
+        let width = self.vw.saturating_sub(2) as usize;
+        let rows = self.vh.saturating_sub(5) as usize; // inside the borders, after the title
+        let shown = ins.lines.iter().skip(ins.scroll).take(rows);
+        for (y, line) in (2..).zip(std::iter::once(&ins.title).chain(shown)) {
+            let line: String = line.chars().take(width).collect();
+            queue!(
+                &self.writer,
+                self.at(1, y),
+                style::Print(format!("{:width$}", line, width = width)),
+            )?;
+        }
+        Ok(())
+    }
 }
 
 impl crate::Output for ConsoleOutput {
-    fn init(&mut self) -> Result<(), Box<dyn Error>> {
-        terminal::enable_raw_mode()?;
+    // The caller holds a session::TerminalGuard for raw mode and the alternate
+    // screen, so clearing here leaves the user's own screen alone
+    fn init(&mut self) -> Result<(), GameError> {
         execute!(
             self.writer,
             terminal::Clear(terminal::ClearType::All),
             terminal::SetTitle(TITLE),
-            cursor::Hide,
             cursor::MoveTo(0, 0),
         )?;
         Ok(())
     }
 
-    fn render(&mut self, w: &mut crate::World) -> Result<(), Box<dyn Error>> {
-        queue!(self.writer, terminal::Clear(terminal::ClearType::All))?;
-        self.draw_board(w)?;
-
-        for id in crate::alive_entities(w) {
-            let sprite = &w.sprite[id];
-            let (_, dir) = w.velocity[id];
-            let is_player = id == w.player1 || id == w.player2;
-            let tx: &str = if is_player && w.shield[id] {
-                "@"
-            } else if w.explode[id].1 {
-                sprite.texture_explosion[0].as_ref().unwrap()
-            } else if dir.is_vertical() {
-                &sprite.texture_vertical[0]
-            } else {
-                &sprite.texture_horizontal[0]
+    fn render(&mut self, v: View) -> Result<(), GameError> {
+        self.follow(v.focus);
+        self.clear()?;
+        self.draw_board(&v)?;
+
+        // behind everything, an entity on a square hides its shade
+        for h in v.heat.iter() {
+            if let Some(at) = self.board_at(h.x, h.y) {
+                queue!(&self.writer, at, style::SetBackgroundColor(h.color), style::Print(' '))?;
+            }
+        }
+        queue!(self.writer, style::ResetColor)?;
+
+        for c in v.cells.iter() {
+            let at = match self.board_at(c.x, c.y) {
+                Some(at) => at,
+                None => continue,
             };
-            if sprite.is_bold {
+            if c.bold {
                 queue!(self.writer, style::SetAttribute(style::Attribute::Bold))?;
             }
-            for pos in w.position[id].iter() {
-                if pos.invalid {
-                    continue;
-                }
-                queue!(
-                    self.writer,
-                    cursor::MoveTo(pos.x as u16, pos.y as u16),
-                    style::SetForegroundColor(COLORS[sprite.color_idx]),
-                    style::Print(tx),
-                )?;
-            }
             queue!(
-                self.writer,
-                style::SetAttribute(style::Attribute::Reset),
-                style::ResetColor,
+                &self.writer,
+                at,
+                style::SetForegroundColor(c.color),
+                style::Print(c.glyph),
             )?;
+            if c.bold {
+                queue!(self.writer, style::SetAttribute(style::Attribute::Reset))?;
+            }
+        }
+        for label in v.labels.iter() {
+            queue!(&self.writer, style::SetForegroundColor(label.color))?;
+            self.board_print(label.x, label.y, &label.text)?;
+        }
+        queue!(self.writer, style::ResetColor)?;
+        for wheel in v.wheels.iter() {
+            self.draw_wheel(wheel)?;
+        }
+        self.draw_feed(&v.feed)?;
+        if let Some(ins) = &v.inspector {
+            self.draw_inspector(ins)?;
+        }
+        if let Some(sounds) = &self.sounds {
+            for &c in v.cues.iter() {
+                if sounds.play(c) {
+                    queue!(self.writer, style::Print('\x07'))?; // bell
+                }
+            }
         }
         self.writer.flush()?;
         Ok(())
     }
 
-    fn dimensions(&self) -> Result<(u16, u16), Box<dyn Error>> {
+    fn dimensions(&self) -> Result<(u16, u16), GameError> {
         Ok((self.w, self.h))
     }
 
-    fn banner(&mut self, msg: &[&str]) -> Result<(), Box<dyn Error>> {
-        let (w, h) = (self.w, self.h);
-        queue!(self.writer, terminal::Clear(terminal::ClearType::All))?;
-        let mut msg_top = h / 2 - msg.len() as u16 / 2;
-        for m in msg {
+    fn is_scrolling(&self) -> bool {
+        self.vw < self.w || self.vh < self.h
+    }
+
+    fn banner(&mut self, msg: &[&str]) -> Result<(), GameError> {
+        let (w, h) = (self.vw, self.vh);
+        self.clear()?;
+        let msg_top = (h / 2).saturating_sub(msg.len() as u16 / 2);
+        for (y, m) in (msg_top..).zip(msg.iter()) {
             queue!(
-                self.writer,
-                cursor::MoveTo(w / 2 - m.len() as u16 / 2, msg_top),
+                &self.writer,
+                self.at((w / 2).saturating_sub(m.len() as u16 / 2), y),
                 style::Print(m),
             )?;
-            msg_top += 1;
         }
         self.writer.flush()?;
         Ok(())
     }
 
-    fn print(&mut self, x: u16, y: u16, s: &str) -> Result<(), Box<dyn Error>> {
-        execute!(&self.writer, cursor::MoveTo(x, y), style::Print(s))?;
+    // The arena's last row is the last row shown, whatever its size
+    fn print(&mut self, x: u16, y: u16, s: &str) -> Result<(), GameError> {
+        execute!(&self.writer, self.at(x, y.min(self.vh - 1)), style::Print(s))?;
         Ok(())
     }
 
-    fn cleanup(&mut self) -> Result<(), Box<dyn Error>> {
-        execute!(
-            self.writer,
-            terminal::Clear(terminal::ClearType::All),
-            cursor::MoveTo(0, 0),
-            cursor::Show
-        )?;
-        terminal::disable_raw_mode()?;
+    // Leaving the alternate screen, in TerminalGuard, puts back what was there
+    fn cleanup(&mut self) -> Result<(), GameError> {
+        self.writer.flush()?;
         Ok(())
     }
 }
 
-fn line<T: Write>(writer: &mut T, width: u16) -> Result<(), std::io::Error> {
-    queue!(writer, style::Print("-".repeat(width as usize)))
+// width wide, including the corners at each end
+fn line(width: u16, start: char, fill: char, end: char) -> String {
+    let middle = fill.to_string().repeat(width.saturating_sub(2) as usize);
+    format!("{}{}{}", start, middle, end)
 }
@@ -0,0 +1,240 @@
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+use crate::maps::{Density, MapKind};
+use crate::stats::MatchRecord;
+
+// The daily challenge, for the `daily` subcommand. Everyone playing on the
+// same day (UTC) gets the same map, board size and modifiers, against the
+// built in bot, so results can be compared.
+
+// Each game's result is appended here, in the current directory like stats.toml
+pub const DAILY_FILE: &str = "daily.toml";
+
+// Every day is played on this board, so the map is the same everywhere
+pub const WIDTH: u16 = 80;
+pub const HEIGHT: u16 = 24;
+
+// Modifiers a day has
+const MODIFIERS_PER_DAY: usize = 2;
+
+// Changes to the usual rules, see Challenge::apply
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Modifier {
+    Fast,        // more frames per second
+    SuddenDeath, // 3 lives
+    ShortRays,   // rays vanish twice as fast
+    LongFuse,    // missiles fly further before they can explode
+    SlowReload,  // energy comes back at half the speed
+    BigBattery,  // twice the energy to start with, and to store
+}
+
+const MODIFIERS: [Modifier; 6] = [
+    Modifier::Fast,
+    Modifier::SuddenDeath,
+    Modifier::ShortRays,
+    Modifier::LongFuse,
+    Modifier::SlowReload,
+    Modifier::BigBattery,
+];
+
+impl Display for Modifier {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let s = match self {
+            Modifier::Fast => "fast",
+            Modifier::SuddenDeath => "sudden death",
+            Modifier::ShortRays => "short rays",
+            Modifier::LongFuse => "long fuse",
+            Modifier::SlowReload => "slow reload",
+            Modifier::BigBattery => "big battery",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Challenge {
+    pub date: String, // YYYY-MM-DD, UTC
+    pub seed: u64,    // for the random map
+    pub modifiers: Vec<Modifier>,
+}
+
+impl Challenge {
+    pub fn today() -> Challenge {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Challenge::for_day(secs / 86_400)
+    }
+
+    // day is days since 1970-01-01
+    pub fn for_day(day: u64) -> Challenge {
+        let mut rng = StdRng::seed_from_u64(day);
+        let seed = rng.gen();
+        let modifiers = MODIFIERS
+            .choose_multiple(&mut rng, MODIFIERS_PER_DAY)
+            .copied()
+            .collect();
+        Challenge {
+            date: date(day),
+            seed,
+            modifiers,
+        }
+    }
+
+    // The day's rules, on a random map. Lives, speed, energy, weapons and systems start
+    // from the defaults, whatever config.toml says, so every player's game is the same.
+    pub fn apply(&self, config: &mut GameConfig) {
+        config.map = MapKind::Random;
+        config.density = Density::Normal;
+        let d = GameConfig::default();
+        config.lives = d.lives;
+        config.frame_gap_ms = d.frame_gap_ms;
+        config.energy = d.energy;
+        config.weapons = d.weapons;
+        config.disabled_systems = d.disabled_systems;
+        config.victory = d.victory;
+        for m in &self.modifiers {
+            match m {
+                Modifier::Fast => config.frame_gap_ms = config.frame_gap_ms * 2 / 3,
+                Modifier::SuddenDeath => config.lives = 3,
+                Modifier::ShortRays => config.weapons.ray_lifetime = config.weapons.ray_lifetime.scaled(1, 2),
+                Modifier::LongFuse => config.weapons.missile_min_range *= 2,
+                Modifier::SlowReload => config.energy.reload_every = config.energy.reload_every.scaled(2, 1),
+                Modifier::BigBattery => config.energy.max *= 2,
+            }
+        }
+    }
+
+    // e.g. "Daily challenge 2026-10-16: fast, long fuse"
+    pub fn title(&self) -> String {
+        let mods: Vec<String> = self.modifiers.iter().map(|m| m.to_string()).collect();
+        format!("Daily challenge {}: {}", self.date, mods.join(", "))
+    }
+}
+
+// YYYY-MM-DD for days since 1970-01-01, in the proleptic Gregorian calendar.
+// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+pub fn date(day: u64) -> String {
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097); // day of era, 0 to 146096
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365; // year of era
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // day of year, from March 1st
+    let mp = (5 * doy + 2) / 153; // month, from March
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+// One daily challenge game, by player 1
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub date: String,
+    pub player: String,
+    pub won: bool,
+    pub lives: u32, // left at the end
+    pub seconds: u64,
+    pub shots: u32,
+    pub hits: u32,
+}
+
+// daily.toml is a list of [[results]], appended to like stats.toml
+#[derive(Default, Serialize, Deserialize)]
+struct DailyFile {
+    #[serde(default)]
+    results: Vec<DailyResult>,
+}
+
+impl DailyResult {
+    pub fn new(c: &Challenge, m: &MatchRecord) -> DailyResult {
+        DailyResult {
+            date: c.date.clone(),
+            player: m.players[0].clone(),
+            won: m.winner.as_ref() == Some(&m.players[0]),
+            lives: m.lives[0],
+            seconds: m.seconds,
+            shots: m.shots[0],
+            hits: m.hits[0],
+        }
+    }
+
+    // Winning beats losing, then more lives left, then a quicker game
+    fn is_better_than(&self, other: &DailyResult) -> bool {
+        (self.won, self.lives, std::cmp::Reverse(self.seconds))
+            > (other.won, other.lives, std::cmp::Reverse(other.seconds))
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{}, {} lives left, {} seconds, {} hits from {} shots",
+            if self.won { "won" } else { "lost" },
+            self.lives,
+            self.seconds,
+            self.hits,
+            self.shots
+        )
+    }
+}
+
+pub fn append(path: &Path, r: &DailyResult) -> Result<(), String> {
+    let file = DailyFile {
+        results: vec![r.clone()],
+    };
+    let s = toml::to_string(&file).map_err(|e| e.to_string())?;
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    writeln!(f, "{}", s).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Oldest first. No file yet is no results.
+pub fn load(path: &Path) -> Result<Vec<DailyResult>, String> {
+    let s = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let file: DailyFile = toml::from_str(&s).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(file.results)
+}
+
+// How player did today, and on the days before
+pub fn show(out: &mut impl Write, c: &Challenge, player: &str, results: &[DailyResult]) -> io::Result<()> {
+    writeln!(out, "{}", c.title())?;
+    let mine: Vec<&DailyResult> = results.iter().filter(|r| r.player == player).collect();
+    let today: Vec<&DailyResult> = mine.iter().copied().filter(|r| r.date == c.date).collect();
+    let last = match today.last() {
+        Some(r) => r,
+        None => return writeln!(out, "  {} hasn't finished today's game", player),
+    };
+    writeln!(out, "  This game: {}", last.describe())?;
+    if today.len() > 1 {
+        let mut best = today[0];
+        for r in &today[1..] {
+            if r.is_better_than(best) {
+                best = r;
+            }
+        }
+        writeln!(out, "  Best of {} today: {}", today.len(), best.describe())?;
+    }
+
+    let mut days: Vec<&str> = mine.iter().map(|r| r.date.as_str()).collect();
+    days.dedup();
+    let mut won: Vec<&str> = mine.iter().filter(|r| r.won).map(|r| r.date.as_str()).collect();
+    won.dedup();
+    writeln!(out, "  Won {} of the {} days {} has played", won.len(), days.len(), player)
+}
@@ -0,0 +1,136 @@
+// The game as a library, for programs that want to drive it themselves: a
+// tutorial stepping through a match, a benchmark, a GUI drawing the board its
+// own way. No terminal, threads, sockets or frame delay, the caller decides
+// when each tick happens and what both players do in it.
+//
+//   let mut game = Game::new(GameConfig::default())?;
+//   loop {
+//       let events = game.tick([my_intent(&game.frame()), Intent::default()]);
+//       game.render_into(&mut my_output)?;
+//       if events.iter().any(|e| matches!(e, Event::MatchOver { .. })) {
+//           break;
+//       }
+//   }
+//
+// Rounds start and end with no banners in between, and after a match the
+// next tick starts another. turn_ticks and idle_seconds have no effect here.
+
+use rs_sdk::{Frame, GameState, Intent, Player};
+
+use crate::headless;
+use crate::options::{HEADLESS_HEIGHT, HEADLESS_WIDTH};
+use crate::victory::Outcome;
+use crate::{
+    apply_intent, both_players_standing, end_round, is_match_over, maps, new_match, start_round, GameConfig,
+    GameError, Output, System, World,
+};
+
+// What happened in a tick, in the order it happened
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    RoundStart,
+    Fired(Player),  // a missile or ray
+    Feed(String),   // a line for the kill feed, e.g. "Player 2's ray hit Player 1"
+    Hit(Player),    // and lost the round
+    RoundOver,      // the next tick starts the next round
+    MatchOver { winner: Option<Player> }, // None for a draw. The next tick starts a rematch.
+}
+
+pub struct Game {
+    w: World,
+    system: Option<[System; 6]>, // None between rounds
+    seed: u64,                   // for the maps placed at random
+}
+
+impl Game {
+    // An 80x24 arena, as for serve. The config is checked as a config.toml would be.
+    pub fn new(config: GameConfig) -> Result<Game, GameError> {
+        config.validate().map_err(GameError::Config)?;
+        Ok(Game::build(config, HEADLESS_WIDTH.into(), HEADLESS_HEIGHT.into(), 0))
+    }
+
+    // Arena size in characters, like a terminal. Before the first tick.
+    pub fn arena(self, width: u32, height: u32) -> Game {
+        Game::build(self.w.config, width, height, self.seed)
+    }
+
+    // Seed for map pillars, maze or random, to play the same map again. Before the first tick.
+    pub fn seed(self, seed: u64) -> Game {
+        let (width, height) = (self.w.width, self.w.height);
+        Game::build(self.w.config, width, height, seed)
+    }
+
+    fn build(config: GameConfig, width: u32, height: u32, seed: u64) -> Game {
+        let mut w = World::with_config(width, height, config);
+        w.layout = maps::layout(w.config.map, w.config.density, width, height, seed);
+        w.add_players();
+        w.add_obstacles();
+        new_match(&mut w);
+        Game { w, system: None, seed }
+    }
+
+    // Play one tick, with what each player, one then two, does in it
+    pub fn tick(&mut self, intents: [Intent; 2]) -> Vec<Event> {
+        let w = &mut self.w;
+        let mut events = Vec::new();
+        if is_match_over(w) {
+            new_match(w);
+            w.reset();
+        }
+        let system = self.system.get_or_insert_with(|| {
+            events.push(Event::RoundStart);
+            start_round(w)
+        });
+
+        for (entity_id, &i) in (1..=2).zip(intents.iter()) {
+            if apply_intent(w, entity_id, i) {
+                events.push(Event::Fired(player(entity_id as usize - 1)));
+            }
+        }
+        for s in system.iter_mut() {
+            s.step(w);
+        }
+        let tick = w.tick;
+        events.extend(w.feed.iter().filter(|(t, _)| *t == tick).map(|(_, text)| Event::Feed(text.clone())));
+        w.tick += 1;
+
+        if both_players_standing(w) && !w.win.is_time_up(w) {
+            return events;
+        }
+        for (idx, id) in [w.player1, w.player2].iter().enumerate() {
+            if !w.alive[*id] {
+                events.push(Event::Hit(player(idx)));
+            }
+        }
+        end_round(w);
+        self.system = None;
+        match w.win.outcome(w) {
+            Some(Outcome::Won(idx)) => events.push(Event::MatchOver { winner: Some(player(idx)) }),
+            Some(Outcome::Draw) => events.push(Event::MatchOver { winner: None }),
+            None => {
+                events.push(Event::RoundOver);
+                w.reset();
+            }
+        }
+        events
+    }
+
+    // The world as a bot would see it, to decide what to do next tick
+    pub fn frame(&self) -> Frame {
+        let state = if is_match_over(&self.w) { GameState::GameOver } else { GameState::Playing };
+        headless::frame(&self.w, state)
+    }
+
+    // Draw the board, as the game draws it each frame
+    pub fn render_into(&self, out: &mut impl Output) -> Result<(), GameError> {
+        out.render(self.w.view())
+    }
+}
+
+fn player(idx: usize) -> Player {
+    if idx == 0 {
+        Player::One
+    } else {
+        Player::Two
+    }
+}
@@ -0,0 +1,52 @@
+use std::io;
+
+use rs_sdk::SDKError;
+use thiserror::Error;
+
+// Everything run, replay, spectate etc can fail with. Terminal and Config
+// are the user's to fix, Internal is a bug.
+#[derive(Debug, Error)]
+pub enum GameError {
+    // No terminal, or it's too small for the board asked for
+    #[error("{0}")]
+    Terminal(String),
+
+    // Drawing, reading the keyboard, files and sockets
+    #[error("io: {0}")]
+    Io(#[from] io::Error),
+
+    // A replay, spectator stream or bot sent something we can't read
+    #[error("protocol error: {0}")]
+    Protocol(String),
+
+    // Bad config.toml, profile or stats file, or Options that can't be played
+    #[error("{0}")]
+    Config(String),
+
+    #[error("internal error: {0}")]
+    Internal(String),
+
+    // The keyboard or a bot server thread died, so the game can't go on.
+    // Says which and why.
+    #[error("{0}")]
+    Stopped(String),
+}
+
+// For the bot subcommand, which talks to the game through the SDK
+impl From<SDKError> for GameError {
+    fn from(e: SDKError) -> GameError {
+        match e {
+            SDKError::Io(e) => GameError::Io(e),
+            SDKError::Connect { ref source, .. } => {
+                GameError::Io(io::Error::new(source.kind(), e.to_string()))
+            }
+            SDKError::Disconnected => {
+                GameError::Io(io::Error::new(io::ErrorKind::UnexpectedEof, e.to_string()))
+            }
+            SDKError::Protocol { .. } | SDKError::HandshakeVersionMismatch { .. } => {
+                GameError::Protocol(e.to_string())
+            }
+            SDKError::BadToken => GameError::Config(e.to_string()),
+        }
+    }
+}
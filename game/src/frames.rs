@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+// State frames are built once a tick and handed to every bot's and
+// spectator's writer thread as the same Arc, see broadcast. A buffer is
+// filled again once all the writers are done with it, so in the steady
+// state a tick neither allocates nor copies the frame.
+
+// Frames waiting for a connection's writer. A bot further behind than this
+// misses frames, a spectator is dropped.
+pub const QUEUE: usize = 4;
+
+pub type Shared = Arc<Vec<u8>>;
+
+#[derive(Default)]
+pub struct FramePool {
+    bufs: Vec<Shared>,
+}
+
+impl FramePool {
+    // Fill a buffer no writer is holding any more, and share it
+    pub fn share(&mut self, fill: impl FnOnce(&mut Vec<u8>)) -> Shared {
+        let idx = match self.bufs.iter().position(|b| Arc::strong_count(b) == 1) {
+            Some(idx) => idx,
+            None => {
+                self.bufs.push(Arc::new(Vec::new()));
+                self.bufs.len() - 1
+            }
+        };
+        // the pool's is the only reference, and only we can clone it
+        fill(Arc::get_mut(&mut self.bufs[idx]).unwrap());
+        self.bufs[idx].clone()
+    }
+}
@@ -0,0 +1,51 @@
+// Entry points for the targets in fuzz/, which can't reach our parsers
+// otherwise. Not part of the API. None of these may panic, whatever data is.
+
+use crate::{server, World};
+
+// A command from a bot, see server::into_input_event
+pub fn command(data: &[u8]) {
+    if let Some(msg) = data.first_chunk::<8>() {
+        let _ = server::into_input_event(msg, 1);
+    }
+}
+
+// A state frame from a replay or a game we're spectating, see World::load_state.
+// If it loads it must also be drawable. Tried as the current protocol and
+// as protocol 5, which old replays have.
+pub fn state_frame(data: &[u8]) {
+    for protocol in [5, rs_sdk::PROTOCOL_VERSION] {
+        let mut w = World::new(80, 24);
+        if w.load_state(data, protocol).is_ok() {
+            let _ = w.view();
+        }
+    }
+}
+
+// A replay file, see replay::Reader. Every frame that loads must be drawable.
+pub fn replay(data: &[u8]) {
+    let mut r = match crate::replay::Reader::from_bytes(data.to_vec()) {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+    let mut w = World::new(80, 24);
+    let mut protocol = rs_sdk::PROTOCOL_VERSION;
+    while let Ok(Some((kind, payload))) = r.next_record() {
+        match kind {
+            crate::replay::RECORD_GAME if !payload.is_empty() => protocol = payload[0],
+            crate::replay::RECORD_CONFIG => {
+                let _ = crate::replay::read_config(&payload);
+            }
+            _ => {}
+        }
+    }
+    let mut r = match crate::replay::Reader::from_bytes(data.to_vec()) {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+    while let Ok(Some(frame)) = r.next_state() {
+        if w.load_state(&frame, protocol).is_ok() {
+            let _ = w.view();
+        }
+    }
+}
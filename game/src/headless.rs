@@ -0,0 +1,353 @@
+// Run bots inside this process against the real game systems. No sockets,
+// no terminal and no frame delay, so a whole match takes milliseconds.
+//
+//   let result = Match::new(80, 24).run(&mut MyBot::new(), &mut OtherBot::new());
+//   println!("{:?} won in {} ticks", result.winner, result.ticks);
+//
+// For training agents, run_logged also writes what happened each tick, see write_step.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use rs_sdk::{
+    Actions, Bot, Dir, EntityKind, EntityState, Event, Frame, GameState, Intent, Map, Player,
+    PlayerStatus,
+};
+
+use crate::{
+    apply_intent, both_players_standing, end_round, is_match_over, maps, start_round,
+    Density, MapKind, World, PLAYER_LIVES,
+};
+use crate::victory::Outcome;
+
+// A match ends in a draw if nobody has won after this many ticks
+const MAX_TICKS: u32 = 20_000;
+
+pub struct Match {
+    width: u32,
+    height: u32,
+    lives: u32,
+    max_ticks: u32,
+    map: MapKind,
+    density: Density,
+    seed: u64, // for the maps placed at random
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchResult {
+    pub winner: Option<Player>, // None for a draw, including running out of ticks
+    pub ticks: u32,
+    pub rounds: u32,
+    pub stats: [PlayerStats; 2], // player one, player two
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayerStats {
+    pub lives: u32,      // left at the end
+    pub shots: u32,      // missiles and rays actually fired
+    pub rounds_won: u32, // rounds where only the other player was hit
+}
+
+impl Match {
+    // Arena size is in characters, like a terminal
+    pub fn new(width: u32, height: u32) -> Match {
+        Match {
+            width,
+            height,
+            lives: PLAYER_LIVES,
+            max_ticks: MAX_TICKS,
+            map: MapKind::Bar,
+            density: Density::Normal,
+            seed: 0,
+        }
+    }
+
+    pub fn lives(mut self, n: u32) -> Match {
+        self.lives = n;
+        self
+    }
+
+    pub fn max_ticks(mut self, n: u32) -> Match {
+        self.max_ticks = n;
+        self
+    }
+
+    // Obstacle layout, the seed is only used by maps placed at random
+    pub fn map(mut self, kind: MapKind, seed: u64) -> Match {
+        self.map = kind;
+        self.seed = seed;
+        self
+    }
+
+    // How much of the board the obstacles cover
+    pub fn density(mut self, d: Density) -> Match {
+        self.density = d;
+        self
+    }
+
+    // Play one match to the end. Bots see the same frames and events as under
+    // run_bot, except there are no Waiting frames between rounds.
+    pub fn run(&self, p1: &mut impl Bot, p2: &mut impl Bot) -> MatchResult {
+        self.play(p1, p2, None).unwrap() // only writing the log can fail
+    }
+
+    // Like run, but write newline delimited JSON to `log`: first the map, then
+    // a (state, action, reward) line for every tick. See write_map and write_step.
+    pub fn run_logged(
+        &self,
+        p1: &mut impl Bot,
+        p2: &mut impl Bot,
+        log: &mut impl Write,
+    ) -> io::Result<MatchResult> {
+        self.play(p1, p2, Some(log))
+    }
+
+    fn play(
+        &self,
+        p1: &mut impl Bot,
+        p2: &mut impl Bot,
+        mut log: Option<&mut dyn Write>,
+    ) -> io::Result<MatchResult> {
+        let mut w = World::new(self.width, self.height);
+        w.p1_lives = self.lives;
+        w.p2_lives = self.lives;
+        w.layout = maps::layout(self.map, self.density, self.width, self.height, self.seed);
+        w.add_players();
+        w.add_obstacles();
+
+        let mut bots: [&mut dyn Bot; 2] = [p1, p2];
+        let map = map(&w);
+        for b in bots.iter_mut() {
+            b.on_connect(&map);
+        }
+        if let Some(log) = log.as_mut() {
+            write_map(log, &map)?;
+        }
+
+        let mut stats = [PlayerStats::default(); 2];
+        let mut rounds = 0;
+        while !is_match_over(&w) && w.tick < self.max_ticks {
+            rounds += 1;
+            let mut system = start_round(&mut w);
+            for b in bots.iter_mut() {
+                b.on_event(Event::RoundStart);
+            }
+
+            let tick_duration = Duration::from_millis(w.config.frame_gap_ms);
+            let mut actions = [Actions::new(tick_duration), Actions::new(tick_duration)];
+            let mut last_frame = None; // what the bots based their actions on
+            while both_players_standing(&w) && w.tick < self.max_ticks {
+                let intents = [actions[0].take(), actions[1].take()];
+                for (idx, &i) in intents.iter().enumerate() {
+                    if apply_intent(&mut w, idx as u8 + 1, i) {
+                        stats[idx].shots += 1;
+                    }
+                }
+
+                for s in system.iter_mut() {
+                    s.step(&mut w);
+                }
+
+                if let (Some(log), Some(state)) = (log.as_mut(), &last_frame) {
+                    let step = Step {
+                        round: rounds,
+                        state,
+                        actions: &intents,
+                        reward: [reward(&w, w.player1, w.player2), reward(&w, w.player2, w.player1)],
+                        done: !both_players_standing(&w),
+                    };
+                    write_step(log, &step)?;
+                }
+
+                let f = frame(&w, GameState::Playing);
+                for (b, a) in bots.iter_mut().zip(actions.iter_mut()) {
+                    a.set_tick(w.tick);
+                    b.on_frame(&f, a);
+                }
+                last_frame = Some(f);
+                w.tick += 1;
+            }
+
+            match (w.alive[w.player1], w.alive[w.player2]) {
+                (true, false) => stats[0].rounds_won += 1,
+                (false, true) => stats[1].rounds_won += 1,
+                _ => {}
+            }
+            end_round(&mut w);
+            if !is_match_over(&w) {
+                for b in bots.iter_mut() {
+                    b.on_event(Event::RoundOver);
+                }
+                w.reset();
+            }
+        }
+
+        let outcome = w.win.outcome(&w);
+        let winner = match outcome {
+            Some(Outcome::Won(0)) => Some(Player::One),
+            Some(Outcome::Won(_)) => Some(Player::Two),
+            _ => None, // a draw, or out of time
+        };
+        if outcome.is_some() {
+            for b in bots.iter_mut() {
+                b.on_event(Event::MatchOver);
+            }
+        }
+        stats[0].lives = w.p1_lives;
+        stats[1].lives = w.p2_lives;
+
+        Ok(MatchResult {
+            winner,
+            ticks: w.tick,
+            rounds,
+            stats,
+        })
+    }
+}
+
+// One tick of a logged match
+struct Step<'a> {
+    round: u32,
+    state: &'a Frame,         // the frame both bots saw
+    actions: &'a [Intent; 2], // what each bot did about it
+    reward: [i32; 2],         // for each player, 1 if they hit the other, -1 if they were hit
+    done: bool,               // this was the last tick of the round
+}
+
+// From `me`'s point of view, after a tick
+fn reward(w: &World, me: usize, opponent: usize) -> i32 {
+    let mut r = 0;
+    if !w.alive[opponent] {
+        r += 1;
+    }
+    if !w.alive[me] {
+        r -= 1;
+    }
+    r
+}
+
+// {"map":{"min_x":1,"min_y":2,"max_x":79,"max_y":22,"obstacles":[[10,5],...]}}
+// Bounds are as in Map::bounds. Obstacles don't move, so they're only here
+// and not in each step.
+fn write_map(out: &mut dyn Write, m: &Map) -> io::Result<()> {
+    let ((min_x, min_y), (max_x, max_y)) = m.bounds();
+    let mut obstacles: Vec<&(u32, u32)> = m.obstacles().collect();
+    obstacles.sort();
+    let obstacles: Vec<String> = obstacles.iter().map(|(x, y)| format!("[{},{}]", x, y)).collect();
+    writeln!(
+        out,
+        r#"{{"map":{{"min_x":{},"min_y":{},"max_x":{},"max_y":{},"obstacles":[{}]}}}}"#,
+        min_x,
+        min_y,
+        max_x,
+        max_y,
+        obstacles.join(",")
+    )
+}
+
+// {"tick":12,"round":1,
+//  "players":[{"lives":10,"energy":80,"weapon":"Missile","cooldown":0,"shield_drain":3,"points":0},{...}],
+//  "entities":[{"id":0,"kind":"Player","x":20,"y":11,"dir":"Up","speed":1,"shield":false,"tier":1},...],
+//  "actions":[{"dir":"Left","shield":false,"weapon":false,"wall":false,"fire":null},{...}],
+//  "reward":[0,0],"done":false}
+// Players and actions are player one then two. Names are as the SDK's Display.
+fn write_step(out: &mut dyn Write, s: &Step) -> io::Result<()> {
+    let f = s.state;
+    let players: Vec<String> = [Player::One, Player::Two]
+        .iter()
+        .map(|&p| {
+            let st = f.player_status(p);
+            format!(
+                r#"{{"lives":{},"energy":{},"weapon":"{}","cooldown":{},"shield_drain":{},"points":{}}}"#,
+                st.lives(),
+                st.energy(),
+                st.weapon().name(),
+                st.cooldown(),
+                st.shield_drain(),
+                st.points()
+            )
+        })
+        .collect();
+    let entities: Vec<String> = f
+        .entities()
+        .iter()
+        .filter(|e| e.kind() != EntityKind::Obstacle)
+        .map(|e| {
+            let (x, y) = e.pos();
+            format!(
+                r#"{{"id":{},"kind":"{}","x":{},"y":{},"dir":"{}","speed":{},"shield":{},"tier":{}}}"#,
+                e.id(),
+                e.kind(),
+                x,
+                y,
+                e.dir(),
+                e.speed(),
+                e.has_shield(),
+                e.tier()
+            )
+        })
+        .collect();
+    let opt_dir = |d: Option<Dir>| match d {
+        Some(d) => format!(r#""{}""#, d),
+        None => "null".to_string(),
+    };
+    let actions: Vec<String> = s
+        .actions
+        .iter()
+        .map(|i| {
+            format!(
+                r#"{{"dir":{},"shield":{},"weapon":{},"wall":{},"fire":{}}}"#,
+                opt_dir(i.dir),
+                i.toggle_shield,
+                i.change_weapon,
+                i.drop_wall,
+                opt_dir(i.fire)
+            )
+        })
+        .collect();
+    writeln!(
+        out,
+        r#"{{"tick":{},"round":{},"players":[{}],"entities":[{}],"actions":[{}],"reward":[{},{}],"done":{}}}"#,
+        f.tick(),
+        s.round,
+        players.join(","),
+        entities.join(","),
+        actions.join(","),
+        s.reward[0],
+        s.reward[1],
+        s.done
+    )
+}
+
+// What a bot would get from the handshake. Bounds must match is_on_board, see map_info.
+pub(crate) fn map(w: &World) -> Map {
+    let obstacles: Vec<(u32, u32)> = w.obstacles().iter().map(|p| (p.x, p.y)).collect();
+    Map::new((1, 2), (w.width - 1, w.height - 2), &obstacles)
+}
+
+// What a bot would get over the socket, see entity_state
+pub(crate) fn frame(w: &World, state: GameState) -> Frame {
+    let points = w.points();
+    let status = |idx: usize, id: usize, lives: u32| {
+        PlayerStatus::new(
+            lives as u8,
+            w.energy[id] as u16,
+            w.active_weapon[id].unwrap(),
+        )
+        .with_cooldown(w.cooldown(id))
+        .with_shield_drain(w.config.energy.shield.min(u8::MAX as u32) as u8)
+        .with_points(points[idx].min(u8::MAX as u32) as u8)
+    };
+    let players = [status(0, w.player1, w.p1_lives), status(1, w.player2, w.p2_lives)];
+    // as many as a frame from the game would have, see World::entity_state
+    let entities = (0..w.name.len().min(u16::MAX as usize))
+        .map(|id| {
+            let p = w.position[id][0];
+            let (speed, dir) = w.velocity[id];
+            EntityState::new(id as u16, w.kind[id], (p.x, p.y), dir, speed)
+                .with_shield(w.shield[id])
+                .with_tier(w.tier[id])
+        })
+        .collect();
+    Frame::new(w.tick, state, players, entities)
+}
@@ -0,0 +1,44 @@
+// Where the players have been lately, shaded behind the board. F3 in debug
+// mode (`--debug`), h when watching a replay. For map designers: squares
+// nobody goes near, and ones everybody fights over, show up as you play.
+
+use std::collections::HashMap;
+
+use crossterm::style::Color;
+
+use crate::render::HeatCell;
+use crate::World;
+
+// Ticks of positions kept, a minute at the default speed. Older visits fade
+// out as they drop off the end.
+pub const TRAIL_TICKS: usize = 1200;
+
+// Background shades, least visited to most: the xterm 256 color grey ramp,
+// skipping the darkest few, which don't show on most terminals
+const SHADES: [u8; 8] = [235, 237, 239, 241, 243, 245, 247, 249];
+
+// Add where the players are now to the trail, dropping the oldest
+pub fn remember(w: &mut World) {
+    if w.trail.len() == TRAIL_TICKS {
+        w.trail.pop_front();
+    }
+    w.trail.push_back([w.position[w.player1][0], w.position[w.player2][0]]);
+}
+
+// Every square a player was on in the trail, shaded by how often compared
+// to the busiest square
+pub fn heat(w: &World) -> Vec<HeatCell> {
+    let mut visits: HashMap<(u32, u32), u32> = HashMap::new();
+    for p in w.trail.iter().flatten().filter(|p| !p.invalid) {
+        *visits.entry((p.x, p.y)).or_default() += 1;
+    }
+    let busiest = visits.values().copied().max().unwrap_or(1);
+    visits
+        .into_iter()
+        .map(|((x, y), n)| HeatCell {
+            x: x as u16,
+            y: y as u16,
+            color: Color::AnsiValue(SHADES[((n - 1) as usize * SHADES.len() / busiest as usize).min(SHADES.len() - 1)]),
+        })
+        .collect()
+}
@@ -1,50 +1,267 @@
-use std::error::Error;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
 use std::time::Duration;
-use std::sync::{self, Arc};
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 
 use crossterm::event;
 use crossterm::event::KeyCode;
 
-use rs_sdk::Dir;
+use rs_sdk::{Dir, Intent};
 
-use log::error;
+use crate::config::Keys;
+use crate::inspector;
+use crate::GameError;
 
+use log::{debug, error, warn};
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+// Events the game takes from each source a tick, see Inbox. A key press is
+// two or three events, and two people can share the keyboard.
+const PER_TICK: usize = 8;
+// Events a source can have waiting. Past this the oldest are dropped, a bot
+// sending faster than the game plays loses its stale commands.
+const MAX_QUEUED: usize = 64;
+
+
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub enum InputEvent {
     Move { entity_id: u8, dir: Dir },
     Fire { entity_id: u8, dir: Dir },
     ToggleShield { entity_id: u8 },
     ChangeWeapon { entity_id: u8 },
+    DropWall { entity_id: u8 },
+    Ready { entity_id: u8 },   // bot is ready for the round to start
+    Rematch { entity_id: u8 }, // bot wants another match
+    KeyPress,                  // any key on the keyboard, as well as it's mapped event
+    ToggleMute,
+    ToggleInspector,        // debug mode only, see inspector.rs
+    ScrollInspector(isize), // lines, negative is up
+    ToggleHeatmap,          // debug mode only, see heatmap.rs
+    SaveSnapshots,          // debug mode only, see snapshots.rs
+    Answer(bool),           // y or n, for the save replay prompt
+    EndTurn,                // Enter, in hot-seat play, see turns.rs
+    Menu(char),             // r, m or q, for the menu after a match
     Quit,
+    Stopped(String), // the keyboard or a bot server thread died, and why. See spawn_watched.
+}
+
+// Where an InputEvent came from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Source {
+    Keyboard,
+    Bot(u8), // the player it's playing
 }
 
-pub fn start(ch: sync::mpsc::Sender<InputEvent>, frame_gap_ms: u64) -> (thread::JoinHandle<()>, Arc<AtomicBool>) {
+// Keys understood while watching a replay
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PlaybackEvent {
+    TogglePause,
+    Step, // advance one frame, when paused
+    Speed(u64),
+    ToggleHeatmap,
+    Quit,
+}
+
+// asking is Inbox::asking, for when y and n answer a question
+pub fn start(
+    ch: Sender<(Source, InputEvent)>,
+    frame_gap_ms: u64,
+    keys: Keys,
+    asking: Arc<AtomicBool>,
+) -> (thread::JoinHandle<()>, Arc<AtomicBool>) {
     let stop = Arc::new(AtomicBool::new(false));
     let thread_stop = stop.clone();
-    let h = thread::spawn(move || {
+    let h = spawn_watched("Keyboard input".to_string(), Source::Keyboard, ch.clone(), move || {
         let poll_dur = Duration::from_millis(frame_gap_ms / 2);
         while !thread_stop.load(Ordering::SeqCst) {
-            match events(poll_dur) {
-                Ok(v) => v.into_iter().for_each(|ev| ch.send(ev).unwrap()),
-                Err(e) => {
-                    error!("Input event err: {}", e);
-                    return;
+            for ev in events(poll_dur, &keys, asking.load(Ordering::SeqCst))? {
+                if ch.send((Source::Keyboard, ev)).is_err() {
+                    return Ok(()); // game is exiting
                 }
-            };
-
+            }
         }
+        Ok(())
     });
     (h, stop)
 }
 
-pub fn wait_for_keypress() {
-    let _ = event::read().unwrap();
+// Run f on a thread of its own. If it fails or panics the game is sent
+// InputEvent::Stopped, so it can say so and exit, rather than carry on
+// without the keyboard or a bot.
+pub fn spawn_watched<F>(name: String, source: Source, ch: Sender<(Source, InputEvent)>, f: F) -> thread::JoinHandle<()>
+where
+    F: FnOnce() -> Result<(), GameError> + Send + 'static,
+{
+    thread::spawn(move || {
+        let why = match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e.to_string(),
+            Err(p) => format!("panicked: {}", panic_message(&*p)),
+        };
+        error!("{} stopped: {}", name, why);
+        // only fails if the game is exiting anyway
+        let _ = ch.send((source, InputEvent::Stopped(format!("{} stopped: {}", name, why))));
+    })
+}
+
+// The game's end of the input channel. Each tick it takes up to PER_TICK
+// events from each source, taking turns, so a bot flooding the channel
+// can't hold up the keyboard or the other bot. The rest wait their turn.
+pub struct Inbox {
+    rx: Receiver<(Source, InputEvent)>,
+    queues: Vec<(Source, VecDeque<InputEvent>)>, // in the order sources first sent something
+    first: usize,                                // queue that goes first this tick, it moves round
+    dropped: usize,                              // over MAX_QUEUED, since we last said
+    asking: Arc<AtomicBool>,                     // a yes or no question is up, see ask
+}
+
+impl Inbox {
+    pub fn new(rx: Receiver<(Source, InputEvent)>) -> Inbox {
+        Inbox {
+            rx,
+            queues: Vec::new(),
+            first: 0,
+            dropped: 0,
+            asking: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // For the keyboard, which only sends Answer while a question is up
+    pub fn asking(&self) -> Arc<AtomicBool> {
+        self.asking.clone()
+    }
+
+    // Whether a yes or no question is on the screen
+    pub fn ask(&self, is_asking: bool) {
+        self.asking.store(is_asking, Ordering::SeqCst);
+    }
+
+    // This tick's events
+    pub fn tick(&mut self) -> Vec<InputEvent> {
+        self.fill();
+        let n = self.queues.len();
+        let mut out = Vec::new();
+        for _ in 0..PER_TICK {
+            for i in 0..n {
+                if let Some(ie) = self.queues[(self.first + i) % n].1.pop_front() {
+                    out.push(ie);
+                }
+            }
+        }
+        if n > 0 {
+            self.first = (self.first + 1) % n;
+        }
+        out
+    }
+
+    // Everything waiting, for clearing out what was pressed during a banner
+    pub fn drain(&mut self) -> Vec<InputEvent> {
+        self.fill();
+        self.queues.iter_mut().flat_map(|(_, q)| q.drain(..)).collect()
+    }
+
+    // The next event from anyone, waiting up to timeout for one. For between
+    // rounds, when there are no ticks to share out.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<InputEvent, RecvTimeoutError> {
+        self.fill();
+        if let Some(ie) = self.queues.iter_mut().find_map(|(_, q)| q.pop_front()) {
+            return Ok(ie);
+        }
+        self.rx.recv_timeout(timeout).map(|(_, ie)| ie)
+    }
+
+    // Queue what's arrived
+    fn fill(&mut self) {
+        for (source, ie) in self.rx.try_iter() {
+            let idx = match self.queues.iter().position(|(s, _)| *s == source) {
+                Some(idx) => idx,
+                None => {
+                    self.queues.push((source, VecDeque::new()));
+                    self.queues.len() - 1
+                }
+            };
+            let q = &mut self.queues[idx].1;
+            q.push_back(ie);
+            if q.len() > MAX_QUEUED {
+                q.pop_front();
+                self.dropped += 1;
+            }
+        }
+        if self.dropped > 0 {
+            warn!("Input arriving faster than the game plays, dropped {} events", self.dropped);
+            self.dropped = 0;
+        }
+    }
+}
+
+// What each player asked for in a tick's events. Movement is where they want
+// to be going now, so only their last Move counts. Fire, shield, weapon and
+// wall are one of each a tick; more is a key repeating or a bot sending twice,
+// and only the last fire is kept. Other events are left to the caller.
+pub fn intents(events: &[InputEvent]) -> [Intent; 2] {
+    let mut out = [Intent::default(); 2];
+    let mut dropped = 0;
+    for ie in events {
+        match *ie {
+            InputEvent::Move { entity_id, dir } => player(&mut out, entity_id).dir = Some(dir),
+            InputEvent::Fire { entity_id, dir } => {
+                let i = player(&mut out, entity_id);
+                if i.fire.replace(dir).is_some() {
+                    dropped += 1;
+                }
+            }
+            InputEvent::ToggleShield { entity_id } => {
+                let i = player(&mut out, entity_id);
+                if i.toggle_shield {
+                    dropped += 1;
+                }
+                i.toggle_shield = true;
+            }
+            InputEvent::ChangeWeapon { entity_id } => {
+                let i = player(&mut out, entity_id);
+                if i.change_weapon {
+                    dropped += 1;
+                }
+                i.change_weapon = true;
+            }
+            InputEvent::DropWall { entity_id } => {
+                let i = player(&mut out, entity_id);
+                if i.drop_wall {
+                    dropped += 1;
+                }
+                i.drop_wall = true;
+            }
+            _ => {}
+        }
+    }
+    if dropped > 0 {
+        debug!("Over one fire, shield, weapon change or wall a tick, dropped {}", dropped);
+    }
+    out
+}
+
+fn player(intents: &mut [Intent; 2], entity_id: u8) -> &mut Intent {
+    match entity_id {
+        1 | 2 => &mut intents[entity_id as usize - 1],
+        _ => panic!("entity_id not 1 or 2, shouldn't happen"),
+    }
+}
+
+fn panic_message(p: &(dyn Any + Send)) -> &str {
+    if let Some(s) = p.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = p.downcast_ref::<String>() {
+        s
+    } else {
+        "unknown error"
+    }
 }
 
-pub fn events(poll_dur: Duration) -> Result<Vec<InputEvent>, Box<dyn Error>> {
+// is_asking when there's a yes or no question on the screen, see Inbox::ask
+pub fn events(poll_dur: Duration, keys: &Keys, is_asking: bool) -> Result<Vec<InputEvent>, GameError> {
     let mut ev = Vec::new();
     // Making poll_dur == 0 maxes out this thread's CPU, so
     // read keypresses for up to half the gap between frames.
@@ -55,109 +272,193 @@ pub fn events(poll_dur: Duration) -> Result<Vec<InputEvent>, Box<dyn Error>> {
                 continue;
             }
         };
-        let alt = e.modifiers.contains(event::KeyModifiers::ALT);
+        // quit
+        if e.code == KeyCode::Esc {
+            // make the main loop and hence the program exit
+            ev.push(InputEvent::Quit);
+            break;
+        }
+        // for "press any key" banners
+        ev.push(InputEvent::KeyPress);
+
+        // the game ignores these unless it's in debug mode
+        match e.code {
+            KeyCode::F(2) => ev.push(InputEvent::ToggleInspector),
+            KeyCode::F(3) => ev.push(InputEvent::ToggleHeatmap),
+            KeyCode::F(4) => ev.push(InputEvent::SaveSnapshots),
+            KeyCode::PageUp => ev.push(InputEvent::ScrollInspector(-inspector::PAGE)),
+            KeyCode::PageDown => ev.push(InputEvent::ScrollInspector(inspector::PAGE)),
+            _ => {}
+        }
+        // and this unless it's taking turns
+        if e.code == KeyCode::Enter {
+            ev.push(InputEvent::EndTurn);
+        }
+        // and these unless it's asking
+        match e.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') if is_asking => ev.push(InputEvent::Answer(true)),
+            KeyCode::Char('n') | KeyCode::Char('N') if is_asking => ev.push(InputEvent::Answer(false)),
+            KeyCode::Char(c @ ('r' | 'm' | 'q' | 'R' | 'M' | 'Q')) => ev.push(InputEvent::Menu(c.to_ascii_lowercase())),
+            _ => {}
+        }
+
+        if let Some(ie) = keys.event(&e) {
+            ev.push(ie);
+        }
+    }
+    Ok(ev)
+}
+
+pub fn playback_events(poll_dur: Duration) -> Result<Vec<PlaybackEvent>, GameError> {
+    let mut ev = Vec::new();
+    while event::poll(poll_dur)? {
+        let e = match event::read()? {
+            event::Event::Key(e) => e,
+            _ => {
+                continue;
+            }
+        };
         match e.code {
-            // quit
-            KeyCode::Esc => {
-                // make the main loop and hence the program exit
-                ev.push(InputEvent::Quit);
+            KeyCode::Esc | KeyCode::Char('q') => {
+                ev.push(PlaybackEvent::Quit);
                 break;
             }
+            KeyCode::Char(' ') => ev.push(PlaybackEvent::TogglePause),
+            KeyCode::Char('s') | KeyCode::Right => ev.push(PlaybackEvent::Step),
+            KeyCode::Char('1') => ev.push(PlaybackEvent::Speed(1)),
+            KeyCode::Char('2') => ev.push(PlaybackEvent::Speed(2)),
+            KeyCode::Char('4') => ev.push(PlaybackEvent::Speed(4)),
+            KeyCode::Char('h') => ev.push(PlaybackEvent::ToggleHeatmap),
+            _ => (),
+        };
+    }
+    Ok(ev)
+}
 
-            // player one keys
-            KeyCode::Char('w') => ev.push(InputEvent::Move {
-                entity_id: 1,
-                dir: Dir::Up,
-            }),
-            KeyCode::Char('W') => ev.push(InputEvent::Fire {
-                entity_id: 1,
-                dir: Dir::Up,
-            }),
-            KeyCode::Char('s') => ev.push(InputEvent::Move {
-                entity_id: 1,
-                dir: Dir::Down,
-            }),
-            KeyCode::Char('S') => ev.push(InputEvent::Fire {
-                entity_id: 1,
-                dir: Dir::Down,
-            }),
-            KeyCode::Char('a') => ev.push(InputEvent::Move {
-                entity_id: 1,
-                dir: Dir::Left,
-            }),
-            KeyCode::Char('A') => ev.push(InputEvent::Fire {
-                entity_id: 1,
-                dir: Dir::Left,
-            }),
-            KeyCode::Char('d') => ev.push(InputEvent::Move {
-                entity_id: 1,
-                dir: Dir::Right,
-            }),
-            KeyCode::Char('D') => ev.push(InputEvent::Fire {
-                entity_id: 1,
-                dir: Dir::Right,
-            }),
-            KeyCode::Char('e') => ev.push(InputEvent::ToggleShield { entity_id: 1 }),
-            KeyCode::Char('q') => ev.push(InputEvent::ChangeWeapon { entity_id: 1 }),
-
-            // player two keys
-            KeyCode::Up => {
-                if alt {
-                    ev.push(InputEvent::Fire {
-                        entity_id: 2,
-                        dir: Dir::Up,
-                    });
-                } else {
-                    ev.push(InputEvent::Move {
-                        entity_id: 2,
-                        dir: Dir::Up,
-                    });
-                }
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use proptest::prelude::*;
+
+    use rs_sdk::Dir;
+
+    use super::{intents, Inbox, InputEvent, Source, MAX_QUEUED, PER_TICK};
+
+    // Numbered, to check the order they come out in
+    fn ev(n: usize) -> InputEvent {
+        InputEvent::ScrollInspector(n as isize)
+    }
+
+    fn num(ie: &InputEvent) -> usize {
+        match ie {
+            InputEvent::ScrollInspector(n) => *n as usize,
+            _ => panic!("not from ev: {:?}", ie),
+        }
+    }
+
+    fn dir() -> impl Strategy<Value = Dir> {
+        prop_oneof![Just(Dir::Up), Just(Dir::Down), Just(Dir::Left), Just(Dir::Right)]
+    }
+
+    // A player's move, fire, shield, weapon change or wall
+    fn command() -> impl Strategy<Value = InputEvent> {
+        (1u8..=2, dir(), 0..5).prop_map(|(entity_id, dir, kind)| match kind {
+            0 => InputEvent::Move { entity_id, dir },
+            1 => InputEvent::Fire { entity_id, dir },
+            2 => InputEvent::ToggleShield { entity_id },
+            3 => InputEvent::DropWall { entity_id },
+            _ => InputEvent::ChangeWeapon { entity_id },
+        })
+    }
+
+    fn bot() -> impl Strategy<Value = Source> {
+        prop_oneof![Just(Source::Bot(1)), Just(Source::Bot(2))]
+    }
+
+    proptest! {
+        #[test]
+        fn flooding_bot_does_not_hold_up_the_keyboard(flood in 0usize..2000, b in bot()) {
+            let (tx, rx) = mpsc::channel();
+            let mut inbox = Inbox::new(rx);
+            for n in 0..flood {
+                tx.send((b, ev(n))).unwrap();
             }
-            KeyCode::Down => {
-                if alt {
-                    ev.push(InputEvent::Fire {
-                        entity_id: 2,
-                        dir: Dir::Down,
-                    });
-                } else {
-                    ev.push(InputEvent::Move {
-                        entity_id: 2,
-                        dir: Dir::Down,
-                    });
-                }
+            tx.send((Source::Keyboard, InputEvent::Quit)).unwrap();
+            let got = inbox.tick();
+            prop_assert!(got.contains(&InputEvent::Quit));
+            prop_assert_eq!(got.len(), 1 + flood.min(PER_TICK));
+        }
+
+        #[test]
+        fn sources_take_turns(flood1 in PER_TICK..500, flood2 in PER_TICK..500) {
+            let (tx, rx) = mpsc::channel();
+            let mut inbox = Inbox::new(rx);
+            for n in 0..flood1 {
+                tx.send((Source::Bot(1), ev(n))).unwrap();
             }
-            KeyCode::Left => {
-                if alt {
-                    ev.push(InputEvent::Fire {
-                        entity_id: 2,
-                        dir: Dir::Left,
-                    });
-                } else {
-                    ev.push(InputEvent::Move {
-                        entity_id: 2,
-                        dir: Dir::Left,
-                    });
-                }
+            for _ in 0..flood2 {
+                tx.send((Source::Bot(2), InputEvent::Ready { entity_id: 2 })).unwrap();
             }
-            KeyCode::Right => {
-                if alt {
-                    ev.push(InputEvent::Fire {
-                        entity_id: 2,
-                        dir: Dir::Right,
-                    });
-                } else {
-                    ev.push(InputEvent::Move {
-                        entity_id: 2,
-                        dir: Dir::Right,
-                    });
-                }
+            let got = inbox.tick();
+            prop_assert_eq!(got.len(), 2 * PER_TICK);
+            for pair in got.chunks(2) {
+                let from_bot2 = pair.iter().filter(|ie| matches!(ie, InputEvent::Ready { .. })).count();
+                prop_assert_eq!(from_bot2, 1, "not interleaved: {:?}", got);
             }
-            KeyCode::Char('.') => ev.push(InputEvent::ToggleShield { entity_id: 2 }),
-            KeyCode::Char(',') => ev.push(InputEvent::ChangeWeapon { entity_id: 2 }),
+        }
 
-            _ => (),
-        };
+        #[test]
+        fn a_flood_comes_out_in_order_over_later_ticks(flood in 0usize..MAX_QUEUED, b in bot()) {
+            let (tx, rx) = mpsc::channel();
+            let mut inbox = Inbox::new(rx);
+            for n in 0..flood {
+                tx.send((b, ev(n))).unwrap();
+            }
+            let mut got = Vec::new();
+            for _ in 0..flood.div_ceil(PER_TICK) {
+                let tick = inbox.tick();
+                prop_assert!(!tick.is_empty() && tick.len() <= PER_TICK);
+                got.extend(tick.iter().map(num));
+            }
+            prop_assert_eq!(got, (0..flood).collect::<Vec<_>>());
+            prop_assert!(inbox.tick().is_empty());
+        }
+
+        #[test]
+        fn past_max_queued_the_oldest_go(over in 1usize..500, b in bot()) {
+            let (tx, rx) = mpsc::channel();
+            let mut inbox = Inbox::new(rx);
+            let flood = MAX_QUEUED + over;
+            for n in 0..flood {
+                tx.send((b, ev(n))).unwrap();
+            }
+            let got: Vec<usize> = inbox.drain().iter().map(num).collect();
+            prop_assert_eq!(got, (over..flood).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn last_move_and_fire_win(events in prop::collection::vec(command(), 0..50)) {
+            let got = intents(&events);
+            for (idx, i) in got.iter().enumerate() {
+                let entity_id = idx as u8 + 1;
+                let last_move = events.iter().rev().find_map(|ie| match ie {
+                    InputEvent::Move { entity_id: id, dir } if *id == entity_id => Some(*dir),
+                    _ => None,
+                });
+                let last_fire = events.iter().rev().find_map(|ie| match ie {
+                    InputEvent::Fire { entity_id: id, dir } if *id == entity_id => Some(*dir),
+                    _ => None,
+                });
+                prop_assert_eq!(i.dir, last_move);
+                prop_assert_eq!(i.fire, last_fire);
+                let shield = InputEvent::ToggleShield { entity_id };
+                let weapon = InputEvent::ChangeWeapon { entity_id };
+                let wall = InputEvent::DropWall { entity_id };
+                prop_assert_eq!(i.toggle_shield, events.contains(&shield));
+                prop_assert_eq!(i.change_weapon, events.contains(&weapon));
+                prop_assert_eq!(i.drop_wall, events.contains(&wall));
+            }
+        }
     }
-    Ok(ev)
 }
@@ -0,0 +1,71 @@
+// The World inspector, for debug mode (`--debug`). F2 shows every alive
+// entity over the board, updated each tick, PgUp and PgDn scroll it. For
+// diagnosing things like ghost missiles or stuck explosions as they happen.
+// Above the entities, how long each system and rendering take, see timing.rs.
+
+use crate::render::Inspector;
+use crate::{alive_entities, Lifetime, World};
+
+// Lines PgUp and PgDn move
+pub const PAGE: isize = 10;
+
+// Positions listed per entity, an explosion has dozens
+const MAX_POSITIONS: usize = 4;
+
+pub fn inspect(w: &World, scroll: usize) -> Inspector {
+    let mut lines = timings(w);
+    lines.extend(alive_entities(w).map(|id| line(w, id)));
+    Inspector {
+        title: format!(
+            "Tick {}. {} of {} entities alive. PgUp PgDn scroll, F2 closes.",
+            w.tick,
+            alive_entities(w).count(),
+            w.name.len()
+        ),
+        scroll: scroll.min(lines.len().saturating_sub(1)),
+        lines,
+    }
+}
+
+// e.g. "Per tick  mean / worst  move 12µs / 40µs  lifetime 2µs / 9µs ..."
+fn timings(w: &World) -> Vec<String> {
+    let stages = w.timings.stages();
+    if stages.is_empty() {
+        return Vec::new();
+    }
+    let each: Vec<String> = stages
+        .iter()
+        .map(|s| format!("{} {:.0?} / {:.0?}", s.name, s.mean, s.worst))
+        .collect();
+    vec![format!("Per tick  mean / worst  {}", each.join("  "))]
+}
+
+// e.g. "  7 Missile 7    Missile  vel 2 Left  life 12    nrg 0   tier 1 at 30,12 31,12"
+// and " on 1" if it's attached to entity 1
+fn line(w: &World, id: usize) -> String {
+    let (speed, dir) = w.velocity[id];
+    let life = match w.lifetime[id] {
+        Lifetime::Solid => "solid".to_string(),
+        Lifetime::Permanent => "permanent".to_string(),
+        Lifetime::Temporary(n) => n.to_string(),
+    };
+    let positions = &w.position[id];
+    let mut at: Vec<String> = positions.iter().take(MAX_POSITIONS).map(|p| p.to_string()).collect();
+    if positions.len() > MAX_POSITIONS {
+        at.push(format!("and {} more", positions.len() - MAX_POSITIONS));
+    }
+    let on = w.parent[id].map(|(p, _)| format!(" on {}", p)).unwrap_or_default();
+    format!(
+        "{:>3} {:<12} {:<9} vel {} {:<5} life {:<9} nrg {:<3} tier {} at {}{}",
+        id,
+        w.name[id],
+        w.kind[id].to_string(),
+        speed,
+        dir.to_string(),
+        life,
+        w.energy[id],
+        w.tier[id],
+        at.join(" "),
+        on
+    )
+}
@@ -0,0 +1,108 @@
+// Small labels next to entities on the board: every entity's id in debug
+// mode (`--debug`), to match them up with the inspector, and the players'
+// names with `labels` on, for spectators telling who is who. A label goes to
+// the right of its entity if there's room, else the left, above or below,
+// never over the border, something on the board or another label. One with
+// nowhere to go isn't shown, it'll likely find room next tick.
+
+use std::collections::HashSet;
+
+use crossterm::style::Color;
+
+use crate::render::{Cell, Label};
+
+// What to label: where the entity is, the text and its color. Earlier ones
+// get first pick of where to go, so put the players first.
+pub struct Wanted {
+    pub x: u16,
+    pub y: u16,
+    pub text: String,
+    pub color: Color,
+}
+
+// Board is width by height, border included, with the status line above it
+// and the score below, as in World::in_bounds
+pub fn place(wanted: Vec<Wanted>, cells: &[Cell], width: u16, height: u16) -> Vec<Label> {
+    let mut taken: HashSet<(u16, u16)> = cells.iter().map(|c| (c.x, c.y)).collect();
+    let mut labels = Vec::new();
+    for w in wanted {
+        let len = w.text.chars().count() as u16;
+        let (x, y) = (i32::from(w.x), i32::from(w.y));
+        let n = i32::from(len);
+        let spots = [(x + 1, y), (x - n, y), (x - n / 2, y - 1), (x - n / 2, y + 1)];
+        let fits = |&(sx, sy): &(i32, i32)| {
+            sx >= 1
+                && sx + n < i32::from(width)
+                && sy >= 2
+                && sy + 2 < i32::from(height)
+                && (sx..sx + n).all(|cx| !taken.contains(&(cx as u16, sy as u16)))
+        };
+        if let Some(&(sx, sy)) = spots.iter().find(|s| fits(s)) {
+            let (sx, sy) = (sx as u16, sy as u16);
+            taken.extend((sx..sx + len).map(|cx| (cx, sy)));
+            labels.push(Label {
+                x: sx,
+                y: sy,
+                text: w.text,
+                color: w.color,
+            });
+        }
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: u16, y: u16, text: &str) -> Wanted {
+        Wanted {
+            x,
+            y,
+            text: text.to_string(),
+            color: Color::Reset,
+        }
+    }
+
+    fn cell(x: u16, y: u16) -> Cell {
+        Cell {
+            x,
+            y,
+            glyph: 'A',
+            color: Color::Reset,
+            bold: false,
+        }
+    }
+
+    fn spots(labels: &[Label]) -> Vec<(u16, u16)> {
+        labels.iter().map(|l| (l.x, l.y)).collect()
+    }
+
+    #[test]
+    fn a_label_goes_right_if_it_can() {
+        let labels = place(vec![at(10, 10, "Alice")], &[cell(10, 10)], 80, 24);
+        assert_eq!(spots(&labels), [(11, 10)]);
+    }
+
+    #[test]
+    fn a_label_stays_off_the_right_border() {
+        let labels = place(vec![at(76, 10, "Alice")], &[cell(76, 10)], 80, 24);
+        assert_eq!(spots(&labels), [(71, 10)]);
+    }
+
+    #[test]
+    fn labels_move_out_of_each_others_way() {
+        // two on one square, e.g. a player picking something up. The first
+        // has the right, so the second goes left.
+        let labels = place(vec![at(10, 10, "12"), at(10, 10, "3")], &[cell(10, 10)], 80, 24);
+        assert_eq!(spots(&labels), [(11, 10), (9, 10)]);
+    }
+
+    #[test]
+    fn a_label_with_nowhere_to_go_is_dropped() {
+        // boxed into the top left corner by a wall of cells
+        let cells: Vec<Cell> = (1..6).flat_map(|x| (2..5).map(move |y| cell(x, y))).collect();
+        let labels = place(vec![at(1, 2, "Alice")], &cells, 80, 24);
+        assert!(labels.is_empty());
+    }
+}
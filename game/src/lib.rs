@@ -1,17 +1,72 @@
-use log::debug;
-use rs_sdk::Dir;
-use simplelog::{Config, LevelFilter, WriteLogger};
-use std::error::Error;
-use std::fs::File;
-use std::sync;
+use bot::hunter::Hunter;
+use bot::skill::{Difficulty, Skill};
+use crossterm::style::Color;
+use log::{debug, error, info, warn};
+use rs_sdk::transport::Endpoint;
+use rs_sdk::{
+    explosion_radius, run_bot_with, Connector, Dir, EntityKind, GameState, Intent, Player,
+    Weapon, MAX_TIER, PICKUP_BEAM, PUSH_DISTANCE, SUPPLY_ENERGY, SUPPLY_LIFE, WALL_LENGTH,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{self, Arc};
 use std::thread;
-use std::time::Duration;
-
-#[macro_use]
-extern crate lazy_static;
+use std::time::{Duration, Instant};
 
+mod analyze;
+mod commands;
+use commands::{Commands, Spawn};
+mod config;
+pub use config::{GameConfig, LineStyle, Mode, Palette, Span, CONFIG_FILE};
 mod console;
+mod daily;
+pub mod embed;
+mod error;
+pub use error::GameError;
+mod frames;
+pub mod headless;
+mod heatmap;
+mod inspector;
+mod labels;
+mod maps;
+pub use maps::{Density, MapKind};
+mod metrics;
+mod momentum;
+mod no_display;
+mod options;
+pub use options::{Backend, Latency, Options};
+mod playback;
+mod profile;
+mod projectile;
+use projectile::{add_projectile, spawn_projectile};
+pub use profile::Profile;
+mod render;
+use render::clock_time;
+pub use render::{Cell, Clock, FeedLine, HeatCell, Inspector, Label, PlayerView, Turn, View, WeaponWheel};
+mod replay;
 pub mod server;
+mod session;
+mod snapshots;
+mod sound;
+pub use sound::Cue;
+mod soak;
+pub use session::{start_logging, BOT_LOG_FILE, LOG_FILE, LOG_FILE_ENV, LOG_LEVEL_ENV, STATS_FILE_ENV};
+mod spectators;
+#[doc(hidden)]
+pub mod bench;
+#[doc(hidden)]
+pub mod fuzz;
+mod stats;
+mod ticker;
+mod timing;
+mod tuning;
+mod turns;
+mod victory;
+use victory::{Outcome, WinCondition};
 
 mod pos;
 use pos::Pos;
@@ -19,45 +74,73 @@ use pos::Pos;
 mod input;
 use input::InputEvent;
 
-mod weapon;
-use weapon::Weapon;
-
+// defaults, see GameConfig
 const PLAYER_LIVES: u32 = 10;
-const MAX_ENERGY: u32 = 100;
 const LIFETIME_RAY: u32 = 10;
-const EXPLODE_DURATION: u32 = 2;
 const MISSILE_MIN_RANGE: u32 = 8; // missiles must go at least this far before exploding
-const ENERGY_MISSILE: u32 = 3;
-const ENERGY_RAY: u32 = 25;
-const ENERGY_SHIELD: u32 = 3; // deduct this every ENERGY_EVERY
-const ENERGY_EVERY: u32 = 5; // new energy every x turns
+const PICKUP_EVERY: u32 = 300; // ticks, 15s at the default speed
+const BEAM_CHANCE: u32 = 10; // percent of pickups which are a beam
+const WALL_LIFETIME: u32 = 100; // ticks a player's wall stands, 5s at the default speed
+const SUPPLY_EVERY: u32 = 900; // ticks, 45s at the default speed
+const SUPPLY_WARNING: u32 = 40; // ticks a supply drop's marker shows before it lands, 2s at the default speed
+const TELEPORT_GRACE: u32 = 10; // ticks something can't be hit after a teleporter, 0.5s at the default speed
+const VICTORY_POINTS: u32 = 5; // hits to win, in points mode
+const VICTORY_SECONDS: u32 = 120; // match length, in timed mode
+// energy costs and missile speed are in rs_sdk, bots need them
+
+const EXPLODE_DURATION: u32 = 2;
+const PICKUP_TRIES: u32 = 20; // random squares to try before giving up on a pickup this time
+const FEED_LINES: usize = 4; // kill feed entries shown at once
+const FEED_FRESH_MS: u64 = 2000; // kill feed entries are dimmed after this
+const FEED_MS: u64 = 4000; // and gone after this
+const WHEEL_FRESH_MS: u64 = 600; // the weapon wheel is dimmed after this
+const WHEEL_MS: u64 = 1000; // and gone after this
 
-const DEBUG: bool = true;
 const DEBUG_SPEED: bool = false;
 
-const FRAME_GAP_MS: u64 = 50;
+const FRAME_GAP_MS: u64 = 50; // default, see GameConfig
+const SNAPSHOT_TICKS: u32 = 60; // default, 3s at the default speed, see snapshots.rs
+const IDLE_SECONDS: u32 = 30; // default, see Server::kick_if_idle
+const FRAME_HEADER_LEN: usize = 25; // state frame protocol, see World::entity_state
+const PLAYER_STATUS_LEN: usize = 9;
+const PLAYER_STATUS_LEN_V14: usize = 8; // protocol 14 had no energy hidden flag, for old replays
+const PLAYER_STATUS_LEN_V13: usize = 7; // protocol 13 had no points, for old replays
+const PLAYER_STATUS_LEN_V7: usize = 4; // protocol 7 had no cooldown or shield drain, for old replays
+const ENTITY_LEN: usize = 15;
+const ENTITY_LEN_V15: usize = 14; // protocol 15 had u8 ids, for old replays
+const ENTITY_LEN_V5: usize = 13; // protocol 5 had no weapon tier either
 const BANNER_PAUSE_S: u64 = 1;
 const HIT_PAUSE_MS: u64 = 600;
-
-trait Output {
+const PAN_MS: u64 = 1500; // the camera going from start to start before a round, see intro_pan
+const ERROR_PAUSE_S: u64 = 3; // to read why we're exiting
+const SAVE_PROMPT_S: u64 = 10; // to answer the save replay prompt, see offer_replay
+const AI_RETRIES: u32 = 10; // for --vs-ai, see start_ai
+
+// Where the game is drawn: the terminal, nowhere for serve, or your own for
+// embed::Game::render_into
+pub trait Output {
     // Setup graphics
-    fn init(&mut self) -> Result<(), Box<dyn Error>>;
+    fn init(&mut self) -> Result<(), GameError>;
 
     // Width and height of display, in whatever units makes sense
-    fn dimensions(&self) -> Result<(u16, u16), Box<dyn Error>>;
+    fn dimensions(&self) -> Result<(u16, u16), GameError>;
 
     // Update display, called every frame
-    fn render(&mut self, w: &mut World) -> Result<(), Box<dyn Error>>;
+    fn render(&mut self, v: View) -> Result<(), GameError>;
 
     // Display a banner, possibly multi-line. Caller must reset screen afterwards.
-    fn banner(&mut self, msg: &[&str]) -> Result<(), Box<dyn Error>>;
+    fn banner(&mut self, msg: &[&str]) -> Result<(), GameError>;
 
-    // Draw a string. Debug, unused.
-    #[allow(dead_code)]
-    fn print(&mut self, x: u16, y: u16, s: &str) -> Result<(), Box<dyn Error>>;
+    // Draw a string
+    fn print(&mut self, x: u16, y: u16, s: &str) -> Result<(), GameError>;
 
     // Reset screen, quit
-    fn cleanup(&mut self) -> Result<(), Box<dyn Error>>;
+    fn cleanup(&mut self) -> Result<(), GameError>;
+
+    // Is the arena bigger than what's shown of it, so it scrolls?
+    fn is_scrolling(&self) -> bool {
+        false
+    }
 }
 
 enum System {
@@ -66,10 +149,27 @@ enum System {
     Collision,
     EnergyReload(u32),
     Explode,
+    Pickup(u32, u32), // ticks since the last weapon upgrade appeared, and supply drop landed
 }
 
 impl System {
+    // For soak reports, and GameConfig::disabled_systems
+    fn name(&self) -> &'static str {
+        match self {
+            System::Move => "move",
+            System::Lifetime => "lifetime",
+            System::Collision => "collision",
+            System::EnergyReload(_) => "energy",
+            System::Explode => "explode",
+            System::Pickup(..) => "pickup",
+        }
+    }
+
     fn step(&mut self, world: &mut World) {
+        // off for this game, see GameConfig::disabled_systems
+        if world.config.disabled_systems.iter().any(|n| n == self.name()) {
+            return;
+        }
         match self {
             System::Move => {
                 move_system(world);
@@ -84,34 +184,59 @@ impl System {
                 if *n == 0 {
                     energy_system(world);
                 }
-                *n = (*n + 1) % ENERGY_EVERY;
+                *n = (*n + 1) % world.ticks(world.config.energy.reload_every).max(1);
             }
             System::Explode => {
                 explode_system(world);
             }
+            System::Pickup(n, m) => {
+                pickup_system(world);
+                let every = world.ticks(world.config.weapons.pickup_every);
+                *n += 1;
+                if every > 0 && *n >= every {
+                    *n = 0;
+                    spawn_pickup(world);
+                }
+                let every = world.ticks(world.config.weapons.supply_every);
+                *m += 1;
+                let warning = world.ticks(world.config.weapons.supply_warning);
+                if every > 0 && every.checked_sub(warning) == Some(*m) {
+                    mark_supply_drop(world);
+                }
+                if every > 0 && *m >= every {
+                    *m = 0;
+                    drop_supply(world);
+                }
+            }
         }
+        // what it queued, before the next system runs
+        commands::apply(world);
     }
 }
 
 struct Render {}
 impl Render {
-    fn render<T: Output>(&self, w: &mut World, out: &mut T) {
-        out.render(w).unwrap();
+    fn render<T: Output>(&self, w: &World, out: &mut T) -> Result<(), GameError> {
+        out.render(w.view())
     }
 }
 
 // Use velocity to update position
 fn move_system(w: &mut World) {
-    for entity_id in alive_entities(w) {
+    for entity_id in 0..w.alive.len() {
+        if !w.alive[entity_id] {
+            continue;
+        }
         let (quantity, direction) = w.velocity[entity_id];
         if quantity == 0 {
             continue;
         }
 
-        // walk each position forward
-        let mut moves = Vec::new();
-        let entity_positions = w.position[entity_id].to_owned(); // copy because borrow checker
-        'top: for (idx, mut next_p) in entity_positions.into_iter().enumerate() {
+        // walk each position forward. is_on_board only looks at obstacles,
+        // which don't move, so we can update as we go.
+        let mut has_moved = false;
+        'top: for idx in 0..w.position[entity_id].len() {
+            let mut next_p = w.position[entity_id][idx];
             for _ in 0..quantity {
                 next_p = next_p.moved(1, direction);
                 if !w.is_on_board(next_p) {
@@ -119,10 +244,15 @@ fn move_system(w: &mut World) {
                     continue 'top;
                 }
             }
-            moves.push((idx, next_p));
+            w.position[entity_id][idx] = next_p;
+            has_moved = true;
         }
 
-        if moves.is_empty() {
+        if has_moved {
+            let arming = &mut w.arming[entity_id].0;
+            *arming = arming.saturating_sub(quantity as u32);
+            teleport(w, entity_id);
+        } else {
             if w.bounce[entity_id] {
                 w.velocity[entity_id] = (quantity, direction.opposite());
             } else {
@@ -130,15 +260,72 @@ fn move_system(w: &mut World) {
                 w.alive[entity_id] = false;
             }
         }
-        for (idx, next_pos) in moves {
-            w.position[entity_id][idx] = next_pos;
+    }
+    follow_parents(w);
+}
+
+// Anything moving onto a corner teleporter comes out of the opposite corner,
+// and can't be hit for teleport_grace. Only things one square big,
+// players and missiles, fit through. It goes on in the same direction, so
+// next tick it's off the far teleporter, or against the wall beside it.
+fn teleport(w: &mut World, id: usize) {
+    if w.position[id].len() != 1 || w.kind[id] == EntityKind::Teleporter {
+        return;
+    }
+    let p = w.position[id][0];
+    if !alive_entities(w).any(|t| w.kind[t] == EntityKind::Teleporter && w.position[t][0].does_hit(p)) {
+        return;
+    }
+    // in_bounds runs from 1 to width - 2 across, 2 to height - 3 down
+    let to = Pos {
+        x: w.width - 1 - p.x,
+        y: w.height - 1 - p.y,
+        invalid: false,
+    };
+    if !w.is_on_board(to) {
+        return;
+    }
+    debug!("{} teleports from {} to {}", w.name[id], p, to);
+    w.position[id][0] = to;
+    w.grace[id] = w.ticks(w.config.teleport_grace);
+}
+
+// Move attached entities as far as their parent has moved since last time,
+// however it moved, so they keep their offset from it. They go when it does.
+fn follow_parents(w: &mut World) {
+    for id in 0..w.alive.len() {
+        let (parent, was) = match w.parent[id] {
+            Some(p) if w.alive[id] => p,
+            _ => continue,
+        };
+        if !w.alive[parent] {
+            w.alive[id] = false;
+            continue;
+        }
+        let now = w.position[parent][0];
+        let (dx, dy) = (i64::from(now.x) - i64::from(was.x), i64::from(now.y) - i64::from(was.y));
+        if dx == 0 && dy == 0 {
+            continue;
+        }
+        for idx in 0..w.position[id].len() {
+            let p = w.position[id][idx].shifted(dx, dy);
+            // hidden while it's off the board or in an obstacle
+            w.position[id][idx] = Pos {
+                invalid: !w.is_on_board(p),
+                ..p
+            };
         }
+        w.parent[id] = Some((parent, now));
     }
 }
 
 // Decrease lifetime, mark entities as not alive
 fn lifetime_system(w: &mut World) {
-    for entity_id in alive_entities(w) {
+    for entity_id in 0..w.alive.len() {
+        if !w.alive[entity_id] {
+            continue;
+        }
+        w.grace[entity_id] = w.grace[entity_id].saturating_sub(1);
         if let Lifetime::Temporary(n) = w.lifetime[entity_id] {
             let next = n - 1;
             if next > 0 {
@@ -148,58 +335,190 @@ fn lifetime_system(w: &mut World) {
             }
         }
     }
+    // walls that ran out, see is_solid
+    let (alive, lifetime) = (&w.alive, &w.lifetime);
+    w.solid.retain(|&id| alive[id] || lifetime[id] == Lifetime::Solid);
 }
 
 // Check for collisions
 // We don't need to worry about blocks/obstacles because move system runs first
 // and prevent us comming into contact with them.
+// Projectiles are fired from the firer's own square, so they arm first: they
+// hit nothing for their first few squares (Projectile::arming), and pass
+// through the firer until they've been clear of them once. A ray runs at
+// walking pace, so someone following their own ray can stay on it.
 fn collision_system(w: &mut World) {
-    let ids = alive_entities(w);
-    'top: for (id1, idx) in ids.iter().enumerate() {
-        for &id2 in ids.iter().skip(*idx) {
-            if id1 == id2 {
+    for id in 0..w.name.len() {
+        if !w.alive[id] {
+            continue;
+        }
+        if let (true, Some(firer)) = (w.arming[id].1, w.owner[id]) {
+            w.arming[id].1 = touches(w, id, firer);
+        }
+    }
+    let is_passing = |id: usize, other: usize| w.arming[id].0 > 0 || (w.arming[id].1 && w.owner[id] == Some(other));
+
+    let mut hit = None;
+    'top: for (idx, id1) in alive_entities(w).enumerate() {
+        for id2 in alive_entities(w).skip(idx + 1) {
+            // pickups are collected and supply drops land, see pickup_system
+            let is_scenery = |id: usize| {
+                matches!(
+                    w.kind[id],
+                    EntityKind::Pickup | EntityKind::Marker | EntityKind::Supply | EntityKind::Teleporter
+                )
+            };
+            if is_scenery(id1) || is_scenery(id2) {
                 continue;
             }
-            for p1 in w.position[id1].iter() {
-                for p2 in w.position[id2].iter() {
-                    if p1.does_hit(*p2) {
-                        debug!("{} hits {}", w.name[id1], w.name[id2]);
-                        // unshielded entites die on contact
-                        if !w.shield[id1] {
-                            w.alive[id1] = false;
-                        }
-                        if !w.shield[id2] {
-                            w.alive[id2] = false;
-                        }
-                        break 'top;
-                    }
-                }
+            // a beam goes through obstacles
+            let kinds = [w.kind[id1], w.kind[id2]];
+            if kinds.contains(&EntityKind::Beam) && kinds.contains(&EntityKind::Obstacle) {
+                continue;
+            }
+            if is_passing(id1, id2) || is_passing(id2, id1) {
+                continue;
+            }
+            if touches(w, id1, id2) {
+                hit = Some((id1, id2));
+                break 'top;
+            }
+        }
+    }
+    if let Some((id1, id2)) = hit {
+        debug!("{} hits {}", w.name[id1], w.name[id2]);
+        let is_player = |id| id == w.player1 || id == w.player2;
+        // a ray turns back off a shield, once, and holding it costs double
+        for (ray, player) in [(id1, id2), (id2, id1)] {
+            if w.kind[ray] == EntityKind::Ray && !w.reflected[ray] && is_player(player) && w.shield[player] {
+                projectile::reflect(w, ray, w.position[player][0]);
+                w.energy[player] = w.energy[player].saturating_sub(w.config.energy.shield);
+                let msg = format!("{} reflected {}", w.name[player], describe(w, ray));
+                w.note(msg);
+                w.cues.push(Cue::Shield);
+                return;
             }
         }
+        if is_player(id1) && is_player(id2) && w.shield[id1] && w.shield[id2] {
+            push_apart(w, id1, id2);
+            let msg = format!("{} and {} bumped shields", w.name[id1], w.name[id2]);
+            w.note(msg);
+            return;
+        }
+        // unshielded entites die on contact, and anything in the middle of a
+        // blast or in a beam. Further out a shield is enough.
+        let is_center = |blast: usize, id: usize| {
+            w.kind[blast] == EntityKind::Beam
+                || w.kind[blast] == EntityKind::Explosion
+                    && w.position[id].iter().any(|&p| blast_ring(w, blast, p) == 0)
+        };
+        let dies = [
+            w.grace[id1] == 0 && (!w.shield[id1] || is_center(id2, id1)),
+            w.grace[id2] == 0 && (!w.shield[id2] || is_center(id1, id2)),
+        ];
+        if dies[0] {
+            w.alive[id1] = false;
+        }
+        if dies[1] {
+            w.alive[id2] = false;
+        }
+        if let Some(msg) = collision_message(w, id1, id2) {
+            w.note(msg);
+        }
+        if !both_players_standing(w) {
+            w.cues.push(Cue::Hit);
+        }
     }
 }
 
-// Add energy at regular intervals, deduct energy for shield
-fn energy_system(w: &mut World) {
-    w.energy.iter_mut().for_each(|n| {
-        if *n < MAX_ENERGY {
-            *n += 1;
+// Whether any square of a is on any square of b
+fn touches(w: &World, a: usize, b: usize) -> bool {
+    w.position[a].iter().any(|pa| w.position[b].iter().any(|pb| pa.does_hit(*pb)))
+}
+
+// For the kill feed, once the collision system has decided who survived.
+// None if it's not worth a line: anything against a wall, or two shielded
+// things, which touch every tick until they part.
+fn collision_message(w: &World, id1: usize, id2: usize) -> Option<String> {
+    if w.kind[id1] == EntityKind::Obstacle || w.kind[id2] == EntityKind::Obstacle {
+        return None;
+    }
+    let is_player = |id| id == w.player1 || id == w.player2;
+    // b is the player, if only one of them is
+    let (a, b) = if is_player(id1) && !is_player(id2) { (id2, id1) } else { (id1, id2) };
+    let (da, db) = (describe(w, a), describe(w, b));
+    match (w.alive[a], w.alive[b]) {
+        (_, false) if is_player(b) && !is_player(a) => Some(format!("{} hit {}", da, db)),
+        (false, false) => Some(format!("{} and {} collided", da, db)),
+        (true, false) if matches!(w.kind[a], EntityKind::Ray | EntityKind::Beam) => {
+            Some(format!("{} intercepted {}", da, db))
         }
-    });
-    let shielded: Vec<usize> = w
-        .shield
-        .iter()
-        .enumerate()
-        .filter_map(|(id, has_shield)| if *has_shield { Some(id) } else { None })
-        .collect();
-    for id in shielded {
-        let e = &mut w.energy[id];
-        if *e > ENERGY_SHIELD {
-            *e -= ENERGY_SHIELD;
-        } else {
-            // ran out of energy, shield off
-            w.shield[id] = false;
+        (true, false) => Some(format!("{} stopped {}", da, db)),
+        (false, true) => Some(format!("{} stopped {}", db, da)),
+        (true, true) => None,
+    }
+}
+
+// e.g. "Alice's missile", or a player's name
+fn describe(w: &World, id: usize) -> String {
+    match w.owner[id] {
+        Some(p) => format!("{}'s {}", w.name[p], w.kind[id].to_string().to_lowercase()),
+        None => w.name[id].clone(),
+    }
+}
+
+// Shielded players who bump into each other bounce back and stop, and it
+// costs them both energy, so sitting shielded on top of the other player
+// isn't free
+fn push_apart(w: &mut World, a: usize, b: usize) {
+    // back the way each came. One standing still goes the way it was pushed.
+    let away = |me: Dir, other: Dir| if me == Dir::None { other } else { me.opposite() };
+    let (va, vb) = (w.velocity[a].1, w.velocity[b].1);
+    let mut da = away(va, vb);
+    if da == Dir::None {
+        da = Dir::Left;
+    }
+    let mut db = away(vb, va);
+    if db == da || db == Dir::None {
+        db = da.opposite();
+    }
+
+    let cost = w.config.energy.push;
+    for (id, dir) in [(a, da), (b, db)] {
+        for _ in 0..PUSH_DISTANCE {
+            let next = w.position[id][0].moved(1, dir);
+            if !w.is_on_board(next) {
+                break;
+            }
+            w.position[id][0] = next;
         }
+        w.velocity[id].1 = Dir::None;
+        w.energy[id] = w.energy[id].saturating_sub(cost);
+    }
+    w.cues.push(Cue::Shield);
+}
+
+// Add energy at regular intervals, deduct energy for shield
+fn energy_system(w: &mut World) {
+    let energy = &w.config.energy;
+    for (e, has_shield) in w.energy.iter_mut().zip(w.shield.iter_mut()) {
+        reload(energy, e, has_shield);
+    }
+}
+
+// One energy back, less what a shield takes
+fn reload(energy: &config::Energy, e: &mut u32, has_shield: &mut bool) {
+    if *e < energy.max {
+        *e += 1;
+    }
+    if !*has_shield {
+        return;
+    }
+    if *e > energy.shield {
+        *e -= energy.shield;
+    } else {
+        // ran out of energy, shield off
+        *has_shield = false;
     }
 }
 
@@ -209,37 +528,46 @@ fn explode_system(w: &mut World) {
     // - explode
     // - are not yet exploding
     // - are within EXPLODE_DURATION of their end of life
-    let to_explode: Vec<usize> = w
-        .explode
-        .iter()
-        .enumerate()
-        .filter_map(|(id, (will_explode, is_exploding))| {
-            if *will_explode && !is_exploding {
-                Some(id)
-            } else {
-                None
-            }
-        })
-        .filter(|&id| matches!(w.lifetime[id], Lifetime::Temporary(n) if n <= EXPLODE_DURATION))
-        .collect();
-
-    to_explode.iter().for_each(|&id| {
+    for id in 0..w.explode.len() {
+        let (will_explode, is_exploding) = w.explode[id];
+        if !will_explode || is_exploding {
+            continue;
+        }
+        if !matches!(w.lifetime[id], Lifetime::Temporary(n) if n <= EXPLODE_DURATION) {
+            continue;
+        }
         w.explode[id].1 = true; // set is_exploding
-        w.position[id] = explosion(w, w.position[id][0]);
+        w.cues.push(Cue::Explosion);
+        projectile::split(w, id, explosion_radius(w.tier[id]));
+        w.kind[id] = EntityKind::Explosion;
+        // the missile's positions become the explosion's
+        let mut positions = std::mem::take(&mut w.position[id]);
+        explosion(w, positions[0], w.tier[id], &mut positions);
+        w.position[id] = positions;
         w.velocity[id] = (0, Dir::None);
-    });
+    }
+}
+
+// Which ring of explosion id p is in: 0 in the middle, out to the tier's
+// explosion_radius. The center is its first position, see explosion.
+fn blast_ring(w: &World, id: usize, p: Pos) -> u32 {
+    let c = w.position[id][0];
+    c.x.abs_diff(p.x).max(c.y.abs_diff(p.y))
 }
 
-// Positions for an explosion originating at p
-fn explosion(w: &World, p: Pos) -> Vec<Pos> {
-    let mut v = Vec::with_capacity(25);
+// Replace v with the positions for an explosion of a tier's missile originating at p
+// The first position is the center. Must match rs_sdk::explosion_area.
+fn explosion(w: &World, p: Pos, tier: u8, v: &mut Vec<Pos>) {
+    v.clear();
+    v.push(p);
     let src_x: i32 = p.x as i32;
     let src_y: i32 = p.y as i32;
-    for x in src_x - 2..=src_x + 2 {
+    let r = explosion_radius(tier) as i32;
+    for x in src_x - r..=src_x + r {
         if x < 0 {
             continue;
         }
-        for y in src_y - 2..=src_y + 2 {
+        for y in src_y - r..=src_y + r {
             if y < 0 {
                 continue;
             }
@@ -248,12 +576,117 @@ fn explosion(w: &World, p: Pos) -> Vec<Pos> {
                 y: y as u32,
                 invalid: false,
             };
-            if w.is_on_board(e) {
+            if w.is_on_board(e) && !e.does_hit(p) {
                 v.push(e);
             }
         }
     }
-    v
+}
+
+// A player moving onto a pickup goes up a weapon tier, or gets a beam, onto
+// a supply drop gets what's in it
+fn pickup_system(w: &mut World) {
+    for id in 0..w.kind.len() {
+        if !w.alive[id] || !matches!(w.kind[id], EntityKind::Pickup | EntityKind::Supply) {
+            continue;
+        }
+        let p = w.position[id][0];
+        for (idx, &player) in [w.player1, w.player2].iter().enumerate() {
+            if w.alive[player] && w.position[player][0].does_hit(p) {
+                debug!("{} picks up {}", w.name[player], w.name[id]);
+                let what = match (w.kind[id], w.tier[id]) {
+                    (EntityKind::Pickup, PICKUP_BEAM) => {
+                        let held = w.active_weapon[player].replace(Weapon::Beam).unwrap();
+                        if !held.is_super() {
+                            w.spare_weapon[idx] = Some(held);
+                        }
+                        w.weapon_changed[idx] = Some(w.tick);
+                        "a beam".to_string()
+                    }
+                    (EntityKind::Pickup, _) => {
+                        w.tier[player] = (w.tier[player] + 1).min(MAX_TIER);
+                        format!("tier {}", w.tier[player])
+                    }
+                    (_, SUPPLY_LIFE) => {
+                        let lives = if idx == 0 { &mut w.p1_lives } else { &mut w.p2_lives };
+                        *lives = (*lives + 1).min(u8::MAX as u32); // u8 on the wire
+                        "an extra life".to_string()
+                    }
+                    _ => {
+                        w.energy[player] = w.config.energy.max;
+                        "full energy".to_string()
+                    }
+                };
+                w.alive[id] = false;
+                w.cues.push(Cue::Pickup);
+                let msg = format!("{} picked up {}", w.name[player], what);
+                w.note(msg);
+                break;
+            }
+        }
+    }
+}
+
+// Put a pickup on a free square, unless there's one already. Once in a
+// while it's a beam.
+fn spawn_pickup(w: &mut World) {
+    if alive_entities(w).any(|id| w.kind[id] == EntityKind::Pickup) {
+        return;
+    }
+    let mut rng = StdRng::seed_from_u64(w.tick as u64);
+    if let Some(p) = free_square(w, &mut rng) {
+        if rng.gen_range(0..100) < w.config.weapons.beam_chance {
+            new_beam_pickup(w, p);
+        } else {
+            new_pickup(w, p);
+        }
+    }
+}
+
+// Mark where the next supply drop will land, weapons.supply_warning ahead
+fn mark_supply_drop(w: &mut World) {
+    if let Some(p) = free_square(w, &mut StdRng::seed_from_u64(w.tick as u64)) {
+        new_marker(w, p);
+    }
+}
+
+// Land a supply drop on its marker. Anyone standing there is crushed,
+// shield or not. Half the drops are full energy, half an extra life.
+fn drop_supply(w: &mut World) {
+    let Some(marker) = alive_entities(w).find(|&id| w.kind[id] == EntityKind::Marker) else {
+        return;
+    };
+    w.commands.despawn(marker);
+    let p = w.position[marker][0];
+    w.cues.push(Cue::Explosion);
+    for player in [w.player1, w.player2] {
+        if w.alive[player] && w.position[player][0].does_hit(p) {
+            w.alive[player] = false;
+            let msg = format!("A supply drop landed on {}", w.name[player]);
+            w.note(msg);
+            w.cues.push(Cue::Hit);
+        }
+    }
+    let what = if StdRng::seed_from_u64(w.tick as u64).gen_bool(0.5) { SUPPLY_LIFE } else { SUPPLY_ENERGY };
+    w.commands.spawn(Spawn::Supply { pos: p, what });
+}
+
+// A random square with nothing on it. Where comes from rng, seeded by the
+// caller from the tick, so the same game played again puts things in the
+// same places. None if PICKUP_TRIES squares were all taken.
+fn free_square(w: &World, rng: &mut StdRng) -> Option<Pos> {
+    for _ in 0..PICKUP_TRIES {
+        let p = Pos {
+            x: rng.gen_range(1..w.width.max(2)),
+            y: rng.gen_range(2..w.height.max(3)),
+            invalid: false,
+        };
+        let is_taken = alive_entities(w).any(|id| w.position[id].iter().any(|q| q.does_hit(p)));
+        if w.in_bounds(p) && w.is_on_board(p) && !is_taken {
+            return Some(p);
+        }
+    }
+    None
 }
 
 struct World {
@@ -263,13 +696,45 @@ struct World {
     player2: usize,
     p1_lives: u32,
     p2_lives: u32,
+    config: GameConfig,
+    win: Box<dyn WinCondition>, // from config.victory
+    layout: Vec<(Pos, Dir)>, // where the obstacles go, see maps::layout
     missile_range_horizontal: u32,
     missile_range_vertical: u32,
+    tick: u32, // frame number, never reset, sent to bots
+    shots: [u32; 2], // missiles and rays each player has fired this match, for stats
+    cues: Vec<Cue>,  // what happened this tick that makes a sound, see sound.rs
+    muted: bool,     // no cues in the View
+    one_match: bool, // no rematch, for the daily challenge
+    serve: bool,     // headless, the next match starts once both bots are Ready
+    score: [u32; 3], // matches player 1 won, player 2 won, drawn, since the game started
+    debug: bool,     // the inspector may be opened, see inspector.rs
+    inspector: Option<usize>, // first line shown, None when it's closed
+    heatmap: bool,            // shade where the players have been, see heatmap.rs
+    trail: VecDeque<[Pos; 2]>, // both players' positions, a tick at a time, newest last. Debug mode and replays only.
+    tuning: Option<tuning::Watcher>, // the config file, in debug mode
+    match_start: Option<u32>, // tick this match started, None if we're only showing frames
+    round_start: u32,         // tick this round started
+    round_ticks: Vec<u32>,    // how long each of this match's rounds took
+    tiebreaker: bool,         // both lost their last life together, see end_round
+    feed: Vec<(u32, String)>, // the kill feed, (tick, what happened), oldest first
+    hits: Vec<(u32, usize)>,  // (tick, 0 or 1 for the player who was hit), for momentum.rs and points
+    weapon_changed: [Option<u32>; 2], // tick each player last changed weapon, for the weapon wheel
+    spare_weapon: [Option<Weapon>; 2], // put away for a beam, back once it's fired
+    facing: [Dir; 2], // the way each player last moved, their walls go behind them
+    commands: Commands, // entities systems want added or gone, see commands.rs
+    pings: [Option<Duration>; 2], // round trips to each player's bot, see Server::ping
+    idle: [bool; 2],              // each player's bot went quiet and their seat is open
+    bots: [bool; 2],              // each player is played by a bot, rather than at the keyboard
+    timings: timing::Timings,     // how long each system and rendering take
+    snapshots: snapshots::Snapshots, // the last few ticks played
+    turns: Option<turns::Turns>,     // whose turn it is in hot-seat play, see turns.rs
 
     name: Vec<String>,
     alive: Vec<bool>,
 
     // components
+    kind: Vec<EntityKind>,
     lifetime: Vec<Lifetime>, // how long it displays for
     sprite: Vec<Sprite>,
     velocity: Vec<(u8, Dir)>, // (quantity, direction)
@@ -279,12 +744,97 @@ struct World {
     bounce: Vec<bool>,
     explode: Vec<(bool, bool)>,         // (will explode, is exploding)
     active_weapon: Vec<Option<Weapon>>, // Is player using ray or missile?
+    tier: Vec<u8>,                      // a player's weapon tier, or the one a missile was fired at
+    owner: Vec<Option<usize>>,          // player who fired it, for the kill feed
+    parent: Vec<Option<(usize, Pos)>>,  // what it's attached to, and where that was, see follow_parents
+    arming: Vec<(u32, bool)>,           // (squares to go before it hits anything, still on its firer), see collision_system
+    reflected: Vec<bool>,               // a ray turned back by a shield, which won't be again
+    grace: Vec<u32>,                    // ticks left that nothing can hit it, after a teleport
+    solid: Vec<usize>,                  // ids of the map's obstacles and live walls, see is_on_board
 }
 
 impl World {
+    fn new(width: u32, height: u32) -> World {
+        World::with_config(width, height, GameConfig::default())
+    }
+    fn with_config(width: u32, height: u32, config: GameConfig) -> World {
+        let (missile_range_horizontal, missile_range_vertical) =
+            missile_ranges(width, height, config.weapons.missile_min_range);
+        World {
+            // static
+            width,
+            height,
+            player1: 0,
+            player2: 0,
+            p1_lives: config.lives,
+            p2_lives: config.lives,
+            muted: config.sound.mute,
+            one_match: false,
+            serve: false,
+            score: [0, 0, 0],
+            debug: false,
+            inspector: None,
+            heatmap: false,
+            trail: VecDeque::new(),
+            tuning: None,
+            match_start: None,
+            round_start: 0,
+            round_ticks: Vec::new(),
+            tiebreaker: false,
+            feed: Vec::new(),
+            hits: Vec::new(),
+            weapon_changed: [None, None],
+            spare_weapon: [None, None],
+            facing: [Dir::Right, Dir::Left],
+            commands: Commands::default(),
+            pings: [None, None],
+            idle: [false, false],
+            bots: [false, false],
+            timings: timing::Timings::default(),
+            snapshots: snapshots::Snapshots::new(config.snapshot_ticks as usize),
+            turns: None,
+            win: victory::of(&config.victory),
+            config,
+            layout: maps::layout(MapKind::Bar, Density::Normal, width, height, 0),
+            missile_range_horizontal,
+            missile_range_vertical,
+            tick: 0,
+            shots: [0, 0],
+            cues: Vec::new(),
+
+            name: Vec::new(),
+            alive: Vec::new(),
+            kind: Vec::new(),
+            lifetime: Vec::new(),
+            sprite: Vec::new(),
+            velocity: Vec::new(),
+            position: Vec::new(),
+            energy: Vec::new(),
+            shield: Vec::new(),
+            bounce: Vec::new(),
+            explode: Vec::new(),
+            active_weapon: Vec::new(),
+            tier: Vec::new(),
+            owner: Vec::new(),
+            parent: Vec::new(),
+            arming: Vec::new(),
+            reflected: Vec::new(),
+            grace: Vec::new(),
+            solid: Vec::new(),
+            // remember to add to reset() as well
+        }
+    }
     fn reset(&mut self) {
+        self.cues.clear();
+        self.clear_entities();
+        self.add_players();
+        self.add_obstacles();
+    }
+    fn clear_entities(&mut self) {
+        self.commands.clear();
         self.name = Vec::new();
         self.alive = Vec::new();
+        self.kind = Vec::new();
         self.lifetime = Vec::new();
         self.sprite = Vec::new();
         self.velocity = Vec::new();
@@ -294,191 +844,619 @@ impl World {
         self.bounce = Vec::new();
         self.explode = Vec::new();
         self.active_weapon = Vec::new();
-
-        self.add_players();
-        self.add_obstacles();
+        self.tier = Vec::new();
+        self.owner = Vec::new();
+        self.parent = Vec::new();
+        self.arming = Vec::new();
+        self.reflected = Vec::new();
+        self.grace = Vec::new();
+        self.solid = Vec::new();
     }
     fn add_players(&mut self) {
-        self.player1 = new_player(self, "Player 1".to_string(), "1".to_string(), 1);
-        self.player2 = new_player(self, "Player 2".to_string(), "2".to_string(), 2);
+        let (names, glyphs) = (self.config.names.clone(), self.config.glyphs.clone());
+        self.player1 = new_player(self, names.player1, glyphs.player1, 1);
+        self.player2 = new_player(self, names.player2, glyphs.player2, 2);
+        self.spare_weapon = [None, None];
+        // towards each other, see to_start_positions
+        self.facing = [Dir::Right, Dir::Left];
     }
     fn add_obstacles(&mut self) {
-        let x = self.width / 2;
-        let third = self.height / 3;
-        for y in third..third * 2 {
-            let p = Pos {
-                x,
-                y,
-                invalid: false,
-            };
-            new_bar(self, p, Dir::Up);
+        for (p, dir) in self.layout.clone() {
+            new_bar(self, p, dir);
+        }
+        if self.config.teleporters {
+            self.add_teleporters();
         }
     }
-    fn is_on_board(&self, pos: Pos) -> bool {
-        // check if off board left or right
-        let x_fit = !pos.invalid && 1 <= pos.x && pos.x < self.width - 1;
-        if !x_fit {
-            return false;
+    // One in each corner, unless an obstacle is there
+    fn add_teleporters(&mut self) {
+        let (right, bottom) = (self.width.saturating_sub(2), self.height.saturating_sub(3));
+        for (x, y) in [(1, 2), (right, 2), (1, bottom), (right, bottom)] {
+            let p = Pos { x, y, invalid: false };
+            if self.is_on_board(p) {
+                new_teleporter(self, p);
+            }
         }
+    }
+    // Inside the walls, ignoring obstacles
+    fn in_bounds(&self, pos: Pos) -> bool {
+        // check if off board left or right
+        let x_fit = !pos.invalid && 1 <= pos.x && pos.x < self.width.saturating_sub(1);
         // check if off board top and bottom
-        let y_fit = 2 <= pos.y && pos.y < self.height - 2;
-        if !y_fit {
+        let y_fit = 2 <= pos.y && pos.y < self.height.saturating_sub(2);
+        x_fit && y_fit
+    }
+    fn is_on_board(&self, pos: Pos) -> bool {
+        if !self.in_bounds(pos) {
             return false;
         }
-        // check if hits an obstacle
-        for (entity_id, _) in self
-            .lifetime
-            .iter()
-            .enumerate()
-            .filter(|(_, l)| **l == Lifetime::Solid)
-        {
+        // check if hits an obstacle, a map's or a player's wall
+        for &entity_id in &self.solid {
             // all blocks are size 1 so far so [0] is OK
-            if self.position[entity_id][0].does_hit(pos) {
+            if self.is_solid(entity_id) && self.position[entity_id][0].does_hit(pos) {
                 return false;
             }
         }
 
         true
     }
+    // Stops things. The map's obstacles are there all round, walls until
+    // they run out.
+    fn is_solid(&self, id: usize) -> bool {
+        match self.lifetime[id] {
+            Lifetime::Solid => true,
+            Lifetime::Temporary(_) => self.alive[id] && self.kind[id] == EntityKind::Obstacle,
+            Lifetime::Permanent => false,
+        }
+    }
 
     // A representation of the state of all our entities,
     // for sending to clients.
     // This is actually protocol, so should be in server, but that would
     // require either making most of World's fields public, or introducing
     // an unnecessary intermediate format.
-    fn entity_state(&self) -> Vec<u8> {
-        let mut state = Vec::with_capacity(self.name.len() * 12);
-        for (entity_id, _name) in self.name.iter().enumerate() {
-            // protocol is: entity_id(u8) x(u32) y(u32) dir(u8) velocity(u8) shield(u8)
+    // Replaces what's in state, so the caller can reuse it every frame.
+    fn entity_state(&self, game_state: GameState, state: &mut Vec<u8>) {
+        state.clear();
+        state.reserve(FRAME_HEADER_LEN + self.name.len() * ENTITY_LEN);
+        // frame header is: tick(u32) game_state(u8) player_status(9 bytes) x 2 num_entities(u16)
+        // player status is: lives(u8) energy(u16) weapon(u8) cooldown(u16) shield_drain(u8) points(u8)
+        // energy_hidden(u8), set by hide_energy
+        state.extend_from_slice(&self.tick.to_be_bytes());
+        state.push(game_state.as_num());
+        let players = [(self.player1, self.p1_lives), (self.player2, self.p2_lives)];
+        for (&(id, lives), &points) in players.iter().zip(self.points().iter()) {
+            state.push(lives as u8);
+            state.extend_from_slice(&(self.energy[id] as u16).to_be_bytes());
+            state.push(self.active_weapon[id].unwrap().as_num());
+            state.extend_from_slice(&self.cooldown(id).to_be_bytes());
+            state.push(self.config.energy.shield.min(u8::MAX as u32) as u8);
+            state.push(points.min(u8::MAX as u32) as u8);
+            state.push(0);
+        }
+        // the count and ids are u16. The dead keep their ids until the round
+        // ends, but it would take hours of firing to run out.
+        let sent = self.name.len().min(u16::MAX as usize);
+        if sent < self.name.len() {
+            warn!("{} entities, frames only have room for {}", self.name.len(), sent);
+        }
+        state.extend_from_slice(&(sent as u16).to_be_bytes());
+        for entity_id in 0..sent {
+            // protocol is: entity_id(u16) x(u32) y(u32) dir(u8) velocity(u8) shield(u8) kind(u8) tier(u8)
 
-            state.push(entity_id as u8);
+            state.extend_from_slice(&(entity_id as u16).to_be_bytes());
             state.extend_from_slice(&self.position[entity_id][0].x.to_be_bytes());
             state.extend_from_slice(&self.position[entity_id][0].y.to_be_bytes());
             state.push(self.velocity[entity_id].1.as_num());
             state.push(self.velocity[entity_id].0);
             state.push(if self.shield[entity_id] { 1 } else { 0 });
+            state.push(self.kind[entity_id].as_num());
+            state.push(self.tier[entity_id]);
         }
-        state
     }
-}
-
-fn both_players_alive(w: &World) -> bool {
-    w.p1_lives > 0 && w.p2_lives > 0
-}
-
-fn both_players_standing(w: &World) -> bool {
-    w.alive[w.player1] && w.alive[w.player2]
-}
 
-// entity ids of the living entitites
-fn alive_entities(w: &World) -> Vec<usize> {
-    w.alive
-        .iter()
-        .enumerate()
-        .filter_map(|(idx, is_alive)| if *is_alive { Some(idx) } else { None })
-        .collect()
-}
+    // Ticks until a frame shows player id with enough energy to fire their
+    // weapon, 0 if this one does. Plays the reloads forward as energy_system
+    // will, from when it last ran. u16::MAX if it never will.
+    fn cooldown(&self, id: usize) -> u16 {
+        let energy = &self.config.energy;
+        let cost = (projectile::of(self.active_weapon[id].unwrap()).cost)(energy);
+        let (mut e, mut has_shield) = (self.energy[id], self.shield[id]);
+        if e > cost {
+            return 0;
+        }
+        let reloading = !self.config.disabled_systems.iter().any(|n| n == System::EnergyReload(0).name());
+        if cost >= energy.max || !reloading {
+            return u16::MAX;
+        }
+        // the reload counter starts each round at 0, see systems
+        let every = self.ticks(energy.reload_every).max(1);
+        let mut ticks = every - self.tick.saturating_sub(self.round_start) % every;
+        while ticks < u16::MAX as u32 {
+            reload(energy, &mut e, &mut has_shield);
+            if e > cost {
+                return ticks as u16;
+            }
+            ticks += every;
+        }
+        u16::MAX
+    }
 
-fn new_player(w: &mut World, name: String, texture: String, color_idx: usize) -> usize {
-    let id = w.name.len();
-    w.name.push(name);
-    w.alive.push(true);
-    w.lifetime.push(Lifetime::Permanent);
-    w.velocity.push((1, Dir::None));
-    w.sprite.push(Sprite {
-        color_idx,
-        is_bold: true,
-        _frame_num: 0,
-        texture_vertical: vec![texture.clone()],
-        texture_horizontal: vec![texture],
-        texture_explosion: vec![None],
-    });
-    w.energy.push(MAX_ENERGY);
-    w.shield.push(false);
-    w.bounce.push(true);
-    w.explode.push((false, false));
-    w.active_weapon.push(Some(Weapon::Missile));
+    // Everything the display draws, see render::View
+    fn view(&self) -> View {
+        let player = |id: usize, lives: u32, keys: &config::PlayerKeys, color_idx: usize| PlayerView {
+            name: self.name[id].clone(),
+            keys: keys.summary(),
+            lives,
+            max_lives: self.config.lives,
+            energy: if self.is_energy_hidden(color_idx - 1) { None } else { Some(self.energy[id]) },
+            weapon: self.active_weapon[id].unwrap(),
+            tier: self.tier[id],
+            shield: self.shield[id],
+            color: self.config.colors.by_index(color_idx),
+            ping: self.pings[color_idx - 1],
+            idle: self.idle[color_idx - 1],
+        };
+        let keys = &self.config.keys;
+        let players = [
+            player(self.player1, self.p1_lives, &keys.player1, 1),
+            player(self.player2, self.p2_lives, &keys.player2, 2),
+        ];
+
+        let mut cells = Vec::new();
+        for id in alive_entities(self) {
+            // supply drop markers flash, a quarter second on, a quarter off
+            if self.kind[id] == EntityKind::Marker && self.play_time(self.tick).as_millis() % 500 >= 250 {
+                continue;
+            }
+            let sprite = &self.sprite[id];
+            let (_, dir) = self.velocity[id];
+            let is_player = id == self.player1 || id == self.player2;
+            let tx: &str = if is_player && self.shield[id] {
+                "@"
+            } else if self.explode[id].1 {
+                sprite.texture_explosion[0].as_ref().unwrap()
+            } else if dir.is_vertical() {
+                &sprite.texture_vertical[0]
+            } else {
+                &sprite.texture_horizontal[0]
+            };
+            let glyph = tx.chars().next().unwrap_or(' ');
+            let color = self.config.colors.by_index(sprite.color_idx);
+            for pos in self.position[id].iter().filter(|p| !p.invalid) {
+                cells.push(Cell {
+                    x: pos.x as u16,
+                    y: pos.y as u16,
+                    glyph,
+                    color,
+                    bold: sprite.is_bold || self.config.palette == Palette::HighContrast,
+                });
+            }
+        }
+        let cues = if self.muted { Vec::new() } else { self.cues.clone() };
+        let clock = self.match_start.map(|start| Clock {
+            round: self.play_time(self.tick - self.round_start),
+            game: self.play_time(self.tick - start),
+        });
+        let inspector = self.inspector.map(|scroll| inspector::inspect(self, scroll));
+        // older lines fade, unless the palette is only the terminal's color
+        let faded = match self.config.palette {
+            Palette::HighContrast => Color::Reset,
+            _ => Color::DarkGrey,
+        };
+        let feed = self
+            .feed
+            .iter()
+            .filter_map(|(tick, text)| {
+                let age = self.play_time(self.tick - tick);
+                if age >= Duration::from_millis(FEED_MS) {
+                    None
+                } else if age >= Duration::from_millis(FEED_FRESH_MS) {
+                    Some(FeedLine { text: text.clone(), color: faded })
+                } else {
+                    Some(FeedLine { text: text.clone(), color: Color::Reset })
+                }
+            })
+            .collect();
+        View {
+            players,
+            cues,
+            clock,
+            feed,
+            inspector,
+            border: self.config.glyphs.border,
+            border_color: self.config.colors.border(),
+            momentum: if self.config.momentum { Some(self.momentum()) } else { None },
+            focus: self.focus(),
+            wheels: self.wheels(faded),
+            heat: if self.heatmap { heatmap::heat(self) } else { Vec::new() },
+            labels: self.labels(&cells),
+            turn: self.turn(),
+            cells,
+        }
+    }
 
-    // placeholder, set later in to_start_positions
-    w.position.push(vec![Pos::nil()]);
+    // With opponent_energy off, a bot's energy isn't shown to the person it's
+    // playing. Two people at one keyboard see each other's anyway, and
+    // watching two bots there's nobody to keep it from.
+    fn is_energy_hidden(&self, idx: usize) -> bool {
+        !self.config.opponent_energy && self.bots[idx] && !self.bots[1 - idx]
+    }
 
-    id
-}
+    // Whose turn it is and what they've picked so far, while a player picks
+    fn turn(&self) -> Option<Turn> {
+        let t = self.turns.as_ref()?;
+        let idx = t.whose()?;
+        let id = [self.player1, self.player2][idx];
+        Some(Turn {
+            text: format!("{}'s turn: {}. Enter to go.", self.name[id], t.describe()),
+            color: self.config.colors.by_index(idx + 1),
+        })
+    }
 
-fn new_missile(w: &mut World, start_pos: Pos, dir: Dir, color_idx: usize) {
-    // missile have size 2. check if second half would hit an edge or obstacle
-    let pos_2 = start_pos.moved(1, dir);
-    if !w.is_on_board(pos_2) {
-        return;
+    // Every entity's id in debug mode, and the players' names with labels on.
+    // Obstacles go without, there are too many.
+    fn labels(&self, cells: &[Cell]) -> Vec<Label> {
+        if !self.debug && !self.config.labels {
+            return Vec::new();
+        }
+        let players = [self.player1, self.player2];
+        let others = alive_entities(self).filter(|id| !players.contains(id) && self.kind[*id] != EntityKind::Obstacle);
+        let wanted = players
+            .iter()
+            .copied()
+            .chain(others.filter(|_| self.debug))
+            .filter(|&id| self.alive[id] && !self.position[id][0].invalid)
+            .map(|id| {
+                let p = self.position[id][0];
+                let is_named = self.config.labels && players.contains(&id);
+                labels::Wanted {
+                    x: p.x as u16,
+                    y: p.y as u16,
+                    text: if is_named { self.name[id].clone() } else { id.to_string() },
+                    color: self.config.colors.by_index(self.sprite[id].color_idx),
+                }
+            })
+            .collect();
+        labels::place(wanted, cells, self.width as u16, self.height as u16)
     }
-    w.name.push(format!("Missile {}", w.name.len()));
-    w.alive.push(true);
-    let range = match dir {
-        Dir::Up | Dir::Down => w.missile_range_vertical,
-        Dir::Left | Dir::Right => w.missile_range_horizontal,
-        Dir::None => {
-            panic!("Missile with no direction. Abort.");
+
+    // Weapon wheels for players who changed weapon within WHEEL_MS
+    fn wheels(&self, faded: Color) -> Vec<WeaponWheel> {
+        let weapons: Vec<Weapon> = (0..).map_while(Weapon::from_num).collect();
+        let mut wheels = Vec::new();
+        for (idx, &id) in [self.player1, self.player2].iter().enumerate() {
+            let (changed, pos) = (self.weapon_changed[idx], self.position[id][0]);
+            let age = match changed {
+                Some(tick) if self.alive[id] && !pos.invalid => self.play_time(self.tick.saturating_sub(tick)),
+                _ => continue,
+            };
+            let color = if age >= Duration::from_millis(WHEEL_MS) {
+                continue;
+            } else if age >= Duration::from_millis(WHEEL_FRESH_MS) {
+                faded
+            } else {
+                self.config.colors.by_index(self.sprite[id].color_idx)
+            };
+            // a beam only while they have one
+            let held: Vec<Weapon> =
+                weapons.iter().copied().filter(|w| !w.is_super() || Some(*w) == self.active_weapon[id]).collect();
+            wheels.push(WeaponWheel {
+                x: pos.x as u16,
+                y: pos.y as u16,
+                weapons: held.iter().map(|w| w.name()).collect(),
+                selected: held.iter().position(|&w| Some(w) == self.active_weapon[id]).unwrap_or(0),
+                color,
+                faded,
+            });
         }
-    };
-    w.lifetime.push(Lifetime::Temporary(range));
-    w.position.push(vec![start_pos, pos_2]);
-    w.velocity.push((2, dir));
-    w.sprite.push(Sprite {
-        color_idx,
-        is_bold: false,
-        _frame_num: 0,
-        texture_vertical: vec!["*".to_string()],
-        texture_horizontal: vec!["*".to_string()],
-        texture_explosion: vec![Some("#".to_string())],
-    });
-    w.energy.push(0);
-    w.shield.push(false);
-    w.bounce.push(false);
-    w.explode.push((true, false));
-    w.active_weapon.push(None);
-}
+        wheels
+    }
 
-fn new_ray(w: &mut World, start_pos: Pos, dir: Dir, color_idx: usize) {
-    let dist_to_edge = match dir {
-        Dir::Left => start_pos.x - 1,
-        Dir::Right => w.width - 2 - start_pos.x,
-        Dir::Up => start_pos.y - 2,
-        Dir::Down => w.height - 2 - start_pos.y - 1,
-        Dir::None => 0,
-    };
-    let mut positions = Vec::with_capacity(dist_to_edge as usize);
-    let mut p = start_pos;
-    for _ in 0..dist_to_edge {
-        positions.push(p);
-        p = p.moved(1, dir);
-        if !w.is_on_board(p) {
-            break;
+    // Halfway between the players, or the middle before they're placed
+    fn focus(&self) -> (u16, u16) {
+        let (p1, p2) = (self.position[self.player1][0], self.position[self.player2][0]);
+        if p1.invalid || p2.invalid {
+            return ((self.width / 2) as u16, (self.height / 2) as u16);
         }
+        (((p1.x + p2.x) / 2) as u16, ((p1.y + p2.y) / 2) as u16)
     }
-    w.position.push(positions);
 
-    w.name.push(format!("Ray {}", w.name.len()));
-    w.alive.push(true);
-    w.lifetime.push(Lifetime::Temporary(LIFETIME_RAY));
-    w.velocity.push((1, dir));
-    w.sprite.push(Sprite {
-        color_idx,
-        is_bold: false,
-        _frame_num: 0,
-        texture_vertical: vec!["|".to_string()],
-        texture_horizontal: vec!["-".to_string()],
-        texture_explosion: vec![None],
-    });
-    w.energy.push(0);
-    w.shield.push(true); // does not get destroyed by a collision
-    w.bounce.push(false);
+    // Player 1's share of the tug of war bar, see momentum.rs
+    fn momentum(&self) -> f32 {
+        let recent = |victim: usize| {
+            let hits = self.hits.iter().filter(|&&(tick, p)| p == victim && self.is_recent(tick));
+            hits.count() as u32
+        };
+        let standing = |id: usize, lives: u32, hit_other: u32| momentum::Standing {
+            lives,
+            energy: self.energy[id],
+            max_energy: self.config.energy.max,
+            recent_hits: hit_other,
+        };
+        momentum::share(
+            &standing(self.player1, self.p1_lives, recent(1)),
+            &standing(self.player2, self.p2_lives, recent(0)),
+        )
+    }
+
+    fn is_recent(&self, tick: u32) -> bool {
+        self.play_time(self.tick.saturating_sub(tick)) < momentum::RECENT
+    }
+
+    // Hits each player has landed on the other this match
+    fn points(&self) -> [u32; 2] {
+        let on = |victim: usize| self.hits.iter().filter(|&&(_, p)| p == victim).count() as u32;
+        [on(1), on(0)]
+    }
+
+    // Player idx, 0 or 1, was hit. Cleared each match, so it stays short.
+    fn note_hit(&mut self, idx: usize) {
+        self.hits.push((self.tick, idx));
+    }
+
+    // A frame says player idx has landed this many hits. Fewer than we have
+    // is a new match.
+    fn load_points(&mut self, idx: usize, points: u32) {
+        let mut had = self.points()[idx];
+        if points < had {
+            self.hits.clear();
+            had = 0;
+        }
+        for _ in had..points {
+            self.note_hit(1 - idx);
+        }
+    }
+
+    // Add to the kill feed, see FEED_LINES
+    fn note(&mut self, msg: String) {
+        debug!("{}", msg);
+        self.feed.push((self.tick, msg));
+        if self.feed.len() > FEED_LINES {
+            self.feed.remove(0);
+        }
+    }
+
+    // A config lifetime or gap in ticks, at the current frame_gap_ms
+    fn ticks(&self, span: Span) -> u32 {
+        span.ticks(self.config.frame_gap_ms)
+    }
+
+    // Time played in this many ticks. Ticks are a fixed frame_gap_ms apart,
+    // see Ticker, so this doesn't count banners or waiting for players.
+    fn play_time(&self, ticks: u32) -> Duration {
+        Duration::from_millis(ticks as u64 * self.config.frame_gap_ms)
+    }
+
+    // The playable area and obstacles, sent to bots when they connect.
+    // Protocol is: min_x(u32) min_y(u32) max_x(u32) max_y(u32) num_obstacles(u16)
+    // then x(u32) y(u32) for each obstacle.
+    // Bounds must match is_on_board.
+    fn map_info(&self) -> Vec<u8> {
+        let obstacles = self.obstacles();
+        let mut m = Vec::with_capacity(18 + obstacles.len() * 8);
+        for n in [1, 2, self.width - 1, self.height - 2].iter() {
+            m.extend_from_slice(&n.to_be_bytes());
+        }
+        m.extend_from_slice(&(obstacles.len() as u16).to_be_bytes());
+        for o in obstacles {
+            m.extend_from_slice(&o.x.to_be_bytes());
+            m.extend_from_slice(&o.y.to_be_bytes());
+        }
+        m
+    }
+
+    // Positions of the map's obstacles, not walls, which come and go
+    fn obstacles(&self) -> Vec<Pos> {
+        self.lifetime
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| **l == Lifetime::Solid)
+            .flat_map(|(id, _)| self.position[id].iter().copied())
+            .collect()
+    }
+
+    // Rebuild the world from a state frame as made by entity_state.
+    // Only what's needed for rendering is restored, so this is a world to look at,
+    // not to run systems on. Used by replay playback and spectating.
+    // A frame entity_state couldn't have made is a GameError::Protocol.
+    // Frames from protocol 5, before weapon tiers, load as tier 1.
+    fn load_state(&mut self, frame: &[u8], protocol: u8) -> Result<(), GameError> {
+        let bad = |msg: String| Err(GameError::Protocol(msg));
+        let status_len = match protocol {
+            ..8 => PLAYER_STATUS_LEN_V7,
+            8..14 => PLAYER_STATUS_LEN_V13,
+            14 => PLAYER_STATUS_LEN_V14,
+            _ => PLAYER_STATUS_LEN,
+        };
+        let header_len = 7 + 2 * status_len; // see entity_state
+        if frame.len() < header_len {
+            return bad(format!("state frame of {} bytes", frame.len()));
+        }
+        let (header, body) = frame.split_at(header_len);
+        let num_entities = u16::from_be_bytes([header[header_len - 2], header[header_len - 1]]) as usize;
+        let entity_len = match protocol {
+            ..6 => ENTITY_LEN_V5,
+            6..16 => ENTITY_LEN_V15,
+            _ => ENTITY_LEN,
+        };
+        let entities = body.chunks_exact(entity_len);
+        if entities.len() != num_entities || !entities.remainder().is_empty() {
+            return bad(format!("{} entities in {} bytes", num_entities, body.len()));
+        }
+
+        // nothing to compare lives with, spectators may join part way through
+        let is_first = self.name.is_empty();
+        let weapons_were = if is_first {
+            [None, None]
+        } else {
+            [self.active_weapon[self.player1], self.active_weapon[self.player2]]
+        };
+        self.clear_entities();
+        self.tick = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let mut player_num = 0;
+        for msg in entities {
+            // ids are only the order entities came in, which we keep
+            let msg = if protocol < 16 { &msg[1..] } else { &msg[2..] };
+            let p = Pos {
+                x: u32::from_be_bytes([msg[0], msg[1], msg[2], msg[3]]),
+                y: u32::from_be_bytes([msg[4], msg[5], msg[6], msg[7]]),
+                invalid: false,
+            };
+            let dir = match Dir::from_num(msg[8]) {
+                Some(d) => d,
+                None => return bad(format!("unknown direction {}", msg[8])),
+            };
+            let kind = match EntityKind::from_num(msg[11]) {
+                Some(k) => k,
+                None => return bad(format!("unknown entity kind {}", msg[11])),
+            };
+            let tier = msg.get(12).copied().unwrap_or(1);
+            if !(1..=MAX_TIER).contains(&tier) {
+                return bad(format!("weapon tier {}", tier));
+            }
+            match kind {
+                EntityKind::Player if player_num == 2 => return bad("more than two players".to_string()),
+                EntityKind::Player => {
+                    player_num += 1;
+                    let id = new_player(
+                        self,
+                        format!("Player {}", player_num),
+                        player_num.to_string(),
+                        player_num,
+                    );
+                    if player_num == 1 {
+                        self.player1 = id;
+                    } else {
+                        self.player2 = id;
+                    }
+                    // before the first round players are nowhere, at 0,0
+                    self.position[id][0] = Pos {
+                        invalid: !self.in_bounds(p),
+                        ..p
+                    };
+                }
+                // nothing else is ever off the board, and the code
+                // below assumes it isn't, so don't draw it
+                _ if !self.in_bounds(p) => continue,
+                EntityKind::Obstacle => new_bar(self, p, dir),
+                EntityKind::Missile | EntityKind::Ray | EntityKind::Beam | EntityKind::Cluster => {
+                    // lifetime doesn't matter, we only draw it
+                    let def = projectile::of_kind(kind).unwrap();
+                    let positions = projectile::positions(self, def, p, dir);
+                    add_projectile(self, def, positions, dir, 1, 0, tier);
+                }
+                EntityKind::Explosion => {
+                    let mut positions = Vec::new();
+                    explosion(self, p, tier, &mut positions);
+                    add_projectile(self, projectile::of(Weapon::Missile), positions, dir, 1, 0, tier);
+                    let id = self.name.len() - 1;
+                    self.explode[id].1 = true;
+                    self.kind[id] = EntityKind::Explosion;
+                }
+                EntityKind::Pickup if tier == PICKUP_BEAM => new_beam_pickup(self, p),
+                EntityKind::Pickup => new_pickup(self, p),
+                EntityKind::Marker => new_marker(self, p),
+                EntityKind::Supply => new_supply(self, p, tier),
+                EntityKind::Teleporter => new_teleporter(self, p),
+            }
+            let id = self.name.len() - 1;
+            self.velocity[id] = (msg[9], dir);
+            self.shield[id] = msg[10] == 1;
+            self.tier[id] = tier;
+        }
+        if player_num != 2 {
+            return bad(format!("{} players", player_num));
+        }
+
+        for (idx, &id) in [self.player1, self.player2].iter().enumerate() {
+            let status = &header[5 + idx * status_len..5 + (idx + 1) * status_len];
+            let lives = status[0] as u32;
+            if let Some(&points) = status.get(7) {
+                self.load_points(idx, points as u32);
+            } else {
+                // before points were sent, hits show as lives lost
+                let was = if idx == 0 { self.p1_lives } else { self.p2_lives };
+                match lives.cmp(&was) {
+                    _ if is_first => {}
+                    Ordering::Less => self.note_hit(idx),
+                    Ordering::Greater => self.hits.clear(), // a new match
+                    Ordering::Equal => {}
+                }
+            }
+            if idx == 0 {
+                self.p1_lives = lives;
+            } else {
+                self.p2_lives = lives;
+            }
+            self.energy[id] = u16::from_be_bytes([status[1], status[2]]) as u32;
+            self.active_weapon[id] = match Weapon::from_num(status[3]) {
+                Some(w) => Some(w),
+                None => return bad(format!("unknown weapon {}", status[3])),
+            };
+            if weapons_were[idx].is_some_and(|w| Some(w) != self.active_weapon[id]) {
+                self.weapon_changed[idx] = Some(self.tick);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Someone has won, or it's a draw, see victory.rs
+fn is_match_over(w: &World) -> bool {
+    w.win.outcome(w).is_some()
+}
+
+fn both_players_standing(w: &World) -> bool {
+    w.alive[w.player1] && w.alive[w.player2]
+}
+
+// entity ids of the living entitites
+fn alive_entities(w: &World) -> impl Iterator<Item = usize> + '_ {
+    w.alive
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, is_alive)| if *is_alive { Some(idx) } else { None })
+}
+
+fn new_player(w: &mut World, name: String, texture: String, color_idx: usize) -> usize {
+    let id = w.name.len();
+    w.name.push(name);
+    w.alive.push(true);
+    w.kind.push(EntityKind::Player);
+    w.lifetime.push(Lifetime::Permanent);
+    w.velocity.push((1, Dir::None));
+    w.sprite.push(Sprite {
+        color_idx,
+        is_bold: true,
+        _frame_num: 0,
+        texture_vertical: vec![texture.clone()],
+        texture_horizontal: vec![texture],
+        texture_explosion: vec![None],
+    });
+    w.energy.push(w.config.energy.max);
+    w.shield.push(false);
+    w.bounce.push(true);
     w.explode.push((false, false));
-    w.active_weapon.push(None);
+    w.active_weapon.push(Some(Weapon::Missile));
+    w.tier.push(1);
+    w.owner.push(None);
+    w.parent.push(None);
+    w.arming.push((0, false));
+    w.reflected.push(false);
+    w.grace.push(0);
+
+    // placeholder, set later in to_start_positions
+    w.position.push(vec![Pos::nil()]);
+
+    id
 }
 
 fn new_bar(w: &mut World, start_pos: Pos, dir: Dir) {
+    let [horizontal, vertical, ..] = w.config.glyphs.obstacles.chars();
     w.name.push(format!("Bar {}", w.name.len()));
     w.alive.push(true);
+    w.kind.push(EntityKind::Obstacle);
     w.lifetime.push(Lifetime::Solid);
     w.position.push(vec![start_pos]);
     w.velocity.push((0, dir));
@@ -486,8 +1464,8 @@ fn new_bar(w: &mut World, start_pos: Pos, dir: Dir) {
         color_idx: 0,
         is_bold: false,
         _frame_num: 0,
-        texture_vertical: vec!["┃".to_string()],
-        texture_horizontal: vec!["━".to_string()],
+        texture_vertical: vec![vertical.to_string()],
+        texture_horizontal: vec![horizontal.to_string()],
         texture_explosion: vec![Some("#".to_string())],
     });
     w.energy.push(0);
@@ -495,9 +1473,100 @@ fn new_bar(w: &mut World, start_pos: Pos, dir: Dir) {
     w.bounce.push(false);
     w.explode.push((false, false));
     w.active_weapon.push(None);
+    w.tier.push(1);
+    w.owner.push(None);
+    w.parent.push(None);
+    w.arming.push((0, false));
+    w.reflected.push(false);
+    w.grace.push(0);
+    w.solid.push(w.name.len() - 1);
+}
+
+// A player's wall, gone after ticks
+fn new_wall(w: &mut World, pos: Pos, dir: Dir, ticks: u32) {
+    new_bar(w, pos, dir);
+    let id = w.name.len() - 1;
+    w.name[id] = format!("Wall {}", id);
+    w.lifetime[id] = Lifetime::Temporary(ticks);
+}
+
+// A weapon upgrade, gone when the next one is due
+fn new_pickup(w: &mut World, pos: Pos) {
+    w.name.push(format!("Pickup {}", w.name.len()));
+    w.alive.push(true);
+    w.kind.push(EntityKind::Pickup);
+    w.lifetime.push(Lifetime::Temporary(w.ticks(w.config.weapons.pickup_every).max(1)));
+    w.position.push(vec![pos]);
+    w.velocity.push((0, Dir::None));
+    w.sprite.push(Sprite {
+        color_idx: 0,
+        is_bold: true,
+        _frame_num: 0,
+        texture_vertical: vec!["+".to_string()],
+        texture_horizontal: vec!["+".to_string()],
+        texture_explosion: vec![None],
+    });
+    w.energy.push(0);
+    w.shield.push(false);
+    w.bounce.push(false);
+    w.explode.push((false, false));
+    w.active_weapon.push(None);
+    w.tier.push(1);
+    w.owner.push(None);
+    w.parent.push(None);
+    w.arming.push((0, false));
+    w.reflected.push(false);
+    w.grace.push(0);
+}
+
+// A Weapon::Beam, gone when the next pickup is due
+fn new_beam_pickup(w: &mut World, pos: Pos) {
+    new_pickup(w, pos);
+    let id = w.name.len() - 1;
+    w.name[id] = format!("Beam pickup {}", id);
+    w.tier[id] = PICKUP_BEAM;
+    w.sprite[id].texture_vertical = vec!["B".to_string()];
+    w.sprite[id].texture_horizontal = vec!["B".to_string()];
+}
+
+// Where a supply drop will land, flashing until it does, see drop_supply
+fn new_marker(w: &mut World, pos: Pos) {
+    new_pickup(w, pos);
+    let id = w.name.len() - 1;
+    w.name[id] = format!("Marker {}", id);
+    w.kind[id] = EntityKind::Marker;
+    // drop_supply takes it away, on the tick after this would run out
+    w.lifetime[id] = Lifetime::Temporary(w.ticks(w.config.weapons.supply_warning) + 1);
+    w.sprite[id].texture_vertical = vec!["X".to_string()];
+    w.sprite[id].texture_horizontal = vec!["X".to_string()];
+}
+
+// A supply drop, gone when the next one lands. what is SUPPLY_ENERGY or
+// SUPPLY_LIFE, kept as its tier.
+fn new_supply(w: &mut World, pos: Pos, what: u8) {
+    new_pickup(w, pos);
+    let id = w.name.len() - 1;
+    w.name[id] = format!("Supply {}", id);
+    w.kind[id] = EntityKind::Supply;
+    w.lifetime[id] = Lifetime::Temporary(w.ticks(w.config.weapons.supply_every).max(1));
+    w.tier[id] = what;
+    let glyph = if what == SUPPLY_LIFE { "L" } else { "E" };
+    w.sprite[id].texture_vertical = vec![glyph.to_string()];
+    w.sprite[id].texture_horizontal = vec![glyph.to_string()];
 }
 
-#[derive(PartialEq)]
+// Sends whatever moves onto it to the opposite corner, see teleport
+fn new_teleporter(w: &mut World, pos: Pos) {
+    new_pickup(w, pos);
+    let id = w.name.len() - 1;
+    w.name[id] = format!("Teleporter {}", id);
+    w.kind[id] = EntityKind::Teleporter;
+    w.lifetime[id] = Lifetime::Permanent;
+    w.sprite[id].texture_vertical = vec!["O".to_string()];
+    w.sprite[id].texture_horizontal = vec!["O".to_string()];
+}
+
+#[derive(Debug, PartialEq)]
 enum Lifetime {
     Solid,          // obstacle: does not get damaged, stops things
     Permanent,      // player: always on screen
@@ -514,243 +1583,1005 @@ struct Sprite {
 }
 
 // MAIN
-pub fn run() -> Result<(), Box<dyn Error>> {
-    if DEBUG {
-        WriteLogger::init(
-            LevelFilter::Trace,
-            Config::default(),
-            File::create("hashbang.log").unwrap(),
-        )?;
+// Play on this terminal, or with opts.headless serve a game for bots only
+pub fn run(opts: Options) -> Result<(), GameError> {
+    opts.config.validate().map_err(GameError::Config)?;
+    debug!("Config:\n{}", opts.config.to_toml());
+
+    if opts.headless {
+        let width = opts.width.unwrap_or(options::HEADLESS_WIDTH);
+        let height = opts.height.unwrap_or(options::HEADLESS_HEIGHT);
+        session::with_output(no_display::new(width, height), |out| play(&opts, out))
+    } else {
+        let console = console::with_size(opts.width, opts.height, opts.config.screen)?;
+        let _terminal = session::TerminalGuard::new()?;
+        let sounds = sound::Sounds::new(&opts.config.sound);
+        let out = render::Threaded::new(console.with_sounds(sounds))?;
+        session::with_output(out, |out| play(&opts, out))
     }
+}
 
-    let mut out = console::new();
-    out.init()?;
-
-    out.banner(&[
+// If opts.record is set a replay of the game is written there. Otherwise
+// each match is kept in memory, and the player asked whether to save it.
+fn play<T: Output>(opts: &Options, out: &mut T) -> Result<(), GameError> {
+    let (keys, names) = (&opts.config.keys, &opts.config.names);
+    let daily_title = opts.daily.as_ref().map(|c| c.title());
+    let mut intro = vec![
         "R U S T   C O N S O L E   G A M E",
         "",
         "Instructions:",
-        "Player 1   Move: w a s d.    Fire: Shift + move direction. Toggle shield: e. Change weapon: q",
-        "Player 2   Move: Arrow keys. Fire: Alt + move direction. Toggle shield: . (period) Change weapon: , (comma)",
-        "",
-        "Esc to quit",
-        "Press any key to start",
-    ])?;
+    ];
+    let (help1, help2) = (
+        format!("{}   {}", names.player1, keys.player1.help()),
+        format!("{}   {}", names.player2, keys.player2.help()),
+    );
+    let mute = format!("{} for sound on and off. Esc to quit", keys.mute);
+    intro.extend_from_slice(&[&help1, &help2, "", &mute]);
+    let rules = victory::of(&opts.config.victory).describe();
+    if opts.config.victory.mode != config::Mode::Lives {
+        intro.extend_from_slice(&["", &rules]);
+    }
+    if let Some(t) = &daily_title {
+        intro.extend_from_slice(&["", t]);
+    }
+    if opts.debug {
+        intro.extend_from_slice(&["", "Debug mode: F2 opens the inspector, F3 shows the heatmap"]);
+    }
+    intro.push("Press any key to start");
+    out.banner(&intro)?;
 
     let (width, height) = out.dimensions()?;
-    let mut world = World {
-        // static
-        width: width as u32,
-        height: height as u32,
-        player1: 0,
-        player2: 0,
-        p1_lives: PLAYER_LIVES,
-        p2_lives: PLAYER_LIVES,
-        missile_range_horizontal: (width as u32 / 6).max(MISSILE_MIN_RANGE),
-        missile_range_vertical: (height as u32 / 5).max(MISSILE_MIN_RANGE),
-
-        name: Vec::new(),
-        alive: Vec::new(),
-        lifetime: Vec::new(),
-        sprite: Vec::new(),
-        velocity: Vec::new(),
-        position: Vec::new(),
-        energy: Vec::new(),
-        shield: Vec::new(),
-        bounce: Vec::new(),
-        explode: Vec::new(),
-        active_weapon: Vec::new(),
-        // remember to add to reset() as well
-    };
+    let mut world = World::with_config(width as u32, height as u32, opts.config.clone());
+    let seed = opts.seed.unwrap_or_else(rand::random);
+    let (map, density) = (world.config.map, world.config.density);
+    if map != MapKind::Bar && map != MapKind::Empty {
+        debug!("Map seed {}", seed); // to play the same map again
+    }
+    world.layout = maps::layout(map, density, world.width, world.height, seed);
     world.add_players();
     world.add_obstacles();
+    world.one_match = opts.daily.is_some();
+    world.serve = opts.headless;
+    world.debug = opts.debug;
+    if opts.debug {
+        world.tuning = opts.config_file.clone().map(tuning::Watcher::new);
+    }
+
+    let start = replay::start(world.width, world.height, map, seed, &world.config);
+    let recorder = match &opts.record {
+        Some(path) => {
+            let rec = replay::Recorder::create(path, &start)
+                .map_err(|e| io::Error::new(e.kind(), format!("replay file {}: {}", path.display(), e)))?;
+            Some(Arc::new(rec))
+        }
+        None if !opts.headless => Some(Arc::new(replay::Recorder::in_memory(&start))),
+        None => None,
+    };
+    let rec = recorder.as_deref();
+
+    let (ch_tx, ch_rx) = sync::mpsc::channel();
+    let mut inbox = input::Inbox::new(ch_rx);
+    let keyboard = if opts.headless {
+        None
+    } else {
+        Some(input::start(ch_tx.clone(), world.config.frame_gap_ms, opts.config.keys.clone(), inbox.asking()))
+    };
+
+    let token = session::token();
+    // what bots are sent after the handshake: tick_ms(u32) then the map
+    let mut welcome = (world.config.frame_gap_ms as u32).to_be_bytes().to_vec();
+    welcome.extend(world.map_info());
+    let srv1 = server::Server::new(
+        1,
+        opts.endpoint(Player::One),
+        ch_tx.clone(),
+        token.clone(),
+        recorder.clone(),
+        welcome.clone(),
+        opts.fake_latency,
+    );
+    let srv2 = server::Server::new(
+        2,
+        opts.endpoint(Player::Two),
+        ch_tx,
+        token.clone(),
+        recorder.clone(),
+        welcome,
+        opts.fake_latency,
+    );
+    if let Some(d) = opts.vs_ai {
+        start_ai(opts.endpoint(Player::Two), token, d.skill());
+    }
+    let spec = spectators::Spectators::new(opts.spectate_endpoint(), start);
 
-    let (ch_tx, mut ch_rx) = sync::mpsc::channel();
-    let (k_thread, k_stop) = input::start(ch_tx.clone(), FRAME_GAP_MS);
+    let metrics = metrics::Metrics::new();
+    if let Some(addr) = opts.metrics {
+        metrics.serve(addr)?;
+    }
 
-    let srv1 = server::Server::new(1, ch_tx.clone());
-    let srv2 = server::Server::new(2, ch_tx);
+    let srv = [&*srv1, &*srv2];
+    let res = matches(&mut world, out, &mut inbox, srv, rec, &spec, &metrics);
+    if let (Some(c), Ok(())) = (&opts.daily, &res) {
+        // unless they quit part way through
+        if is_match_over(&world) {
+            let r = daily::DailyResult::new(c, &match_record(&world));
+            if let Err(e) = daily::append(Path::new(daily::DAILY_FILE), &r) {
+                error!("daily: {}", e);
+            }
+        }
+    }
+    if let (Err(GameError::Stopped(why)), false) = (&res, opts.headless) {
+        // say why before the terminal is put back and we exit
+        let _ = out.banner(&[why, "Exiting"]);
+        thread::sleep(Duration::from_secs(ERROR_PAUSE_S));
+    }
 
-    while both_players_alive(&world) {
-        input::wait_for_keypress();
-        if game_loop(&mut world, &mut out, &mut ch_rx, [&srv1, &srv2])? {
-            break; // user pressed quit
+    if let Some(r) = rec {
+        r.finish();
+    }
+    for s in srv.iter() {
+        s.log_pings();
+    }
+    if let Some((k_thread, k_stop)) = keyboard {
+        k_stop.store(true, sync::atomic::Ordering::SeqCst);
+        k_thread.join().unwrap();
+    }
+
+    res
+}
+
+// Where matches() is up to
+enum Stage {
+    Match,      // playing one
+    Menu,       // it's over, asking what next
+    ChangeMode, // picked from the menu, back to it after
+    Rematch,    // same players and settings, scores carry on
+    Quit,
+}
+
+// Play matches until someone quits. After each one humans get a menu, and
+// bots can ask for a rematch from it. Serving, they follow each other until
+// we're killed.
+fn matches<T: Output>(
+    world: &mut World,
+    out: &mut T,
+    ch_rx: &mut input::Inbox,
+    srv: [&server::Server; 2],
+    rec: Option<&replay::Recorder>,
+    spec: &spectators::Spectators,
+    metrics: &metrics::Metrics,
+) -> Result<(), GameError> {
+    new_match(world);
+    let mut stage = Stage::Match;
+    loop {
+        stage = match stage {
+            Stage::Match => one_match(world, out, ch_rx, srv, rec, spec, metrics)?,
+            Stage::Menu => menu(world, out, ch_rx, srv, rec, spec)?,
+            Stage::ChangeMode => {
+                world.config.victory.mode = world.config.victory.mode.next();
+                world.win = victory::of(&world.config.victory);
+                Stage::Menu
+            }
+            Stage::Rematch => {
+                new_match(world);
+                world.reset();
+                Stage::Match
+            }
+            Stage::Quit => return Ok(()),
+        };
+    }
+}
+
+// Rounds until the match is over, then the winner, stats and replay. Returns
+// the stage after.
+fn one_match<T: Output>(
+    world: &mut World,
+    out: &mut T,
+    ch_rx: &mut input::Inbox,
+    srv: [&server::Server; 2],
+    rec: Option<&replay::Recorder>,
+    spec: &spectators::Spectators,
+    metrics: &metrics::Metrics,
+) -> Result<Stage, GameError> {
+    if let Some(r) = rec {
+        r.new_match();
+    }
+    while !is_match_over(world) {
+        if wait_for_players(world, ch_rx, srv, rec, spec, metrics)? {
+            return Ok(Stage::Quit); // user pressed quit
+        }
+        if game_loop(world, out, ch_rx, srv, rec, spec, metrics)? {
+            return Ok(Stage::Quit);
         }
 
         // game over?
-        if !both_players_alive(&world) {
+        if is_match_over(world) {
             break;
         }
 
         // a player must have been hit, freeze the screen
         let p1a = world.alive[world.player1];
         let p2a = world.alive[world.player2];
-        let name = if !p1a && !p2a {
-            let mut s = world.name[world.player1].clone();
-            s.push_str(" and ");
-            s.push_str(&world.name[world.player2]);
-            s
+        let msg = if !p1a && !p2a {
+            "Draw!".to_string()
         } else if !p1a {
-            world.name[world.player1].clone()
+            format!("{} hit!", world.name[world.player1])
         } else {
-            world.name[world.player2].clone()
+            format!("{} hit!", world.name[world.player2])
+        };
+        let next = if world.tiebreaker {
+            "Tiebreaker round, press any key to continue"
+        } else {
+            "Press any key to continue"
         };
         thread::sleep(Duration::from_millis(HIT_PAUSE_MS));
-        out.banner(&[&format!("{} hit!", &name), "Press any key to continue"])?;
+        out.banner(&[&msg, next])?;
         thread::sleep(Duration::from_secs(BANNER_PAUSE_S));
 
         world.reset();
     }
 
-    if !both_players_alive(&world) {
-        winner_banner(&mut world, &mut out)?;
+    winner_banner(world, out)?;
+    record_stats(world);
+    // nobody to ask in a game between bots
+    if let Some(r) = rec.filter(|r| r.is_in_memory() && connected(srv) < 2) {
+        if offer_replay(world, out, ch_rx, srv, r, spec)? {
+            return Ok(Stage::Quit);
+        }
+    }
+    if world.one_match {
+        return Ok(Stage::Quit);
+    }
+    keep_score(world);
+    // one GameOver frame so bots and the replay see the match end
+    broadcast(world, &mut frames::FramePool::default(), srv, rec, spec, GameState::GameOver);
+    if world.serve {
+        // bots only have to be Ready, even if they went and came back in between
+        return Ok(Stage::Rematch);
+    }
+    Ok(Stage::Menu)
+}
+
+// AI against AI for `rounds` rounds as fast as possible, reporting on stdout
+// how big the World gets and where the time goes. For `serve --soak`.
+pub fn soak(opts: Options, rounds: u32) -> Result<(), GameError> {
+    opts.config.validate().map_err(GameError::Config)?;
+    soak::run(&opts, rounds, &mut io::stdout().lock())
+}
+
+// Play player 2 with the reference bot, for --vs-ai
+fn start_ai(endpoint: Endpoint, token: Option<String>, skill: Skill) {
+    thread::spawn(move || {
+        // the server may not be listening yet
+        let c = ai_connector(Player::Two, endpoint, token).retries(AI_RETRIES);
+        if let Err(e) = run_bot_with(c, Hunter::new(Player::Two, Player::One, skill)) {
+            error!("AI player: {}", e);
+        }
+    });
+}
+
+// Play the reference bot against a game running elsewhere, for the `bot`
+// subcommand. If record is set everything the game sends is saved there.
+pub fn bot(player: Player, skill: Skill, endpoint: Endpoint, record: Option<PathBuf>) -> Result<(), GameError> {
+    let opponent = match player {
+        Player::One => Player::Two,
+        Player::Two => Player::One,
+    };
+    let mut c = ai_connector(player, endpoint, session::token());
+    if let Some(path) = record {
+        c = c.record_to(path);
     }
-    out.cleanup()?;
-    k_stop.store(true, sync::atomic::Ordering::SeqCst);
-    k_thread.join().unwrap();
+    run_bot_with(c, Hunter::new(player, opponent, skill))?;
+    Ok(())
+}
 
+fn ai_connector(player: Player, endpoint: Endpoint, token: Option<String>) -> Connector {
+    let c = Connector::new(player).endpoint(endpoint);
+    match token {
+        Some(t) => c.token(&t),
+        None => c,
+    }
+}
+
+// Watch a replay file made by `run` with `record` set
+pub fn replay(path: &Path, palette: Option<Palette>, momentum: bool, labels: bool) -> Result<(), GameError> {
+    playback::run(path, palette, momentum, labels)
+}
+
+// Print each round's shots, hits and energy from a replay file, and where
+// the players spent their time
+pub fn analyze(path: &Path) -> Result<(), GameError> {
+    analyze::run(path, &mut io::stdout().lock())
+}
+
+// Watch a game being played, by `run` on this or another machine
+pub fn spectate(endpoint: &Endpoint, palette: Option<Palette>, momentum: bool, labels: bool) -> Result<(), GameError> {
+    playback::spectate(endpoint, palette, momentum, labels)
+}
+
+// Play today's daily challenge against the built in bot, then show how it went
+pub fn daily(mut opts: Options) -> Result<(), GameError> {
+    let c = daily::Challenge::today();
+    c.apply(&mut opts.config);
+    opts.width = Some(daily::WIDTH);
+    opts.height = Some(daily::HEIGHT);
+    opts.seed = Some(c.seed);
+    opts.vs_ai = Some(Difficulty::Normal);
+    let player = opts.config.names.player1.clone();
+    opts.daily = Some(c.clone());
+    run(opts)?;
+
+    let results = daily::load(Path::new(daily::DAILY_FILE)).map_err(GameError::Config)?;
+    daily::show(&mut std::io::stdout(), &c, &player, &results)?;
     Ok(())
 }
 
-fn winner_banner<T: Output>(w: &mut World, out: &mut T) -> Result<(), Box<dyn Error>> {
-    let winner = if w.p1_lives == 0 {
-        &w.name[w.player2]
-    } else {
-        &w.name[w.player1]
+// How far missiles go before exploding, horizontal and vertical, on a board this size
+fn missile_ranges(width: u32, height: u32, min_range: u32) -> (u32, u32) {
+    ((width / 6).max(min_range), (height / 5).max(min_range))
+}
+
+// Energy costs and weapons from the config file, which changed, see tuning.rs
+fn retune(w: &mut World, c: GameConfig) {
+    w.config.energy = c.energy;
+    w.config.weapons = c.weapons;
+    let (h, v) = missile_ranges(w.width, w.height, w.config.weapons.missile_min_range);
+    w.missile_range_horizontal = h;
+    w.missile_range_vertical = v;
+    info!("Config reloaded:\n{}", w.config.to_toml());
+    w.note("Config reloaded".to_string());
+}
+
+// Full lives, and the clock and stats start again
+fn new_match(w: &mut World) {
+    w.p1_lives = w.config.lives;
+    w.p2_lives = w.config.lives;
+    w.shots = [0, 0];
+    w.feed.clear();
+    w.hits.clear();
+    w.match_start = Some(w.tick);
+    w.round_ticks.clear();
+    w.tiebreaker = false;
+}
+
+// The match that just finished
+fn match_record(w: &World) -> stats::MatchRecord {
+    let winner = match w.win.outcome(w) {
+        Some(Outcome::Won(idx)) => Some(idx),
+        _ => None,
     };
-    out.banner(&[&format!("{} wins!", winner)])?;
+    stats::MatchRecord::new(
+        [w.name[w.player1].clone(), w.name[w.player2].clone()],
+        winner,
+        [w.p1_lives, w.p2_lives],
+        w.points(),
+        w.shots,
+        w.tick - w.match_start.unwrap_or(w.tick),
+        w.config.frame_gap_ms,
+    )
+}
+
+// Count the match that just finished towards the running score, and log it
+fn keep_score(w: &mut World) {
+    let winner = match w.win.outcome(w) {
+        Some(Outcome::Won(idx)) => idx,
+        _ => 2,
+    };
+    w.score[winner] += 1;
+    let [p1, p2, draws] = w.score;
+    info!(
+        "Match {}: {} {} - {} {}, {} drawn",
+        p1 + p2 + draws,
+        w.name[w.player1],
+        p1,
+        p2,
+        w.name[w.player2],
+        draws
+    );
+}
+
+// Add the match that just finished to the stats file. Best effort, like replays.
+fn record_stats(w: &World) {
+    let path = match session::stats_path() {
+        Some(p) => p,
+        None => {
+            error!("stats: no data directory, set {}", session::STATS_FILE_ENV);
+            return;
+        }
+    };
+    if let Err(e) = stats::append(&path, &match_record(w)) {
+        error!("stats: {}", e);
+    }
+}
+
+// Print the leaderboard and head to head records from the stats file
+pub fn show_stats() -> Result<(), GameError> {
+    let path = session::stats_path()
+        .ok_or_else(|| GameError::Config(format!("no data directory, set {}", session::STATS_FILE_ENV)))?;
+    let matches = stats::load(&path).map_err(GameError::Config)?;
+    stats::show(&mut std::io::stdout(), &path, &matches)?;
+    Ok(())
+}
+
+fn winner_banner<T: Output>(w: &mut World, out: &mut T) -> Result<(), GameError> {
+    let msg = match w.win.outcome(w) {
+        Some(Outcome::Won(0)) => format!("{} wins!", w.name[w.player1]),
+        Some(Outcome::Won(_)) => format!("{} wins!", w.name[w.player2]),
+        _ => "Draw!".to_string(),
+    };
+    let times = match w.round_ticks.iter().max() {
+        Some(&longest) => format!(
+            "Match time {}. {} rounds, longest {}",
+            clock_time(w.play_time(w.tick - w.match_start.unwrap_or(w.tick))),
+            w.round_ticks.len(),
+            clock_time(w.play_time(longest))
+        ),
+        None => String::new(),
+    };
+    w.cues.clear();
+    w.cues.push(Cue::Win);
+    out.render(w.view())?;
+    out.banner(&[&msg, "", &times])?;
     thread::sleep(Duration::from_secs(2));
     Ok(())
 }
 
+// Ask whether to save the match that just finished. No answer is no, so a
+// game left unattended carries on. Bots are sent the world meanwhile, as in
+// wait_for_players. Returns Ok(true) when it's time to exit.
+fn offer_replay<T: Output>(
+    w: &World,
+    out: &mut T,
+    input_ch: &mut input::Inbox,
+    srv: [&server::Server; 2],
+    rec: &replay::Recorder,
+    spec: &spectators::Spectators,
+) -> Result<bool, GameError> {
+    // ignore anything pressed during the winner banner
+    for ie in input_ch.drain() {
+        match ie {
+            InputEvent::Quit => return Ok(true),
+            InputEvent::Stopped(why) => return Err(GameError::Stopped(why)),
+            _ => {}
+        }
+    }
+    out.banner(&["Save replay? [y/n]"])?;
+    input_ch.ask(true);
+    let answer = answer(w, input_ch, srv, spec);
+    input_ch.ask(false);
+    match answer? {
+        Some(true) => {}
+        Some(false) => return Ok(false),
+        None => return Ok(true),
+    }
+
+    let name = replay::timestamped_name();
+    let msg = match rec.save(Path::new(&name)) {
+        Ok(()) => format!("Saved {}", name),
+        Err(e) => {
+            error!("replay save {}: {}", name, e);
+            format!("Couldn't save {}: {}", name, e)
+        }
+    };
+    out.banner(&[&msg])?;
+    thread::sleep(Duration::from_secs(BANNER_PAUSE_S));
+    Ok(false)
+}
+
+// y or n to the question on the screen, no answer in SAVE_PROMPT_S being no.
+// None if it's time to exit.
+fn answer(
+    w: &World,
+    input_ch: &mut input::Inbox,
+    srv: [&server::Server; 2],
+    spec: &spectators::Spectators,
+) -> Result<Option<bool>, GameError> {
+    let deadline = Instant::now() + Duration::from_secs(SAVE_PROMPT_S);
+    let mut frames = frames::FramePool::default();
+    while Instant::now() < deadline {
+        // not to the replay, it's finished
+        broadcast(w, &mut frames, srv, None, spec, GameState::GameOver);
+        match input_ch.recv_timeout(Duration::from_millis(w.config.frame_gap_ms)) {
+            Ok(InputEvent::Answer(yes)) => return Ok(Some(yes)),
+            Ok(InputEvent::Quit) | Err(sync::mpsc::RecvTimeoutError::Disconnected) => return Ok(None),
+            Ok(InputEvent::Stopped(why)) => return Err(GameError::Stopped(why)),
+            Ok(_) | Err(sync::mpsc::RecvTimeoutError::Timeout) => {}
+        }
+    }
+    Ok(Some(false))
+}
+
+// Ask what next after a match: r for a rematch, m to change how it's won, q
+// or Esc to quit. Two bots can start a rematch by both sending Rematch.
+// Meanwhile bots are sent the world, as in wait_for_players.
+fn menu<T: Output>(
+    w: &World,
+    out: &mut T,
+    input_ch: &mut input::Inbox,
+    srv: [&server::Server; 2],
+    rec: Option<&replay::Recorder>,
+    spec: &spectators::Spectators,
+) -> Result<Stage, GameError> {
+    // ignore anything pressed during the winner banner
+    for ie in input_ch.drain() {
+        match ie {
+            InputEvent::Quit => return Ok(Stage::Quit),
+            InputEvent::Stopped(why) => return Err(GameError::Stopped(why)),
+            _ => {}
+        }
+    }
+    let [p1, p2, draws] = w.score;
+    let score = match draws {
+        0 => format!("{} {} - {} {}", w.name[w.player1], p1, p2, w.name[w.player2]),
+        _ => format!("{} {} - {} {}, {} drawn", w.name[w.player1], p1, p2, w.name[w.player2], draws),
+    };
+    let mode = format!("m  Change mode: {}", w.win.describe());
+    out.banner(&[&score, "", "r  Rematch", &mode, "q  Quit"])?;
+
+    let mut agreed = [false, false];
+    let mut frames = frames::FramePool::default();
+    while !(agreed[0] && agreed[1] && srv.iter().all(|s| s.is_connected())) {
+        // the replay already has the GameOver frame
+        broadcast(w, &mut frames, srv, None, spec, GameState::GameOver);
+        let received = input_ch.recv_timeout(Duration::from_millis(w.config.frame_gap_ms));
+        if let (Ok(ie), Some(r)) = (&received, rec) {
+            r.event(w.tick, ie);
+        }
+        match received {
+            Ok(InputEvent::Menu('r')) => break,
+            Ok(InputEvent::Menu('m')) => return Ok(Stage::ChangeMode),
+            Ok(InputEvent::Menu('q')) | Ok(InputEvent::Quit) | Err(sync::mpsc::RecvTimeoutError::Disconnected) => {
+                return Ok(Stage::Quit);
+            }
+            Ok(InputEvent::Stopped(why)) => return Err(GameError::Stopped(why)),
+            Ok(InputEvent::Rematch { entity_id }) => {
+                agreed[entity_id as usize - 1] = true;
+            }
+            Ok(_) | Err(sync::mpsc::RecvTimeoutError::Timeout) => {}
+        }
+    }
+    Ok(Stage::Rematch)
+}
+
+// Block until a key is pressed, or both players are bots and have both sent
+// Ready. Meanwhile bots are sent the world every frame so they know what
+// state we're in. Returns Ok(true) when it's time to exit.
+fn wait_for_players(
+    w: &mut World,
+    input_ch: &mut input::Inbox,
+    srv: [&server::Server; 2],
+    rec: Option<&replay::Recorder>,
+    spec: &spectators::Spectators,
+    metrics: &metrics::Metrics,
+) -> Result<bool, GameError> {
+    // ignore anything pressed during the previous round or banner pause
+    for ie in input_ch.drain() {
+        match ie {
+            InputEvent::Quit => return Ok(true),
+            InputEvent::Stopped(why) => return Err(GameError::Stopped(why)),
+            _ => {}
+        }
+    }
+
+    let mut agreed = [false, false];
+    let mut frames = frames::FramePool::default();
+    while !(agreed[0] && agreed[1] && srv.iter().all(|s| s.is_connected())) {
+        broadcast(w, &mut frames, srv, rec, spec, GameState::Waiting);
+        metrics.set_clients(connected(srv), spec.count());
+
+        let received = input_ch.recv_timeout(Duration::from_millis(w.config.frame_gap_ms));
+        if let Ok(ie) = &received {
+            metrics.command();
+            if let Some(r) = rec {
+                r.event(w.tick, ie);
+            }
+        }
+        match received {
+            Ok(InputEvent::Quit) | Err(sync::mpsc::RecvTimeoutError::Disconnected) => {
+                return Ok(true);
+            }
+            Ok(InputEvent::Stopped(why)) => return Err(GameError::Stopped(why)),
+            // a human starts the round
+            Ok(InputEvent::KeyPress) => return Ok(false),
+            Ok(InputEvent::Ready { entity_id }) => {
+                agreed[entity_id as usize - 1] = true;
+            }
+            Ok(_) | Err(sync::mpsc::RecvTimeoutError::Timeout) => {}
+        }
+    }
+    Ok(false)
+}
+
+// frame, with player idx's energy and cooldown zeroed and flagged hidden,
+// for the other's bot when opponent_energy is off
+fn hide_energy(frame: &[u8], idx: usize, buf: &mut Vec<u8>) {
+    buf.clear();
+    buf.extend_from_slice(frame);
+    let status = 5 + idx * PLAYER_STATUS_LEN; // after tick(u32) game_state(u8), see entity_state
+    buf[status + 1..status + 3].fill(0); // energy
+    buf[status + 4..status + 6].fill(0); // cooldown
+    buf[status + 8] = 1;
+}
+
+// How many bots are playing
+fn connected(srv: [&server::Server; 2]) -> usize {
+    srv.iter().filter(|s| s.is_connected()).count()
+}
+
+// Send the world to bots and spectators, and to the replay if we're recording.
+// The state frame is built once, in a buffer from frames, and shared. Returns
+// it, for the snapshots.
+fn broadcast(
+    w: &World,
+    frames: &mut frames::FramePool,
+    srv: [&server::Server; 2],
+    rec: Option<&replay::Recorder>,
+    spec: &spectators::Spectators,
+    state: GameState,
+) -> frames::Shared {
+    let frame = frames.share(|buf| w.entity_state(state, buf));
+    for (idx, s) in srv.iter().enumerate() {
+        if w.config.opponent_energy {
+            s.send_state(w.tick, &frame);
+        } else {
+            s.send_state(w.tick, &frames.share(|buf| hide_energy(&frame, 1 - idx, buf)));
+        }
+    }
+    spec.send_state(w.tick, &frame);
+    if let Some(r) = rec {
+        r.state(w.tick, &frame);
+    }
+    frame
+}
+
 // Returns Ok(true) when it's time to exit
 fn game_loop<T: Output>(
     w: &mut World,
     out: &mut T,
-    input_ch: &mut sync::mpsc::Receiver<InputEvent>,
+    input_ch: &mut input::Inbox,
     srv: [&server::Server; 2],
-) -> Result<bool, Box<dyn Error>> {
-    w.alive[w.player1] = true;
-    w.alive[w.player2] = true;
-
-    let mut system = [
-        System::Move,
-        System::Lifetime,
-        System::Collision,
-        System::EnergyReload(0),
-        System::Explode,
-    ];
+    rec: Option<&replay::Recorder>,
+    spec: &spectators::Spectators,
+    metrics: &metrics::Metrics,
+) -> Result<bool, GameError> {
+    let mut system = start_round(w);
+    if out.is_scrolling() {
+        intro_pan(w, out)?;
+    }
+    for s in srv.iter() {
+        s.start_idle_clock();
+    }
     let render = Render {};
-
-    to_start_positions(w);
+    let mut frames = frames::FramePool::default();
+    let mut ticker = ticker::Ticker::new(if DEBUG_SPEED {
+        Duration::from_secs(1)
+    } else {
+        Duration::from_millis(w.config.frame_gap_ms)
+    });
 
     let mut is_quit = false;
-    while !is_quit && both_players_standing(w) {
-        for ie in input_ch.try_iter() {
-            // for ie in input::events()? {
+    while !is_quit && both_players_standing(w) && !w.win.is_time_up(w) {
+        let tick_start = Instant::now();
+        w.cues.clear();
+        let events = input_ch.tick();
+        for ie in &events {
+            metrics.command();
+            if let Some(r) = rec {
+                r.event(w.tick, ie);
+            }
             match ie {
                 InputEvent::Quit => {
                     is_quit = true;
                     break;
                 }
-
-                InputEvent::Move { entity_id: 1, dir } => {
-                    let cur = &mut w.velocity[w.player1].1;
-                    if cur.opposite() == dir {
-                        *cur = Dir::None;
-                    } else {
-                        *cur = dir;
-                    }
+                InputEvent::Stopped(why) => return Err(GameError::Stopped(why.clone())),
+                InputEvent::ToggleMute => w.muted = !w.muted,
+                InputEvent::ToggleInspector if w.debug => {
+                    w.inspector = if w.inspector.is_some() { None } else { Some(0) };
                 }
-                InputEvent::Move { entity_id: 2, dir } => {
-                    let cur = &mut w.velocity[w.player2].1;
-                    if cur.opposite() == dir {
-                        *cur = Dir::None;
-                    } else {
-                        *cur = dir;
+                InputEvent::ScrollInspector(n) => {
+                    if let Some(scroll) = w.inspector.as_mut() {
+                        // inspect stops it past the end
+                        *scroll = scroll.saturating_add_signed(*n).min(w.name.len());
                     }
                 }
-
-                InputEvent::ToggleShield { entity_id: 1 } => {
-                    w.shield[w.player1] = !w.shield[w.player1];
-                }
-                InputEvent::ToggleShield { entity_id: 2 } => {
-                    w.shield[w.player2] = !w.shield[w.player2];
-                }
-
-                InputEvent::ChangeWeapon { entity_id: 1 } => {
-                    w.active_weapon[w.player1].as_mut().unwrap().next();
-                }
-                InputEvent::ChangeWeapon { entity_id: 2 } => {
-                    w.active_weapon[w.player2].as_mut().unwrap().next();
-                }
-
-                InputEvent::Fire { entity_id, dir } => {
-                    let id = match entity_id {
-                        1 => w.player1,
-                        2 => w.player2,
-                        _ => panic!("impossible player id"),
-                    };
-                    let mut pos = w.position[id][0];
-
-                    // if firing forward move ahead of the player
-                    if dir == w.velocity[id].1 {
-                        pos = pos.moved(1, dir);
-                        if !w.is_on_board(pos) {
-                            continue;
-                        }
-                    }
-
-                    let e = w.energy[id];
-                    match w.active_weapon[id].as_ref().unwrap() {
-                        Weapon::Missile => {
-                            if e > ENERGY_MISSILE {
-                                new_missile(w, pos, dir, w.sprite[id].color_idx);
-                                w.energy[id] -= ENERGY_MISSILE;
-                            }
-                        }
-                        Weapon::Ray => {
-                            if e > ENERGY_RAY {
-                                new_ray(w, pos, dir, w.sprite[id].color_idx);
-                                w.energy[id] -= ENERGY_RAY;
-                            }
-                        }
+                InputEvent::ToggleHeatmap if w.debug => w.heatmap = !w.heatmap,
+                InputEvent::SaveSnapshots if w.debug => {
+                    if let Some(r) = rec {
+                        save_snapshots(w, r.start());
                     }
                 }
-                _ => panic!("entity_id not 1 or 2, shouldn't happen"),
+                _ => {} // the players', below
             }
         } // end input event handling
 
         if is_quit {
             continue;
         }
+        w.bots = [srv[0].is_connected(), srv[1].is_connected()];
+        let intents = match w.turns.as_mut() {
+            Some(t) => t.next(&events, w.bots),
+            None => Some(input::intents(&events)),
+        };
+        let Some(intents) = intents else {
+            // a player is picking their turn. Bots need frames to reply to,
+            // the replay and snapshots don't need them over and over.
+            broadcast(w, &mut frames, srv, None, spec, GameState::Playing);
+            kick_idle(w, srv);
+            render.render(w, out)?;
+            ticker.wait();
+            continue;
+        };
+        for (entity_id, i) in (1..=2).zip(intents) {
+            apply_intent(w, entity_id, i);
+        }
+        if let Some(c) = w.tuning.as_mut().and_then(|t| t.poll()) {
+            retune(w, c);
+        }
 
         for s in system.iter_mut() {
+            let t0 = Instant::now();
             s.step(w);
+            w.timings.add(s.name(), t0.elapsed());
+        }
+        if w.debug {
+            heatmap::remember(w);
         }
-        render.render(w, out);
 
-        // update bots
-        let es = w.entity_state();
-        for s in srv.iter() {
-            s.send_state(&es);
+        // bots first, they're waiting on us
+        let frame = broadcast(w, &mut frames, srv, rec, spec, GameState::Playing);
+        w.snapshots.push(w.tick, frame);
+        for (p, s) in w.pings.iter_mut().zip(srv.iter()) {
+            *p = s.ping();
         }
+        kick_idle(w, srv);
+        let t0 = Instant::now();
+        render.render(w, out)?;
+        w.timings.add("render", t0.elapsed());
+        let work = tick_start.elapsed();
+        w.timings.check(w.tick, work, Duration::from_millis(w.config.frame_gap_ms));
+        w.tick += 1;
+
+        metrics.tick(work, alive_entities(w).count(), connected(srv), spec.count());
+        metrics.stages(w.timings.stages());
+        ticker.wait();
+    }
 
-        if DEBUG_SPEED {
-            thread::sleep(Duration::from_secs(1));
-        } else {
-            thread::sleep(Duration::from_millis(FRAME_GAP_MS));
+    end_round(w);
+
+    Ok(is_quit)
+}
+
+// Open the seat of a bot that's stopped talking to us, e.g. it hung, so
+// another can connect and play on as that player
+fn kick_idle(w: &mut World, srv: [&server::Server; 2]) {
+    let limit = Duration::from_secs(w.config.idle_seconds.into());
+    for (idx, s) in srv.iter().enumerate() {
+        let name = w.name[[w.player1, w.player2][idx]].clone();
+        if w.config.idle_seconds > 0 && s.kick_if_idle(limit) {
+            w.note(format!("{}'s bot went quiet, seat open", name));
+        }
+        if w.idle[idx] && !s.is_idle() {
+            w.note(format!("A new bot took over {}", name));
+        }
+        w.idle[idx] = s.is_idle();
+    }
+}
+
+// F4 in debug mode: the last few ticks as a replay, the game goes on
+fn save_snapshots(w: &mut World, start: &[u8]) {
+    let name = replay::timestamped_name();
+    let msg = match w.snapshots.save(Path::new(&name), start) {
+        Ok(()) => format!("Saved the last {} ticks to {}", w.snapshots.iter().count(), name),
+        Err(e) => {
+            error!("snapshots save {}: {}", name, e);
+            format!("Couldn't save {}: {}", name, e)
         }
+    };
+    w.note(msg);
+}
+
+// Players back to their corners with tier 1 weapons, and fresh systems for the round
+fn start_round(w: &mut World) -> [System; 6] {
+    w.alive[w.player1] = true;
+    w.alive[w.player2] = true;
+    w.tier[w.player1] = 1;
+    w.tier[w.player2] = 1;
+    to_start_positions(w);
+    w.round_start = w.tick;
+    w.turns = (w.config.turn_ticks > 0).then(|| turns::Turns::new(w.config.turn_ticks));
+    systems()
+}
+
+// On an arena bigger than the screen, show where everyone starts before the
+// round does: the camera goes from player 1 to player 2, past whatever is
+// between them, then the round follows the players as usual
+fn intro_pan<T: Output>(w: &World, out: &mut T) -> Result<(), GameError> {
+    let gap = w.config.frame_gap_ms.max(1);
+    let frames = (PAN_MS / gap).max(1) as i64;
+    let (from, to) = (w.position[w.player1][0], w.position[w.player2][0]);
+    for n in 0..=frames {
+        let along = |a: u32, b: u32| (i64::from(a) + (i64::from(b) - i64::from(a)) * n / frames) as u16;
+        let mut v = w.view();
+        v.focus = (along(from.x, to.x), along(from.y, to.y));
+        out.render(v)?;
+        thread::sleep(Duration::from_millis(gap));
     }
+    Ok(())
+}
 
+// Every system, in the order they run each tick
+fn systems() -> [System; 6] {
+    [
+        System::Move,
+        System::Lifetime,
+        System::Collision,
+        System::EnergyReload(0),
+        System::Explode,
+        System::Pickup(0, 0),
+    ]
+}
+
+// Whoever got hit loses a life. If both lose their last one in the same
+// tick, they get one back each for a tiebreaker round, until someone wins it.
+fn end_round(w: &mut World) {
+    w.round_ticks.push(w.tick - w.round_start);
+    let takes_lives = w.win.takes_lives();
     if !w.alive[w.player1] {
-        w.p1_lives -= 1;
+        if takes_lives {
+            w.p1_lives -= 1;
+        }
+        w.note_hit(0);
     }
     if !w.alive[w.player2] {
-        w.p2_lives -= 1;
+        if takes_lives {
+            w.p2_lives -= 1;
+        }
+        w.note_hit(1);
+    }
+    if w.p1_lives == 0 && w.p2_lives == 0 {
+        w.p1_lives = 1;
+        w.p2_lives = 1;
+        w.tiebreaker = true;
+        w.note("Tiebreaker round".to_string());
     }
+}
 
-    Ok(is_quit)
+// Apply a player's move, fire, etc to the world
+fn handle_event(w: &mut World, ie: InputEvent) {
+    match ie {
+        InputEvent::Move { entity_id: 1, dir } => {
+            let cur = &mut w.velocity[w.player1].1;
+            if cur.opposite() == dir {
+                *cur = Dir::None;
+            } else {
+                *cur = dir;
+                w.facing[0] = dir;
+            }
+        }
+        InputEvent::Move { entity_id: 2, dir } => {
+            let cur = &mut w.velocity[w.player2].1;
+            if cur.opposite() == dir {
+                *cur = Dir::None;
+            } else {
+                *cur = dir;
+                w.facing[1] = dir;
+            }
+        }
+
+        InputEvent::ToggleShield { entity_id: 1 } => {
+            w.shield[w.player1] = !w.shield[w.player1];
+            w.cues.push(Cue::Shield);
+        }
+        InputEvent::ToggleShield { entity_id: 2 } => {
+            w.shield[w.player2] = !w.shield[w.player2];
+            w.cues.push(Cue::Shield);
+        }
+
+        InputEvent::ChangeWeapon { entity_id: 1 } => {
+            w.active_weapon[w.player1].as_mut().unwrap().next();
+            w.weapon_changed[0] = Some(w.tick);
+        }
+        InputEvent::ChangeWeapon { entity_id: 2 } => {
+            w.active_weapon[w.player2].as_mut().unwrap().next();
+            w.weapon_changed[1] = Some(w.tick);
+        }
+
+        InputEvent::DropWall { entity_id } => {
+            let id = match entity_id {
+                1 => w.player1,
+                2 => w.player2,
+                _ => panic!("impossible player id"),
+            };
+            let cost = w.config.energy.wall;
+            if w.energy[id] > cost && drop_wall(w, id, entity_id as usize - 1) {
+                w.energy[id] -= cost;
+            }
+        }
+
+        InputEvent::Fire { entity_id, dir } => {
+            let id = match entity_id {
+                1 => w.player1,
+                2 => w.player2,
+                _ => panic!("impossible player id"),
+            };
+            let (pos, idx) = (w.position[id][0], entity_id as usize - 1);
+            let weapon = w.active_weapon[id].unwrap();
+            let def = projectile::of(weapon);
+            let cost = (def.cost)(&w.config.energy);
+            if w.energy[id] > cost && spawn_projectile(w, def, pos, dir, id) {
+                w.energy[id] -= cost;
+                w.shots[idx] += 1;
+                w.cues.push(Cue::Fire);
+                if weapon.is_super() {
+                    w.active_weapon[id] = Some(w.spare_weapon[idx].take().unwrap_or(Weapon::Missile));
+                    w.weapon_changed[idx] = Some(w.tick);
+                }
+            }
+        }
+
+        // only used between rounds, or handled by the caller
+        InputEvent::Ready { .. }
+        | InputEvent::Rematch { .. }
+        | InputEvent::KeyPress
+        | InputEvent::ToggleMute
+        | InputEvent::ToggleInspector
+        | InputEvent::ScrollInspector(_)
+        | InputEvent::ToggleHeatmap
+        | InputEvent::SaveSnapshots
+        | InputEvent::EndTurn
+        | InputEvent::Answer(_)
+        | InputEvent::Quit
+        | InputEvent::Stopped(_) => {}
+
+        _ => panic!("entity_id not 1 or 2, shouldn't happen"),
+    }
+}
+
+// Do what a player asked for this tick, see input::intents. Returns true if it fired something, not
+// enough energy means nothing is.
+pub(crate) fn apply_intent(w: &mut World, entity_id: u8, i: Intent) -> bool {
+    if let Some(dir) = i.dir {
+        handle_event(w, InputEvent::Move { entity_id, dir });
+    }
+    if i.toggle_shield {
+        handle_event(w, InputEvent::ToggleShield { entity_id });
+    }
+    if i.change_weapon {
+        handle_event(w, InputEvent::ChangeWeapon { entity_id });
+    }
+    if i.drop_wall {
+        handle_event(w, InputEvent::DropWall { entity_id });
+    }
+    match i.fire {
+        Some(dir) => {
+            let before = w.name.len();
+            handle_event(w, InputEvent::Fire { entity_id, dir });
+            w.name.len() > before
+        }
+        None => false,
+    }
+}
+
+// Put up a wall across the square behind player id, idx 0 or 1, the way
+// they last moved. Squares with something on them are left out. Returns
+// false if none of it fits.
+fn drop_wall(w: &mut World, id: usize, idx: usize) -> bool {
+    let back = w.facing[idx].opposite();
+    let center = w.position[id][0].moved(1, back);
+    if !w.in_bounds(center) {
+        return false;
+    }
+    let across = if matches!(back, Dir::Up | Dir::Down) { Dir::Right } else { Dir::Down };
+    let start = center.moved(WALL_LENGTH / 2, across.opposite());
+    let is_free = |p: Pos| w.is_on_board(p) && !alive_entities(w).any(|e| w.position[e].iter().any(|q| q.does_hit(p)));
+    let squares: Vec<Pos> = (0..WALL_LENGTH).map(|n| start.moved(n, across)).filter(|&p| is_free(p)).collect();
+    let ticks = w.ticks(w.config.weapons.wall_lifetime).max(1);
+    for &p in &squares {
+        new_wall(w, p, across, ticks);
+    }
+    debug!("{} put up {} squares of wall", w.name[id], squares.len());
+    !squares.is_empty()
 }
 
 fn to_start_positions(w: &mut World) {
@@ -774,3 +2605,471 @@ fn to_start_positions(w: &mut World) {
     w.position[p2][0] = p2_pos;
     w.velocity[p2].1 = Dir::None;
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use rs_sdk::{Dir, EntityKind, GameState, Player, Weapon, SUPPLY_LIFE};
+
+    use super::config::{Mode, Victory};
+    use super::frames::FramePool;
+    use super::snapshots::Snapshots;
+    use super::victory::{self, Outcome};
+    use super::{
+        add_projectile, collision_system, commands, drop_supply, end_round, explode_system, explosion, handle_event,
+        hide_energy, intro_pan, maps, move_system, new_bar, new_beam_pickup, new_marker, new_pickup, pickup_system,
+        projectile, start_round, systems, Density, GameConfig, GameError, InputEvent, Lifetime, MapKind, Output, Pos, Span, View, World,
+    };
+
+    // An 80x24 board with nothing on it but the players, their first round
+    // started. commands.rs uses it too.
+    pub(crate) fn arena() -> World {
+        let mut w = World::new(80, 24);
+        w.layout = Vec::new();
+        w.add_players();
+        start_round(&mut w);
+        w
+    }
+
+    // A world on a random map, ready to play
+    fn world(width: u32, height: u32, seed: u64) -> World {
+        let mut w = World::new(width, height);
+        w.layout = maps::layout(MapKind::Random, Density::Normal, width, height, seed);
+        w.add_players();
+        w.add_obstacles();
+        start_round(&mut w);
+        w
+    }
+
+    fn dir() -> impl Strategy<Value = Dir> {
+        prop_oneof![Just(Dir::Up), Just(Dir::Down), Just(Dir::Left), Just(Dir::Right)]
+    }
+
+    fn map() -> impl Strategy<Value = (MapKind, Density)> {
+        let kind = prop_oneof![
+            Just(MapKind::Bar),
+            Just(MapKind::Pillars),
+            Just(MapKind::Maze),
+            Just(MapKind::Random),
+            Just(MapKind::Empty),
+        ];
+        let density = prop_oneof![Just(Density::Low), Just(Density::Normal), Just(Density::High)];
+        (kind, density)
+    }
+
+    #[test]
+    fn both_losing_their_last_life_is_a_tiebreaker() {
+        let mut w = world(80, 24, 1);
+        w.p1_lives = 1;
+        w.p2_lives = 2;
+        w.alive[w.player1] = false;
+        w.alive[w.player2] = false;
+        end_round(&mut w);
+        assert_eq!((w.p1_lives, w.p2_lives, w.tiebreaker), (0, 1, false));
+
+        w.p1_lives = 1;
+        w.p2_lives = 1;
+        end_round(&mut w);
+        assert_eq!((w.p1_lives, w.p2_lives, w.tiebreaker), (1, 1, true));
+    }
+
+    #[test]
+    fn firing_the_way_you_are_going_is_safe() {
+        for weapon in [Weapon::Missile, Weapon::Ray] {
+            let mut w = arena();
+            let mut systems = systems();
+            w.active_weapon[w.player1] = Some(weapon);
+            handle_event(&mut w, InputEvent::Move { entity_id: 1, dir: Dir::Up });
+            let before = w.name.len();
+            handle_event(&mut w, InputEvent::Fire { entity_id: 1, dir: Dir::Up });
+            assert_eq!(w.name.len(), before + 1, "{:?} not fired", weapon);
+            // keep going, a ray at the same speed
+            for tick in 0..8 {
+                for s in systems.iter_mut() {
+                    s.step(&mut w);
+                }
+                assert!(w.alive[w.player1], "{:?} hit its firer on tick {}", weapon, tick);
+            }
+        }
+    }
+
+    #[test]
+    fn only_the_middle_of_a_blast_goes_through_shields() {
+        for (offset, survives) in [(0, false), (1, true), (2, true)] {
+            let mut w = arena();
+            w.shield[w.player1] = true;
+            let p = w.position[w.player1][0];
+            let center = Pos { x: p.x + offset, ..p };
+            let mut positions = Vec::new();
+            explosion(&w, center, 1, &mut positions);
+            add_projectile(&mut w, projectile::of(Weapon::Missile), positions, Dir::None, 3, 2, 1);
+            let blast = w.name.len() - 1;
+            w.kind[blast] = EntityKind::Explosion;
+            collision_system(&mut w);
+            assert_eq!(w.alive[w.player1], survives, "{} from the middle", offset);
+        }
+    }
+
+    // Keeps where the camera was for each frame drawn
+    #[derive(Default)]
+    struct Camera {
+        focus: Vec<(u16, u16)>,
+    }
+
+    impl Output for Camera {
+        fn init(&mut self) -> Result<(), GameError> {
+            Ok(())
+        }
+
+        fn dimensions(&self) -> Result<(u16, u16), GameError> {
+            Ok((80, 24))
+        }
+
+        fn render(&mut self, v: View) -> Result<(), GameError> {
+            self.focus.push(v.focus);
+            Ok(())
+        }
+
+        fn banner(&mut self, _msg: &[&str]) -> Result<(), GameError> {
+            Ok(())
+        }
+
+        fn print(&mut self, _x: u16, _y: u16, _s: &str) -> Result<(), GameError> {
+            Ok(())
+        }
+
+        fn cleanup(&mut self) -> Result<(), GameError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn the_camera_pans_from_start_to_start() {
+        let mut w = World::new(200, 60);
+        w.layout = Vec::new();
+        w.config.frame_gap_ms = 300;
+        w.add_players();
+        start_round(&mut w);
+        let mut camera = Camera::default();
+        intro_pan(&w, &mut camera).unwrap();
+
+        let at = |p: Pos| (p.x as u16, p.y as u16);
+        let f = &camera.focus;
+        assert_eq!(f.len(), 6);
+        assert_eq!(f[0], at(w.position[w.player1][0]));
+        assert_eq!(f[5], at(w.position[w.player2][0]));
+        assert!(f.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn the_dead_hit_nothing() {
+        let mut w = arena();
+        // a dead missile, another well away, and a third where the first was
+        for x in [40, 60, 40] {
+            let p = Pos { x, y: 5, invalid: false };
+            add_projectile(&mut w, projectile::of(Weapon::Missile), vec![p], Dir::None, 3, 2, 1);
+        }
+        let dead = w.name.len() - 3;
+        w.alive[dead] = false;
+        collision_system(&mut w);
+        assert!(w.alive[dead + 1] && w.alive[dead + 2]);
+    }
+
+    #[test]
+    fn a_supply_drop_crushes_whoever_is_under_it() {
+        let mut w = arena();
+        w.shield[w.player1] = true;
+        let p = w.position[w.player1][0];
+        new_marker(&mut w, p);
+        let marker = w.name.len() - 1;
+        drop_supply(&mut w);
+        commands::apply(&mut w);
+        assert!(!w.alive[w.player1]);
+        assert!(!w.alive[marker]);
+        let supply = w.name.len() - 1;
+        assert_eq!(w.kind[supply], EntityKind::Supply);
+        assert!(w.position[supply][0].does_hit(p));
+
+        // the other player walks over and takes it
+        w.alive[w.player1] = false;
+        w.position[w.player2] = vec![p];
+        w.energy[w.player2] = 0;
+        let lives = w.p2_lives;
+        pickup_system(&mut w);
+        assert!(!w.alive[supply]);
+        match w.tier[supply] {
+            SUPPLY_LIFE => assert_eq!(w.p2_lives, lives + 1),
+            _ => assert_eq!(w.energy[w.player2], w.config.energy.max),
+        }
+    }
+
+    #[test]
+    fn a_beam_goes_through_everything_once() {
+        let mut w = arena();
+        let at = |x| Pos { x, y: 10, invalid: false };
+        w.position[w.player1] = vec![at(10)];
+        w.position[w.player2] = vec![at(60)];
+        w.shield[w.player2] = true;
+        new_bar(&mut w, at(40), Dir::Down);
+        w.active_weapon[w.player1] = Some(Weapon::Ray);
+
+        new_beam_pickup(&mut w, at(10));
+        pickup_system(&mut w);
+        assert_eq!(w.active_weapon[w.player1], Some(Weapon::Beam));
+        handle_event(&mut w, InputEvent::Fire { entity_id: 1, dir: Dir::Right });
+        assert_eq!(w.kind[w.name.len() - 1], EntityKind::Beam);
+        collision_system(&mut w);
+        assert!(!w.alive[w.player2]);
+        // and it's back to what they had
+        assert_eq!(w.active_weapon[w.player1], Some(Weapon::Ray));
+    }
+
+    #[test]
+    fn a_cluster_splits_four_ways() {
+        let mut w = arena();
+        let center = Pos { x: 40, y: 12, invalid: false };
+        add_projectile(&mut w, projectile::of(Weapon::Cluster), vec![center], Dir::Right, 1, 1, 1);
+        let cluster = w.name.len() - 1;
+        w.owner[cluster] = Some(w.player1);
+        explode_system(&mut w);
+        assert_eq!(w.kind[cluster], EntityKind::Explosion);
+        commands::apply(&mut w);
+        let mut dirs: Vec<Dir> = (cluster + 1..w.name.len()).map(|id| w.velocity[id].1).collect();
+        dirs.sort_by_key(|d| format!("{:?}", d));
+        assert_eq!(dirs, [Dir::Down, Dir::Left, Dir::Right, Dir::Up]);
+        assert!((cluster + 1..w.name.len()).all(|id| w.kind[id] == EntityKind::Missile));
+    }
+
+    #[test]
+    fn a_wall_stops_a_ray_until_it_goes() {
+        let mut w = arena();
+        w.config.weapons.wall_lifetime = Span::Ticks(5);
+        let mut systems = systems();
+        let p = w.position[w.player1][0];
+        handle_event(&mut w, InputEvent::Move { entity_id: 1, dir: Dir::Left });
+        handle_event(&mut w, InputEvent::DropWall { entity_id: 1 });
+        // across the square to their right, the way they came from
+        let behind = Pos { x: p.x + 1, ..p };
+        for y in p.y - 1..=p.y + 1 {
+            assert!(!w.is_on_board(Pos { y, ..behind }));
+        }
+        assert_eq!(w.energy[w.player1], w.config.energy.max - w.config.energy.wall);
+
+        // player 2 is to the right, their ray stops at it
+        handle_event(&mut w, InputEvent::Move { entity_id: 2, dir: Dir::Left });
+        handle_event(&mut w, InputEvent::Move { entity_id: 2, dir: Dir::Right }); // stop
+        w.active_weapon[w.player2] = Some(Weapon::Ray);
+        handle_event(&mut w, InputEvent::Fire { entity_id: 2, dir: Dir::Left });
+        let ray = w.name.len() - 1;
+        assert!(w.position[ray].iter().all(|r| r.x > behind.x));
+
+        for _ in 0..5 {
+            for s in systems.iter_mut() {
+                s.step(&mut w);
+            }
+        }
+        assert!(w.is_on_board(behind));
+        // and is_on_board stops looking at it
+        assert!(w.solid.is_empty());
+    }
+
+    #[test]
+    fn a_corner_teleporter_sends_you_to_the_opposite_one() {
+        let mut w = World::new(80, 24);
+        w.layout = Vec::new();
+        w.config.teleporters = true;
+        w.reset();
+        start_round(&mut w);
+        w.position[w.player1] = vec![Pos { x: 2, y: 2, invalid: false }];
+        handle_event(&mut w, InputEvent::Move { entity_id: 1, dir: Dir::Left });
+        move_system(&mut w);
+        assert!(w.position[w.player1][0].does_hit(Pos { x: 78, y: 21, invalid: false }));
+
+        // and nothing can hit you as you come out
+        let p = w.position[w.player1][0];
+        add_projectile(&mut w, projectile::of(Weapon::Missile), vec![p], Dir::None, 3, 2, 1);
+        let missile = w.name.len() - 1;
+        w.arming[missile] = (0, false);
+        collision_system(&mut w);
+        assert!(w.alive[w.player1]);
+        assert!(!w.alive[missile]);
+    }
+
+    #[test]
+    fn the_first_match_has_teleporters() {
+        // set up as run does, reset only comes between matches
+        let mut w = World::with_config(80, 24, GameConfig { teleporters: true, ..GameConfig::default() });
+        w.layout = Vec::new();
+        w.add_players();
+        w.add_obstacles();
+        let corners = w.kind.iter().filter(|&&k| k == EntityKind::Teleporter);
+        assert_eq!(corners.count(), 4);
+    }
+
+    #[test]
+    fn snapshots_keep_the_last_few_ticks() {
+        let mut w = arena();
+        let mut snaps = Snapshots::new(3);
+        let mut frames = FramePool::default();
+        for tick in 0..5 {
+            w.tick = tick;
+            w.position[w.player1][0].x = 10 + tick;
+            snaps.push(tick, frames.share(|buf| w.entity_state(GameState::Playing, buf)));
+        }
+        assert_eq!(snaps.iter().map(|(tick, _)| tick).collect::<Vec<_>>(), [2, 3, 4]);
+
+        let (_, oldest) = snaps.iter().next().unwrap();
+        let mut back = World::new(80, 24);
+        back.load_state(oldest, rs_sdk::PROTOCOL_VERSION).unwrap();
+        assert_eq!((back.tick, back.position[back.player1][0].x), (2, 12));
+    }
+
+    #[test]
+    fn a_bot_can_be_kept_from_its_opponents_energy() {
+        let mut w = arena();
+        // never ready to fire isn't hidden
+        w.config.disabled_systems = vec!["energy".to_string()];
+        w.energy[w.player1] = 0;
+        let mut full = Vec::new();
+        w.entity_state(GameState::Playing, &mut full);
+        let mut hidden = Vec::new();
+        hide_energy(&full, 1, &mut hidden);
+
+        let f = rs_sdk::Frame::from_network(&hidden).unwrap();
+        let mine = f.player_status(Player::One);
+        assert_eq!(u32::from(mine.energy()), w.energy[w.player1]);
+        assert_eq!(mine.cooldown(), u16::MAX);
+        assert!(!mine.is_energy_hidden());
+        let theirs = f.player_status(Player::Two);
+        assert!(theirs.is_energy_hidden());
+        assert_eq!((theirs.energy(), theirs.cooldown()), (0, 0));
+        assert_eq!(theirs.lives(), w.p2_lives as u8);
+    }
+
+    #[test]
+    fn entities_past_255_keep_their_ids() {
+        let mut w = arena();
+        for n in 0..300 {
+            new_pickup(&mut w, Pos { x: 10 + n % 50, y: 5 + n / 50, invalid: false });
+        }
+        let mut frame = Vec::new();
+        w.entity_state(GameState::Playing, &mut frame);
+        let f = rs_sdk::Frame::from_network(&frame).unwrap();
+        let ids: Vec<u16> = f.entities().iter().map(|e| e.id()).collect();
+        assert_eq!(ids, (0..w.name.len() as u16).collect::<Vec<_>>());
+
+        // replays from before, with u8 ids, still load
+        let (header, entities) = frame.split_at(super::FRAME_HEADER_LEN);
+        let mut old = header.to_vec();
+        for e in entities.chunks(super::ENTITY_LEN) {
+            old.extend_from_slice(&e[1..]);
+        }
+        let mut back = World::new(80, 24);
+        back.load_state(&old, 15).unwrap();
+        assert_eq!(back.kind, w.kind);
+        let (last, was) = (back.position[back.name.len() - 1][0], w.position[w.name.len() - 1][0]);
+        assert_eq!((last.x, last.y), (was.x, was.y));
+    }
+
+    #[test]
+    fn first_to_points_needs_a_clear_lead() {
+        let mut w = world(80, 24, 1);
+        w.win = victory::of(&Victory {
+            mode: Mode::Points,
+            points: 2,
+            ..Victory::default()
+        });
+        let round = |w: &mut World, hit: [bool; 2]| {
+            w.alive[w.player1] = !hit[0];
+            w.alive[w.player2] = !hit[1];
+            end_round(w);
+            (w.points(), w.win.outcome(w))
+        };
+        assert_eq!(round(&mut w, [false, true]), ([1, 0], None));
+        assert_eq!(round(&mut w, [true, false]), ([1, 1], None));
+        // both hit at once, both at the target
+        assert_eq!(round(&mut w, [true, true]), ([2, 2], None));
+        assert_eq!(round(&mut w, [false, true]), ([3, 2], Some(Outcome::Won(0))));
+        assert_eq!((w.p1_lives, w.p2_lives), (10, 10));
+    }
+
+    #[test]
+    fn a_ray_in_milliseconds_lasts_as_long_at_any_speed() {
+        let c: GameConfig = toml::from_str("[weapons]\nray_lifetime = \"500ms\"\nwall_lifetime = 30").unwrap();
+        assert_eq!(c.weapons.ray_lifetime, Span::Millis(500));
+        for (gap, ticks) in [(50, 10), (25, 20)] {
+            let mut w = arena();
+            w.config = GameConfig { frame_gap_ms: gap, ..c.clone() };
+            handle_event(&mut w, InputEvent::ChangeWeapon { entity_id: 1 });
+            handle_event(&mut w, InputEvent::Fire { entity_id: 1, dir: Dir::Right });
+            let ray = w.kind.iter().position(|&k| k == EntityKind::Ray).unwrap();
+            assert_eq!(w.lifetime[ray], Lifetime::Temporary(ticks));
+        }
+        // ticks stay ticks
+        assert_eq!(c.weapons.wall_lifetime.ticks(25), 30);
+        assert!(toml::from_str::<GameConfig>("[weapons]\nray_lifetime = \"half a second\"").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn obstacles_are_not_on_board(width in 10u32..200, height in 10u32..80, seed in any::<u64>()) {
+            let w = world(width, height, seed);
+            for o in w.obstacles() {
+                prop_assert!(!w.is_on_board(o), "obstacle at {}", o);
+            }
+        }
+
+        #[test]
+        fn every_map_leaves_the_starts_clear(
+            width in 10u32..200,
+            height in 10u32..80,
+            seed in any::<u64>(),
+            (kind, density) in map(),
+        ) {
+            let mut w = World::new(width, height);
+            w.layout = maps::layout(kind, density, width, height, seed);
+            w.add_players();
+            w.add_obstacles();
+            start_round(&mut w);
+            for id in [w.player1, w.player2] {
+                prop_assert!(w.is_on_board(w.position[id][0]), "{:?} map has player {} at {}", kind, id, w.position[id][0]);
+            }
+        }
+
+        #[test]
+        fn on_board_is_inside_the_walls(
+            width in 10u32..200,
+            height in 10u32..80,
+            seed in any::<u64>(),
+            x in prop_oneof![0u32..200, any::<u32>()],
+            y in prop_oneof![0u32..80, any::<u32>()],
+        ) {
+            let w = world(width, height, seed);
+            let p = Pos { x, y, invalid: false };
+            if w.is_on_board(p) {
+                prop_assert!(1 <= x && x < width - 1 && 2 <= y && y < height - 2);
+                prop_assert!(!w.obstacles().iter().any(|o| o.does_hit(p)));
+            }
+        }
+
+        #[test]
+        fn players_stay_on_board(
+            width in 10u32..120,
+            height in 10u32..50,
+            seed in any::<u64>(),
+            moves in prop::collection::vec((1u8..=2, dir()), 0..150),
+        ) {
+            let mut w = world(width, height, seed);
+            for (entity_id, dir) in moves {
+                handle_event(&mut w, InputEvent::Move { entity_id, dir });
+                move_system(&mut w);
+                for id in [w.player1, w.player2] {
+                    // a player who just bounced off something is flagged
+                    // invalid for a tick, but never goes anywhere odd
+                    let p = Pos { invalid: false, ..w.position[id][0] };
+                    prop_assert!(w.is_on_board(p), "player {} at {}", id, p);
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,440 @@
-use std::error::Error;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
 
-use rust_console_game::run;
+use bot::skill::Difficulty;
+use clap::{Args, Parser, Subcommand};
+use log::LevelFilter;
+use rs_sdk::transport::Endpoint;
+use rs_sdk::Player;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    run()
+use rust_console_game::{
+    analyze, bot, daily, replay, run, show_stats, soak, spectate, start_logging, Backend, Density, GameConfig, GameError, MapKind,
+    Latency, Mode, Options, Palette, Profile, BOT_LOG_FILE, CONFIG_FILE, LOG_FILE,
+};
+
+// Smallest board the game can be played on
+const MIN_WIDTH: i64 = 20;
+const MIN_HEIGHT: i64 = 10;
+
+/// Two player console shooter. Humans play on the keyboard, bots connect over a socket.
+/// With no subcommand, plays on this terminal.
+#[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    play: PlayArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+// For every subcommand
+#[derive(Args)]
+struct LogArgs {
+    /// Log level: off, error, warn, info, debug or trace.
+    /// Defaults to $RUST_CONSOLE_GAME_LOG, or warn.
+    #[arg(long, global = true, value_name = "LEVEL")]
+    log_level: Option<LevelFilter>,
+
+    /// Log file. Defaults to $RUST_CONSOLE_GAME_LOG_FILE, or hashbang.log in
+    /// the cache directory (~/.cache/rust-console-game on Linux).
+    #[arg(long, global = true, value_name = "FILE")]
+    log_file: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Play on this terminal (the default)
+    Play(PlayArgs),
+
+    /// Run a game for bots only, with no display or keyboard. Runs until killed:
+    /// each match starts once both bots are Ready, and the score is logged at info.
+    Serve(ServeArgs),
+
+    /// Play today's daily challenge: one match against the built in bot, on the
+    /// same map and with the same rule changes as everyone else playing today
+    Daily(DailyArgs),
+
+    /// Watch a saved replay
+    Replay {
+        #[arg(value_name = "FILE.rcgr")]
+        file: PathBuf,
+
+        /// Colors: normal, colorblind or high-contrast. Defaults to the game's.
+        #[arg(long)]
+        palette: Option<Palette>,
+
+        /// Show a tug of war bar of which player has the momentum
+        #[arg(long)]
+        momentum: bool,
+
+        /// Show the players' names next to them
+        #[arg(long)]
+        labels: bool,
+    },
+
+    /// Print what happened in a saved replay: each round's shots, hits and
+    /// energy, and a heatmap of where each player spent their time
+    Analyze {
+        #[arg(value_name = "FILE.rcgr")]
+        file: PathBuf,
+    },
+
+    /// Watch a game as it's played
+    Spectate {
+        /// Where the game is, a socket path or tcp://host:port.
+        /// Defaults to the game's usual spectator endpoint on this machine.
+        endpoint: Option<Endpoint>,
+
+        /// Colors: normal, colorblind or high-contrast. Defaults to the game's.
+        #[arg(long)]
+        palette: Option<Palette>,
+
+        /// Show a tug of war bar of which player has the momentum
+        #[arg(long)]
+        momentum: bool,
+
+        /// Show the players' names next to them
+        #[arg(long)]
+        labels: bool,
+    },
+
+    /// Play the built in bot against a game started separately
+    Bot(BotArgs),
+
+    /// Show the leaderboard, and head to head records between players
+    Stats,
+}
+
+#[derive(Args)]
+struct PlayArgs {
+    #[command(flatten)]
+    game: GameArgs,
+
+    /// Play against the built in bot, which takes player 2: easy, normal or hard
+    #[arg(long, value_name = "DIFFICULTY", num_args = 0..=1, default_missing_value = "normal")]
+    vs_ai: Option<Difficulty>,
+
+    /// Debug mode: F2 lists every entity in the game over the board, PgUp and PgDn scroll it.
+    /// Energy and weapon changes to the config file are applied as you save them.
+    #[arg(long)]
+    debug: bool,
+}
+
+#[derive(Args)]
+struct ServeArgs {
+    #[command(flatten)]
+    game: GameArgs,
+
+    /// Instead of serving, play the built in bot against itself for this many
+    /// rounds as fast as possible, and report entity counts, memory and system timings
+    #[arg(long, value_name = "ROUNDS", conflicts_with_all = ["backend", "record"],
+          value_parser = clap::value_parser!(u32).range(1..))]
+    soak: Option<u32>,
+
+    /// Serve runtime metrics at http://ADDR/metrics, in the Prometheus text format:
+    /// ticks, tick times, commands, connected bots and spectators, entities alive
+    #[arg(long, value_name = "ADDR", conflicts_with = "soak")]
+    metrics: Option<SocketAddr>,
+}
+
+// Shared by play and serve
+#[derive(Args)]
+struct GameArgs {
+    /// Arena width. Defaults to the terminal's, or 80 for serve
+    #[arg(long, value_parser = clap::value_parser!(u16).range(MIN_WIDTH..))]
+    width: Option<u16>,
+
+    /// Arena height. Defaults to the terminal's, or 24 for serve
+    #[arg(long, value_parser = clap::value_parser!(u16).range(MIN_HEIGHT..))]
+    height: Option<u16>,
+
+    /// Arena width and height, e.g. 80x24, so the game plays the same in any
+    /// terminal. A smaller one is centered, a bigger one scrolls to follow the players.
+    #[arg(long, value_name = "WxH", value_parser = parse_arena, conflicts_with_all = ["width", "height"])]
+    arena: Option<(u16, u16)>,
+
+    /// Game settings: lives, speed, energy costs, colors and keys.
+    /// Defaults to config.toml in the current directory, if there is one.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Player 1: profile:NAME to use profiles/NAME.toml, or just a name
+    #[arg(long, value_name = "PLAYER", value_parser = Profile::parse)]
+    p1: Option<Profile>,
+
+    /// Player 2, as for --p1
+    #[arg(long, value_name = "PLAYER", value_parser = Profile::parse)]
+    p2: Option<Profile>,
+
+    /// Lives each player starts with. Overrides the config file.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=255))]
+    lives: Option<u32>,
+
+    /// Frames per second. Overrides the config file.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=100))]
+    fps: Option<u32>,
+
+    /// Colors: normal, colorblind (yellow and blue) or high-contrast (bold, no
+    /// colors, and player 2's weapons drawn differently). Overrides the config file.
+    #[arg(long)]
+    palette: Option<Palette>,
+
+    /// Obstacle layout: bar, pillars, maze, random or empty. Overrides the config file.
+    #[arg(long)]
+    map: Option<MapKind>,
+
+    /// How much of the board the obstacles cover: low, normal or high. Overrides the config file.
+    #[arg(long)]
+    density: Option<Density>,
+
+    /// Put a teleporter in each corner, which sends whatever moves onto it to
+    /// the opposite one. Overrides the config file.
+    #[arg(long)]
+    teleporters: bool,
+
+    /// Take turns: the game waits for a player to pick a move and an action
+    /// and press Enter, plays this many ticks, then waits for the other. For
+    /// screen-share or slow terminals. Overrides the config file.
+    #[arg(long, value_name = "TICKS", value_parser = clap::value_parser!(u32).range(1..))]
+    turns: Option<u32>,
+
+    /// Keep each player's energy from the other: not shown for a bot playing
+    /// someone at the keyboard, and not sent to the opposing bot. Overrides the config file.
+    #[arg(long)]
+    hide_opponent_energy: bool,
+
+    /// How a match is won: lives (last with lives left), points (first to
+    /// [victory] points hits) or timed (most lives after [victory] seconds).
+    /// Overrides the config file.
+    #[arg(long)]
+    mode: Option<Mode>,
+
+    /// Seed for --map pillars, maze or random, to play the same map again. Defaults to a random one.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// How bots and spectators connect: unix (sockets in /tmp) or tcp
+    /// (127.0.0.1:7771 and 7772, spectators 7773). Defaults to unix where there is one.
+    #[arg(long)]
+    backend: Option<Backend>,
+
+    /// Hold every frame sent to a bot, and every command from one, for MS
+    /// milliseconds, give or take up to JITTER, to see how a bot copes with a
+    /// slow connection. Each way, so a round trip takes twice as long.
+    #[arg(long, value_name = "MS[:JITTER]")]
+    fake_latency: Option<Latency>,
+
+    /// Save a replay of the game
+    #[arg(long, value_name = "FILE.rcgr")]
+    record: Option<PathBuf>,
+}
+
+// The day decides the board, map and rules, so only what doesn't change the game
+#[derive(Args)]
+struct DailyArgs {
+    /// Names, colors, keys and screen margins from this file, as for play. The rest of it is ignored.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Your name, or profile:NAME, as for play. Results are kept by name.
+    #[arg(long, value_name = "PLAYER", value_parser = Profile::parse)]
+    p1: Option<Profile>,
+
+    /// Colors: normal, colorblind or high-contrast
+    #[arg(long)]
+    palette: Option<Palette>,
+
+    /// Save a replay of the game
+    #[arg(long, value_name = "FILE.rcgr")]
+    record: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct BotArgs {
+    /// Which player to be: 1 or 2
+    #[arg(value_parser = clap::value_parser!(u8).range(1..=2))]
+    player: u8,
+
+    /// How well the bot plays: easy, normal or hard
+    #[arg(long, default_value = "hard")]
+    difficulty: Difficulty,
+
+    /// How often the bot decides what to do, overrides the difficulty's
+    #[arg(long, value_name = "MS")]
+    reaction_ms: Option<u64>,
+
+    /// How to reach the game, as for play and serve
+    #[arg(long)]
+    backend: Option<Backend>,
+
+    /// Save everything the game sends, see rs_sdk::replay
+    #[arg(long, value_name = "FILE")]
+    record: Option<PathBuf>,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Play(cli.play));
+    let log_name = match command {
+        Command::Bot(_) => BOT_LOG_FILE,
+        _ => LOG_FILE,
+    };
+    start_logging(cli.log.log_level, cli.log.log_file, log_name);
+
+    let res = match command {
+        Command::Play(args) => {
+            let debug = args.debug;
+            options(args.game, args.vs_ai, false)
+                .map(|o| Options { debug, ..o })
+                .and_then(run)
+        }
+        Command::Serve(args) => {
+            let (soak_rounds, metrics) = (args.soak, args.metrics);
+            let opts = options(args.game, None, true).map(|o| Options { metrics, ..o });
+            match soak_rounds {
+                Some(rounds) => opts.and_then(|o| soak(o, rounds)),
+                None => opts.and_then(run),
+            }
+        }
+        Command::Daily(args) => {
+            let game = GameArgs {
+                width: None,
+                height: None,
+                arena: None,
+                config: args.config,
+                p1: args.p1,
+                p2: None,
+                lives: None,
+                fps: None,
+                palette: args.palette,
+                map: None,
+                density: None,
+                teleporters: false,
+                turns: None,
+                hide_opponent_energy: false,
+                mode: None,
+                seed: None,
+                backend: None,
+                fake_latency: None,
+                record: args.record,
+            };
+            options(game, None, false).and_then(daily)
+        }
+        Command::Replay {
+            file,
+            palette,
+            momentum,
+            labels,
+        } => replay(&file, palette, momentum, labels),
+        Command::Analyze { file } => analyze(&file),
+        Command::Spectate {
+            endpoint,
+            palette,
+            momentum,
+            labels,
+        } => spectate(
+            &endpoint.unwrap_or_else(|| Options::default().spectate_endpoint()),
+            palette,
+            momentum,
+            labels,
+        ),
+        Command::Bot(args) => {
+            let player = if args.player == 2 { Player::Two } else { Player::One };
+            let mut skill = args.difficulty.skill();
+            if let Some(ms) = args.reaction_ms {
+                skill.reaction = Duration::from_millis(ms);
+            }
+            let endpoint = match args.backend {
+                Some(b) => b.endpoint(player),
+                None => player.endpoint(),
+            };
+            bot(player, skill, endpoint, args.record)
+        }
+        Command::Stats => show_stats(),
+    };
+    if let Err(e) = res {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+// --arena, e.g. 80x24
+fn parse_arena(s: &str) -> Result<(u16, u16), String> {
+    let bad = || format!("arena '{}' should be WIDTHxHEIGHT, e.g. 80x24", s);
+    let (w, h) = s.split_once('x').ok_or_else(bad)?;
+    let (w, h): (u16, u16) = (w.parse().map_err(|_| bad())?, h.parse().map_err(|_| bad())?);
+    if (w as i64) < MIN_WIDTH || (h as i64) < MIN_HEIGHT {
+        return Err(format!("arena must be at least {}x{}", MIN_WIDTH, MIN_HEIGHT));
+    }
+    Ok((w, h))
+}
+
+// run checks the result is playable
+fn options(args: GameArgs, vs_ai: Option<Difficulty>, headless: bool) -> Result<Options, GameError> {
+    let config_file = args.config.clone().unwrap_or_else(|| PathBuf::from(CONFIG_FILE));
+    let mut config = match args.config {
+        Some(path) => GameConfig::load(&path).map_err(GameError::Config)?,
+        None if Path::new(CONFIG_FILE).exists() => {
+            GameConfig::load(Path::new(CONFIG_FILE)).map_err(GameError::Config)?
+        }
+        None => GameConfig::default(),
+    };
+    if let Some(p) = &args.p1 {
+        p.apply(&mut config, Player::One);
+    }
+    if let Some(p) = &args.p2 {
+        p.apply(&mut config, Player::Two);
+    }
+    if let Some(lives) = args.lives {
+        config.lives = lives;
+    }
+    if let Some(fps) = args.fps {
+        config.frame_gap_ms = 1000 / fps as u64;
+    }
+    if let Some(map) = args.map {
+        config.map = map;
+    }
+    if let Some(density) = args.density {
+        config.density = density;
+    }
+    if args.teleporters {
+        config.teleporters = true;
+    }
+    if let Some(ticks) = args.turns {
+        config.turn_ticks = ticks;
+    }
+    if args.hide_opponent_energy {
+        config.opponent_energy = false;
+    }
+    if let Some(mode) = args.mode {
+        config.victory.mode = mode;
+    }
+    // last, it overrides profile colors
+    let palette = args.palette.unwrap_or(config.palette);
+    config.set_palette(palette);
+    let (width, height) = match args.arena {
+        Some((w, h)) => (Some(w), Some(h)),
+        None => (args.width, args.height),
+    };
+    Ok(Options {
+        width,
+        height,
+        config,
+        config_file: Some(config_file),
+        seed: args.seed,
+        backend: args.backend,
+        vs_ai,
+        headless,
+        record: args.record,
+        metrics: None,
+        daily: None,
+        debug: false,
+        fake_latency: args.fake_latency,
+    })
 }
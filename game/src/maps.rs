@@ -0,0 +1,371 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use log::{debug, warn};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rs_sdk::Dir;
+use serde::{Deserialize, Serialize};
+
+use crate::pos::Pos;
+
+// Keep obstacles at least this far from where players start
+const START_CLEARANCE: u32 = 2;
+
+// Every obstacle square is an entity, sent to bots in every frame
+const MAX_OBSTACLES: usize = 200;
+
+// How many moves nearer the middle one start can be than the other on a fair
+// map: a tenth of the longer way there, and never under 2, as on some widths
+// the starts are a square or so off symmetric even with no obstacles
+const FAIR_SLACK_MIN: u32 = 2;
+
+// Seeds to try for a fair layout, see Board::unfairness
+const FAIR_TRIES: u64 = 20;
+
+// Maze rooms, in squares
+const MAZE_ROOM_W: u32 = 10;
+const MAZE_ROOM_H: u32 = 5;
+
+// Obstacle layouts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MapKind {
+    #[default]
+    Bar, // one bar down the middle
+    Pillars, // single squares scattered about, placed using the seed
+    Maze,    // walls between rooms, placed using the seed
+    Random,  // a few bars, placed using the seed
+    Empty,   // no obstacles
+}
+
+impl FromStr for MapKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MapKind, String> {
+        match s {
+            "bar" => Ok(MapKind::Bar),
+            "pillars" => Ok(MapKind::Pillars),
+            "maze" => Ok(MapKind::Maze),
+            "random" => Ok(MapKind::Random),
+            "empty" | "none" => Ok(MapKind::Empty),
+            _ => Err(format!("unknown map '{}', use bar, pillars, maze, random or empty", s)),
+        }
+    }
+}
+
+// How much of the board a layout covers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Density {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl FromStr for Density {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Density, String> {
+        match s {
+            "low" => Ok(Density::Low),
+            "normal" => Ok(Density::Normal),
+            "high" => Ok(Density::High),
+            _ => Err(format!("unknown density '{}', use low, normal or high", s)),
+        }
+    }
+}
+
+impl Density {
+    // n is how many at normal density
+    fn scale(&self, n: u32) -> u32 {
+        match self {
+            Density::Low => n / 2,
+            Density::Normal => n,
+            Density::High => n * 2,
+        }
+    }
+}
+
+// The playable area, see World::is_on_board, and where players start
+struct Board {
+    width: u32,
+    height: u32,
+    min_x: u32,
+    max_x: u32, // exclusive
+    min_y: u32,
+    max_y: u32, // exclusive
+    starts: [(u32, u32); 2],
+}
+
+impl Board {
+    fn new(width: u32, height: u32) -> Board {
+        Board {
+            width,
+            height,
+            min_x: 1,
+            max_x: width - 1,
+            min_y: 2,
+            max_y: height - 2,
+            starts: [(width / 4, height / 2), (width / 4 * 3, height / 2)],
+        }
+    }
+
+    // Somewhere an obstacle can go
+    fn is_free(&self, x: u32, y: u32) -> bool {
+        let near_start = self
+            .starts
+            .iter()
+            .any(|&(sx, sy)| x.abs_diff(sx) <= START_CLEARANCE && y.abs_diff(sy) <= START_CLEARANCE);
+        self.min_x <= x && x < self.max_x && self.min_y <= y && y < self.max_y && !near_start
+    }
+
+    // Why a layout favors one player, if it does: a start nearer the middle
+    // of the board than the other, or the starts cut off from each other.
+    // Distances are in moves, around the obstacles.
+    fn unfairness(&self, obstacles: &[(Pos, Dir)]) -> Option<String> {
+        let mut blocked = vec![false; (self.width * self.height) as usize];
+        for (p, _) in obstacles {
+            blocked[(p.y * self.width + p.x) as usize] = true;
+        }
+        let [s1, s2] = self.starts;
+        if self.moves(s1, &blocked)[self.idx(s2)].is_none() {
+            return Some("the starts can't reach each other".to_string());
+        }
+        // the middle may be an obstacle, this is to next to it
+        let from_middle = self.moves((self.width / 2, self.height / 2), &blocked);
+        let (Some(d1), Some(d2)) = (from_middle[self.idx(s1)], from_middle[self.idx(s2)]) else {
+            return Some("a start can't reach the middle".to_string());
+        };
+        let slack = (d1.max(d2) / 10).max(FAIR_SLACK_MIN);
+        if d1.abs_diff(d2) > slack {
+            return Some(format!("the starts are {} and {} moves from the middle", d1, d2));
+        }
+        None
+    }
+
+    // Fewest moves from `from` to every square of the board, None where it
+    // can't get to. Breadth first, up, down, left and right.
+    fn moves(&self, from: (u32, u32), blocked: &[bool]) -> Vec<Option<u32>> {
+        let mut dist = vec![None; blocked.len()];
+        dist[self.idx(from)] = Some(0);
+        let mut queue = VecDeque::from([(from, 0)]);
+        while let Some(((x, y), d)) = queue.pop_front() {
+            let next = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+            for (nx, ny) in next {
+                let on_board = self.min_x <= nx && nx < self.max_x && self.min_y <= ny && ny < self.max_y;
+                if !on_board || blocked[self.idx((nx, ny))] || dist[self.idx((nx, ny))].is_some() {
+                    continue;
+                }
+                dist[self.idx((nx, ny))] = Some(d + 1);
+                queue.push_back(((nx, ny), d + 1));
+            }
+        }
+        dist
+    }
+
+    fn idx(&self, (x, y): (u32, u32)) -> usize {
+        (y * self.width + x) as usize
+    }
+}
+
+// Where the obstacles go on a width x height board. Dir is which way the
+// bar the obstacle is part of runs, for drawing. Each kind of map has a
+// generator here, World::add_obstacles puts what it returns on the board.
+// A layout that favors one player is thrown away and the next seed tried,
+// so the same seed still always gives the same map.
+pub(crate) fn layout(kind: MapKind, density: Density, width: u32, height: u32, seed: u64) -> Vec<(Pos, Dir)> {
+    let board = Board::new(width, height);
+    for n in 0..FAIR_TRIES {
+        let seed = seed.wrapping_add(n);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut obstacles = match kind {
+            MapKind::Bar => bar(&board, density),
+            MapKind::Pillars => pillars(&board, density, &mut rng),
+            MapKind::Maze => maze(&board, density, &mut rng),
+            MapKind::Random => random(&board, density, &mut rng),
+            MapKind::Empty => Vec::new(),
+        };
+        obstacles.truncate(MAX_OBSTACLES);
+        match board.unfairness(&obstacles) {
+            None => return obstacles,
+            Some(why) => debug!("{:?} map from seed {} is unfair, {}", kind, seed, why),
+        }
+    }
+    warn!("No fair {:?} map in {} tries, playing without obstacles", kind, FAIR_TRIES);
+    Vec::new()
+}
+
+// Down the middle, a third of the height at normal density
+fn bar(board: &Board, density: Density) -> Vec<(Pos, Dir)> {
+    let x = board.width / 2;
+    let len = density.scale(board.height / 3).clamp(1, board.max_y - board.min_y);
+    let top = (board.height / 2).saturating_sub(len / 2).max(board.min_y);
+    (top..top + len)
+        .filter(|&y| board.is_free(x, y))
+        .map(|y| (at(x, y), Dir::Up))
+        .collect()
+}
+
+// One square in every 60 or so at normal density
+fn pillars(board: &Board, density: Density, rng: &mut StdRng) -> Vec<(Pos, Dir)> {
+    let area = (board.max_x - board.min_x) * (board.max_y - board.min_y);
+    let mut obstacles: Vec<(Pos, Dir)> = Vec::new();
+    for _ in 0..density.scale(area / 60).max(1) {
+        let x = rng.gen_range(board.min_x..board.max_x);
+        let y = rng.gen_range(board.min_y..board.max_y);
+        if board.is_free(x, y) && !obstacles.iter().any(|(p, _)| p.x == x && p.y == y) {
+            obstacles.push((at(x, y), Dir::Up));
+        }
+    }
+    obstacles
+}
+
+// The board is split into rooms, and a maze carved through them so every room
+// can reach every other. Then some walls are knocked through: most of them at
+// low density, none at high.
+fn maze(board: &Board, density: Density, rng: &mut StdRng) -> Vec<(Pos, Dir)> {
+    let cols = ((board.max_x - board.min_x) / MAZE_ROOM_W).max(1);
+    let rows = ((board.max_y - board.min_y) / MAZE_ROOM_H).max(1);
+    let room = |c: u32, r: u32| (r * cols + c) as usize;
+
+    // walls between neighbouring rooms, (room, room to the right or below)
+    let mut walls: Vec<(u32, u32, bool)> = Vec::new(); // col, row, is to the right
+    for r in 0..rows {
+        for c in 0..cols {
+            if c + 1 < cols {
+                walls.push((c, r, true));
+            }
+            if r + 1 < rows {
+                walls.push((c, r, false));
+            }
+        }
+    }
+
+    // depth first from room 0, removing the wall to each room we go into
+    let mut visited = vec![false; (cols * rows) as usize];
+    let mut open: Vec<(u32, u32, bool)> = Vec::new();
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    while let Some(&(c, r)) = stack.last() {
+        let mut next = Vec::new();
+        if c > 0 && !visited[room(c - 1, r)] {
+            next.push((c - 1, r, (c - 1, r, true)));
+        }
+        if c + 1 < cols && !visited[room(c + 1, r)] {
+            next.push((c + 1, r, (c, r, true)));
+        }
+        if r > 0 && !visited[room(c, r - 1)] {
+            next.push((c, r - 1, (c, r - 1, false)));
+        }
+        if r + 1 < rows && !visited[room(c, r + 1)] {
+            next.push((c, r + 1, (c, r, false)));
+        }
+        match next.choose(rng) {
+            Some(&(nc, nr, wall)) => {
+                visited[room(nc, nr)] = true;
+                open.push(wall);
+                stack.push((nc, nr));
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+    walls.retain(|w| !open.contains(w));
+    walls.shuffle(rng);
+    let keep = match density {
+        Density::Low => walls.len() / 3,
+        Density::Normal => walls.len() * 2 / 3,
+        Density::High => walls.len(),
+    };
+    walls.truncate(keep);
+
+    let mut obstacles = Vec::new();
+    for (c, r, is_right) in walls {
+        let (x0, y0) = (board.min_x + c * MAZE_ROOM_W, board.min_y + r * MAZE_ROOM_H);
+        let squares: Vec<(u32, u32)> = if is_right {
+            (y0..y0 + MAZE_ROOM_H).map(|y| (x0 + MAZE_ROOM_W, y)).collect()
+        } else {
+            (x0..x0 + MAZE_ROOM_W).map(|x| (x, y0 + MAZE_ROOM_H)).collect()
+        };
+        let dir = if is_right { Dir::Up } else { Dir::Right };
+        for (x, y) in squares {
+            if board.is_free(x, y) && !obstacles.iter().any(|(p, _): &(Pos, Dir)| p.x == x && p.y == y) {
+                obstacles.push((at(x, y), dir));
+            }
+        }
+    }
+    obstacles
+}
+
+// A few bars, 3 to 6 at normal density
+fn random(board: &Board, density: Density, rng: &mut StdRng) -> Vec<(Pos, Dir)> {
+    let (min_x, max_x, min_y, max_y) = (board.min_x, board.max_x, board.min_y, board.max_y);
+    let (width, height) = (board.width, board.height);
+    let mut obstacles = Vec::new();
+    let bars = rng.gen_range(3..=6);
+    for _ in 0..density.scale(bars).max(1) {
+        let dir = if rng.gen_bool(0.5) { Dir::Up } else { Dir::Right };
+        let max_len = if dir == Dir::Up { height / 3 } else { width / 6 }.max(3);
+        let len = rng.gen_range(2..=max_len);
+        let x = rng.gen_range(min_x..max_x);
+        let y = rng.gen_range(min_y..max_y);
+        for i in 0..len {
+            let (x, y) = if dir == Dir::Up { (x, y + i) } else { (x + i, y) };
+            if x >= max_x || y >= max_y {
+                break;
+            }
+            if board.is_free(x, y) && !obstacles.iter().any(|(p, _): &(Pos, Dir)| p.x == x && p.y == y) {
+                obstacles.push((at(x, y), dir));
+            }
+        }
+    }
+    obstacles
+}
+
+fn at(x: u32, y: u32) -> Pos {
+    Pos {
+        x,
+        y,
+        invalid: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use rs_sdk::Dir;
+
+    use super::{at, Board};
+
+    proptest! {
+        #[test]
+        fn empty_boards_are_fair(width in 10u32..200, height in 10u32..80) {
+            let board = Board::new(width, height);
+            prop_assert_eq!(board.unfairness(&[]), None);
+        }
+    }
+
+    #[test]
+    fn a_start_walled_in_is_unfair() {
+        let board = Board::new(80, 24);
+        let (x, y) = board.starts[0];
+        let ring: Vec<_> = (x - 3..=x + 3)
+            .flat_map(|rx| (y - 3..=y + 3).map(move |ry| (rx, ry)))
+            .filter(|&(rx, ry)| rx.abs_diff(x) == 3 || ry.abs_diff(y) == 3)
+            .map(|(rx, ry)| (at(rx, ry), Dir::Up))
+            .collect();
+        assert_eq!(board.unfairness(&ring), Some("the starts can't reach each other".to_string()));
+    }
+
+    #[test]
+    fn a_start_with_the_long_way_round_is_unfair() {
+        let board = Board::new(80, 24);
+        // a wall from top to bottom just left of the middle, open only at the top
+        let wall: Vec<_> = (board.min_y + 1..board.max_y).map(|y| (at(39, y), Dir::Up)).collect();
+        assert!(board.unfairness(&wall).is_some());
+    }
+}
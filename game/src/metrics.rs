@@ -0,0 +1,185 @@
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{debug, error};
+
+use crate::timing::Stage;
+
+// Runtime numbers for a long running `serve`, scraped over HTTP in the
+// Prometheus text format: GET /metrics. The game loop updates them every tick.
+
+// A scraper that takes longer than this to send its request is dropped
+const READ_TIMEOUT_MS: u64 = 1000;
+
+// Longest request line and headers we'll read
+const MAX_REQUEST: u64 = 8 * 1024;
+
+#[derive(Default)]
+pub struct Metrics {
+    ticks: AtomicU64,
+    tick_ns: AtomicU64,       // work in the most recent tick, not counting the sleep after
+    tick_ns_sum: AtomicU64,   // all ticks' work, over ticks for the average
+    ticks_per_sec: AtomicU64, // f64 bits, over the last whole second
+    commands: AtomicU64,      // everything read from bots and the keyboard
+    bots: AtomicU64,
+    spectators: AtomicU64,
+    entities: AtomicU64, // alive, including obstacles
+    window: Mutex<Option<(Instant, u64)>>, // start of the current second, and ticks then
+    stages: Mutex<Vec<Stage>>,             // each system and render, see timing.rs
+}
+
+impl Metrics {
+    pub fn new() -> Arc<Metrics> {
+        Arc::new(Metrics::default())
+    }
+
+    // After every tick. `work` is how long it took.
+    pub fn tick(&self, work: Duration, entities: usize, bots: usize, spectators: usize) {
+        let ns = work.as_nanos() as u64;
+        let ticks = self.ticks.fetch_add(1, Ordering::Relaxed) + 1;
+        self.tick_ns.store(ns, Ordering::Relaxed);
+        self.tick_ns_sum.fetch_add(ns, Ordering::Relaxed);
+        self.set_clients(bots, spectators);
+        self.entities.store(entities as u64, Ordering::Relaxed);
+
+        let mut window = self.window.lock().unwrap();
+        match *window {
+            Some((start, n)) if start.elapsed() >= Duration::from_secs(1) => {
+                let tps = (ticks - n) as f64 / start.elapsed().as_secs_f64();
+                self.ticks_per_sec.store(tps.to_bits(), Ordering::Relaxed);
+                *window = Some((Instant::now(), ticks));
+            }
+            Some(_) => {}
+            None => *window = Some((Instant::now(), ticks)),
+        }
+    }
+
+    // Between rounds nothing ticks, but bots still come and go
+    pub fn set_clients(&self, bots: usize, spectators: usize) {
+        self.bots.store(bots as u64, Ordering::Relaxed);
+        self.spectators.store(spectators as u64, Ordering::Relaxed);
+    }
+
+    pub fn stages(&self, stages: Vec<Stage>) {
+        *self.stages.lock().unwrap() = stages;
+    }
+
+    pub fn command(&self) {
+        self.commands.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Answer scrapes on a thread of its own. Binds here so a bad address
+    // stops the game starting, rather than failing where nobody sees.
+    pub fn serve(self: &Arc<Metrics>, addr: SocketAddr) -> io::Result<()> {
+        let l = TcpListener::bind(addr)
+            .map_err(|e| io::Error::new(e.kind(), format!("metrics listen on {}: {}", addr, e)))?;
+        debug!("Metrics on http://{}/metrics", addr);
+        let m = self.clone();
+        let _ = thread::spawn(move || {
+            for conn in l.incoming() {
+                let res = conn.and_then(|c| m.answer(c));
+                if let Err(e) = res {
+                    error!("metrics: {}", e);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn answer(&self, mut conn: TcpStream) -> io::Result<()> {
+        conn.set_read_timeout(Some(Duration::from_millis(READ_TIMEOUT_MS)))?;
+        let mut r = BufReader::new((&conn).take(MAX_REQUEST));
+        let mut request = String::new();
+        r.read_line(&mut request)?;
+        // skip the headers, we don't need any
+        let mut line = String::new();
+        while r.read_line(&mut line)? > 2 {
+            line.clear();
+        }
+
+        let mut parts = request.split_whitespace();
+        let (status, body) = match (parts.next(), parts.next()) {
+            (Some("GET"), Some("/metrics")) => ("200 OK", self.to_text()),
+            _ => ("404 Not Found", "Try GET /metrics\n".to_string()),
+        };
+        write!(
+            conn,
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+    }
+
+    // Prometheus text exposition format
+    fn to_text(&self) -> String {
+        let get = |a: &AtomicU64| a.load(Ordering::Relaxed);
+        let secs = |ns: u64| ns as f64 / 1e9;
+        let mut s = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: f64| {
+            let _ = writeln!(s, "# HELP {} {}\n# TYPE {} {}\n{} {}", name, help, name, kind, name, value);
+        };
+        metric("rcg_ticks_total", "counter", "Game ticks played.", get(&self.ticks) as f64);
+        metric(
+            "rcg_ticks_per_second",
+            "gauge",
+            "Ticks per second, over the most recent second a round was played in.",
+            f64::from_bits(get(&self.ticks_per_sec)),
+        );
+        metric(
+            "rcg_tick_duration_seconds",
+            "gauge",
+            "Time the most recent tick took, not counting the wait for the next.",
+            secs(get(&self.tick_ns)),
+        );
+        metric(
+            "rcg_tick_work_seconds_total",
+            "counter",
+            "Time all ticks took, divide by rcg_ticks_total for the average.",
+            secs(get(&self.tick_ns_sum)),
+        );
+        metric(
+            "rcg_commands_total",
+            "counter",
+            "Commands received from bots and the keyboard.",
+            get(&self.commands) as f64,
+        );
+        metric("rcg_bots_connected", "gauge", "Bots connected, 0 to 2.", get(&self.bots) as f64);
+        metric(
+            "rcg_spectators_connected",
+            "gauge",
+            "Spectators watching.",
+            get(&self.spectators) as f64,
+        );
+        metric(
+            "rcg_entities_alive",
+            "gauge",
+            "Players, weapons and obstacles on the board.",
+            get(&self.entities) as f64,
+        );
+        // one of each per stage
+        let stages = self.stages.lock().unwrap();
+        let mut labeled = |name: &str, help: &str, value: fn(&Stage) -> Duration| {
+            let _ = writeln!(s, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+            for st in stages.iter() {
+                let _ = writeln!(s, "{}{{stage=\"{}\"}} {}", name, st.name, value(st).as_secs_f64());
+            }
+        };
+        labeled(
+            "rcg_stage_mean_seconds",
+            "Average time a system, or render, took per tick over the last 100 ticks.",
+            |st| st.mean,
+        );
+        labeled(
+            "rcg_stage_worst_seconds",
+            "Longest a system, or render, took in one tick of the last 100.",
+            |st| st.worst,
+        );
+        s
+    }
+}
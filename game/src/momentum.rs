@@ -0,0 +1,37 @@
+// Which way the match is going, for the tug of war bar across the top of the
+// board. A rough guess from what the players have left and who has been hit
+// lately, not a prediction. Turn it on with `momentum = true`, or `--momentum`
+// when watching.
+
+use std::time::Duration;
+
+// Hits older than this no longer count
+pub const RECENT: Duration = Duration::from_secs(15);
+
+// How much each thing is worth, a life being 1
+const ENERGY_WEIGHT: f32 = 0.5; // a full battery
+const HIT_WEIGHT: f32 = 0.75; // each recent hit on the other player
+
+// What the guess is made from, per player
+pub struct Standing {
+    pub lives: u32,
+    pub energy: u32,
+    pub max_energy: u32,
+    pub recent_hits: u32, // landed on the other player, within RECENT
+}
+
+impl Standing {
+    fn strength(&self) -> f32 {
+        let battery = self.energy.min(self.max_energy) as f32 / self.max_energy.max(1) as f32;
+        self.lives as f32 + ENERGY_WEIGHT * battery + HIT_WEIGHT * self.recent_hits as f32
+    }
+}
+
+// Player 1's share, from 0 (player 2 is well ahead) to 1. Even is 0.5.
+pub fn share(p1: &Standing, p2: &Standing) -> f32 {
+    let (s1, s2) = (p1.strength(), p2.strength());
+    if s1 + s2 <= 0.0 {
+        return 0.5;
+    }
+    s1 / (s1 + s2)
+}
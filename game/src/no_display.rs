@@ -0,0 +1,39 @@
+
+use crate::render::View;
+use crate::GameError;
+
+// Output for serve: draws nothing, for when only bots are playing
+pub struct NoDisplay {
+    w: u16,
+    h: u16,
+}
+
+pub fn new(w: u16, h: u16) -> NoDisplay {
+    NoDisplay { w, h }
+}
+
+impl crate::Output for NoDisplay {
+    fn init(&mut self) -> Result<(), GameError> {
+        Ok(())
+    }
+
+    fn dimensions(&self) -> Result<(u16, u16), GameError> {
+        Ok((self.w, self.h))
+    }
+
+    fn render(&mut self, _v: View) -> Result<(), GameError> {
+        Ok(())
+    }
+
+    fn banner(&mut self, _msg: &[&str]) -> Result<(), GameError> {
+        Ok(())
+    }
+
+    fn print(&mut self, _x: u16, _y: u16, _s: &str) -> Result<(), GameError> {
+        Ok(())
+    }
+
+    fn cleanup(&mut self) -> Result<(), GameError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use bot::skill::Difficulty;
+use rs_sdk::transport::Endpoint;
+use rs_sdk::{Player, TCP_ADDR_1, TCP_ADDR_2};
+
+use crate::config::GameConfig;
+use crate::daily::Challenge;
+
+// Arena size when there's no terminal to fill
+pub const HEADLESS_WIDTH: u16 = 80;
+pub const HEADLESS_HEIGHT: u16 = 24;
+
+// Where spectators connect, see spectators.rs
+#[cfg(unix)]
+pub const SPECTATE_SOCK_NAME: &str = "/tmp/rust-console-game-spectate.sock";
+pub const SPECTATE_TCP_ADDR: &str = "127.0.0.1:7773";
+
+// How to run the game, see main.rs for the command line
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    pub width: Option<u16>, // None to fill the terminal
+    pub height: Option<u16>,
+    pub config: GameConfig, // lives, speed, map, energy, colors, keys
+    pub config_file: Option<PathBuf>, // where config came from, or would have, watched in debug mode
+    pub seed: Option<u64>,          // for the random map. None picks one.
+    pub backend: Option<Backend>,   // how bots connect. None for the platform's usual.
+    pub vs_ai: Option<Difficulty>,  // built in bot plays player 2
+    pub headless: bool,             // no display or keyboard, bots only. The serve subcommand.
+    pub record: Option<PathBuf>,    // save a replay here
+    pub metrics: Option<SocketAddr>, // serve runtime metrics over HTTP here, see metrics.rs
+    pub daily: Option<Challenge>,    // the daily challenge being played, see daily.rs
+    pub debug: bool,                 // F2 opens the World inspector, see inspector.rs
+    pub fake_latency: Option<Latency>, // lag added to each bot's connection, see server::delay_line
+}
+
+impl Options {
+    pub fn endpoint(&self, p: Player) -> Endpoint {
+        match self.backend {
+            Some(b) => b.endpoint(p),
+            None => p.endpoint(),
+        }
+    }
+
+    pub fn spectate_endpoint(&self) -> Endpoint {
+        match self.backend {
+            Some(b) => b.spectate_endpoint(),
+            None => Backend::default().spectate_endpoint(),
+        }
+    }
+}
+
+// How bots connect to the game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    #[cfg(unix)]
+    Unix, // rs_sdk::SOCK_NAME_1 and SOCK_NAME_2
+    Tcp, // rs_sdk::TCP_ADDR_1 and TCP_ADDR_2
+}
+
+impl Backend {
+    pub fn endpoint(self, p: Player) -> Endpoint {
+        match (self, p) {
+            #[cfg(unix)]
+            (Backend::Unix, p) => p.endpoint(),
+            (Backend::Tcp, Player::One) => Endpoint::Tcp(TCP_ADDR_1.to_string()),
+            (Backend::Tcp, Player::Two) => Endpoint::Tcp(TCP_ADDR_2.to_string()),
+        }
+    }
+
+    pub fn spectate_endpoint(self) -> Endpoint {
+        match self {
+            #[cfg(unix)]
+            Backend::Unix => Endpoint::Unix(SPECTATE_SOCK_NAME.into()),
+            Backend::Tcp => Endpoint::Tcp(SPECTATE_TCP_ADDR.to_string()),
+        }
+    }
+}
+
+// The platform's usual, as for Player::endpoint
+impl Default for Backend {
+    #[cfg(unix)]
+    fn default() -> Backend {
+        Backend::Unix
+    }
+
+    #[cfg(not(unix))]
+    fn default() -> Backend {
+        Backend::Tcp
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Backend, String> {
+        match s {
+            #[cfg(unix)]
+            "unix" => Ok(Backend::Unix),
+            "tcp" => Ok(Backend::Tcp),
+            #[cfg(unix)]
+            _ => Err(format!("unknown backend '{}', use unix or tcp", s)),
+            #[cfg(not(unix))]
+            _ => Err(format!("unknown backend '{}', only tcp is available here", s)),
+        }
+    }
+}
+
+// Pretend lag on bot connections, each way, for testing how a bot copes with
+// a slow network. Each message is held for delay plus or minus up to jitter,
+// but never overtakes one sent before it, as on a real connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Latency {
+    pub delay: Duration,
+    pub jitter: Duration,
+}
+
+// ms, or ms:jitter, e.g. 100:20
+impl FromStr for Latency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Latency, String> {
+        let ms = |n: &str| {
+            n.parse::<u64>()
+                .map(Duration::from_millis)
+                .map_err(|_| format!("bad latency '{}', use ms or ms:jitter, e.g. 100:20", s))
+        };
+        let (delay, jitter) = match s.split_once(':') {
+            Some((d, j)) => (ms(d)?, ms(j)?),
+            None => (ms(s)?, Duration::ZERO),
+        };
+        if jitter > delay {
+            return Err(format!("latency jitter {:?} is more than the delay {:?}", jitter, delay));
+        }
+        Ok(Latency { delay, jitter })
+    }
+}
@@ -0,0 +1,166 @@
+use std::convert::TryFrom;
+use std::io::{self, Read};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use log::debug;
+use rs_sdk::transport::Endpoint;
+use rs_sdk::GameState;
+
+use crate::input::{self, PlaybackEvent};
+use crate::config::Screen;
+use crate::replay;
+use crate::{console, heatmap, session, GameConfig, GameError, Output, Palette, World, FRAME_GAP_MS};
+
+// Replays from before weapon tiers still play, see World::load_state
+const OLDEST_PROTOCOL: u8 = 5;
+
+// Re-render a replay recorded with --record.
+// Space pauses, s (or right arrow) steps a frame, 1 / 2 / 4 set the speed.
+pub fn run(path: &Path, palette: Option<Palette>, momentum: bool, labels: bool) -> Result<(), GameError> {
+    let mut reader = replay::Reader::open(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    let game = read_game(&mut reader)?
+        .ok_or_else(|| GameError::Protocol(format!("{} is not a replay file", path.display())))?;
+    let console = console_for(&game)?;
+    let _terminal = session::TerminalGuard::new()?;
+    session::with_output(console, |out| watch(out, reader, game, palette, momentum, labels, None))
+}
+
+// Watch a game as it's played, see spectators.rs. Only Esc works, the
+// game sets the pace.
+pub fn spectate(endpoint: &Endpoint, palette: Option<Palette>, momentum: bool, labels: bool) -> Result<(), GameError> {
+    let conn = endpoint.connect().map_err(|e| {
+        io::Error::new(e.kind(), format!("no game to watch on {}: {}", endpoint, e))
+    })?;
+    let mut reader = replay::Reader::new(conn)
+        .map_err(|e| io::Error::new(e.kind(), format!("watching {}: {}", endpoint, e)))?;
+    let game = read_game(&mut reader)?
+        .ok_or_else(|| GameError::Protocol(format!("{} is not a spectator endpoint", endpoint)))?;
+    let console = console_for(&game)?;
+    let _terminal = session::TerminalGuard::new()?;
+    session::with_output(console, |out| watch(out, reader, game, palette, momentum, labels, Some(endpoint)))
+}
+
+// What a replay or stream starts with
+pub struct Game {
+    pub protocol: u8, // of the state frames, see World::load_state
+    pub width: u32,
+    pub height: u32,
+    pub config: GameConfig, // as played, or the defaults for a format 0 replay
+}
+
+// The game's arena, centered or scrolling as it was when played
+fn console_for(game: &Game) -> Result<console::ConsoleOutput, GameError> {
+    let size = |n: u32| u16::try_from(n).ok();
+    console::with_size(size(game.width), size(game.height), Screen::default())
+}
+
+// The RECORD_GAME record, and RECORD_CONFIG after it. None if they're not there.
+pub fn read_game<R: Read>(reader: &mut replay::Reader<R>) -> Result<Option<Game>, GameError> {
+    let first = match reader.next_record() {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None), // too short
+        res => res?,
+    };
+    let (protocol, width, height) = match first {
+        Some((replay::RECORD_GAME, payload)) if payload.len() == 9 => {
+            if !(OLDEST_PROTOCOL..=rs_sdk::PROTOCOL_VERSION).contains(&payload[0]) {
+                return Err(GameError::Protocol(format!(
+                    "replay is protocol version {}, we only play versions {} to {}",
+                    payload[0],
+                    OLDEST_PROTOCOL,
+                    rs_sdk::PROTOCOL_VERSION
+                )));
+            }
+            (
+                payload[0],
+                u32::from_be_bytes([payload[1], payload[2], payload[3], payload[4]]),
+                u32::from_be_bytes([payload[5], payload[6], payload[7], payload[8]]),
+            )
+        }
+        _ => return Ok(None),
+    };
+    if reader.version() == 0 {
+        return Ok(Some(Game { protocol, width, height, config: GameConfig::default() }));
+    }
+    match reader.next_record()? {
+        Some((replay::RECORD_CONFIG, payload)) => {
+            let (map, seed, config) = replay::read_config(&payload)?;
+            debug!("Replay of a {:?} map, seed {}", map, seed);
+            let config = config.unwrap_or_default();
+            Ok(Some(Game { protocol, width, height, config }))
+        }
+        _ => Ok(None),
+    }
+}
+
+// live is where we're spectating from, None for a replay file
+fn watch<T: Output, R: Read>(
+    out: &mut T,
+    mut reader: replay::Reader<R>,
+    game: Game,
+    palette: Option<Palette>,
+    momentum: bool, // show the bar, whatever the game did
+    labels: bool,   // players' names next to them, whatever the game did
+    live: Option<&Endpoint>,
+) -> Result<(), GameError> {
+    let (_, screen_height) = out.dimensions()?;
+
+    let mut config = game.config;
+    if let Some(p) = palette {
+        config.set_palette(p);
+    }
+    config.momentum |= momentum;
+    config.labels |= labels;
+    let mut world = World::with_config(game.width, game.height, config);
+    let mut speed = 1;
+    let mut is_paused = false;
+    let mut is_step = false;
+    loop {
+        if !is_paused || is_step {
+            let frame = match reader.next_state()? {
+                Some(frame) => frame,
+                None => break,
+            };
+            world.load_state(&frame, game.protocol)?;
+            // not the start positions they wait on between rounds
+            if GameState::from_num(frame[4]) == Some(GameState::Playing) {
+                heatmap::remember(&mut world);
+            }
+            out.render(world.view())?;
+            is_step = false;
+        }
+        let status = match live {
+            Some(endpoint) => format!("Spectating {}. Tick {}. h heatmap, Esc to quit.", endpoint, world.tick),
+            None => format!(
+                "Replay. Tick {}. Speed {}x{}. Keys: space pause, s step, 1 2 4 speed, h heatmap, Esc quit.",
+                world.tick,
+                speed,
+                if is_paused { ". PAUSED" } else { "" },
+            ),
+        };
+        out.print(0, screen_height - 1, &status)?;
+
+        // when live, reading the next frame is what waits
+        let poll_dur = match live {
+            Some(_) => Duration::from_millis(1),
+            None => Duration::from_millis(FRAME_GAP_MS / speed),
+        };
+        for ev in input::playback_events(poll_dur)? {
+            match ev {
+                PlaybackEvent::Quit => return Ok(()),
+                PlaybackEvent::ToggleHeatmap => world.heatmap = !world.heatmap,
+                _ if live.is_some() => {}
+                PlaybackEvent::TogglePause => is_paused = !is_paused,
+                PlaybackEvent::Step => is_step = true,
+                PlaybackEvent::Speed(n) => speed = n,
+            }
+        }
+    }
+
+    let end = if live.is_some() { "Game over" } else { "End of replay" };
+    out.banner(&[end])?;
+    thread::sleep(Duration::from_secs(2));
+    Ok(())
+}
@@ -7,7 +7,8 @@ pub struct Pos {
     pub invalid: bool,
 }
 impl Pos {
-    // A new position moved amount units in given direction
+    // A new position moved amount units in given direction.
+    // If that would go below 0 or past u32::MAX it's invalid, and doesn't move.
     pub fn moved(&self, amount: u32, dir: crate::Dir) -> Pos {
         let (mut x, mut y) = (self.x, self.y);
         let mut invalid = false;
@@ -19,7 +20,10 @@ impl Pos {
                     invalid = true;
                 }
             }
-            crate::Dir::Down => y += amount,
+            crate::Dir::Down => match self.y.checked_add(amount) {
+                Some(n) => y = n,
+                None => invalid = true,
+            },
             crate::Dir::Left => {
                 if self.x > amount {
                     x -= amount;
@@ -27,11 +31,28 @@ impl Pos {
                     invalid = true;
                 }
             }
-            crate::Dir::Right => x += amount,
+            crate::Dir::Right => match self.x.checked_add(amount) {
+                Some(n) => x = n,
+                None => invalid = true,
+            },
             crate::Dir::None => (),
         }
         Pos { x, y, invalid }
     }
+    // A new position dx across and dy down. As for moved, going to 0 or
+    // past u32::MAX is invalid, and doesn't move.
+    pub fn shifted(&self, dx: i64, dy: i64) -> Pos {
+        let (x, y) = (i64::from(self.x) + dx, i64::from(self.y) + dy);
+        let on = |n: i64| (1..=i64::from(u32::MAX)).contains(&n);
+        if !on(x) || !on(y) {
+            return Pos { invalid: true, ..*self };
+        }
+        Pos {
+            x: x as u32,
+            y: y as u32,
+            invalid: false,
+        }
+    }
     pub fn does_hit(&self, pos: Pos) -> bool {
         self.x == pos.x && self.y == pos.y
     }
@@ -52,3 +73,97 @@ impl Display for Pos {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use rs_sdk::Dir;
+
+    use super::Pos;
+
+    fn dir() -> impl Strategy<Value = Dir> {
+        prop_oneof![
+            Just(Dir::None),
+            Just(Dir::Up),
+            Just(Dir::Down),
+            Just(Dir::Left),
+            Just(Dir::Right),
+        ]
+    }
+
+    fn pos(x: u32, y: u32) -> Pos {
+        Pos { x, y, invalid: false }
+    }
+
+    fn parts(p: Pos) -> (u32, u32, bool) {
+        (p.x, p.y, p.invalid)
+    }
+
+    proptest! {
+        #[test]
+        fn opposite_twice_is_the_same(d in dir()) {
+            prop_assert_eq!(d.opposite().opposite(), d);
+        }
+
+        #[test]
+        fn from_num_undoes_as_num(d in dir()) {
+            prop_assert_eq!(Dir::from_num(d.as_num()), Some(d));
+        }
+
+        #[test]
+        fn from_num_never_panics(n in any::<u8>()) {
+            prop_assert_eq!(Dir::from_num(n).is_some(), n <= 4);
+        }
+
+        #[test]
+        fn moving_back_returns(x in 1..=u32::MAX, y in 1..=u32::MAX, n in 0u32..100, d in dir()) {
+            // (0 is never on the board, moving Up or Left onto it is invalid)
+            let there = pos(x, y).moved(n, d);
+            prop_assume!(!there.invalid);
+            prop_assert_eq!(parts(there.moved(n, d.opposite())), (x, y, false));
+        }
+
+        #[test]
+        fn invalid_moves_stay_put(x in any::<u32>(), y in any::<u32>(), n in any::<u32>(), d in dir()) {
+            // no underflow or overflow: either a real move, or flagged and unmoved
+            let p = pos(x, y).moved(n, d);
+            if p.invalid {
+                prop_assert_eq!((p.x, p.y), (x, y));
+            } else {
+                let dx = i64::from(p.x) - i64::from(x);
+                let dy = i64::from(p.y) - i64::from(y);
+                let want = match d {
+                    Dir::None => (0, 0),
+                    Dir::Up => (0, -i64::from(n)),
+                    Dir::Down => (0, i64::from(n)),
+                    Dir::Left => (-i64::from(n), 0),
+                    Dir::Right => (i64::from(n), 0),
+                };
+                prop_assert_eq!((dx, dy), want);
+            }
+        }
+
+        #[test]
+        fn shifting_back_returns(x in 1..=u32::MAX, y in 1..=u32::MAX, dx in -100i64..100, dy in -100i64..100) {
+            let there = pos(x, y).shifted(dx, dy);
+            prop_assume!(!there.invalid);
+            prop_assert_eq!(parts(there.shifted(-dx, -dy)), (x, y, false));
+        }
+
+        #[test]
+        fn invalid_shifts_stay_put(x in any::<u32>(), y in any::<u32>(), dx in any::<i32>(), dy in any::<i32>()) {
+            let p = pos(x, y).shifted(i64::from(dx), i64::from(dy));
+            if p.invalid {
+                prop_assert_eq!((p.x, p.y), (x, y));
+            } else {
+                prop_assert_eq!((i64::from(p.x) - i64::from(x), i64::from(p.y) - i64::from(y)), (i64::from(dx), i64::from(dy)));
+            }
+        }
+
+        #[test]
+        fn up_and_left_stop_above_zero(x in 0u32..50, y in 0u32..50, n in 0u32..50) {
+            prop_assert_eq!(pos(x, y).moved(n, Dir::Up).invalid, y <= n);
+            prop_assert_eq!(pos(x, y).moved(n, Dir::Left).invalid, x <= n);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use rs_sdk::Player;
+
+use crate::config::{ColorName, GameConfig, PlayerKeys};
+
+// Profiles are read from here, one file per profile: profiles/alice.toml
+pub const PROFILE_DIR: &str = "profiles";
+
+const PREFIX: &str = "profile:";
+
+// A player's name and how they like to play, e.g. profiles/alice.toml:
+//
+//   name = "Alice"
+//   color = "magenta"
+//   glyph = "A"
+//   [keys]
+//   up = "i"
+//   ...
+//
+// Only name is required. keys, if given, must have all of them, as in config.toml.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub name: String,
+    pub color: Option<ColorName>,
+    pub glyph: Option<String>,
+    pub keys: Option<PlayerKeys>,
+}
+
+impl Profile {
+    // From the command line: "profile:alice" loads profiles/alice.toml,
+    // anything else is just a name.
+    pub fn parse(s: &str) -> Result<Profile, String> {
+        match s.strip_prefix(PREFIX) {
+            Some(id) => Profile::load(id),
+            None => Ok(Profile {
+                name: s.to_string(),
+                color: None,
+                glyph: None,
+                keys: None,
+            }),
+        }
+    }
+
+    pub fn load(id: &str) -> Result<Profile, String> {
+        if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+            return Err(format!("'{}' is not a profile name", id));
+        }
+        let path: PathBuf = [PROFILE_DIR, &format!("{}.toml", id)].iter().collect();
+        let s = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        toml::from_str(&s).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // Use this profile for player. Call GameConfig::validate after, two
+    // profiles may want the same keys.
+    pub fn apply(&self, config: &mut GameConfig, player: Player) {
+        let (name, glyph, color, keys) = match player {
+            Player::One => (
+                &mut config.names.player1,
+                &mut config.glyphs.player1,
+                &mut config.colors.player1,
+                &mut config.keys.player1,
+            ),
+            Player::Two => (
+                &mut config.names.player2,
+                &mut config.glyphs.player2,
+                &mut config.colors.player2,
+                &mut config.keys.player2,
+            ),
+        };
+        *name = self.name.clone();
+        if let Some(g) = &self.glyph {
+            *glyph = g.clone();
+        }
+        if let Some(c) = &self.color {
+            *color = c.clone();
+        }
+        if let Some(k) = &self.keys {
+            *keys = k.clone();
+        }
+    }
+}
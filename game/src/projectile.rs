@@ -0,0 +1,267 @@
+use rs_sdk::{missile_speed, Dir, EntityKind, Weapon, CLUSTER_FLIGHT};
+
+use crate::commands::Spawn;
+use crate::config::{Energy, Palette};
+use crate::pos::Pos;
+use crate::{Lifetime, Sprite, World, EXPLODE_DURATION};
+
+// A reflected ray moves back one square, hitting whatever it finds there,
+// then it's gone. See reflect.
+const REFLECT_TICKS: u32 = 2;
+
+// How long a beam stays on the board
+const BEAM_TICKS: u32 = 6;
+
+// What each weapon fires. A new weapon is an entry here, and a Weapon
+// variant in rs_sdk for players to pick it with.
+pub(crate) struct Projectile {
+    kind: EntityKind,
+    name: &'static str,               // of the entities, e.g. "Missile 12"
+    speed: fn(u8) -> u8,              // squares a tick, for the weapon tier
+    length: Option<u32>,              // squares long, or None to reach to the edge
+    lifetime: fn(&World, Dir) -> u32, // ticks before it's gone
+    pub cost: fn(&Energy) -> u32,     // to fire one
+    explodes: bool,                   // at the end of its lifetime, see explode_system
+    bounces: bool,                    // off obstacles, instead of vanishing
+    shielded: bool,                   // survives collisions
+    pierces: bool,                    // goes through obstacles and shields
+    splits: bool,                     // into four missiles as it explodes, see split
+    anchored: bool,                   // moves with the player who fired it, see follow_parents
+    arming: u32,                      // squares it goes before it can hit anything, see collision_system
+    glyphs: [&'static str; 3],        // vertical, horizontal, exploding ("" if it doesn't)
+    contrast_glyphs: [&'static str; 3], // player 2's, for Palette::HighContrast
+}
+
+// In Weapon::as_num order
+const PROJECTILES: [Projectile; 4] = [
+    Projectile {
+        kind: EntityKind::Missile,
+        name: "Missile",
+        speed: missile_speed,
+        length: Some(2),
+        lifetime: |w, dir| match dir {
+            Dir::Up | Dir::Down => w.missile_range_vertical,
+            _ => w.missile_range_horizontal,
+        },
+        cost: |e| e.missile,
+        explodes: true,
+        bounces: false,
+        shielded: false,
+        pierces: false,
+        splits: false,
+        anchored: false,
+        arming: 2,
+        glyphs: ["*", "*", "#"],
+        contrast_glyphs: ["o", "o", "%"],
+    },
+    Projectile {
+        kind: EntityKind::Ray,
+        name: "Ray",
+        speed: |_| 1,
+        length: None,
+        lifetime: |w, _| w.ticks(w.config.weapons.ray_lifetime),
+        cost: |e| e.ray,
+        explodes: false,
+        bounces: false,
+        shielded: true,
+        pierces: false,
+        splits: false,
+        anchored: false,
+        arming: 0,
+        glyphs: ["|", "-", ""],
+        contrast_glyphs: ["!", "=", ""],
+    },
+    // from a PICKUP_BEAM, fired once. All at once, wall to wall.
+    Projectile {
+        kind: EntityKind::Beam,
+        name: "Beam",
+        speed: |_| 0,
+        length: None,
+        lifetime: |_, _| BEAM_TICKS,
+        cost: |_| 0,
+        explodes: false,
+        bounces: false,
+        shielded: true,
+        pierces: true,
+        splits: false,
+        anchored: false,
+        arming: 0,
+        glyphs: ["H", "~", ""],
+        contrast_glyphs: ["$", "$", ""],
+    },
+    Projectile {
+        kind: EntityKind::Cluster,
+        name: "Cluster",
+        speed: missile_speed,
+        length: Some(2),
+        lifetime: |w, dir| match dir {
+            Dir::Up | Dir::Down => w.missile_range_vertical,
+            _ => w.missile_range_horizontal,
+        },
+        cost: |e| e.cluster,
+        explodes: true,
+        bounces: false,
+        shielded: false,
+        pierces: false,
+        splits: true,
+        anchored: false,
+        arming: 2,
+        glyphs: ["&", "&", "#"],
+        contrast_glyphs: ["8", "8", "%"],
+    },
+];
+
+pub(crate) fn of(weapon: Weapon) -> &'static Projectile {
+    &PROJECTILES[weapon.as_num() as usize]
+}
+
+// The one a state frame's entity kind is drawn as, if it's a projectile's
+pub(crate) fn of_kind(kind: EntityKind) -> Option<&'static Projectile> {
+    PROJECTILES.iter().find(|p| p.kind == kind)
+}
+
+impl Projectile {
+    // For the player with this color_idx (see Colors::by_index)
+    fn glyphs(&self, palette: Palette, color_idx: usize) -> [&'static str; 3] {
+        match (palette, color_idx) {
+            (Palette::HighContrast, 2) => self.contrast_glyphs,
+            _ => self.glyphs,
+        }
+    }
+}
+
+// Fire def from pos, by player owner at their weapon tier. Returns false if
+// it doesn't fit on the board.
+pub(crate) fn spawn_projectile(w: &mut World, def: &Projectile, pos: Pos, dir: Dir, owner: usize) -> bool {
+    if dir == Dir::None {
+        return false;
+    }
+    let positions = positions(w, def, pos, dir);
+    if def.length.is_some_and(|n| positions.len() < n as usize) {
+        return false;
+    }
+    let lifetime = (def.lifetime)(w, dir);
+    let (color_idx, tier) = (w.sprite[owner].color_idx, w.tier[owner]);
+    add_projectile(w, def, positions, dir, lifetime, color_idx, tier);
+    let id = w.name.len() - 1;
+    w.owner[id] = Some(owner);
+    w.arming[id] = (def.arming, true);
+    if def.anchored {
+        w.parent[id] = Some((owner, w.position[owner][0]));
+    }
+    true
+}
+
+// A projectile at exactly these positions, fired by no one in particular.
+// State frames are drawn with these.
+pub(crate) fn add_projectile(
+    w: &mut World,
+    def: &Projectile,
+    positions: Vec<Pos>,
+    dir: Dir,
+    lifetime: u32,
+    color_idx: usize,
+    tier: u8,
+) {
+    w.name.push(format!("{} {}", def.name, w.name.len()));
+    w.alive.push(true);
+    w.kind.push(def.kind);
+    w.lifetime.push(Lifetime::Temporary(lifetime));
+    w.position.push(positions);
+    w.velocity.push(((def.speed)(tier), dir));
+    let [vertical, horizontal, exploding] = def.glyphs(w.config.palette, color_idx);
+    w.sprite.push(Sprite {
+        color_idx,
+        is_bold: false,
+        _frame_num: 0,
+        texture_vertical: vec![vertical.to_string()],
+        texture_horizontal: vec![horizontal.to_string()],
+        texture_explosion: vec![Some(exploding.to_string()).filter(|s| !s.is_empty())],
+    });
+    w.energy.push(0);
+    w.shield.push(def.shielded);
+    w.bounce.push(def.bounces);
+    w.explode.push((def.explodes, false));
+    w.active_weapon.push(None);
+    w.tier.push(tier);
+    w.owner.push(None);
+    w.parent.push(None);
+    w.arming.push((0, false));
+    w.reflected.push(false);
+    w.grace.push(0);
+}
+
+// If cluster id splits, queue its four missiles, one each way from just
+// outside its blast, where they can't be caught in it. Not through obstacles.
+pub(crate) fn split(w: &mut World, id: usize, radius: u32) {
+    let (Some(def), Some(owner)) = (of_kind(w.kind[id]), w.owner[id]) else {
+        return;
+    };
+    if !def.splits {
+        return;
+    }
+    let center = w.position[id][0];
+    for dir in [Dir::Up, Dir::Down, Dir::Left, Dir::Right] {
+        if !(1..=radius + 1).all(|n| w.is_on_board(center.moved(n, dir))) {
+            continue;
+        }
+        let pos = center.moved(radius + 1, dir);
+        w.commands.spawn(Spawn::Fragment { pos, dir, owner });
+    }
+}
+
+// One of split's missiles, flying until it explodes
+pub(crate) fn fragment(w: &mut World, pos: Pos, dir: Dir, owner: usize) {
+    if spawn_projectile(w, of(Weapon::Missile), pos, dir, owner) {
+        let id = w.name.len() - 1;
+        w.lifetime[id] = Lifetime::Temporary(CLUSTER_FLIGHT + EXPLODE_DURATION);
+    }
+}
+
+// Turn ray id back the way it came off a shield at pos: from the square
+// before pos out to the edge of the board, there for a tick. Bots see it
+// coming from the other way, so it doesn't need a weapon of its own.
+pub(crate) fn reflect(w: &mut World, id: usize, pos: Pos) {
+    let back = w.velocity[id].1.opposite();
+    let start = pos.moved(1, back);
+    w.reflected[id] = true;
+    if !w.is_on_board(start) {
+        w.alive[id] = false;
+        return;
+    }
+    w.position[id] = to_edge(w, start, back, false);
+    w.velocity[id].1 = back;
+    w.lifetime[id] = Lifetime::Temporary(REFLECT_TICKS);
+}
+
+// Where def is, fired from pos: as much of its length as fits on the board
+pub(crate) fn positions(w: &World, def: &Projectile, pos: Pos, dir: Dir) -> Vec<Pos> {
+    match def.length {
+        Some(n) => std::iter::once(pos)
+            .chain((1..n).map(|i| pos.moved(i, dir)).take_while(|&p| w.is_on_board(p)))
+            .collect(),
+        None => to_edge(w, pos, dir, def.pierces),
+    }
+}
+
+// From start in dir up to the edge of the board, or the first obstacle
+// unless it pierces
+fn to_edge(w: &World, start: Pos, dir: Dir, pierces: bool) -> Vec<Pos> {
+    let dist_to_edge = match dir {
+        Dir::Left => start.x - 1,
+        Dir::Right => w.width - 2 - start.x,
+        Dir::Up => start.y - 2,
+        Dir::Down => w.height - 2 - start.y - 1,
+        Dir::None => 0,
+    };
+    let mut positions = Vec::with_capacity(dist_to_edge as usize);
+    let mut p = start;
+    for _ in 0..dist_to_edge {
+        positions.push(p);
+        p = p.moved(1, dir);
+        if !w.in_bounds(p) || (!pierces && !w.is_on_board(p)) {
+            break;
+        }
+    }
+    positions
+}
@@ -0,0 +1,255 @@
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crossterm::style::Color;
+use log::trace;
+use rs_sdk::Weapon;
+
+use crate::config::LineStyle;
+use crate::sound::Cue;
+use crate::{GameError, Output};
+
+// Frames waiting for the render thread. If it falls further behind than
+// this we drop frames rather than slow the game down.
+const FRAME_QUEUE: usize = 2;
+
+// What the display needs from the World, copied out each frame by
+// World::view so it can be drawn while the next frame is simulated.
+#[derive(Clone)]
+pub struct View {
+    pub players: [PlayerView; 2], // player one, player two
+    pub cells: Vec<Cell>,         // everything alive, drawn in this order
+    pub cues: Vec<Cue>,           // sounds to play with it
+    pub clock: Option<Clock>,     // None when watching, we don't know when rounds start
+    pub feed: Vec<FeedLine>,      // kill feed, oldest first
+    pub inspector: Option<Inspector>, // debug mode's entity list, when it's open
+    pub border: LineStyle,
+    pub border_color: Color,
+    pub momentum: Option<f32>, // player 1's share when the bar is on, see momentum.rs
+    pub focus: (u16, u16),     // between the players, followed when the arena is bigger than the screen
+    pub wheels: Vec<WeaponWheel>, // for players who just changed weapon
+    pub heat: Vec<HeatCell>,      // where the players have been, when the heatmap is on
+    pub labels: Vec<Label>,       // next to entities, see labels.rs
+    pub turn: Option<Turn>,       // in hot-seat play while a player picks, see turns.rs
+}
+
+// e.g. "Player 1's turn: move Left. Enter to go.", under the board
+#[derive(Clone)]
+pub struct Turn {
+    pub text: String,
+    pub color: Color, // the player's
+}
+
+// Text on the board, e.g. a player's name beside them
+#[derive(Clone)]
+pub struct Label {
+    pub x: u16, // the first character
+    pub y: u16,
+    pub text: String,
+    pub color: Color,
+}
+
+// A square's background, shaded by how often the players were on it, see heatmap.rs
+#[derive(Clone, Copy)]
+pub struct HeatCell {
+    pub x: u16,
+    pub y: u16,
+    pub color: Color,
+}
+
+// The weapons a player can pick from, shown by them for a moment after
+// they change, so they can see what they changed to
+#[derive(Clone)]
+pub struct WeaponWheel {
+    pub x: u16, // the player
+    pub y: u16,
+    pub weapons: Vec<String>, // in the order change weapon goes through them
+    pub selected: usize,
+    pub color: Color, // the selected one's, the player's then dimmed as it gets older
+    pub faded: Color, // the others'
+}
+
+// What just happened, e.g. "Player 1's missile hit Player 2"
+#[derive(Clone)]
+pub struct FeedLine {
+    pub text: String,
+    pub color: Color, // dimmed as it gets older
+}
+
+// Every alive entity, one per line, see inspector.rs
+#[derive(Clone)]
+pub struct Inspector {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub scroll: usize, // first line shown
+}
+
+// Time played, for the status bar
+#[derive(Clone, Copy)]
+pub struct Clock {
+    pub round: Duration,
+    pub game: Duration, // this match
+}
+
+#[derive(Clone)]
+pub struct PlayerView {
+    pub name: String,
+    pub keys: String, // Keys summary, for the status bar
+    pub lives: u32,
+    pub max_lives: u32,
+    pub energy: Option<u32>, // None when it's hidden from the other player, see opponent_energy
+    pub weapon: Weapon,
+    pub tier: u8, // see rs_sdk::MAX_TIER
+    pub shield: bool,
+    pub color: Color,
+    pub ping: Option<Duration>, // to the bot playing them, see Server::ping
+    pub idle: bool,             // their bot went quiet, waiting for another
+}
+
+// m:ss, or h:mm:ss for an hour or more
+pub fn clock_time(d: Duration) -> String {
+    let s = d.as_secs();
+    match s / 3600 {
+        0 => format!("{}:{:02}", s / 60, s % 60),
+        h => format!("{}:{:02}:{:02}", h, s / 60 % 60, s % 60),
+    }
+}
+
+// One character on the board
+#[derive(Clone, Copy)]
+pub struct Cell {
+    pub x: u16,
+    pub y: u16,
+    pub glyph: char,
+    pub color: Color,
+    pub bold: bool,
+}
+
+enum Command {
+    Frame(Box<View>),
+    Banner(Vec<String>),
+    Print(u16, u16, String),
+}
+
+// Wraps an Output so everything is drawn on a thread of its own, and a slow
+// terminal can't hold up the game loop or the bots. Calls return once the
+// drawing is queued. An error drawing comes back from the next call.
+pub struct Threaded<T: Output + Send + 'static> {
+    dims: (u16, u16),
+    scrolling: bool, // as out is, see Output::is_scrolling
+    out: Option<T>, // ours before init and after cleanup, the thread's between
+    tx: Option<SyncSender<Command>>,
+    thread: Option<JoinHandle<(T, Result<(), GameError>)>>,
+}
+
+impl<T: Output + Send + 'static> Threaded<T> {
+    pub fn new(out: T) -> Result<Threaded<T>, GameError> {
+        Ok(Threaded {
+            dims: out.dimensions()?,
+            scrolling: out.is_scrolling(),
+            out: Some(out),
+            tx: None,
+            thread: None,
+        })
+    }
+
+    fn send(&mut self, cmd: Command) -> Result<(), GameError> {
+        let tx = self
+            .tx
+            .as_ref()
+            .ok_or_else(|| GameError::Internal("render thread is not running".to_string()))?;
+        match tx.send(cmd) {
+            Ok(()) => Ok(()),
+            Err(_) => self.stop(), // it stopped on an error, go and get it
+        }
+    }
+
+    // Let the thread draw what's queued, and take the output back
+    fn stop(&mut self) -> Result<(), GameError> {
+        self.tx = None;
+        let t = match self.thread.take() {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+        let (out, res) = t
+            .join()
+            .map_err(|_| GameError::Internal("render thread panicked".to_string()))?;
+        self.out = Some(out);
+        res
+    }
+}
+
+impl<T: Output + Send + 'static> Output for Threaded<T> {
+    fn init(&mut self) -> Result<(), GameError> {
+        let mut out = self
+            .out
+            .take()
+            .ok_or_else(|| GameError::Internal("render thread already started".to_string()))?;
+        if let Err(e) = out.init() {
+            self.out = Some(out);
+            return Err(e);
+        }
+        let (tx, rx) = mpsc::sync_channel(FRAME_QUEUE);
+        self.tx = Some(tx);
+        self.thread = Some(thread::spawn(move || draw(out, rx)));
+        Ok(())
+    }
+
+    fn dimensions(&self) -> Result<(u16, u16), GameError> {
+        Ok(self.dims)
+    }
+
+    fn is_scrolling(&self) -> bool {
+        self.scrolling
+    }
+
+    fn render(&mut self, v: View) -> Result<(), GameError> {
+        let tx = self
+            .tx
+            .as_ref()
+            .ok_or_else(|| GameError::Internal("render thread is not running".to_string()))?;
+        match tx.try_send(Command::Frame(Box::new(v))) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                trace!("Render thread is behind, dropped a frame");
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(_)) => self.stop(),
+        }
+    }
+
+    fn banner(&mut self, msg: &[&str]) -> Result<(), GameError> {
+        self.send(Command::Banner(msg.iter().map(|m| m.to_string()).collect()))
+    }
+
+    fn print(&mut self, x: u16, y: u16, s: &str) -> Result<(), GameError> {
+        self.send(Command::Print(x, y, s.to_string()))
+    }
+
+    fn cleanup(&mut self) -> Result<(), GameError> {
+        let res = self.stop();
+        if let Some(out) = self.out.as_mut() {
+            out.cleanup()?;
+        }
+        res
+    }
+}
+
+// The render thread. Runs until the sender goes away or drawing fails.
+fn draw<T: Output>(mut out: T, rx: Receiver<Command>) -> (T, Result<(), GameError>) {
+    for cmd in rx {
+        let res = match cmd {
+            Command::Frame(v) => out.render(*v),
+            Command::Banner(msg) => {
+                let msg: Vec<&str> = msg.iter().map(String::as_str).collect();
+                out.banner(&msg)
+            }
+            Command::Print(x, y, s) => out.print(x, y, &s),
+        };
+        if let Err(e) = res {
+            return (out, Err(e));
+        }
+    }
+    (out, Ok(()))
+}
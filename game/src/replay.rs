@@ -0,0 +1,521 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Cursor, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{error, warn};
+use rs_sdk::Dir;
+
+use crate::{daily, GameConfig, InputEvent, MapKind};
+
+// A replay file (.rcgr) is a header, a sequence of records and a trailer:
+//  header: MAGIC format_version(u16)
+//  record: kind(u8) len(u32) payload(len bytes)
+//  trailer: a RECORD_END record, the last in the file
+// Payloads:
+//  RECORD_GAME: protocol_version(u8) width(u32) height(u32)
+//  RECORD_CONFIG: map(u8) seed(u64) then the GameConfig as in config.toml
+//  RECORD_HANDSHAKE: player(u8) status(u8), status as in rs_sdk::HANDSHAKE_*
+//  RECORD_STATE: tick(u32) then a state frame exactly as sent to bots
+//  RECORD_EVENT: tick(u32) entity_id(u8) cmd(u8) dir(u8), cmd as in server::into_input_event
+//  RECORD_END: crc32(u32) of every byte before this record, header included
+// The game starts with RECORD_GAME then RECORD_CONFIG. Frames are stamped with
+// their tick outside the frame, so a reader can find its way around a replay
+// whose frames are from a protocol it doesn't know.
+//
+// A spectator stream is the same, without the trailer.
+//
+// Format 0 was records only, no header or trailer, RECORD_GAME alone at the
+// start and state records without a tick. We still read it.
+pub const RECORD_GAME: u8 = 1;
+pub const RECORD_HANDSHAKE: u8 = 2;
+pub const RECORD_STATE: u8 = 3;
+pub const RECORD_EVENT: u8 = 4;
+pub const RECORD_CONFIG: u8 = 5;
+pub const RECORD_END: u8 = 6;
+
+const MAGIC: &[u8; 4] = b"RCGR";
+pub const FORMAT_VERSION: u16 = 1;
+const HEADER_LEN: usize = 6;
+const RECORD_HEAD_LEN: usize = 5; // kind(u8) len(u32)
+
+// Not a bot command, only used in replays
+const CMD_KEY_PRESS: u8 = 7;
+
+pub struct Recorder {
+    w: Mutex<Checksummed<Sink>>,
+    start: Vec<u8>, // what a replay in memory starts again from, see new_match
+}
+
+// Where a Recorder writes: the file given with --record, or memory until
+// the player decides whether to keep it, see save
+enum Sink {
+    File(BufWriter<File>),
+    Memory(Vec<u8>),
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::File(f) => f.write(buf),
+            Sink::Memory(v) => v.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::File(f) => f.flush(),
+            Sink::Memory(_) => Ok(()),
+        }
+    }
+}
+
+impl Recorder {
+    // start is the header and first records, see start
+    pub fn create(path: &Path, start: &[u8]) -> io::Result<Recorder> {
+        Recorder::with_sink(Sink::File(BufWriter::new(File::create(path)?)), start)
+    }
+
+    // Keeps the latest match, for save
+    pub fn in_memory(start: &[u8]) -> Recorder {
+        // writing to a Vec can't fail
+        Recorder::with_sink(Sink::Memory(Vec::new()), start).unwrap()
+    }
+
+    fn with_sink(sink: Sink, start: &[u8]) -> io::Result<Recorder> {
+        let mut w = Checksummed::new(sink);
+        w.write_all(start)?;
+        Ok(Recorder {
+            w: Mutex::new(w),
+            start: start.to_vec(),
+        })
+    }
+
+    // The header and first records it was made with
+    pub fn start(&self) -> &[u8] {
+        &self.start
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        matches!(self.w.lock().unwrap().w, Sink::Memory(_))
+    }
+
+    // A match is starting. A replay in memory drops the one before, a file
+    // keeps everything.
+    pub fn new_match(&self) {
+        let mut w = self.w.lock().unwrap();
+        if let Sink::Memory(_) = w.w {
+            *w = Checksummed::new(Sink::Memory(Vec::new()));
+            w.write_all(&self.start).unwrap();
+        }
+    }
+
+    // Write a replay kept in memory to path, with a trailer, as a file made
+    // with --record would be. Recording carries on.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let w = self.w.lock().unwrap();
+        let mut data = match &w.w {
+            Sink::Memory(v) => v.clone(),
+            Sink::File(_) => return Err(io::Error::other("the replay is already going to a file")),
+        };
+        let crc = w.hasher.clone().finalize();
+        write_record(&mut data, RECORD_END, &crc.to_be_bytes())?;
+        fs::write(path, data)
+    }
+
+    // A bot connected, and whether we let it in
+    pub fn handshake(&self, player: u8, status: u8) {
+        self.record(RECORD_HANDSHAKE, &[player, status]);
+    }
+
+    pub fn state(&self, tick: u32, frame: &[u8]) {
+        let mut w = self.w.lock().unwrap();
+        if let Err(e) = write_state(&mut *w, tick, frame) {
+            error!("replay record: {}", e);
+        }
+    }
+
+    // An input event from keyboard or bot, as the game received it
+    pub fn event(&self, tick: u32, ie: &InputEvent) {
+        let mut payload = Vec::with_capacity(7);
+        payload.extend_from_slice(&tick.to_be_bytes());
+        payload.extend_from_slice(&encode_event(ie));
+        self.record(RECORD_EVENT, &payload);
+    }
+
+    // Call before exiting, it writes the trailer. The server threads hold a
+    // reference until the process ends, so we can't rely on Drop. Anything
+    // recorded after this is lost.
+    pub fn finish(&self) {
+        let mut w = self.w.lock().unwrap();
+        if w.is_finished {
+            return;
+        }
+        let crc = w.hasher.clone().finalize();
+        let res = write_record(&mut *w, RECORD_END, &crc.to_be_bytes()).and_then(|_| w.flush());
+        if let Err(e) = res {
+            error!("replay finish: {}", e);
+        }
+        w.is_finished = true;
+    }
+
+    // Recording is best effort, we don't stop the game if the disk is full
+    fn record(&self, kind: u8, payload: &[u8]) {
+        let mut w = self.w.lock().unwrap();
+        if let Err(e) = write_record(&mut *w, kind, payload) {
+            error!("replay record: {}", e);
+        }
+    }
+}
+
+// Adds up a crc32 of everything written. Once finished, writes are dropped.
+struct Checksummed<W: Write> {
+    w: W,
+    hasher: crc32fast::Hasher,
+    is_finished: bool,
+}
+
+impl<W: Write> Checksummed<W> {
+    fn new(w: W) -> Checksummed<W> {
+        Checksummed {
+            w,
+            hasher: crc32fast::Hasher::new(),
+            is_finished: false,
+        }
+    }
+}
+
+impl<W: Write> Write for Checksummed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.is_finished {
+            return Ok(buf.len());
+        }
+        let n = self.w.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+// How a replay or spectator stream begins: the header, RECORD_GAME and
+// RECORD_CONFIG
+pub fn start(width: u32, height: u32, map: MapKind, seed: u64, config: &GameConfig) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+
+    let mut game = Vec::with_capacity(9);
+    game.push(rs_sdk::PROTOCOL_VERSION);
+    game.extend_from_slice(&width.to_be_bytes());
+    game.extend_from_slice(&height.to_be_bytes());
+    // writing to a Vec can't fail
+    write_record(&mut out, RECORD_GAME, &game).unwrap();
+
+    let mut cfg = vec![map_num(map)];
+    cfg.extend_from_slice(&seed.to_be_bytes());
+    cfg.extend_from_slice(config.to_toml().as_bytes());
+    write_record(&mut out, RECORD_CONFIG, &cfg).unwrap();
+    out
+}
+
+// Where save puts a replay, e.g. replay-2026-10-16-210257.rcgr, in the
+// current directory like stats.toml. The time is UTC.
+pub fn timestamped_name() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let t = secs % 86_400;
+    format!(
+        "replay-{}-{:02}{:02}{:02}.rcgr",
+        daily::date(secs / 86_400),
+        t / 3600,
+        t / 60 % 60,
+        t % 60
+    )
+}
+
+// Also used to stream a live game to spectators, see spectators.rs
+pub fn write_record(w: &mut impl Write, kind: u8, payload: &[u8]) -> io::Result<()> {
+    w.write_all(&[kind])?;
+    w.write_all(&(payload.len() as u32).to_be_bytes())?;
+    w.write_all(payload)
+}
+
+// A RECORD_STATE, without copying the frame
+pub fn write_state(w: &mut impl Write, tick: u32, frame: &[u8]) -> io::Result<()> {
+    w.write_all(&[RECORD_STATE])?;
+    w.write_all(&(frame.len() as u32 + 4).to_be_bytes())?;
+    w.write_all(&tick.to_be_bytes())?;
+    w.write_all(frame)
+}
+
+// The RECORD_CONFIG payload: map, seed and config. The config is None if it's
+// from a game with settings we don't know, we play it with the defaults.
+pub fn read_config(payload: &[u8]) -> io::Result<(MapKind, u64, Option<GameConfig>)> {
+    if payload.len() < 9 {
+        return Err(invalid("RECORD_CONFIG too short"));
+    }
+    let map = map_from_num(payload[0]).ok_or_else(|| invalid("unknown map in RECORD_CONFIG"))?;
+    let mut seed = [0u8; 8];
+    seed.copy_from_slice(&payload[1..9]);
+    let config = std::str::from_utf8(&payload[9..])
+        .map_err(|e| e.to_string())
+        .and_then(|s| toml::from_str::<GameConfig>(s).map_err(|e| e.to_string()));
+    let config = match config {
+        Ok(c) => Some(c),
+        Err(e) => {
+            warn!("replay config: {}", e);
+            None
+        }
+    };
+    Ok((map, u64::from_be_bytes(seed), config))
+}
+
+fn map_num(map: MapKind) -> u8 {
+    match map {
+        MapKind::Bar => 0,
+        MapKind::Empty => 1,
+        MapKind::Random => 2,
+        MapKind::Pillars => 3,
+        MapKind::Maze => 4,
+    }
+}
+
+fn map_from_num(n: u8) -> Option<MapKind> {
+    match n {
+        0 => Some(MapKind::Bar),
+        1 => Some(MapKind::Empty),
+        2 => Some(MapKind::Random),
+        3 => Some(MapKind::Pillars),
+        4 => Some(MapKind::Maze),
+        _ => None,
+    }
+}
+
+// entity_id(u8) cmd(u8) dir(u8)
+fn encode_event(ie: &InputEvent) -> [u8; 3] {
+    match *ie {
+        InputEvent::Quit | InputEvent::Stopped(_) => [0, 0, 0], // the game ended
+        InputEvent::Move { entity_id, dir } => [entity_id, 1, dir.as_num()],
+        InputEvent::Fire { entity_id, dir } => [entity_id, 2, dir.as_num()],
+        InputEvent::ToggleShield { entity_id } => [entity_id, 3, 0],
+        InputEvent::ChangeWeapon { entity_id } => [entity_id, 4, 0],
+        InputEvent::Ready { entity_id } => [entity_id, 5, 0],
+        InputEvent::Rematch { entity_id } => [entity_id, 6, 0],
+        InputEvent::DropWall { entity_id } => [entity_id, 8, 0],
+        InputEvent::KeyPress
+        | InputEvent::ToggleMute
+        | InputEvent::ToggleInspector
+        | InputEvent::ScrollInspector(_)
+        | InputEvent::ToggleHeatmap
+        | InputEvent::SaveSnapshots
+        | InputEvent::EndTurn
+        | InputEvent::Answer(_)
+        | InputEvent::Menu(_) => [0, CMD_KEY_PRESS, 0],
+    }
+}
+
+// A RECORD_EVENT payload back into (tick, event). Keys the replay doesn't
+// tell apart come back as KeyPress, and the end of the game as Quit. None
+// if it's not one encode_event could have made.
+pub fn read_event(payload: &[u8]) -> Option<(u32, InputEvent)> {
+    let [t0, t1, t2, t3, entity_id, cmd, dir] = *payload else {
+        return None;
+    };
+    let dir = Dir::from_num(dir)?;
+    let ie = match cmd {
+        0 => InputEvent::Quit,
+        1 => InputEvent::Move { entity_id, dir },
+        2 => InputEvent::Fire { entity_id, dir },
+        3 => InputEvent::ToggleShield { entity_id },
+        4 => InputEvent::ChangeWeapon { entity_id },
+        5 => InputEvent::Ready { entity_id },
+        6 => InputEvent::Rematch { entity_id },
+        8 => InputEvent::DropWall { entity_id },
+        CMD_KEY_PRESS => InputEvent::KeyPress,
+        _ => return None,
+    };
+    Some((u32::from_be_bytes([t0, t1, t2, t3]), ie))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg.to_string())
+}
+
+// Reads a replay file, or a spectator stream
+pub struct Reader<R: Read> {
+    r: BufReader<R>,
+    version: u16,
+}
+
+impl Reader<Cursor<Vec<u8>>> {
+    // Checks the whole file before anything is played
+    pub fn open(path: &Path) -> io::Result<Reader<Cursor<Vec<u8>>>> {
+        Reader::from_bytes(fs::read(path)?)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> io::Result<Reader<Cursor<Vec<u8>>>> {
+        if !data.starts_with(MAGIC) {
+            // format 0, which starts with RECORD_GAME, or not a replay at all
+            return Ok(Reader {
+                r: BufReader::new(Cursor::new(data)),
+                version: 0,
+            });
+        }
+        let version = read_header(&data)?;
+        verify(&data)?;
+        let mut c = Cursor::new(data);
+        c.set_position(HEADER_LEN as u64);
+        Ok(Reader {
+            r: BufReader::new(c),
+            version,
+        })
+    }
+}
+
+impl<R: Read> Reader<R> {
+    // A stream, starting with the header
+    pub fn new(r: R) -> io::Result<Reader<R>> {
+        let mut r = BufReader::new(r);
+        let mut header = [0u8; HEADER_LEN];
+        r.read_exact(&mut header)?;
+        if !header.starts_with(MAGIC) {
+            return Err(invalid("no replay header, is it from an older game?"));
+        }
+        let version = read_header(&header)?;
+        Ok(Reader { r, version })
+    }
+
+    // Which format this is, 0 for files from before the header
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    // The next (kind, payload), or None at end of file. The trailer is not
+    // returned.
+    pub fn next_record(&mut self) -> io::Result<Option<(u8, Vec<u8>)>> {
+        let mut head = [0u8; RECORD_HEAD_LEN];
+        if let Err(e) = self.r.read_exact(&mut head) {
+            return match e.kind() {
+                ErrorKind::UnexpectedEof => Ok(None),
+                _ => Err(e),
+            };
+        }
+        let len = u32::from_be_bytes([head[1], head[2], head[3], head[4]]);
+        let mut payload = Vec::new();
+        // take, so a corrupt length can't have us allocate gigabytes
+        (&mut self.r).take(len as u64).read_to_end(&mut payload)?;
+        if payload.len() != len as usize {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        if head[0] == RECORD_END {
+            return Ok(None);
+        }
+        Ok(Some((head[0], payload)))
+    }
+
+    // Skip ahead to the next state frame, returning the frame alone
+    pub fn next_state(&mut self) -> io::Result<Option<Vec<u8>>> {
+        while let Some((kind, mut payload)) = self.next_record()? {
+            if kind != RECORD_STATE {
+                continue;
+            }
+            if self.version > 0 {
+                if payload.len() < 4 {
+                    return Err(invalid("RECORD_STATE too short"));
+                }
+                payload.drain(..4); // the tick
+            }
+            return Ok(Some(payload));
+        }
+        Ok(None)
+    }
+}
+
+// The format version, if we can read it
+fn read_header(data: &[u8]) -> io::Result<u16> {
+    if data.len() < HEADER_LEN {
+        return Err(invalid("replay header too short"));
+    }
+    let version = u16::from_be_bytes([data[4], data[5]]);
+    if version > FORMAT_VERSION {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "replay is format version {}, made by a newer game. We read up to version {}.",
+                version, FORMAT_VERSION
+            ),
+        ));
+    }
+    Ok(version)
+}
+
+// Walk the records to the trailer and check the checksum. A replay without one
+// is from a game that didn't exit cleanly, we play what there is.
+fn verify(data: &[u8]) -> io::Result<()> {
+    let mut pos = HEADER_LEN;
+    while pos < data.len() {
+        if data.len() - pos < RECORD_HEAD_LEN {
+            return Err(invalid("replay is corrupt, it ends part way through a record"));
+        }
+        let kind = data[pos];
+        let len = u32::from_be_bytes([data[pos + 1], data[pos + 2], data[pos + 3], data[pos + 4]]) as usize;
+        let end = pos + RECORD_HEAD_LEN + len;
+        if end > data.len() {
+            return Err(invalid("replay is corrupt, it ends part way through a record"));
+        }
+        if kind == RECORD_END {
+            let payload = &data[pos + RECORD_HEAD_LEN..end];
+            if len != 4 || end != data.len() {
+                return Err(invalid("replay is corrupt, bad trailer"));
+            }
+            let want = u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+            if crc32fast::hash(&data[..pos]) != want {
+                return Err(invalid("replay is corrupt, checksum doesn't match"));
+            }
+            return Ok(());
+        }
+        pos = end;
+    }
+    warn!("replay has no trailer, the game didn't finish recording it");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_recording_reads_back() {
+        let path = std::env::temp_dir().join(format!("rcg-test-{}.rcgr", std::process::id()));
+        let r = Recorder::create(&path, &start(30, 20, MapKind::Maze, 7, &GameConfig::default())).unwrap();
+        r.handshake(1, rs_sdk::HANDSHAKE_OK);
+        r.state(3, &[9, 9]);
+        r.event(3, &InputEvent::Fire { entity_id: 2, dir: Dir::Up });
+        r.finish();
+
+        let mut data = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let mut replay = Reader::from_bytes(data.clone()).unwrap();
+        assert_eq!(replay.version(), FORMAT_VERSION);
+        assert_eq!(replay.next_record().unwrap().unwrap().0, RECORD_GAME);
+        let (_, config) = replay.next_record().unwrap().unwrap();
+        let (map, seed, _) = read_config(&config).unwrap();
+        assert_eq!((map, seed), (MapKind::Maze, 7));
+        assert_eq!(replay.next_record().unwrap(), Some((RECORD_HANDSHAKE, vec![1, rs_sdk::HANDSHAKE_OK])));
+        assert_eq!(replay.next_state().unwrap(), Some(vec![9, 9]));
+        let (kind, event) = replay.next_record().unwrap().unwrap();
+        assert_eq!(kind, RECORD_EVENT);
+        assert_eq!(read_event(&event), Some((3, InputEvent::Fire { entity_id: 2, dir: Dir::Up })));
+        assert_eq!(replay.next_record().unwrap(), None);
+
+        // one byte changed and the checksum catches it
+        data[HEADER_LEN + RECORD_HEAD_LEN] ^= 1;
+        assert!(Reader::from_bytes(data).is_err());
+    }
+}
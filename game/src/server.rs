@@ -1,122 +1,634 @@
-use std::thread;
-use std::path;
-use std::fs;
-use std::os::unix::net;
+use std::collections::VecDeque;
 use std::net::Shutdown;
-use std::error;
-use std::io::ErrorKind;
+use std::io::{self, ErrorKind};
 use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, SyncSender, TrySendError};
 use std::sync::{self, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{debug, error, info, trace, warn};
+use rand::Rng;
+
+use crate::frames::{self, Shared};
+use crate::input::{spawn_watched, Source};
+use crate::options::Latency;
+use crate::replay::Recorder;
+use crate::{GameError, InputEvent};
+use rs_sdk::transport::{Endpoint, Stream};
+use rs_sdk::{
+    Dir, HANDSHAKE_BAD_TOKEN, HANDSHAKE_BAD_VERSION, HANDSHAKE_OK, PROTOCOL_VERSION,
+};
+
+// Command a bot sends when a frame arrives, see rs_sdk BotOut::heartbeat
+const HEARTBEAT: u8 = 7;
+
+// Frames we remember sending. A heartbeat for an older one isn't timed.
+const SENT_KEEP: usize = 64;
+
+// Round trips kept for the percentiles logged when a bot goes
+const PING_SAMPLES: usize = 10_000;
 
-use log::{debug, error};
+// The ping shown is the median of this many of the latest
+const PING_WINDOW: usize = 30;
 
-use crate::InputEvent;
-use rs_sdk::{Dir, SOCK_NAME_1, SOCK_NAME_2};
+// Commands a bot can have waiting for future ticks, and how far ahead. Any
+// more, or further, are dropped.
+const PENDING_MAX: usize = 64;
+const PENDING_AHEAD: u32 = 100;
+
+// A client that hasn't sent its hello by then is dropped, so it can't hold
+// the seat
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
 
 pub struct Server {
     entity_id: u8,
-    conn: Mutex<Option<net::UnixStream>>,
+    conn: Mutex<Option<SyncSender<Shared>>>, // frames for the connected bot's writer thread
+    ch: sync::mpsc::Sender<(Source, InputEvent)>,
+    token: Option<String>, // shared secret clients must send in handshake
+    recorder: Option<Arc<Recorder>>,
+    welcome: Vec<u8>, // tick_ms then the arena layout, sent after the handshake. see World::map_info
+    tick: AtomicU32, // tick of the most recent state frame sent
+    pending: Mutex<Pending>, // commands for future ticks, from the connected bot
+    due: Mutex<Vec<InputEvent>>, // those taken out of pending to pass on, kept to reuse its space
+    sent: Mutex<VecDeque<(u32, Instant)>>, // when recent frames were queued for the bot, by tick
+    pings: Mutex<VecDeque<Duration>>,      // round trips timed by heartbeats, newest last
+    latency: Option<Latency>,              // --fake-latency, added each way
+    stream: Mutex<Option<Stream>>,         // the connected bot's, to drop it if it goes quiet
+    heard: Mutex<Instant>,                 // when the bot last sent anything, heartbeats included, or play started
+    idle: AtomicBool,                      // its bot went quiet and was dropped, until another connects
 }
 
 impl Server {
 
-    // Start a server for given player (1 or 2)
-    pub fn new(player: u8, ch: sync::mpsc::Sender<InputEvent>) -> Arc<Server> {
-        let sock_name = match player {
-            1 => SOCK_NAME_1,
-            2 => SOCK_NAME_2,
-            _ => panic!("invalid player number"),
-        };
+    // Start a server for given player (1 or 2), listening on endpoint.
+    // If token is set clients must provide it to connect.
+    // If recorder is set handshakes are saved to the replay.
+    // welcome, the time between frames and the map, is sent to clients after
+    // the handshake.
+    // If latency is set frames and commands are held that long, see delay_line.
+    pub fn new(
+        player: u8,
+        endpoint: Endpoint,
+        ch: sync::mpsc::Sender<(Source, InputEvent)>,
+        token: Option<String>,
+        recorder: Option<Arc<Recorder>>,
+        welcome: Vec<u8>,
+        latency: Option<Latency>,
+    ) -> Arc<Server> {
+        if player != 1 && player != 2 {
+            panic!("invalid player number");
+        }
         let s = Arc::new(Server{
             entity_id: player,
             conn: Mutex::new(Option::None),
+            ch: ch.clone(),
+            token,
+            recorder,
+            welcome,
+            tick: AtomicU32::new(0),
+            pending: Mutex::new(Pending::default()),
+            due: Mutex::new(Vec::new()),
+            sent: Mutex::new(VecDeque::new()),
+            pings: Mutex::new(VecDeque::new()),
+            latency,
+            stream: Mutex::new(None),
+            heard: Mutex::new(Instant::now()),
+            idle: AtomicBool::new(false),
         });
 
         let inner_s = s.clone();
-        let _ = thread::spawn(move || inner_s.run(sock_name, ch));
+        let name = format!("Player {} bot server", player);
+        let _ = spawn_watched(name, Source::Bot(player), ch, move || inner_s.run(endpoint));
 
         s
     }
 
-    // accept a connection and call handler
-    fn run(&self, sock_name: &str, ch: sync::mpsc::Sender<InputEvent>) {
-        let sock_path: path::PathBuf = sock_name.into();
-        if sock_path.exists() {
-            fs::remove_file(&sock_path).unwrap();
-        }
-        debug!("Player {} server listening on {}", self.entity_id, sock_name);
+    // accept a connection and call handler. Only returns if we can't carry on.
+    fn run(&self, endpoint: Endpoint) -> Result<(), GameError> {
+        debug!("Player {} server listening on {}", self.entity_id, endpoint);
 
-        let l = net::UnixListener::bind(&sock_path).expect("local socket bind error");
+        let l = endpoint
+            .listen()
+            .map_err(|e| io::Error::new(e.kind(), format!("listen on {}: {}", endpoint, e)))?;
         loop {
             match l.accept() {
-                Ok((conn, addr)) => {
-                    debug!("Connection from {:?}", addr);
-                    let out_conn = match conn.try_clone() {
-                        Ok(c) => c,
-                        Err(e) => {
-                            error!("try_clone: {}", e);
-                            return;
-                        }
-                    };
-                    self.conn.lock().unwrap().replace(out_conn);
-
-                    handler(conn, self.entity_id, ch.clone()).unwrap();
+                Ok((mut conn, addr)) => {
+                    debug!("Connection from {}", addr);
+                    if let Err(e) = self.handshake(&mut conn) {
+                        error!("Player {} handshake: {}", self.entity_id, e);
+                        let _ = conn.shutdown(Shutdown::Both);
+                        continue;
+                    }
+                    let out_conn = conn.try_clone()?;
+                    self.stream.lock().unwrap().replace(conn.try_clone()?);
+                    *self.heard.lock().unwrap() = Instant::now();
+                    if self.idle.swap(false, Ordering::SeqCst) {
+                        info!("Player {} seat taken by a new bot", self.entity_id);
+                    }
+                    let (tx, rx) = sync::mpsc::sync_channel(frames::QUEUE);
+                    let (entity_id, latency) = (self.entity_id, self.latency);
+                    let writer = thread::spawn(move || write_frames(entity_id, out_conn, rx, latency));
+                    self.conn.lock().unwrap().replace(tx);
+
+                    if let Err(e) = self.handler(conn) {
+                        error!("Player {} connection: {}", self.entity_id, e);
+                    }
+                    self.conn.lock().unwrap().take(); // writer stops
+                    self.stream.lock().unwrap().take();
+                    // not for whoever takes the seat next
+                    self.pending.lock().unwrap().clear();
+                    self.log_pings();
+                    self.sent.lock().unwrap().clear();
+                    self.pings.lock().unwrap().clear();
+                    let _ = writer.join();
                 },
-                Err(e) => error!("accept on {}: {}", sock_path.display(), e),
+                Err(e) => error!("accept on {}: {}", endpoint, e),
             }
         }
     }
 
-    // send all our connections the latest world state. called every tick
-    pub fn send_state(&self, state: &[u8]) {
+    // is a bot playing this player?
+    pub fn is_connected(&self) -> bool {
+        self.conn.lock().unwrap().is_some()
+    }
+
+    // send all our connections the latest world state. called every tick.
+    // The bot's writer thread does the sending, so a slow bot can't hold up
+    // the game, it misses frames instead.
+    pub fn send_state(&self, tick: u32, state: &Shared) {
+        self.tick.store(tick, Ordering::SeqCst);
+        self.release_pending(tick);
+
         let mut l = self.conn.lock().unwrap();
-        if l.is_none() {
+        let tx = match l.as_ref() {
+            Some(tx) => tx,
+            None => return,
+        };
+        match tx.try_send(state.clone()) {
+            // between rounds the same tick is sent again, time the first
+            Ok(()) => {
+                let mut sent = self.sent.lock().unwrap();
+                if sent.back().map(|&(t, _)| t) != Some(tick) {
+                    sent.push_back((tick, Instant::now()));
+                    if sent.len() > SENT_KEEP {
+                        sent.pop_front();
+                    }
+                }
+            }
+            Err(TrySendError::Full(_)) => trace!("Player {} bot is behind, dropped a frame", self.entity_id),
+            // writer failed, the handler will see the connection is gone
+            Err(TrySendError::Disconnected(_)) => {
+                l.take();
+            }
+        }
+    }
+
+    // Drop the bot if it hasn't sent anything, not even a heartbeat, for
+    // limit, so the seat is free for another to connect and take over. True
+    // if it was dropped.
+    pub fn kick_if_idle(&self, limit: Duration) -> bool {
+        if !self.is_connected() || self.heard.lock().unwrap().elapsed() < limit {
+            return false;
+        }
+        let stream = self.stream.lock().unwrap();
+        let Some(s) = stream.as_ref() else {
+            return false;
+        };
+        // once, it stays connected until the handler sees the shutdown
+        if self.idle.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        warn!("Player {} bot sent nothing for {:?}, dropping it", self.entity_id, limit);
+        // the handler's read fails, and we go back to accepting
+        let _ = s.shutdown(Shutdown::Both);
+        true
+    }
+
+    // A round is starting. Quiet while waiting for it doesn't count
+    // towards kick_if_idle.
+    pub fn start_idle_clock(&self) {
+        *self.heard.lock().unwrap() = Instant::now();
+    }
+
+    // The bot was dropped by kick_if_idle, and no other has connected since
+    pub fn is_idle(&self) -> bool {
+        self.idle.load(Ordering::SeqCst)
+    }
+
+    // Median of the latest round trips to the bot, None until it sends heartbeats
+    pub fn ping(&self) -> Option<Duration> {
+        if !self.is_connected() {
+            return None;
+        }
+        let pings = self.pings.lock().unwrap();
+        let mut latest: Vec<Duration> = pings.iter().rev().take(PING_WINDOW).copied().collect();
+        latest.sort();
+        latest.get(latest.len() / 2).copied()
+    }
+
+    // Round trip percentiles for the bot's connection, if it sent heartbeats.
+    // When it goes, and when the game exits.
+    pub fn log_pings(&self) {
+        let mut pings: Vec<Duration> = self.pings.lock().unwrap().iter().copied().collect();
+        if pings.is_empty() {
             return;
         }
-        if let Err(e) = l.as_mut().unwrap().write_all(state) {
-            error!("server.send_state err: {}", e);
-            let c = l.take().unwrap();
-            // should happen at most once
-            c.shutdown(Shutdown::Both).expect("server: conn shutdown err");
+        pings.sort();
+        let at = |pct: usize| pings[(pings.len() - 1) * pct / 100].as_secs_f64() * 1000.0;
+        info!(
+            "Player {} bot round trips over {} frames: median {:.1}ms, 90% {:.1}ms, 99% {:.1}ms, worst {:.1}ms",
+            self.entity_id,
+            pings.len(),
+            at(50),
+            at(90),
+            at(99),
+            at(100)
+        );
+    }
+
+    // The bot has the frame for tick
+    fn heartbeat(&self, tick: u32) {
+        let sent = self.sent.lock().unwrap();
+        let at = match sent.iter().find(|&&(t, _)| t == tick) {
+            Some(&(_, at)) => at,
+            None => return, // too old, or a tick we never sent
+        };
+        let mut pings = self.pings.lock().unwrap();
+        pings.push_back(at.elapsed());
+        if pings.len() > PING_SAMPLES {
+            pings.pop_front();
+        }
+    }
+
+    // check client's protocol version and token
+    fn handshake(&self, conn: &mut Stream) -> Result<(), GameError> {
+        conn.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        let mut hello = [0u8; 2]; // version(u8) token_len(u8)
+        conn.read_exact(&mut hello)?;
+        let mut client_token = vec![0u8; hello[1] as usize];
+        conn.read_exact(&mut client_token)?;
+
+        let status = if hello[0] != PROTOCOL_VERSION {
+            HANDSHAKE_BAD_VERSION
+        } else if !self.is_token_valid(&client_token) {
+            HANDSHAKE_BAD_TOKEN
+        } else {
+            HANDSHAKE_OK
+        };
+        conn.write_all(&[PROTOCOL_VERSION, status])?;
+        if let Some(r) = &self.recorder {
+            r.handshake(self.entity_id, status);
+        }
+        match status {
+            HANDSHAKE_OK => {
+                conn.write_all(&self.welcome)?;
+                // the bot may think a while before its first command
+                conn.set_read_timeout(None)?;
+                Ok(())
+            }
+            HANDSHAKE_BAD_VERSION => Err(GameError::Protocol(format!("client protocol version {}", hello[0]))),
+            _ => Err(GameError::Protocol("invalid token".to_string())),
+        }
+    }
+
+    fn is_token_valid(&self, client_token: &[u8]) -> bool {
+        let expected = match &self.token {
+            None => return true,
+            Some(t) => t.as_bytes(),
+        };
+        // compare every byte so timing doesn't reveal how much matched
+        expected.len() == client_token.len()
+            && expected.iter().zip(client_token).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+
+    // pass on to the game any commands which are now due
+    fn release_pending(&self, tick: u32) {
+        let mut due = self.due.lock().unwrap();
+        self.pending.lock().unwrap().due(tick, &mut due);
+        for iv in due.drain(..) {
+            // only fails if game is exiting
+            let _ = self.ch.send((Source::Bot(self.entity_id), iv));
         }
     }
 
+    fn handler(&self, mut conn: Stream) -> Result<(), GameError> {
+        let latency = match self.latency {
+            Some(l) => l,
+            None => return read_commands(&mut conn, |buf| self.command(buf)),
+        };
+        // held commands wait on their own thread, so reading carries on
+        let (tx, rx) = sync::mpsc::channel();
+        thread::scope(|s| {
+            s.spawn(|| {
+                delay_line(Some(latency), rx, |buf| match self.command(buf) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        error!("Player {} connection: {}", self.entity_id, e);
+                        false
+                    }
+                })
+            });
+            // tx goes when reading stops, which stops the delay line
+            read_commands(&mut conn, move |buf| {
+                let _ = tx.send(buf);
+                Ok(())
+            })
+        })
+    }
+
+    // Act on a command from the bot
+    fn command(&self, buf: [u8; 8]) -> Result<(), GameError> {
+        *self.heard.lock().unwrap() = Instant::now();
+        // command applies to tick >= apply_tick. 0 means as soon as possible.
+        let apply_tick = u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]);
+        if buf[0] == HEARTBEAT {
+            self.heartbeat(apply_tick);
+            return Ok(());
+        }
+        let iv = match into_input_event(&buf, self.entity_id) {
+            Some(iv) => iv,
+            None => {
+                warn!("Player {}: ignoring unknown command {:?}", self.entity_id, buf);
+                return Ok(());
+            }
+        };
+        let tick = self.tick.load(Ordering::SeqCst);
+        if apply_tick <= tick {
+            self.ch
+                .send((Source::Bot(self.entity_id), iv))
+                .map_err(|_| GameError::Internal("game stopped listening to bots".to_string()))?;
+        } else if !self.pending.lock().unwrap().push(tick, apply_tick, iv) {
+            debug!("Player {}: dropped command for tick {}, too many or too far ahead", self.entity_id, apply_tick);
+        }
+        Ok(())
+    }
+
+}
+
+// Commands for ticks not played yet, by tick, and in the order they arrived
+// for the same tick
+#[derive(Default)]
+struct Pending {
+    queue: VecDeque<(u32, InputEvent)>, // apply_tick and command
+}
+
+impl Pending {
+    // Keep iv for apply_tick, now being the latest tick sent. False if it was
+    // dropped, the queue being full or apply_tick too far ahead.
+    fn push(&mut self, now: u32, apply_tick: u32, iv: InputEvent) -> bool {
+        if self.queue.len() >= PENDING_MAX || apply_tick.saturating_sub(now) > PENDING_AHEAD {
+            return false;
+        }
+        // bots mostly ask for later ticks as they go, so this is usually the end
+        let at = self.queue.partition_point(|(t, _)| *t <= apply_tick);
+        self.queue.insert(at, (apply_tick, iv));
+        true
+    }
+
+    // Move those due by tick to the end of out
+    fn due(&mut self, tick: u32, out: &mut Vec<InputEvent>) {
+        while self.queue.front().is_some_and(|(t, _)| *t <= tick) {
+            out.extend(self.queue.pop_front().map(|(_, iv)| iv));
+        }
+    }
+
+    fn clear(&mut self) {
+        self.queue.clear();
+    }
 }
 
-fn handler(mut conn: net::UnixStream, entity_id: u8, ch: sync::mpsc::Sender<InputEvent>) -> Result<(), Box<dyn error::Error>> {
+// Pass each command read from conn to f, until the bot closes the connection
+fn read_commands(
+    conn: &mut Stream,
+    mut f: impl FnMut([u8; 8]) -> Result<(), GameError>,
+) -> Result<(), GameError> {
     let mut buf = [0u8; 8]; // protocol is u64 messages
     loop {
         if let Err(e) = conn.read_exact(&mut buf) {
             match e.kind() {
                 ErrorKind::UnexpectedEof => return Ok(()), // remote closed connection
                 _ => {
-                    error!("read_exact: {}", e);
-                    return Err(Box::new(e));
+                    return Err(e.into());
                 },
             }
         }
-        let iv = into_input_event(&buf, entity_id);
-        ch.send(iv)?;
+        f(buf)?;
+    }
+}
+
+// A connection's writer thread. Runs until the game stops sending, or the bot
+// goes away.
+fn write_frames(entity_id: u8, mut conn: Stream, rx: Receiver<Shared>, latency: Option<Latency>) {
+    delay_line(latency, rx, |frame| match conn.write_all(&frame) {
+        Ok(()) => true,
+        Err(e) => {
+            error!("Player {} send state: {}", entity_id, e);
+            // so the handler stops reading too
+            let _ = conn.shutdown(Shutdown::Both);
+            false
+        }
+    });
+}
+
+// Pass everything from rx on to out, in order, each held for the latency
+// from when it arrived. Stops when out returns false, or rx is closed, as the
+// connection has gone: anything still held is dropped, as it would be if it
+// were still on the wire. With no latency it's just a loop.
+fn delay_line<T>(latency: Option<Latency>, rx: Receiver<T>, mut out: impl FnMut(T) -> bool) {
+    let latency = match latency {
+        Some(l) => l,
+        None => {
+            for item in rx {
+                if !out(item) {
+                    return;
+                }
+            }
+            return;
+        }
+    };
+    let mut rng = rand::thread_rng();
+    let jitter = latency.jitter.as_millis() as i64;
+    let mut held: VecDeque<(Instant, T)> = VecDeque::new();
+    loop {
+        let next = match held.front() {
+            Some(&(due, _)) => rx.recv_timeout(due.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match next {
+            Ok(item) => {
+                let hold = latency.delay.as_millis() as i64 + rng.gen_range(-jitter..=jitter);
+                let due = Instant::now() + Duration::from_millis(hold.max(0) as u64);
+                // no overtaking
+                let due = held.back().map_or(due, |&(last, _)| due.max(last));
+                held.push_back((due, item));
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        while held.front().is_some_and(|&(due, _)| due <= Instant::now()) {
+            let (_, item) = held.pop_front().unwrap();
+            if !out(item) {
+                return;
+            }
+        }
     }
 }
 
-fn into_input_event(b: &[u8; 8], entity_id: u8) -> InputEvent {
-    match b[0] {
+// None if it's not a command we know, or a move or fire in no known direction
+pub(crate) fn into_input_event(b: &[u8; 8], entity_id: u8) -> Option<InputEvent> {
+    let ie = match b[0] {
         0 => InputEvent::Quit,
         1 => {
             InputEvent::Move {
                 entity_id,
-                dir: Dir::from_num(b[1]),
+                dir: Dir::from_num(b[1])?,
             }
         },
         2 => {
             InputEvent::Fire {
                 entity_id,
-                dir: Dir::from_num(b[1]),
+                dir: Dir::from_num(b[1])?,
             }
         },
         3 => InputEvent::ToggleShield { entity_id },
         4 => InputEvent::ChangeWeapon { entity_id },
-        _ => panic!("Undefined command: {}", b[0]),
+        5 => InputEvent::Ready { entity_id },
+        6 => InputEvent::Rematch { entity_id },
+        8 => InputEvent::DropWall { entity_id },
+        _ => return None,
+    };
+    Some(ie)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    use std::sync::mpsc::{self, Receiver};
+
+    use super::*;
+
+    // A player 1 server on a socket of its own, wanting token "secret"
+    #[cfg(unix)]
+    fn server(name: &str) -> (Arc<Server>, Endpoint, Receiver<(Source, InputEvent)>) {
+        let path = std::env::temp_dir().join(format!("rcg-test-{}-{}.sock", std::process::id(), name));
+        let endpoint = Endpoint::Unix(path);
+        let (tx, rx) = mpsc::channel();
+        let w = crate::World::new(30, 20);
+        let mut welcome = 50u32.to_be_bytes().to_vec();
+        welcome.extend(w.map_info());
+        let s = Server::new(1, endpoint.clone(), tx, Some("secret".to_string()), None, welcome, None);
+        (s, endpoint, rx)
+    }
+
+    // Connect, once the server is listening
+    #[cfg(unix)]
+    fn connect(endpoint: &Endpoint) -> Stream {
+        for _ in 0..100 {
+            if let Ok(c) = endpoint.connect() {
+                c.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+                return c;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("server never listened on {}", endpoint);
+    }
+
+    #[cfg(unix)]
+    fn hello(conn: &mut Stream, token: &str) -> [u8; 2] {
+        conn.write_all(&[PROTOCOL_VERSION, token.len() as u8]).unwrap();
+        conn.write_all(token.as_bytes()).unwrap();
+        let mut reply = [0u8; 2];
+        conn.read_exact(&mut reply).unwrap();
+        reply
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn only_the_right_token_gets_in() {
+        let (_s, endpoint, _rx) = server("token");
+        let mut c = connect(&endpoint);
+        assert_eq!(hello(&mut c, "guess"), [PROTOCOL_VERSION, HANDSHAKE_BAD_TOKEN]);
+
+        let mut c = connect(&endpoint);
+        assert_eq!(hello(&mut c, "secret"), [PROTOCOL_VERSION, HANDSHAKE_OK]);
+        let mut tick_ms = [0u8; 4];
+        c.read_exact(&mut tick_ms).unwrap();
+        assert_eq!(u32::from_be_bytes(tick_ms), 50);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn a_silent_client_does_not_hold_the_seat() {
+        let (_s, endpoint, _rx) = server("silent");
+        let _silent = connect(&endpoint);
+        let mut c = connect(&endpoint);
+        assert_eq!(hello(&mut c, "secret"), [PROTOCOL_VERSION, HANDSHAKE_OK]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn a_quiet_bot_is_kicked_once() {
+        let (s, endpoint, _rx) = server("idle");
+        let mut c = connect(&endpoint);
+        assert_eq!(hello(&mut c, "secret"), [PROTOCOL_VERSION, HANDSHAKE_OK]);
+        while !s.is_connected() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(s.kick_if_idle(Duration::ZERO));
+        assert!(!s.kick_if_idle(Duration::ZERO));
+        assert!(s.is_idle());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn a_bots_ready_and_rematch_reach_the_game() {
+        let (_s, endpoint, rx) = server("ready");
+        let connector = rs_sdk::Connector::new(rs_sdk::Player::One).endpoint(endpoint).token("secret");
+        let (_bot_in, mut bot_out) = connector.retries(100).connect().unwrap();
+        bot_out.ready().unwrap();
+        bot_out.rematch().unwrap();
+        let next = || rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(next(), (Source::Bot(1), InputEvent::Ready { entity_id: 1 }));
+        assert_eq!(next(), (Source::Bot(1), InputEvent::Rematch { entity_id: 1 }));
+    }
+
+    fn due(p: &mut Pending, tick: u32) -> Vec<InputEvent> {
+        let mut out = Vec::new();
+        p.due(tick, &mut out);
+        out
+    }
+
+    #[test]
+    fn commands_wait_for_their_tick() {
+        let mut p = Pending::default();
+        let (shield, weapon, wall) = (
+            InputEvent::ToggleShield { entity_id: 1 },
+            InputEvent::ChangeWeapon { entity_id: 1 },
+            InputEvent::DropWall { entity_id: 1 },
+        );
+        assert!(p.push(10, 12, shield.clone()));
+        assert!(p.push(10, 11, weapon.clone()));
+        assert!(p.push(10, 12, wall.clone()));
+        assert!(due(&mut p, 10).is_empty());
+        assert_eq!(due(&mut p, 11), [weapon]);
+        assert_eq!(due(&mut p, 20), [shield, wall]);
+        assert!(due(&mut p, 30).is_empty());
+    }
+
+    #[test]
+    fn pending_commands_are_capped() {
+        let mut p = Pending::default();
+        let iv = InputEvent::ToggleShield { entity_id: 2 };
+        assert!(!p.push(10, u32::MAX, iv.clone()));
+        assert!(!p.push(10, 11 + PENDING_AHEAD, iv.clone()));
+        assert!(p.push(10, 10 + PENDING_AHEAD, iv.clone()));
+        for _ in 1..PENDING_MAX {
+            assert!(p.push(10, 11, iv.clone()));
+        }
+        assert!(!p.push(10, 11, iv));
+        p.clear();
+        assert!(due(&mut p, u32::MAX).is_empty());
     }
 }
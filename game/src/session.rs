@@ -0,0 +1,197 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::stdout;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::{Mutex, Once};
+use std::thread::{self, ThreadId};
+
+use crossterm::{cursor, execute, terminal};
+use simplelog::{Config, LevelFilter, WriteLogger};
+
+use crate::{GameError, Output};
+
+// Startup and teardown shared by the subcommands in main.rs
+
+// Log level and file, when not given on the command line
+pub const LOG_LEVEL_ENV: &str = "RUST_CONSOLE_GAME_LOG";
+pub const LOG_FILE_ENV: &str = "RUST_CONSOLE_GAME_LOG_FILE";
+const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Warn;
+
+// Default log file names, in the cache directory. The game and the bot
+// client log to different files so running both keeps them apart.
+pub const LOG_FILE: &str = "hashbang.log";
+pub const BOT_LOG_FILE: &str = "hashbang-bot.log";
+
+// Where finished matches are kept, when not the default, see stats_path
+pub const STATS_FILE_ENV: &str = "RUST_CONSOLE_GAME_STATS_FILE";
+
+const APP_DIR: &str = "rust-console-game";
+
+// Log at level to file. Either may be None, to use the environment
+// variables above, or failing that warnings only to `name` in the cache
+// directory. If the file can't be written we say so and carry on without.
+pub fn start_logging(level: Option<LevelFilter>, file: Option<PathBuf>, name: &str) {
+    let level = level.unwrap_or_else(|| match env::var(LOG_LEVEL_ENV) {
+        Ok(l) if !l.is_empty() => l.parse().unwrap_or_else(|_| {
+            eprintln!("{}: unknown log level '{}', using {}", LOG_LEVEL_ENV, l, DEFAULT_LOG_LEVEL);
+            DEFAULT_LOG_LEVEL
+        }),
+        _ => DEFAULT_LOG_LEVEL,
+    });
+    if level == LevelFilter::Off {
+        return;
+    }
+    let path = match file.or_else(|| non_empty_var(LOG_FILE_ENV).map(PathBuf::from)) {
+        Some(p) => p,
+        None => match cache_dir() {
+            Some(dir) => dir.join(name),
+            None => {
+                eprintln!("Not logging: no cache directory, use --log-file");
+                return;
+            }
+        },
+    };
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("Not logging: {}: {}", dir.display(), e);
+            return;
+        }
+    }
+    let f = match File::create(&path) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Not logging: {}: {}", path.display(), e);
+            return;
+        }
+    };
+    if let Err(e) = WriteLogger::init(level, Config::default(), f) {
+        eprintln!("Not logging: {}", e); // only if a logger is already set
+    }
+}
+
+// Where the platform keeps caches, plus our own directory
+fn cache_dir() -> Option<PathBuf> {
+    if let Some(d) = non_empty_var("XDG_CACHE_HOME") {
+        return Some(PathBuf::from(d).join(APP_DIR));
+    }
+    let base = if cfg!(windows) {
+        PathBuf::from(non_empty_var("LOCALAPPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(non_empty_var("HOME")?).join("Library").join("Caches")
+    } else {
+        PathBuf::from(non_empty_var("HOME")?).join(".cache")
+    };
+    Some(base.join(APP_DIR))
+}
+
+// Where the platform keeps a user's data, plus our own directory
+fn data_dir() -> Option<PathBuf> {
+    if let Some(d) = non_empty_var("XDG_DATA_HOME") {
+        return Some(PathBuf::from(d).join(APP_DIR));
+    }
+    let base = if cfg!(windows) {
+        PathBuf::from(non_empty_var("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(non_empty_var("HOME")?).join("Library").join("Application Support")
+    } else {
+        PathBuf::from(non_empty_var("HOME")?).join(".local").join("share")
+    };
+    Some(base.join(APP_DIR))
+}
+
+// The stats file: STATS_FILE_ENV if set, otherwise stats::STATS_FILE in the
+// data directory. None if there's neither.
+pub(crate) fn stats_path() -> Option<PathBuf> {
+    non_empty_var(STATS_FILE_ENV)
+        .map(PathBuf::from)
+        .or_else(|| Some(data_dir()?.join(crate::stats::STATS_FILE)))
+}
+
+fn non_empty_var(name: &str) -> Option<OsString> {
+    env::var_os(name).filter(|v| !v.is_empty())
+}
+
+// Shared secret bots must present to connect, if set
+pub(crate) fn token() -> Option<String> {
+    env::var(rs_sdk::TOKEN_ENV).ok().filter(|t| !t.is_empty())
+}
+
+// The terminal, in raw mode and on its alternate screen so the game doesn't
+// scroll away what was there before. Dropping it puts everything back, so
+// hold one for as long as something draws, and every way out of that,
+// an error, an early return or a panic unwinding, leaves the terminal usable.
+pub(crate) struct TerminalGuard {}
+
+// The thread holding the TerminalGuard. A panic there restores the terminal
+// before the message is printed, otherwise it goes to the alternate screen and
+// vanishes with it. Other threads' panics are reported by input::spawn_watched
+// and the game exits the usual way.
+static GUARDED: Mutex<Option<ThreadId>> = Mutex::new(None);
+static PANIC_HOOK: Once = Once::new();
+
+impl TerminalGuard {
+    pub(crate) fn new() -> Result<TerminalGuard, GameError> {
+        PANIC_HOOK.call_once(|| {
+            let default = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                if release(thread::current().id()) {
+                    restore_terminal();
+                }
+                default(info);
+            }));
+        });
+        terminal::enable_raw_mode().map_err(|e| GameError::Terminal(format!("can't use this terminal: {}", e)))?;
+        *guarded() = Some(thread::current().id());
+        // from here on Drop undoes it, even if the rest fails
+        let guard = TerminalGuard {};
+        execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // unless the panic hook got there first
+        if release(thread::current().id()) {
+            restore_terminal();
+        }
+    }
+}
+
+fn guarded() -> std::sync::MutexGuard<'static, Option<ThreadId>> {
+    // a panic can't leave the Option half written
+    GUARDED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// True if id held the terminal, which it no longer does
+fn release(id: ThreadId) -> bool {
+    let mut g = guarded();
+    if *g == Some(id) {
+        *g = None;
+        return true;
+    }
+    false
+}
+
+fn restore_terminal() {
+    // nothing to do about a failure, and we may be panicking already
+    let _ = execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+}
+
+// Set up the display, run f, and put the display back however f ends,
+// so an error doesn't leave it half drawn. See TerminalGuard for raw mode.
+pub(crate) fn with_output<T, R, F>(mut out: T, f: F) -> Result<R, GameError>
+where
+    T: Output,
+    F: FnOnce(&mut T) -> Result<R, GameError>,
+{
+    out.init()?;
+    let res = f(&mut out);
+    let cleaned = out.cleanup();
+    let r = res?;
+    cleaned?;
+    Ok(r)
+}
@@ -0,0 +1,53 @@
+// The last few ticks of play, kept as the state frames sent to bots, which
+// are small and already built every tick. Any of them loads back into a World
+// with World::load_state. F4 in debug mode saves them as a replay, an instant
+// replay of what just happened. `snapshot_ticks` in config.toml sets how
+// many, 0 for none.
+
+use std::collections::VecDeque;
+use std::io;
+use std::path::Path;
+
+use crate::frames::Shared;
+use crate::replay::Recorder;
+
+pub struct Snapshots {
+    depth: usize,
+    frames: VecDeque<(u32, Shared)>, // by tick, oldest first
+}
+
+impl Snapshots {
+    pub fn new(depth: usize) -> Snapshots {
+        Snapshots {
+            depth,
+            frames: VecDeque::with_capacity(depth),
+        }
+    }
+
+    // The frame for tick, dropping the oldest if we're full. It's the
+    // broadcast's own, so keeping it doesn't copy it.
+    pub fn push(&mut self, tick: u32, frame: Shared) {
+        if self.depth == 0 {
+            return;
+        }
+        if self.frames.len() == self.depth {
+            self.frames.pop_front();
+        }
+        self.frames.push_back((tick, frame));
+    }
+
+    // (tick, frame), oldest first, .rev() for newest first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (u32, &[u8])> {
+        self.frames.iter().map(|(tick, frame)| (*tick, frame.as_slice()))
+    }
+
+    // Everything we have as a replay file at path. start is the game's, see
+    // replay::start.
+    pub fn save(&self, path: &Path, start: &[u8]) -> io::Result<()> {
+        let rec = Recorder::in_memory(start);
+        for (tick, frame) in self.iter() {
+            rec.state(tick, frame);
+        }
+        rec.save(path)
+    }
+}
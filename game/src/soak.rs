@@ -0,0 +1,217 @@
+// Soak test, for `serve --soak N`. The built in bot plays itself for N rounds
+// in one World, the way a long running server would, but with no sockets,
+// display or sleeping. Reports how many entities the World holds, memory use
+// and the time spent in each system as it goes, so anything that grows over a
+// long session shows up.
+
+use std::fs;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use bot::hunter::Hunter;
+use bot::skill::Difficulty;
+use rs_sdk::{Actions, Bot, Event, Frame, GameState, Player};
+
+use crate::headless::map;
+use crate::options::{HEADLESS_HEIGHT, HEADLESS_WIDTH};
+use crate::{
+    alive_entities, apply_intent, both_players_standing, end_round, is_match_over, maps, new_match,
+    start_round,
+    GameError, Options, World,
+};
+
+// A round nobody has been hit in by then is a draw
+const ROUND_TICKS: u32 = 20_000;
+
+// Progress lines over the whole soak
+const REPORTS: u32 = 10;
+
+// Time spent in a system, or encoding state frames
+#[derive(Clone, Copy, Default)]
+struct Timing {
+    total: Duration,
+    worst: Duration,
+}
+
+impl Timing {
+    fn add(&mut self, d: Duration) {
+        self.total += d;
+        self.worst = self.worst.max(d);
+    }
+}
+
+pub fn run(opts: &Options, rounds: u32, out: &mut impl Write) -> Result<(), GameError> {
+    let width = opts.width.unwrap_or(HEADLESS_WIDTH) as u32;
+    let height = opts.height.unwrap_or(HEADLESS_HEIGHT) as u32;
+    let mut w = World::with_config(width, height, opts.config.clone());
+    let seed = opts.seed.unwrap_or_else(rand::random);
+    let (kind, density) = (w.config.map, w.config.density);
+    w.layout = maps::layout(kind, density, width, height, seed);
+    w.add_players();
+    w.add_obstacles();
+
+    // easy bots miss more, so rounds last longer and leave more behind
+    let skill = Difficulty::Easy.skill();
+    let mut bots = [
+        Hunter::new(Player::One, Player::Two, skill),
+        Hunter::new(Player::Two, Player::One, skill),
+    ];
+    let m = map(&w);
+    for b in bots.iter_mut() {
+        b.on_connect(&m);
+    }
+    let tick_duration = Duration::from_millis(w.config.frame_gap_ms);
+    let mut actions = [Actions::new(tick_duration), Actions::new(tick_duration)];
+
+    writeln!(
+        out,
+        "Soak: {} rounds of easy bot against easy bot, {}x{} {:?} map, seed {}",
+        rounds, width, height, kind, seed
+    )?;
+    writeln!(
+        out,
+        "{:>8} {:>10} {:>10} {:>10} {:>8} {:>10}",
+        "rounds", "ticks", "ticks/s", "entities", "alive", "rss"
+    )?;
+
+    let rss_start = rss();
+    let start = Instant::now();
+    let mut names = ["frame"; 7];
+    let mut timings = [Timing::default(); 7]; // each system, then the frame
+    let mut frame = Vec::new();
+    let (mut matches, mut draws) = (0, 0);
+    let mut entities_total = 0;
+    let mut most_entities = 0;
+
+    // since the last progress line
+    let every = (rounds / REPORTS).max(1);
+    let (mut window_start, mut window_ticks) = (Instant::now(), 0);
+    let (mut window_entities, mut window_alive) = (0, 0);
+
+    for round in 1..=rounds {
+        let mut system = start_round(&mut w);
+        for (n, s) in names.iter_mut().zip(system.iter()) {
+            *n = s.name();
+        }
+        for b in bots.iter_mut() {
+            b.on_event(Event::RoundStart);
+        }
+
+        let round_start = w.tick;
+        while both_players_standing(&w) && !w.win.is_time_up(&w) {
+            if w.tick - round_start == ROUND_TICKS {
+                draws += 1;
+                break;
+            }
+            for (idx, a) in actions.iter_mut().enumerate() {
+                apply_intent(&mut w, idx as u8 + 1, a.take());
+            }
+            for (s, t) in system.iter_mut().zip(timings.iter_mut()) {
+                let t0 = Instant::now();
+                s.step(&mut w);
+                t.add(t0.elapsed());
+            }
+
+            // what the server would send, and the bots read back
+            let t0 = Instant::now();
+            w.entity_state(GameState::Playing, &mut frame);
+            timings[6].add(t0.elapsed());
+            let f = Frame::from_network(&frame)?;
+            for (b, a) in bots.iter_mut().zip(actions.iter_mut()) {
+                a.set_tick(w.tick);
+                b.on_frame(&f, a);
+            }
+
+            window_alive = window_alive.max(alive_entities(&w).count());
+            window_ticks += 1;
+            w.tick += 1;
+        }
+
+        // dead entities are only cleared out between rounds
+        window_entities = window_entities.max(w.name.len());
+        entities_total += w.name.len();
+
+        end_round(&mut w);
+        if !is_match_over(&w) {
+            for b in bots.iter_mut() {
+                b.on_event(Event::RoundOver);
+            }
+        } else {
+            // rematch, as bots ask for under serve
+            for b in bots.iter_mut() {
+                b.on_event(Event::MatchOver);
+            }
+            matches += 1;
+            new_match(&mut w);
+        }
+        w.reset();
+
+        if round % every == 0 || round == rounds {
+            let secs = window_start.elapsed().as_secs_f64();
+            writeln!(
+                out,
+                "{:>8} {:>10} {:>10.0} {:>10} {:>8} {:>10}",
+                round,
+                w.tick,
+                window_ticks as f64 / secs,
+                window_entities,
+                window_alive,
+                megabytes(rss())
+            )?;
+            most_entities = most_entities.max(window_entities);
+            window_start = Instant::now();
+            window_ticks = 0;
+            window_entities = 0;
+            window_alive = 0;
+        }
+    }
+
+    let elapsed = start.elapsed();
+    let ticks = w.tick.max(1);
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{} ticks in {:.1?}, {} matches finished, {} rounds drawn after {} ticks",
+        w.tick, elapsed, matches, draws, ROUND_TICKS
+    )?;
+    writeln!(
+        out,
+        "Entities at the end of a round: {} on average, {} at most",
+        entities_total / rounds.max(1) as usize,
+        most_entities
+    )?;
+    writeln!(
+        out,
+        "Memory: {} at the start, {} at the end",
+        megabytes(rss_start),
+        megabytes(rss())
+    )?;
+    writeln!(out)?;
+    writeln!(out, "{:<10} {:>10} {:>10} {:>6}", "per tick", "mean", "worst", "share")?;
+    let busy: Duration = timings.iter().map(|t| t.total).sum();
+    for (name, t) in names.iter().zip(timings.iter()) {
+        writeln!(
+            out,
+            "{:<10} {:>10} {:>10} {:>5.1}%",
+            name,
+            format!("{:.2?}", t.total / ticks),
+            format!("{:.2?}", t.worst),
+            100.0 * t.total.as_secs_f64() / busy.as_secs_f64().max(f64::MIN_POSITIVE)
+        )?;
+    }
+    Ok(())
+}
+
+// Resident memory, where the OS tells us
+fn rss() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let kb = status.lines().find_map(|l| l.strip_prefix("VmRSS:"))?;
+    kb.trim().trim_end_matches("kB").trim().parse::<u64>().ok().map(|kb| kb * 1024)
+}
+
+fn megabytes(bytes: Option<u64>) -> String {
+    match bytes {
+        Some(b) => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        None => "n/a".to_string(),
+    }
+}
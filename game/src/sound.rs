@@ -0,0 +1,143 @@
+// Sound effects. The World notes what happened each tick as Cues, they go to
+// the console with the frame's View, and it plays them as it draws. With the
+// `sound` feature they're synthesized and played through the system's audio,
+// on a thread of their own. Without it hits and wins ring the terminal bell.
+
+#[cfg(feature = "sound")]
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(feature = "sound")]
+use std::thread;
+
+#[cfg(feature = "sound")]
+use log::warn;
+
+use crate::config;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cue {
+    Fire,
+    Explosion,
+    Shield, // on or off, or shielded players bumping
+    Hit,    // a player
+    Pickup, // a weapon upgrade
+    Win,
+}
+
+pub struct Sounds {
+    #[cfg(feature = "sound")]
+    tx: Sender<Cue>,
+}
+
+impl Sounds {
+    pub fn new(cfg: &config::Sound) -> Sounds {
+        #[cfg(feature = "sound")]
+        {
+            let (tx, rx) = mpsc::channel();
+            let volume = cfg.volume;
+            let _ = thread::spawn(move || play_all(rx, volume));
+            Sounds { tx }
+        }
+        #[cfg(not(feature = "sound"))]
+        {
+            let _ = cfg;
+            Sounds {}
+        }
+    }
+
+    // Returns true if the terminal should ring its bell instead
+    pub fn play(&self, cue: Cue) -> bool {
+        #[cfg(feature = "sound")]
+        {
+            // fails if there's no audio device, we carry on quietly
+            let _ = self.tx.send(cue);
+            false
+        }
+        #[cfg(not(feature = "sound"))]
+        {
+            matches!(cue, Cue::Hit | Cue::Win)
+        }
+    }
+}
+
+#[cfg(feature = "sound")]
+const SAMPLE_RATE: u32 = 22_050;
+
+// The audio thread. rodio's output stream has to stay on the thread that
+// opened it.
+#[cfg(feature = "sound")]
+fn play_all(rx: Receiver<Cue>, volume: f32) {
+    use rodio::buffer::SamplesBuffer;
+    use rodio::{OutputStream, Source};
+
+    let (_stream, handle) = match OutputStream::try_default() {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("No sound: {}", e);
+            return;
+        }
+    };
+    let cues = [Cue::Fire, Cue::Explosion, Cue::Shield, Cue::Hit, Cue::Pickup, Cue::Win];
+    let samples: Vec<Vec<f32>> = cues.iter().map(|&c| synth(c)).collect();
+    for cue in rx {
+        let idx = cues.iter().position(|&c| c == cue).unwrap();
+        let s = SamplesBuffer::new(1, SAMPLE_RATE, samples[idx].clone()).amplify(volume);
+        if let Err(e) = handle.play_raw(s) {
+            warn!("Sound: {}", e);
+        }
+    }
+}
+
+// Short effects, made up rather than shipped as files
+#[cfg(feature = "sound")]
+fn synth(cue: Cue) -> Vec<f32> {
+    match cue {
+        Cue::Fire => sweep(70, 1200.0, 600.0, false),
+        Cue::Explosion => noise(300),
+        Cue::Shield => sweep(120, 330.0, 660.0, false),
+        Cue::Hit => sweep(250, 160.0, 110.0, true),
+        Cue::Pickup => [440.0, 880.0]
+            .iter()
+            .flat_map(|&f| sweep(80, f, f, false))
+            .collect(),
+        Cue::Win => [523.0, 659.0, 784.0, 1046.0]
+            .iter()
+            .flat_map(|&f| sweep(150, f, f, false))
+            .collect(),
+    }
+}
+
+// A tone gliding from f0 to f1 Hz, fading out. Square is harsher than sine.
+#[cfg(feature = "sound")]
+fn sweep(ms: u32, f0: f32, f1: f32, square: bool) -> Vec<f32> {
+    use std::f32::consts::TAU;
+
+    let n = SAMPLE_RATE * ms / 1000;
+    let mut phase = 0.0f32;
+    (0..n)
+        .map(|i| {
+            let t = i as f32 / n as f32;
+            phase = (phase + (f0 + (f1 - f0) * t) / SAMPLE_RATE as f32) % 1.0;
+            let s = (phase * TAU).sin();
+            let s = if square { s.signum() * 0.5 } else { s };
+            s * (1.0 - t)
+        })
+        .collect()
+}
+
+// A rumble, dying away
+#[cfg(feature = "sound")]
+fn noise(ms: u32) -> Vec<f32> {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    let n = SAMPLE_RATE * ms / 1000;
+    let mut last = 0.0f32;
+    (0..n)
+        .map(|i| {
+            let t = i as f32 / n as f32;
+            // smoothing takes the hiss off
+            last = last * 0.8 + rng.gen_range(-1.0f32..1.0) * 0.2;
+            last * 3.0 * (1.0 - t) * (1.0 - t)
+        })
+        .collect()
+}
@@ -0,0 +1,99 @@
+use std::io::Write;
+use std::net::Shutdown;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use log::{debug, error};
+
+use crate::frames::{self, Shared};
+use crate::replay;
+use rs_sdk::transport::{Endpoint, Stream};
+
+// Anyone connected to the spectator endpoint is sent the game as it is played,
+// in the replay file format, see replay::start, then a RECORD_STATE every frame.
+// They can't send anything back. See `spectate`.
+
+// A spectator whose connection blocks this long is dropped. One who falls
+// frames::QUEUE frames behind is too.
+const WRITE_TIMEOUT_MS: u64 = 100;
+
+pub struct Spectators {
+    conns: Mutex<Vec<SyncSender<(u32, Shared)>>>, // tick and frame for each spectator's writer thread
+    start: Vec<u8>, // see replay::start, sent first
+}
+
+impl Spectators {
+    // Start listening on endpoint. If that fails the game goes on, unwatched.
+    pub fn new(endpoint: Endpoint, start: Vec<u8>) -> Arc<Spectators> {
+        let s = Arc::new(Spectators {
+            conns: Mutex::new(Vec::new()),
+            start,
+        });
+
+        let inner_s = s.clone();
+        let _ = thread::spawn(move || inner_s.run(endpoint));
+
+        s
+    }
+
+    fn run(&self, endpoint: Endpoint) {
+        let l = match endpoint.listen() {
+            Ok(l) => l,
+            Err(e) => {
+                error!("Spectator listen on {}: {}", endpoint, e);
+                return;
+            }
+        };
+        debug!("Spectators listening on {}", endpoint);
+        loop {
+            match l.accept() {
+                Ok((mut conn, addr)) => {
+                    debug!("Spectator from {}", addr);
+                    if let Err(e) = conn.set_write_timeout(Some(Duration::from_millis(WRITE_TIMEOUT_MS))) {
+                        error!("Spectator {}: {}", addr, e);
+                        continue;
+                    }
+                    if let Err(e) = conn.write_all(&self.start) {
+                        error!("Spectator {}: {}", addr, e);
+                        continue;
+                    }
+                    let (tx, rx) = mpsc::sync_channel(frames::QUEUE);
+                    let _ = thread::spawn(move || write_frames(conn, rx));
+                    self.conns.lock().unwrap().push(tx);
+                }
+                Err(e) => error!("accept on {}: {}", endpoint, e),
+            }
+        }
+    }
+
+    // How many are watching
+    pub fn count(&self) -> usize {
+        self.conns.lock().unwrap().len()
+    }
+
+    // Called every tick, like Server::send_state. Spectators who have gone, or
+    // can't keep up, are dropped.
+    pub fn send_state(&self, tick: u32, state: &Shared) {
+        let mut conns = self.conns.lock().unwrap();
+        conns.retain(|tx| match tx.try_send((tick, state.clone())) {
+            Ok(()) => true,
+            Err(e) => {
+                debug!("Spectator gone: {}", e);
+                false // its writer stops
+            }
+        });
+    }
+}
+
+// A spectator's writer thread. Runs until they're dropped or go away.
+fn write_frames(mut conn: Stream, rx: Receiver<(u32, Shared)>) {
+    for (tick, frame) in rx {
+        if let Err(e) = replay::write_state(&mut conn, tick, &frame) {
+            debug!("Spectator gone: {}", e);
+            break;
+        }
+    }
+    let _ = conn.shutdown(Shutdown::Both);
+}
@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+// Every finished match is appended here, in the data directory unless
+// RUST_CONSOLE_GAME_STATS_FILE says otherwise, see session::stats_path
+pub const STATS_FILE: &str = "stats.toml";
+
+// One finished match. Players are by name, so use profiles (or the same
+// --p1 / --p2 names) to have your games counted together.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MatchRecord {
+    pub finished: u64, // unix time, seconds
+    pub players: [String; 2],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winner: Option<String>, // None if both lost their last life together
+    pub lives: [u32; 2], // left at the end, the score
+    pub seconds: u64,    // of play, not counting banners and pauses
+    pub ticks: u32,
+    pub shots: [u32; 2], // missiles and rays fired
+    pub hits: [u32; 2],  // lives taken from the other player
+}
+
+// stats.toml is a list of [[matches]], so a new match can be appended
+// without reading the file.
+#[derive(Default, Serialize, Deserialize)]
+struct StatsFile {
+    #[serde(default)]
+    matches: Vec<MatchRecord>,
+}
+
+impl MatchRecord {
+    // winner is 0 or 1, None for a draw
+    pub fn new(
+        players: [String; 2],
+        winner: Option<usize>,
+        lives: [u32; 2],
+        hits: [u32; 2],
+        shots: [u32; 2],
+        ticks: u32,
+        frame_gap_ms: u64,
+    ) -> MatchRecord {
+        let winner = winner.map(|idx| players[idx].clone());
+        let finished = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        MatchRecord {
+            finished,
+            hits,
+            players,
+            winner,
+            lives,
+            seconds: ticks as u64 * frame_gap_ms / 1000,
+            ticks,
+            shots,
+        }
+    }
+}
+
+// Making its directory if need be
+pub fn append(path: &Path, m: &MatchRecord) -> Result<(), String> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let file = StatsFile {
+        matches: vec![m.clone()],
+    };
+    let s = toml::to_string(&file).map_err(|e| e.to_string())?;
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    writeln!(f, "{}", s).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Oldest first. No file yet is no matches.
+pub fn load(path: &Path) -> Result<Vec<MatchRecord>, String> {
+    let s = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let file: StatsFile = toml::from_str(&s).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(file.matches)
+}
+
+#[derive(Default)]
+struct Record {
+    played: u32,
+    won: u32,
+    lost: u32,
+    shots: u32,
+    hits: u32,
+    streak: i32, // current: +3 is three wins in a row, -2 two losses
+    best: u32,   // longest run of wins
+}
+
+impl Record {
+    fn add(&mut self, won: Option<bool>, shots: u32, hits: u32) {
+        self.played += 1;
+        self.shots += shots;
+        self.hits += hits;
+        match won {
+            Some(true) => {
+                self.won += 1;
+                self.streak = self.streak.max(0) + 1;
+                self.best = self.best.max(self.streak as u32);
+            }
+            Some(false) => {
+                self.lost += 1;
+                self.streak = self.streak.min(0) - 1;
+            }
+            None => self.streak = 0, // a draw ends any streak
+        }
+    }
+
+    fn streak(&self) -> String {
+        match self.streak {
+            0 => "-".to_string(),
+            n if n > 0 => format!("W{}", n),
+            n => format!("L{}", -n),
+        }
+    }
+
+    // hits per shot
+    fn accuracy(&self) -> String {
+        match self.shots {
+            0 => "-".to_string(),
+            s => format!("{}%", self.hits * 100 / s),
+        }
+    }
+}
+
+// The leaderboard, best win rate first, then every pairing head to head
+// path is where they were loaded from.
+pub fn show(out: &mut impl Write, path: &Path, matches: &[MatchRecord]) -> io::Result<()> {
+    if matches.is_empty() {
+        return writeln!(out, "No matches yet. Finished matches are saved to {}.", path.display());
+    }
+
+    let mut players: BTreeMap<&str, Record> = BTreeMap::new();
+    // keyed by the two names in alphabetical order, the record is the first's
+    let mut pairs: BTreeMap<(&str, &str), Record> = BTreeMap::new();
+    for m in matches {
+        for i in 0..2 {
+            let won = m.winner.as_ref().map(|w| *w == m.players[i]);
+            players
+                .entry(&m.players[i])
+                .or_default()
+                .add(won, m.shots[i], m.hits[i]);
+        }
+        let first = if m.players[0] <= m.players[1] { 0 } else { 1 };
+        let key = (m.players[first].as_str(), m.players[1 - first].as_str());
+        let won = m.winner.as_ref().map(|w| *w == m.players[first]);
+        pairs.entry(key).or_default().add(won, m.shots[first], m.hits[first]);
+    }
+
+    let mut board: Vec<(&str, Record)> = players.into_iter().collect();
+    board.sort_by(|(_, a), (_, b)| {
+        // cross multiply rather than divide, and more wins breaks a tie
+        (b.won * a.played)
+            .cmp(&(a.won * b.played))
+            .then(b.won.cmp(&a.won))
+    });
+    let width = board.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0).max(4);
+
+    writeln!(out, "Leaderboard, {} matches", matches.len())?;
+    writeln!(
+        out,
+        "  {:width$}  Played  Won  Lost  Win%  Accuracy  Streak  Best",
+        "Name",
+        width = width
+    )?;
+    for (name, r) in &board {
+        writeln!(
+            out,
+            "  {:width$}  {:>6}  {:>3}  {:>4}  {:>3}%  {:>8}  {:>6}  {:>4}",
+            name,
+            r.played,
+            r.won,
+            r.lost,
+            r.won * 100 / r.played,
+            r.accuracy(),
+            r.streak(),
+            r.best,
+            width = width
+        )?;
+    }
+
+    writeln!(out)?;
+    writeln!(out, "Head to head")?;
+    for ((a, b), r) in &pairs {
+        let draws = r.played - r.won - r.lost;
+        let mut line = format!("  {} {} - {} {}", a, r.won, r.lost, b);
+        if draws > 0 {
+            line += &format!(", {} drawn", draws);
+        }
+        match r.streak {
+            n if n > 1 => line += &format!(". {} has won the last {}", a, n),
+            n if n < -1 => line += &format!(". {} has won the last {}", b, -n),
+            _ => {}
+        }
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
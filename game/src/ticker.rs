@@ -0,0 +1,48 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+// Paces the game loop: frames start `gap` apart, however long the input,
+// systems and rendering took. A frame that takes longer than the gap makes
+// the next one start straight away, and we don't try to catch up after, so
+// bots never see a burst of frames.
+pub struct Ticker {
+    gap: Duration,
+    next: Instant,   // when the next frame should start
+    overrun: u32,    // frames in a row that took longer than gap
+    worst: Duration, // longest overrun in this run of them
+}
+
+impl Ticker {
+    pub fn new(gap: Duration) -> Ticker {
+        Ticker {
+            gap,
+            next: Instant::now() + gap,
+            overrun: 0,
+            worst: Duration::ZERO,
+        }
+    }
+
+    // Call at the end of each frame, sleeps until the next one is due.
+    // Overruns are logged once per run of them, not every frame.
+    pub fn wait(&mut self) {
+        let now = Instant::now();
+        if now <= self.next {
+            if self.overrun > 0 {
+                warn!(
+                    "{} frame(s) overran the {:?} frame time, worst by {:?}",
+                    self.overrun, self.gap, self.worst
+                );
+                self.overrun = 0;
+                self.worst = Duration::ZERO;
+            }
+            thread::sleep(self.next - now);
+            self.next += self.gap;
+        } else {
+            self.overrun += 1;
+            self.worst = self.worst.max(now - self.next);
+            self.next = now + self.gap;
+        }
+    }
+}
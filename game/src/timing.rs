@@ -0,0 +1,87 @@
+// How long each system, and drawing the frame, take per tick, over the last
+// few seconds: the average and the worst. Shown in the inspector in debug
+// mode, served as metrics, and when a tick's work runs over the time between
+// frames a warning says where it went.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+// Ticks averaged over, 5s at the default speed
+const WINDOW: usize = 100;
+
+// At most one over budget warning this often, a slow machine would be over
+// every tick
+const WARN_EVERY: Duration = Duration::from_secs(5);
+
+// A system, or "render"
+#[derive(Clone, Debug)]
+pub struct Stage {
+    pub name: &'static str,
+    pub mean: Duration,
+    pub worst: Duration,
+}
+
+#[derive(Default)]
+pub struct Timings {
+    recent: Vec<(&'static str, VecDeque<Duration>)>, // newest last
+    over: u32,                                       // ticks over budget since the last warning
+    warned: Option<Instant>,
+}
+
+impl Timings {
+    // How long name took this tick. Stages are listed in the order they're
+    // first seen.
+    pub fn add(&mut self, name: &'static str, d: Duration) {
+        let idx = match self.recent.iter().position(|(n, _)| *n == name) {
+            Some(idx) => idx,
+            None => {
+                self.recent.push((name, VecDeque::with_capacity(WINDOW)));
+                self.recent.len() - 1
+            }
+        };
+        let times = &mut self.recent[idx].1;
+        if times.len() == WINDOW {
+            times.pop_front();
+        }
+        times.push_back(d);
+    }
+
+    // The tick took work in all, and had budget for it
+    pub fn check(&mut self, tick: u32, work: Duration, budget: Duration) {
+        if work <= budget {
+            return;
+        }
+        self.over += 1;
+        if self.warned.is_some_and(|at| at.elapsed() < WARN_EVERY) {
+            return;
+        }
+        let last: Vec<String> = self
+            .recent
+            .iter()
+            .filter_map(|(name, times)| times.back().map(|d| format!("{} {:.2?}", name, d)))
+            .collect();
+        warn!(
+            "Tick {} took {:.2?}, over the {:?} between frames, {} over since the last warning: {}",
+            tick,
+            work,
+            budget,
+            self.over,
+            last.join(", ")
+        );
+        self.over = 0;
+        self.warned = Some(Instant::now());
+    }
+
+    pub fn stages(&self) -> Vec<Stage> {
+        self.recent
+            .iter()
+            .map(|(name, times)| Stage {
+                name,
+                mean: times.iter().sum::<Duration>() / times.len().max(1) as u32,
+                worst: times.iter().copied().max().unwrap_or_default(),
+            })
+            .collect()
+    }
+}
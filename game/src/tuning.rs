@@ -0,0 +1,56 @@
+// In debug mode the config file is watched, and changes to energy costs and
+// weapons are applied to the running game, for trying out balance changes
+// without starting again. Anything else in the file needs a restart.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use log::warn;
+
+use crate::config::GameConfig;
+
+// Looking at the file's modified time is cheap, but not every tick
+const CHECK_EVERY: Duration = Duration::from_millis(500);
+
+pub struct Watcher {
+    path: PathBuf,
+    modified: Option<SystemTime>, // None if there's no file
+    next_check: Instant,
+}
+
+impl Watcher {
+    pub fn new(path: PathBuf) -> Watcher {
+        Watcher {
+            modified: modified(&path),
+            path,
+            next_check: Instant::now() + CHECK_EVERY,
+        }
+    }
+
+    // The file's settings, if it has changed since we last looked. One that
+    // doesn't load is logged, and the game carries on as it was.
+    pub fn poll(&mut self) -> Option<GameConfig> {
+        let now = Instant::now();
+        if now < self.next_check {
+            return None;
+        }
+        self.next_check = now + CHECK_EVERY;
+        let m = modified(&self.path);
+        if m == self.modified || m.is_none() {
+            return None;
+        }
+        self.modified = m;
+        match GameConfig::load(&self.path) {
+            Ok(c) => Some(c),
+            Err(e) => {
+                warn!("Not reloading config: {}", e);
+                None
+            }
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
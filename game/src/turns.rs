@@ -0,0 +1,151 @@
+// Hot-seat play, `turn_ticks` in config.toml or --turns: instead of running
+// in real time the game waits for a player to pick a move and an action, and
+// press Enter, then plays that many ticks and waits for the other. For
+// playing over screen-share, or on a terminal too slow to keep up. Keys
+// pressed while a turn plays out are ignored.
+//
+// A bot has no Enter key, its turn is whatever it sends in reply to the first
+// frame of it. One that sends nothing for BOT_TURN stands still.
+
+use std::time::{Duration, Instant};
+
+use rs_sdk::Intent;
+
+use crate::input::{self, InputEvent};
+
+const BOT_TURN: Duration = Duration::from_secs(1);
+
+pub struct Turns {
+    ticks: u32,       // played after each turn
+    whose: usize,     // 0 for player 1, 1 for player 2
+    picked: Intent,   // their choice so far
+    left: u32,        // ticks still to play of the last turn, 0 while picking
+    started: Instant, // when whose started picking
+}
+
+impl Turns {
+    // Player 1 goes first
+    pub fn new(ticks: u32) -> Turns {
+        Turns {
+            ticks,
+            whose: 0,
+            picked: Intent::default(),
+            left: 0,
+            started: Instant::now(),
+        }
+    }
+
+    // What to apply this tick, given its events, or None if we're waiting on
+    // a player and the world should stand still. bots says which players are
+    // played by one.
+    pub fn next(&mut self, events: &[InputEvent], bots: [bool; 2]) -> Option<[Intent; 2]> {
+        if self.left > 0 {
+            self.left -= 1;
+            if self.left == 0 {
+                self.started = Instant::now();
+            }
+            return Some([Intent::default(); 2]);
+        }
+
+        let i = input::intents(events)[self.whose];
+        let p = &mut self.picked;
+        p.dir = i.dir.or(p.dir);
+        p.fire = i.fire.or(p.fire);
+        p.toggle_shield ^= i.toggle_shield;
+        p.change_weapon ^= i.change_weapon;
+        p.drop_wall ^= i.drop_wall;
+
+        let is_done = if bots[self.whose] {
+            *p != Intent::default() || self.started.elapsed() >= BOT_TURN
+        } else {
+            events.contains(&InputEvent::EndTurn)
+        };
+        if !is_done {
+            return None;
+        }
+        let mut out = [Intent::default(); 2];
+        out[self.whose] = std::mem::take(&mut self.picked);
+        self.whose = 1 - self.whose;
+        self.left = self.ticks - 1;
+        if self.left == 0 {
+            self.started = Instant::now();
+        }
+        Some(out)
+    }
+
+    // The player picking, None while a turn plays out
+    pub fn whose(&self) -> Option<usize> {
+        (self.left == 0).then_some(self.whose)
+    }
+
+    // What they've picked so far, e.g. "move Left, fire Up", for the display
+    pub fn describe(&self) -> String {
+        let p = &self.picked;
+        let mut parts = Vec::new();
+        if let Some(dir) = p.dir {
+            parts.push(format!("move {}", dir));
+        }
+        if p.change_weapon {
+            parts.push("change weapon".to_string());
+        }
+        if p.toggle_shield {
+            parts.push("shield".to_string());
+        }
+        if p.drop_wall {
+            parts.push("wall".to_string());
+        }
+        if let Some(dir) = p.fire {
+            parts.push(format!("fire {}", dir));
+        }
+        if parts.is_empty() {
+            "stay put".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rs_sdk::Dir;
+
+    use super::*;
+
+    const HUMANS: [bool; 2] = [false, false];
+
+    #[test]
+    fn nothing_happens_until_the_player_ends_their_turn() {
+        let mut t = Turns::new(3);
+        let left = InputEvent::Move { entity_id: 1, dir: Dir::Left };
+        assert!(t.next(&[left], HUMANS).is_none());
+        assert!(t.next(&[], HUMANS).is_none());
+        assert_eq!(t.describe(), "move Left");
+
+        let played = t.next(&[InputEvent::EndTurn], HUMANS).unwrap();
+        assert_eq!(played[0].dir, Some(Dir::Left));
+        assert_eq!(played[1], Intent::default());
+        // then two more ticks of nothing new, and it's player 2's turn
+        assert_eq!(t.whose(), None);
+        assert_eq!(t.next(&[], HUMANS), Some([Intent::default(); 2]));
+        assert_eq!(t.next(&[], HUMANS), Some([Intent::default(); 2]));
+        assert_eq!(t.whose(), Some(1));
+        assert!(t.next(&[], HUMANS).is_none());
+    }
+
+    #[test]
+    fn the_other_players_keys_wait_their_turn() {
+        let mut t = Turns::new(1);
+        let fire = InputEvent::Fire { entity_id: 2, dir: Dir::Up };
+        let played = t.next(&[fire, InputEvent::EndTurn], HUMANS).unwrap();
+        assert_eq!(played, [Intent::default(); 2]);
+        assert_eq!(t.whose(), Some(1));
+    }
+
+    #[test]
+    fn a_bots_turn_is_what_it_sends() {
+        let mut t = Turns::new(1);
+        let shield = InputEvent::ToggleShield { entity_id: 1 };
+        let played = t.next(&[shield], [true, false]).unwrap();
+        assert!(played[0].toggle_shield);
+    }
+}
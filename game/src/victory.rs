@@ -0,0 +1,119 @@
+// How a match is won, picked by `mode` under [victory] in config.toml, or
+// --mode. After every round the game asks its WinCondition whether the match
+// is over, and who won.
+
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use crate::config::{Mode, Victory};
+use crate::World;
+
+// How a finished match came out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Won(usize), // by player 0 or 1
+    Draw,
+}
+
+pub trait WinCondition {
+    // For the intro, e.g. "First to 5 hits wins"
+    fn describe(&self) -> String;
+
+    // Whether being hit costs a life. If not, lives stay as they started and
+    // only hits count.
+    fn takes_lives(&self) -> bool {
+        true
+    }
+
+    // The match clock has run out, which also ends the round being played
+    fn is_time_up(&self, _w: &World) -> bool {
+        false
+    }
+
+    // None while the match goes on
+    fn outcome(&self, w: &World) -> Option<Outcome>;
+}
+
+pub fn of(v: &Victory) -> Box<dyn WinCondition> {
+    match v.mode {
+        Mode::Lives => Box::new(LastAlive),
+        Mode::Points => Box::new(FirstTo(v.points)),
+        Mode::Timed => Box::new(Timed(Duration::from_secs(v.seconds as u64))),
+    }
+}
+
+// The usual game. Both losing their last life together is a tiebreaker
+// round, see end_round, so this is only a draw if a bot quits on one.
+struct LastAlive;
+
+impl WinCondition for LastAlive {
+    fn describe(&self) -> String {
+        "Last player with lives left wins".to_string()
+    }
+
+    fn outcome(&self, w: &World) -> Option<Outcome> {
+        out_of_lives(w)
+    }
+}
+
+// A hit on both at once scores for both, so if that takes them both to the
+// target, play goes on until one is ahead
+struct FirstTo(u32);
+
+impl WinCondition for FirstTo {
+    fn describe(&self) -> String {
+        format!("First to {} hits wins", self.0)
+    }
+
+    fn takes_lives(&self) -> bool {
+        false
+    }
+
+    fn outcome(&self, w: &World) -> Option<Outcome> {
+        let [p1, p2] = w.points();
+        match p1.cmp(&p2) {
+            _ if p1.max(p2) < self.0 => None,
+            Ordering::Greater => Some(Outcome::Won(0)),
+            Ordering::Less => Some(Outcome::Won(1)),
+            Ordering::Equal => None,
+        }
+    }
+}
+
+// Most lives when the clock runs out, level is a draw. The clock is play
+// time, as shown under the board, not banners or waiting for players. Losing
+// every life before then still loses.
+struct Timed(Duration);
+
+impl WinCondition for Timed {
+    fn describe(&self) -> String {
+        format!("Most lives after {} wins", crate::render::clock_time(self.0))
+    }
+
+    fn is_time_up(&self, w: &World) -> bool {
+        w.match_start.is_some_and(|start| w.play_time(w.tick - start) >= self.0)
+    }
+
+    fn outcome(&self, w: &World) -> Option<Outcome> {
+        if let Some(o) = out_of_lives(w) {
+            return Some(o);
+        }
+        if !self.is_time_up(w) {
+            return None;
+        }
+        Some(match w.p1_lives.cmp(&w.p2_lives) {
+            Ordering::Greater => Outcome::Won(0),
+            Ordering::Less => Outcome::Won(1),
+            Ordering::Equal => Outcome::Draw,
+        })
+    }
+}
+
+fn out_of_lives(w: &World) -> Option<Outcome> {
+    match (w.p1_lives, w.p2_lives) {
+        (0, 0) => Some(Outcome::Draw),
+        (0, _) => Some(Outcome::Won(1)),
+        (_, 0) => Some(Outcome::Won(0)),
+        _ => None,
+    }
+}
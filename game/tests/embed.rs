@@ -0,0 +1,36 @@
+// The game driven a tick at a time through embed::Game, as another program
+// would, checking the events it reports.
+
+use rs_sdk::{Dir, Intent, Player};
+use rust_console_game::embed::{Event, Game};
+use rust_console_game::{GameConfig, MapKind};
+
+#[test]
+fn a_missile_wins_the_match_then_a_rematch_starts() {
+    let config = GameConfig {
+        lives: 1,
+        map: MapKind::Empty,
+        ..GameConfig::default()
+    };
+    let mut game = Game::new(config).unwrap().arena(30, 20);
+
+    let fire = Intent {
+        fire: Some(Dir::Right),
+        ..Intent::default()
+    };
+    let mut events = game.tick([fire, Intent::default()]);
+    assert_eq!(events[..2], [Event::RoundStart, Event::Fired(Player::One)]);
+    for _ in 0..40 {
+        if events.iter().any(|e| matches!(e, Event::MatchOver { .. })) {
+            break;
+        }
+        events = game.tick([Intent::default(); 2]);
+    }
+    // the missile blows up as it hits
+    let feed = Event::Feed("Player 1's explosion hit Player 2".to_string());
+    assert_eq!(events, [feed, Event::Hit(Player::Two), Event::MatchOver { winner: Some(Player::One) }]);
+
+    let events = game.tick([Intent::default(); 2]);
+    assert_eq!(events, [Event::RoundStart]);
+    assert_eq!(game.frame().player_status(Player::Two).lives(), 1);
+}
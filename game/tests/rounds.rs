@@ -0,0 +1,207 @@
+// Whole rounds played headless with scripted bots, checking the game rules
+// from what the bots are sent. No sockets or terminal, and no randomness, so
+// the same script always plays out the same way.
+
+use rs_sdk::{Actions, Bot, Dir, EntityKind, Event, Frame, Player};
+use rust_console_game::headless::Match;
+use rust_console_game::MapKind;
+
+// Small enough that a missile fired from one start position reaches the other
+const WIDTH: u32 = 30;
+const HEIGHT: u32 = 20;
+
+// What a bot saw in one frame
+#[derive(Debug)]
+struct Seen {
+    round_tick: u32, // frames into the round, from 0
+    lives: [u8; 2],
+    players: [Option<(u32, u32)>; 2],
+    weapons: usize, // missiles, rays and explosions on the board
+}
+
+// Plays `script(round_tick, frame, out)` each frame and remembers the frames
+// and events.
+struct Scripted<F> {
+    script: F,
+    round_tick: u32,
+    seen: Vec<Seen>,
+    events: Vec<Event>,
+}
+
+fn scripted<F: FnMut(u32, &Frame, &mut Actions)>(script: F) -> Scripted<F> {
+    Scripted {
+        script,
+        round_tick: 0,
+        seen: Vec::new(),
+        events: Vec::new(),
+    }
+}
+
+// Does nothing, for the player being shot at
+fn idle() -> Scripted<impl FnMut(u32, &Frame, &mut Actions)> {
+    scripted(|_, _, _| {})
+}
+
+impl<F: FnMut(u32, &Frame, &mut Actions)> Bot for Scripted<F> {
+    fn on_frame(&mut self, frame: &Frame, out: &mut Actions) {
+        let pos = |p| frame.player(p).map(|e| e.pos());
+        self.seen.push(Seen {
+            round_tick: self.round_tick,
+            lives: [
+                frame.player_status(Player::One).lives(),
+                frame.player_status(Player::Two).lives(),
+            ],
+            players: [pos(Player::One), pos(Player::Two)],
+            weapons: frame
+                .entities()
+                .iter()
+                .filter(|e| e.kind() != EntityKind::Player && e.kind() != EntityKind::Obstacle)
+                .count(),
+        });
+        (self.script)(self.round_tick, frame, out);
+        self.round_tick += 1;
+    }
+
+    fn on_event(&mut self, event: Event) {
+        if event == Event::RoundStart {
+            self.round_tick = 0;
+        }
+        self.events.push(event);
+    }
+}
+
+fn start_positions() -> [Option<(u32, u32)>; 2] {
+    [Some((WIDTH / 4, HEIGHT / 2)), Some((WIDTH / 4 * 3, HEIGHT / 2))]
+}
+
+#[test]
+fn missile_hits_player_two() {
+    // only in the first round
+    let mut p1 = scripted(|t, f, out| {
+        if t == 0 && f.player_status(Player::Two).lives() == 3 {
+            out.fire(Dir::Right);
+        }
+    });
+    let mut p2 = idle();
+    let res = Match::new(WIDTH, HEIGHT)
+        .map(MapKind::Empty, 0)
+        .lives(3)
+        .max_ticks(40)
+        .run(&mut p1, &mut p2);
+
+    assert_eq!(res.winner, None, "out of ticks, nobody is out of lives");
+    assert_eq!(res.stats[0].shots, 1);
+    assert_eq!(res.stats[0].rounds_won, 1);
+    assert_eq!(res.stats[0].lives, 3);
+    assert_eq!(res.stats[1].lives, 2);
+    assert_eq!(res.rounds, 2);
+    assert_eq!(p1.events[..3], [Event::RoundStart, Event::RoundOver, Event::RoundStart]);
+
+    // the missile was in flight, then the second round started afresh
+    let second_round = p2.seen.iter().position(|s| s.lives[1] == 2).unwrap();
+    assert!(p2.seen[..second_round].iter().skip(1).all(|s| s.weapons == 1));
+    let s = &p2.seen[second_round];
+    assert_eq!(s.round_tick, 0);
+    assert_eq!(s.players, start_positions());
+    assert_eq!(s.weapons, 0);
+}
+
+#[test]
+fn ray_wins_the_match() {
+    let mut p1 = scripted(|t, _, out| match t {
+        0 => out.change_weapon(),
+        1 => out.fire(Dir::Right),
+        _ => {}
+    });
+    let mut p2 = idle();
+    let res = Match::new(WIDTH, HEIGHT)
+        .map(MapKind::Empty, 0)
+        .lives(1)
+        .run(&mut p1, &mut p2);
+
+    assert_eq!(res.winner, Some(Player::One));
+    assert_eq!(res.rounds, 1);
+    assert_eq!(res.stats[0].lives, 1);
+    assert_eq!(res.stats[1].lives, 0);
+    assert_eq!(p2.events, vec![Event::RoundStart, Event::MatchOver]);
+}
+
+#[test]
+fn shield_sends_a_ray_back() {
+    let mut p1 = scripted(|t, _, out| match t {
+        0 => out.change_weapon(),
+        2 => out.fire(Dir::Right),
+        _ => {}
+    });
+    let mut p2 = scripted(|t, _, out| {
+        if t == 0 {
+            out.toggle_shield();
+        }
+    });
+    let res = Match::new(WIDTH, HEIGHT)
+        .map(MapKind::Empty, 0)
+        .lives(1)
+        .max_ticks(60)
+        .run(&mut p1, &mut p2);
+
+    assert_eq!(res.stats[0].shots, 1);
+    assert_eq!(res.winner, Some(Player::Two));
+    assert_eq!(res.rounds, 1);
+    assert_eq!([res.stats[0].lives, res.stats[1].lives], [0, 1]);
+}
+
+#[test]
+fn obstacle_stops_a_ray() {
+    // the bar map has a wall down the middle, between the start positions
+    let mut p1 = scripted(|t, _, out| match t {
+        0 => out.change_weapon(),
+        1 => out.fire(Dir::Right),
+        _ => {}
+    });
+    let mut p2 = idle();
+    let res = Match::new(WIDTH, HEIGHT)
+        .map(MapKind::Bar, 0)
+        .lives(3)
+        .max_ticks(60)
+        .run(&mut p1, &mut p2);
+
+    assert_eq!(res.stats[0].shots, 1);
+    assert_eq!(res.rounds, 1);
+    assert_eq!([res.stats[0].lives, res.stats[1].lives], [3, 3]);
+}
+
+#[test]
+fn players_bounce_off_the_edge() {
+    let mut p1 = scripted(|t, _, out| {
+        if t == 0 {
+            out.dir(Dir::Up);
+        }
+    });
+    let mut p2 = idle();
+    Match::new(WIDTH, HEIGHT)
+        .map(MapKind::Empty, 0)
+        .max_ticks(30)
+        .run(&mut p1, &mut p2);
+
+    // up one a tick to the top of the playable area (see Map::bounds), then back down
+    let ys: Vec<u32> = p1.seen.iter().map(|s| s.players[0].unwrap().1).collect();
+    let top = ys.iter().position(|&y| y == 2).unwrap();
+    assert_eq!(top, HEIGHT as usize / 2 - 2);
+    assert!(ys[..=top].windows(2).all(|w| w[1] == w[0] - 1), "{:?}", ys);
+    assert_eq!(ys[top + 1..top + 3], [2, 3], "stays a tick to turn round");
+    assert!(p1.seen.iter().all(|s| s.players[0].unwrap().0 == WIDTH / 4));
+}
+
+#[test]
+fn firing_costs_energy() {
+    // missiles need more than ENERGY_MISSILE, so holding fire runs out
+    let mut p1 = scripted(|_, _, out| out.fire(Dir::Up));
+    let mut p2 = idle();
+    let res = Match::new(WIDTH, HEIGHT)
+        .map(MapKind::Empty, 0)
+        .max_ticks(100)
+        .run(&mut p1, &mut p2);
+
+    let fired = res.stats[0].shots;
+    assert!(fired > 0 && fired < 99, "fired {} of 99", fired);
+}
@@ -0,0 +1,23 @@
+// Regenerate include/rs_sdk.h from the extern "C" functions in src/lib.rs
+
+use std::env;
+
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        include_guard: Some("RS_SDK_H".to_string()),
+        autogen_warning: Some(
+            "/* Generated by cbindgen from rs-sdk-ffi/src/lib.rs. Do not edit. */".to_string(),
+        ),
+        cpp_compat: true,
+        ..Default::default()
+    };
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("generating C header")
+        .write_to_file(format!("{}/include/rs_sdk.h", crate_dir));
+    println!("cargo:rerun-if-changed=src/lib.rs");
+}
@@ -0,0 +1,68 @@
+// A C bot which walks toward the other player and fires at them.
+//
+//   cargo build --release -p rs-sdk-ffi
+//   cc -Irs-sdk-ffi/include rs-sdk-ffi/examples/bot.c target/release/librs_sdk_ffi.a -lpthread -ldl -lm -o cbot
+//   ./cbot 1
+
+#include <stdio.h>
+#include <stdlib.h>
+#include "rs_sdk.h"
+
+static const RcgEntity *find_player(const RcgFrame *f, uint8_t id) {
+    for (uintptr_t i = 0; i < f->num_entities; i++) {
+        if (f->entities[i].kind == RCG_KIND_PLAYER && f->entities[i].id == id) {
+            return &f->entities[i];
+        }
+    }
+    return NULL;
+}
+
+static uint8_t toward(const RcgEntity *from, const RcgEntity *to) {
+    uint32_t dx = from->x > to->x ? from->x - to->x : to->x - from->x;
+    uint32_t dy = from->y > to->y ? from->y - to->y : to->y - from->y;
+    if (dx > dy) {
+        return to->x > from->x ? RCG_DIR_RIGHT : RCG_DIR_LEFT;
+    }
+    return to->y > from->y ? RCG_DIR_DOWN : RCG_DIR_UP;
+}
+
+int main(int argc, char **argv) {
+    int player = argc > 1 ? atoi(argv[1]) : 1;
+    RcgBot *bot = rcg_connect(player, getenv("RUST_CONSOLE_GAME_TOKEN"));
+    if (bot == NULL) {
+        fprintf(stderr, "connect: %s\n", rcg_last_error());
+        return 1;
+    }
+
+    RcgFrame f;
+    int rc;
+    while ((rc = rcg_next_frame(bot, &f)) == RCG_OK) {
+        switch (f.state) {
+        case RCG_STATE_WAITING:
+            rc = rcg_ready(bot);
+            break;
+        case RCG_STATE_GAME_OVER:
+            rc = rcg_rematch(bot);
+            break;
+        default: {
+            // entity ids of players are 0 and 1
+            const RcgEntity *me = find_player(&f, player - 1);
+            const RcgEntity *them = find_player(&f, 2 - player);
+            if (me == NULL || them == NULL || f.tick % 4 != 0) {
+                break;
+            }
+            uint8_t d = toward(me, them);
+            rc = f.tick % 8 == 0 ? rcg_dir(bot, d) : rcg_fire(bot, d);
+        }
+        }
+        if (rc != RCG_OK) {
+            break;
+        }
+    }
+
+    if (rc == RCG_ERROR) {
+        fprintf(stderr, "error: %s\n", rcg_last_error());
+    }
+    rcg_free(bot);
+    return rc == RCG_ERROR;
+}
@@ -0,0 +1,235 @@
+#ifndef RS_SDK_H
+#define RS_SDK_H
+
+/* Generated by cbindgen from rs-sdk-ffi/src/lib.rs. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define RCG_OK 0
+
+#define RCG_DISCONNECTED 1
+
+#define RCG_ERROR -1
+
+#define RCG_DIR_NONE 0
+
+#define RCG_DIR_UP 1
+
+#define RCG_DIR_DOWN 2
+
+#define RCG_DIR_LEFT 3
+
+#define RCG_DIR_RIGHT 4
+
+#define RCG_STATE_PLAYING 0
+
+#define RCG_STATE_WAITING 1
+
+#define RCG_STATE_GAME_OVER 2
+
+#define RCG_KIND_PLAYER 0
+
+#define RCG_KIND_MISSILE 1
+
+#define RCG_KIND_RAY 2
+
+#define RCG_KIND_OBSTACLE 3
+
+#define RCG_KIND_EXPLOSION 4
+
+#define RCG_KIND_PICKUP 5
+
+#define RCG_KIND_MARKER 6
+
+#define RCG_KIND_SUPPLY 7
+
+#define RCG_KIND_TELEPORTER 8
+
+#define RCG_KIND_BEAM 9
+
+#define RCG_KIND_CLUSTER 10
+
+/**
+ * A connection to the game. Opaque, free with rcg_free.
+ */
+typedef struct RcgBot RcgBot;
+
+typedef struct RcgPlayerStatus {
+  uint8_t lives;
+  uint16_t energy;
+  /**
+   * 0 missile, 1 ray, 2 beam, 3 cluster
+   */
+  uint8_t weapon;
+  /**
+   * Ticks until there's energy to fire the weapon, 0 now, 65535 never
+   */
+  uint16_t cooldown;
+  /**
+   * Energy a raised shield takes every reload
+   */
+  uint8_t shield_drain;
+  /**
+   * Hits landed on the other player this match
+   */
+  uint8_t points;
+  /**
+   * The game keeps the opponent's energy and cooldown hidden, both are 0
+   */
+  bool energy_hidden;
+} RcgPlayerStatus;
+
+typedef struct RcgEntity {
+  uint16_t id;
+  /**
+   * RCG_KIND_*
+   */
+  uint8_t kind;
+  uint32_t x;
+  uint32_t y;
+  /**
+   * RCG_DIR_*
+   */
+  uint8_t dir;
+  uint8_t speed;
+  bool has_shield;
+} RcgEntity;
+
+/**
+ * One tick of the game. `entities` is owned by the RcgBot and is valid
+ * until the next rcg_next_frame or rcg_free.
+ */
+typedef struct RcgFrame {
+  uint32_t tick;
+  /**
+   * RCG_STATE_*
+   */
+  uint8_t state;
+  struct RcgPlayerStatus players[2];
+  uintptr_t num_entities;
+  const struct RcgEntity *entities;
+} RcgFrame;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Connect to the game as player 1 or 2. `token` is the game's shared secret,
+ * or NULL if it doesn't have one. Returns NULL on failure, see rcg_last_error.
+ *
+ * # Safety
+ * `token` must be NULL or a valid nul terminated string.
+ */
+struct RcgBot *rcg_connect(uint8_t player, const char *token);
+
+/**
+ * Close the connection. `bot` may be NULL.
+ *
+ * # Safety
+ * `bot` must come from rcg_connect and not already be freed.
+ */
+void rcg_free(struct RcgBot *bot);
+
+/**
+ * Milliseconds between frames while playing, as sent by the game when we connected
+ *
+ * # Safety
+ * `bot` must come from rcg_connect.
+ */
+uint32_t rcg_tick_ms(const struct RcgBot *bot);
+
+/**
+ * Block until the next frame, and fill in `out`. While playing this also
+ * tells the game the frame arrived, so it can show the bot's ping.
+ *
+ * # Safety
+ * `bot` must come from rcg_connect, `out` must point to an RcgFrame.
+ */
+int32_t rcg_next_frame(struct RcgBot *bot, struct RcgFrame *out);
+
+/**
+ * Change direction, see RCG_DIR_*. Moving the opposite way to current direction stops.
+ *
+ * # Safety
+ * `bot` must come from rcg_connect.
+ */
+int32_t rcg_dir(struct RcgBot *bot, uint8_t dir);
+
+/**
+ * Like rcg_dir, but the game applies it at `tick`
+ *
+ * # Safety
+ * `bot` must come from rcg_connect.
+ */
+int32_t rcg_dir_at(struct RcgBot *bot, uint8_t dir, uint32_t tick);
+
+/**
+ * Fire the current weapon in a direction
+ *
+ * # Safety
+ * `bot` must come from rcg_connect.
+ */
+int32_t rcg_fire(struct RcgBot *bot, uint8_t dir);
+
+/**
+ * Like rcg_fire, but the game applies it at `tick`
+ *
+ * # Safety
+ * `bot` must come from rcg_connect.
+ */
+int32_t rcg_fire_at(struct RcgBot *bot, uint8_t dir, uint32_t tick);
+
+/**
+ * Turn the shield on if it's off, off if it's on
+ *
+ * # Safety
+ * `bot` must come from rcg_connect.
+ */
+int32_t rcg_toggle_shield(struct RcgBot *bot);
+
+/**
+ * Switch between missile and ray
+ *
+ * # Safety
+ * `bot` must come from rcg_connect.
+ */
+int32_t rcg_change_weapon(struct RcgBot *bot);
+
+/**
+ * Put up a wall behind us, see WALL_LENGTH in the Rust SDK
+ *
+ * # Safety
+ * `bot` must come from rcg_connect.
+ */
+int32_t rcg_drop_wall(struct RcgBot *bot);
+
+/**
+ * Between rounds, tell the game we're ready for the next one
+ *
+ * # Safety
+ * `bot` must come from rcg_connect.
+ */
+int32_t rcg_ready(struct RcgBot *bot);
+
+/**
+ * After game over, ask for another match
+ *
+ * # Safety
+ * `bot` must come from rcg_connect.
+ */
+int32_t rcg_rematch(struct RcgBot *bot);
+
+/**
+ * Why the last call on this thread failed, or NULL. Valid until the next failure.
+ */
+const char *rcg_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RS_SDK_H */
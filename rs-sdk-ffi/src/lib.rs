@@ -0,0 +1,340 @@
+// C API for rs-sdk. See include/rs_sdk.h (generated) and examples/bot.c.
+//
+// Every function takes the RcgBot* from rcg_connect. Functions returning
+// int32_t return RCG_OK, RCG_DISCONNECTED when the game has gone away,
+// or RCG_ERROR, in which case rcg_last_error says why.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use rs_sdk::{BotIn, BotOut, Dir, Frame, GameState, Player, SDKError};
+
+pub const RCG_OK: i32 = 0;
+pub const RCG_DISCONNECTED: i32 = 1;
+pub const RCG_ERROR: i32 = -1;
+
+// Directions, for dir, fire and RcgEntity.dir
+pub const RCG_DIR_NONE: u8 = 0;
+pub const RCG_DIR_UP: u8 = 1;
+pub const RCG_DIR_DOWN: u8 = 2;
+pub const RCG_DIR_LEFT: u8 = 3;
+pub const RCG_DIR_RIGHT: u8 = 4;
+
+// RcgFrame.state
+pub const RCG_STATE_PLAYING: u8 = 0;
+pub const RCG_STATE_WAITING: u8 = 1;
+pub const RCG_STATE_GAME_OVER: u8 = 2;
+
+// RcgEntity.kind
+pub const RCG_KIND_PLAYER: u8 = 0;
+pub const RCG_KIND_MISSILE: u8 = 1;
+pub const RCG_KIND_RAY: u8 = 2;
+pub const RCG_KIND_OBSTACLE: u8 = 3;
+pub const RCG_KIND_EXPLOSION: u8 = 4;
+pub const RCG_KIND_PICKUP: u8 = 5;
+pub const RCG_KIND_MARKER: u8 = 6;
+pub const RCG_KIND_SUPPLY: u8 = 7;
+pub const RCG_KIND_TELEPORTER: u8 = 8;
+pub const RCG_KIND_BEAM: u8 = 9;
+pub const RCG_KIND_CLUSTER: u8 = 10;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A connection to the game. Opaque, free with rcg_free.
+pub struct RcgBot {
+    b_in: BotIn,
+    b_out: BotOut,
+    entities: Vec<RcgEntity>, // entities of the last frame, RcgFrame points here
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct RcgPlayerStatus {
+    pub lives: u8,
+    pub energy: u16,
+    /// 0 missile, 1 ray, 2 beam, 3 cluster
+    pub weapon: u8,
+    /// Ticks until there's energy to fire the weapon, 0 now, 65535 never
+    pub cooldown: u16,
+    /// Energy a raised shield takes every reload
+    pub shield_drain: u8,
+    /// Hits landed on the other player this match
+    pub points: u8,
+    /// The game keeps the opponent's energy and cooldown hidden, both are 0
+    pub energy_hidden: bool,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct RcgEntity {
+    pub id: u16,
+    /// RCG_KIND_*
+    pub kind: u8,
+    pub x: u32,
+    pub y: u32,
+    /// RCG_DIR_*
+    pub dir: u8,
+    pub speed: u8,
+    pub has_shield: bool,
+}
+
+/// One tick of the game. `entities` is owned by the RcgBot and is valid
+/// until the next rcg_next_frame or rcg_free.
+#[repr(C)]
+pub struct RcgFrame {
+    pub tick: u32,
+    /// RCG_STATE_*
+    pub state: u8,
+    pub players: [RcgPlayerStatus; 2],
+    pub num_entities: usize,
+    pub entities: *const RcgEntity,
+}
+
+/// Connect to the game as player 1 or 2. `token` is the game's shared secret,
+/// or NULL if it doesn't have one. Returns NULL on failure, see rcg_last_error.
+///
+/// # Safety
+/// `token` must be NULL or a valid nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_connect(player: u8, token: *const c_char) -> *mut RcgBot {
+    let p = match player {
+        1 => Player::One,
+        2 => Player::Two,
+        n => {
+            set_error(format!("player must be 1 or 2, got {}", n));
+            return ptr::null_mut();
+        }
+    };
+    let token = if token.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(token).to_str() {
+            Ok(t) => Some(t),
+            Err(_) => {
+                set_error("token is not valid UTF-8".to_string());
+                return ptr::null_mut();
+            }
+        }
+    };
+    match rs_sdk::connect(p, token) {
+        Ok((b_in, b_out)) => Box::into_raw(Box::new(RcgBot {
+            b_in,
+            b_out,
+            entities: Vec::new(),
+        })),
+        Err(e) => {
+            set_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Close the connection. `bot` may be NULL.
+///
+/// # Safety
+/// `bot` must come from rcg_connect and not already be freed.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_free(bot: *mut RcgBot) {
+    if !bot.is_null() {
+        drop(Box::from_raw(bot));
+    }
+}
+
+/// Milliseconds between frames while playing, as sent by the game when we connected
+///
+/// # Safety
+/// `bot` must come from rcg_connect.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_tick_ms(bot: *const RcgBot) -> u32 {
+    (*bot).b_in.tick_duration().as_millis() as u32
+}
+
+/// Block until the next frame, and fill in `out`. While playing this also
+/// tells the game the frame arrived, so it can show the bot's ping.
+///
+/// # Safety
+/// `bot` must come from rcg_connect, `out` must point to an RcgFrame.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_next_frame(bot: *mut RcgBot, out: *mut RcgFrame) -> i32 {
+    let bot = &mut *bot;
+    let f = match bot.b_in.next_frame() {
+        Ok(f) => f,
+        Err(e) => return error_code(e),
+    };
+    if f.game_state() == GameState::Playing {
+        if let Err(e) = bot.b_out.heartbeat(f.tick()) {
+            return error_code(e);
+        }
+    }
+    bot.entities = to_entities(&f);
+    *out = RcgFrame {
+        tick: f.tick(),
+        state: f.game_state().as_num(),
+        players: [to_status(&f, Player::One), to_status(&f, Player::Two)],
+        num_entities: bot.entities.len(),
+        entities: bot.entities.as_ptr(),
+    };
+    RCG_OK
+}
+
+/// Change direction, see RCG_DIR_*. Moving the opposite way to current direction stops.
+///
+/// # Safety
+/// `bot` must come from rcg_connect.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_dir(bot: *mut RcgBot, dir: u8) -> i32 {
+    with_dir(bot, dir, |b_out, d| b_out.dir(d))
+}
+
+/// Like rcg_dir, but the game applies it at `tick`
+///
+/// # Safety
+/// `bot` must come from rcg_connect.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_dir_at(bot: *mut RcgBot, dir: u8, tick: u32) -> i32 {
+    with_dir(bot, dir, |b_out, d| b_out.dir_at(d, tick))
+}
+
+/// Fire the current weapon in a direction
+///
+/// # Safety
+/// `bot` must come from rcg_connect.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_fire(bot: *mut RcgBot, dir: u8) -> i32 {
+    with_dir(bot, dir, |b_out, d| b_out.fire(d))
+}
+
+/// Like rcg_fire, but the game applies it at `tick`
+///
+/// # Safety
+/// `bot` must come from rcg_connect.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_fire_at(bot: *mut RcgBot, dir: u8, tick: u32) -> i32 {
+    with_dir(bot, dir, |b_out, d| b_out.fire_at(d, tick))
+}
+
+/// Turn the shield on if it's off, off if it's on
+///
+/// # Safety
+/// `bot` must come from rcg_connect.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_toggle_shield(bot: *mut RcgBot) -> i32 {
+    result_code((*bot).b_out.toggle_shield())
+}
+
+/// Switch between missile and ray
+///
+/// # Safety
+/// `bot` must come from rcg_connect.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_change_weapon(bot: *mut RcgBot) -> i32 {
+    result_code((*bot).b_out.change_weapon())
+}
+
+/// Put up a wall behind us, see WALL_LENGTH in the Rust SDK
+///
+/// # Safety
+/// `bot` must come from rcg_connect.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_drop_wall(bot: *mut RcgBot) -> i32 {
+    result_code((*bot).b_out.drop_wall())
+}
+
+/// Between rounds, tell the game we're ready for the next one
+///
+/// # Safety
+/// `bot` must come from rcg_connect.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_ready(bot: *mut RcgBot) -> i32 {
+    result_code((*bot).b_out.ready())
+}
+
+/// After game over, ask for another match
+///
+/// # Safety
+/// `bot` must come from rcg_connect.
+#[no_mangle]
+pub unsafe extern "C" fn rcg_rematch(bot: *mut RcgBot) -> i32 {
+    result_code((*bot).b_out.rematch())
+}
+
+/// Why the last call on this thread failed, or NULL. Valid until the next failure.
+#[no_mangle]
+pub extern "C" fn rcg_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| match &*e.borrow() {
+        Some(msg) => msg.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+unsafe fn with_dir(
+    bot: *mut RcgBot,
+    dir: u8,
+    f: impl FnOnce(&mut BotOut, Dir) -> Result<(), SDKError>,
+) -> i32 {
+    match Dir::from_num(dir) {
+        Some(d) => result_code(f(&mut (*bot).b_out, d)),
+        None => {
+            set_error(format!("invalid direction {}", dir));
+            RCG_ERROR
+        }
+    }
+}
+
+fn result_code(r: Result<(), SDKError>) -> i32 {
+    match r {
+        Ok(()) => RCG_OK,
+        Err(e) => error_code(e),
+    }
+}
+
+fn error_code(e: SDKError) -> i32 {
+    match e {
+        SDKError::Disconnected => RCG_DISCONNECTED,
+        e => {
+            set_error(e.to_string());
+            RCG_ERROR
+        }
+    }
+}
+
+fn set_error(msg: String) {
+    // our messages don't contain nul bytes, but don't panic across FFI if one does
+    let msg = CString::new(msg.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+}
+
+fn to_status(f: &Frame, p: Player) -> RcgPlayerStatus {
+    let s = f.player_status(p);
+    RcgPlayerStatus {
+        lives: s.lives(),
+        energy: s.energy(),
+        weapon: s.weapon().as_num(),
+        cooldown: s.cooldown(),
+        shield_drain: s.shield_drain(),
+        points: s.points(),
+        energy_hidden: s.is_energy_hidden(),
+    }
+}
+
+fn to_entities(f: &Frame) -> Vec<RcgEntity> {
+    f.entities()
+        .iter()
+        .map(|e| {
+            let (x, y) = e.pos();
+            RcgEntity {
+                id: e.id(),
+                kind: e.kind().as_num(),
+                x,
+                y,
+                dir: e.dir().as_num(),
+                speed: e.speed(),
+                has_shield: e.has_shield(),
+            }
+        })
+        .collect()
+}
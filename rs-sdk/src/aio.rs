@@ -0,0 +1,165 @@
+// Async version of the SDK, for bots living in a tokio runtime.
+// Enable with the "async" feature.
+
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio::net::TcpStream;
+
+use crate::transport::Endpoint;
+use crate::{
+    check_handshake_reply, command, entities_from_network, hello_msg, read_err, write_err, Dir,
+    Frame, FrameHeader, Map, Player, SDKError, ENTITY_LEN, FIRE, FRAME_HEADER_LEN, HEARTBEAT,
+    MAP_HEADER_LEN, MOVE, OBSTACLE_LEN, READY, REMATCH, SHIELD, WALL, WEAPON,
+};
+
+type Reader = Box<dyn AsyncRead + Unpin + Send>;
+type Writer = Box<dyn AsyncWrite + Unpin + Send>;
+
+pub struct BotIn {
+    sock_in: Reader,
+    map: Map,
+    tick_duration: Duration,
+    header: [u8; FRAME_HEADER_LEN],
+    body: Vec<u8>,
+}
+
+pub struct BotOut {
+    sock_out: Writer,
+}
+
+// Connect to the game as the given player. `token` is the game's shared
+// secret, if it was started with one.
+pub async fn connect(p: Player, token: Option<&str>) -> Result<(BotIn, BotOut), SDKError> {
+    let sp = p.endpoint();
+    let connect_err = |source| SDKError::Connect {
+        endpoint: sp.to_string(),
+        source,
+    };
+    let (mut sock_in, mut sock_out): (Reader, Writer) = match &sp {
+        #[cfg(unix)]
+        Endpoint::Unix(path) => {
+            let (r, w) = UnixStream::connect(path).await.map_err(connect_err)?.into_split();
+            (Box::new(r), Box::new(w))
+        }
+        Endpoint::Tcp(addr) => {
+            let s = TcpStream::connect(addr.as_str()).await.map_err(connect_err)?;
+            s.set_nodelay(true)?;
+            let (r, w) = s.into_split();
+            (Box::new(r), Box::new(w))
+        }
+    };
+
+    sock_out.write_all(&hello_msg(token.unwrap_or(""))?).await?;
+    let mut reply = [0u8; 2];
+    sock_in.read_exact(&mut reply).await.map_err(read_err)?;
+    check_handshake_reply(reply)?;
+
+    let mut tick_ms = [0u8; 4];
+    sock_in.read_exact(&mut tick_ms).await?;
+    let tick_duration = Duration::from_millis(u32::from_be_bytes(tick_ms) as u64);
+
+    let mut map_header = [0u8; MAP_HEADER_LEN];
+    sock_in.read_exact(&mut map_header).await?;
+    let (mut map, num_obstacles) = Map::from_network_header(&map_header);
+    let mut obstacles = vec![0u8; num_obstacles * OBSTACLE_LEN];
+    sock_in.read_exact(&mut obstacles).await?;
+    map.add_obstacles(&obstacles);
+
+    let b_in = BotIn {
+        sock_in,
+        map,
+        tick_duration,
+        header: [0u8; FRAME_HEADER_LEN],
+        body: Vec::new(),
+    };
+    Ok((b_in, BotOut { sock_out }))
+}
+
+impl BotIn {
+    // The arena, as sent when we connected
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    // How often the game sends a frame while playing
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    // Read all the entities for the next tick
+    pub async fn next_frame(&mut self) -> Result<Frame, SDKError> {
+        read_exact(&mut self.sock_in, &mut self.header).await?;
+        let h = FrameHeader::from_network(&self.header)?;
+        self.body.resize(h.num_entities as usize * ENTITY_LEN, 0);
+        read_exact(&mut self.sock_in, &mut self.body).await?;
+        Ok(Frame {
+            tick: h.tick,
+            state: h.state,
+            players: h.players,
+            entities: entities_from_network(h.tick, &self.body)?,
+        })
+    }
+}
+
+impl BotOut {
+    // Set bot direction
+    pub async fn dir(&mut self, d: Dir) -> Result<(), SDKError> {
+        self.dir_at(d, 0).await
+    }
+
+    // Set bot direction, starting at the given tick or later
+    pub async fn dir_at(&mut self, d: Dir, tick: u32) -> Result<(), SDKError> {
+        self.send_cmd(command(MOVE, d, tick)).await
+    }
+
+    // Fire in a direction
+    pub async fn fire(&mut self, d: Dir) -> Result<(), SDKError> {
+        self.fire_at(d, 0).await
+    }
+
+    // Fire in a direction, at the given tick or later
+    pub async fn fire_at(&mut self, d: Dir, tick: u32) -> Result<(), SDKError> {
+        self.send_cmd(command(FIRE, d, tick)).await
+    }
+
+    // Turn our shield on if it's off, off if it's on
+    pub async fn toggle_shield(&mut self) -> Result<(), SDKError> {
+        self.send_cmd(command(SHIELD, Dir::None, 0)).await
+    }
+
+    // Put up a wall behind us, see WALL_LENGTH
+    pub async fn drop_wall(&mut self) -> Result<(), SDKError> {
+        self.send_cmd(command(WALL, Dir::None, 0)).await
+    }
+
+    // Switch between missile and ray
+    pub async fn change_weapon(&mut self) -> Result<(), SDKError> {
+        self.send_cmd(command(WEAPON, Dir::None, 0)).await
+    }
+
+    // Tell the game we are ready for the next round to start
+    pub async fn ready(&mut self) -> Result<(), SDKError> {
+        self.send_cmd(command(READY, Dir::None, 0)).await
+    }
+
+    // Ask for another match once this one is over
+    pub async fn rematch(&mut self) -> Result<(), SDKError> {
+        self.send_cmd(command(REMATCH, Dir::None, 0)).await
+    }
+
+    // Tell the game we've received the frame for tick, so it can show our ping
+    pub async fn heartbeat(&mut self, tick: u32) -> Result<(), SDKError> {
+        self.send_cmd(command(HEARTBEAT, Dir::None, tick)).await
+    }
+
+    async fn send_cmd(&mut self, cmd: [u8; 8]) -> Result<(), SDKError> {
+        self.sock_out.write_all(&cmd).await.map_err(write_err)
+    }
+}
+
+async fn read_exact(sock: &mut Reader, buf: &mut [u8]) -> Result<(), SDKError> {
+    sock.read_exact(buf).await.map(|_| ()).map_err(read_err)
+}
@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::transport::Endpoint;
+use crate::{handshake, BotIn, BotOut, Player, SDKError};
+
+// Wait this long between connection attempts
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+// Connect to the game with more control than `connect`:
+//
+//   Connector::new(Player::One).tcp("host:7777").retries(5).connect()
+pub struct Connector {
+    player: Player,
+    endpoint: Endpoint,
+    token: Option<String>,
+    retries: u32,
+    timeout: Option<Duration>,
+    record: Option<PathBuf>,
+}
+
+impl Connector {
+    // Defaults to the player's usual endpoint, no token, one attempt, no timeout
+    pub fn new(player: Player) -> Connector {
+        Connector {
+            player,
+            endpoint: player.endpoint(),
+            token: None,
+            retries: 0,
+            timeout: None,
+            record: None,
+        }
+    }
+
+    // Connect over a unix socket at this path
+    #[cfg(unix)]
+    pub fn path(mut self, path: &str) -> Connector {
+        self.endpoint = Endpoint::Unix(path.into());
+        self
+    }
+
+    // Connect over TCP to host:port
+    pub fn tcp(mut self, addr: &str) -> Connector {
+        self.endpoint = Endpoint::Tcp(addr.to_string());
+        self
+    }
+
+    // Connect to any endpoint, e.g. a testing::MockServer
+    pub fn endpoint(mut self, endpoint: Endpoint) -> Connector {
+        self.endpoint = endpoint;
+        self
+    }
+
+    // The game's shared secret, if it was started with one
+    pub fn token(mut self, token: &str) -> Connector {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    // If we can't connect, try again this many more times.
+    // Useful if the bot might start before the game.
+    pub fn retries(mut self, n: u32) -> Connector {
+        self.retries = n;
+        self
+    }
+
+    // Give up on each connection attempt and the handshake after this long
+    pub fn timeout(mut self, dur: Duration) -> Connector {
+        self.timeout = Some(dur);
+        self
+    }
+
+    // Record everything the game sends to this file, see BotIn::record_to
+    pub fn record_to(mut self, path: impl AsRef<Path>) -> Connector {
+        self.record = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn connect(self) -> Result<(BotIn, BotOut), SDKError> {
+        let mut attempt = 0;
+        loop {
+            match self.try_connect() {
+                Ok(conn) => return Ok(conn),
+                Err(e) if attempt >= self.retries => return Err(e),
+                Err(_) => {
+                    attempt += 1;
+                    thread::sleep(RETRY_DELAY);
+                }
+            }
+        }
+    }
+
+    fn try_connect(&self) -> Result<(BotIn, BotOut), SDKError> {
+        let conn = match self.timeout {
+            Some(t) => self.endpoint.connect_timeout(t),
+            None => self.endpoint.connect(),
+        };
+        let mut sock_out = conn.map_err(|source| SDKError::Connect {
+            endpoint: format!("{} as {:?}", self.endpoint, self.player),
+            source,
+        })?;
+        sock_out.set_read_timeout(self.timeout)?;
+        let (map, tick_duration) = handshake(&mut sock_out, self.token.as_deref().unwrap_or(""))?;
+        sock_out.set_read_timeout(None)?;
+        let sock_in = sock_out.try_clone()?;
+        let mut b_in = BotIn::new(sock_in, map, tick_duration);
+        if let Some(path) = &self.record {
+            b_in.record_to(path)?;
+        }
+        Ok((b_in, BotOut { sock_out }))
+    }
+}
@@ -12,8 +12,9 @@ pub enum Dir {
 const DIRS: [Dir; 5] = [Dir::None, Dir::Up, Dir::Down, Dir::Left, Dir::Right];
 
 impl Dir {
-    pub fn from_num(n: u8) -> Dir {
-        DIRS[n as usize]
+    // None if n isn't one of ours, see as_num
+    pub fn from_num(n: u8) -> Option<Dir> {
+        DIRS.get(n as usize).copied()
     }
 
     pub fn opposite(&self) -> Dir {
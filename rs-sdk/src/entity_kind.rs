@@ -0,0 +1,72 @@
+use std::fmt::{Display, Formatter};
+
+// What sort of thing an entity is
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum EntityKind {
+    Player,
+    Missile,
+    Ray,
+    Obstacle,
+    Explosion, // a missile which has exploded
+    Pickup,    // a weapon upgrade, collected by moving onto it
+    Marker,    // where a supply drop is about to land, crushing anyone on it
+    Supply,    // a supply drop, collected like a pickup. Its tier says what's in it, see SUPPLY_ENERGY
+    Teleporter, // in a corner. Moving onto one comes out of the opposite corner.
+    Beam,       // a ray from a super weapon, through obstacles and shields
+    Cluster,    // a missile which splits into four missiles when it explodes, see CLUSTER_FLIGHT
+}
+
+const KINDS: [EntityKind; 11] = [
+    EntityKind::Player,
+    EntityKind::Missile,
+    EntityKind::Ray,
+    EntityKind::Obstacle,
+    EntityKind::Explosion,
+    EntityKind::Pickup,
+    EntityKind::Marker,
+    EntityKind::Supply,
+    EntityKind::Teleporter,
+    EntityKind::Beam,
+    EntityKind::Cluster,
+];
+
+impl EntityKind {
+    // None if n isn't one of ours, see as_num
+    pub fn from_num(n: u8) -> Option<EntityKind> {
+        KINDS.get(n as usize).copied()
+    }
+
+    pub fn as_num(&self) -> u8 {
+        match self {
+            EntityKind::Player => 0,
+            EntityKind::Missile => 1,
+            EntityKind::Ray => 2,
+            EntityKind::Obstacle => 3,
+            EntityKind::Explosion => 4,
+            EntityKind::Pickup => 5,
+            EntityKind::Marker => 6,
+            EntityKind::Supply => 7,
+            EntityKind::Teleporter => 8,
+            EntityKind::Beam => 9,
+            EntityKind::Cluster => 10,
+        }
+    }
+}
+
+impl Display for EntityKind {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match *self {
+            EntityKind::Player => write!(f, "Player"),
+            EntityKind::Missile => write!(f, "Missile"),
+            EntityKind::Ray => write!(f, "Ray"),
+            EntityKind::Obstacle => write!(f, "Obstacle"),
+            EntityKind::Explosion => write!(f, "Explosion"),
+            EntityKind::Pickup => write!(f, "Pickup"),
+            EntityKind::Marker => write!(f, "Marker"),
+            EntityKind::Supply => write!(f, "Supply"),
+            EntityKind::Teleporter => write!(f, "Teleporter"),
+            EntityKind::Beam => write!(f, "Beam"),
+            EntityKind::Cluster => write!(f, "Cluster"),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use std::io;
+
+use thiserror::Error;
+
+// Everything that can go wrong talking to the game
+#[derive(Debug, Error)]
+pub enum SDKError {
+    // The game closed the connection, usually because it exited
+    #[error("game disconnected")]
+    Disconnected,
+
+    #[error("couldn't connect to {endpoint}: {source}")]
+    Connect { endpoint: String, source: io::Error },
+
+    // The game sent something we didn't understand, or we tried to send something invalid
+    #[error("protocol error: expected {expected}, got {got}")]
+    Protocol { expected: String, got: String },
+
+    #[error("protocol version mismatch. ours {ours}, game's {theirs}")]
+    HandshakeVersionMismatch { ours: u8, theirs: u8 },
+
+    #[error("game rejected our token. Check {}", crate::TOKEN_ENV)]
+    BadToken,
+
+    #[error("io: {0}")]
+    Io(#[from] io::Error),
+}
@@ -0,0 +1,89 @@
+use crate::{
+    entities_from_network, EntityKind, EntityState, FrameHeader, GameState, Player, PlayerStatus,
+    SDKError, ENTITY_LEN, FRAME_HEADER_LEN,
+};
+
+// Everything the game sent in one tick
+#[derive(Debug)]
+pub struct Frame {
+    pub(crate) tick: u32,
+    pub(crate) state: GameState,
+    pub(crate) players: [PlayerStatus; 2],
+    pub(crate) entities: Vec<EntityState>,
+}
+
+impl Frame {
+    // players is [player one, player two]
+    pub fn new(
+        tick: u32,
+        state: GameState,
+        players: [PlayerStatus; 2],
+        mut entities: Vec<EntityState>,
+    ) -> Frame {
+        for e in entities.iter_mut() {
+            e.tick = tick;
+        }
+        Frame {
+            tick,
+            state,
+            players,
+            entities,
+        }
+    }
+
+    // Parse one whole frame as the game sends it: the header, then exactly as
+    // many entities as it says. Anything else is an SDKError::Protocol.
+    pub fn from_network(msg: &[u8]) -> Result<Frame, SDKError> {
+        let (header, body) = msg.split_first_chunk::<FRAME_HEADER_LEN>().ok_or_else(|| {
+            SDKError::Protocol {
+                expected: "frame header".to_string(),
+                got: format!("{} bytes", msg.len()),
+            }
+        })?;
+        let h = FrameHeader::from_network(header)?;
+        if body.len() != h.num_entities as usize * ENTITY_LEN {
+            return Err(SDKError::Protocol {
+                expected: format!("{} entities", h.num_entities),
+                got: format!("{} bytes of them", body.len()),
+            });
+        }
+        Ok(Frame {
+            tick: h.tick,
+            state: h.state,
+            players: h.players,
+            entities: entities_from_network(h.tick, body)?,
+        })
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    pub fn game_state(&self) -> GameState {
+        self.state
+    }
+
+    // Lives, energy and weapon of a player
+    pub fn player_status(&self, p: Player) -> PlayerStatus {
+        self.players[p.id() as usize]
+    }
+
+    pub fn entities(&self) -> &[EntityState] {
+        &self.entities
+    }
+
+    // Position, direction, etc of a player
+    pub fn player(&self, p: Player) -> Option<&EntityState> {
+        self.entities.iter().find(|e| e.is_player(p))
+    }
+
+    // All the entities of one kind, e.g. every Ray
+    pub fn entities_of(&self, kind: EntityKind) -> impl Iterator<Item = &EntityState> {
+        self.entities.iter().filter(move |e| e.kind == kind)
+    }
+
+    // Missiles in flight. Exploding ones are not included.
+    pub fn missiles(&self) -> impl Iterator<Item = &EntityState> {
+        self.entities_of(EntityKind::Missile)
+    }
+}
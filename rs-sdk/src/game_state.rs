@@ -0,0 +1,36 @@
+use std::fmt::{Display, Formatter};
+
+// What the game is doing, sent in every frame header
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GameState {
+    Playing,
+    Waiting,  // between rounds, waiting for players to be Ready
+    GameOver, // match finished, waiting for a Rematch
+}
+
+const STATES: [GameState; 3] = [GameState::Playing, GameState::Waiting, GameState::GameOver];
+
+impl GameState {
+    // None if n isn't one of ours, see as_num
+    pub fn from_num(n: u8) -> Option<GameState> {
+        STATES.get(n as usize).copied()
+    }
+
+    pub fn as_num(&self) -> u8 {
+        match self {
+            GameState::Playing => 0,
+            GameState::Waiting => 1,
+            GameState::GameOver => 2,
+        }
+    }
+}
+
+impl Display for GameState {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match *self {
+            GameState::Playing => write!(f, "Playing"),
+            GameState::Waiting => write!(f, "Waiting"),
+            GameState::GameOver => write!(f, "GameOver"),
+        }
+    }
+}
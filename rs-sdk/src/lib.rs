@@ -1,35 +1,110 @@
-use std::convert::TryInto;
-use std::error;
-use std::fmt;
+use std::fs::File;
 use std::io::ErrorKind;
-use std::io::{Read, Write};
-use std::os::unix::net;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+mod connector;
+pub use connector::Connector;
 
 mod dir;
 pub use dir::Dir;
 
+mod error;
+pub use error::SDKError;
+
+mod entity_kind;
+pub use entity_kind::EntityKind;
+
+mod frame;
+pub use frame::Frame;
+
+mod game_state;
+pub use game_state::GameState;
+
+mod map;
+pub use map::Map;
+use map::{MAP_HEADER_LEN, OBSTACLE_LEN};
+
+mod predict;
+pub use predict::{danger_zone, explosion_area};
+
+mod rules;
+pub use rules::*;
+
+mod runner;
+pub use runner::{run_bot, run_bot_with, Actions, Bot, Event, Intent};
+
+#[cfg(feature = "async")]
+pub mod aio;
+
+mod weapon;
+pub use weapon::Weapon;
+
+pub mod replay;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+pub mod transport;
+use transport::{Endpoint, Stream};
+
 pub const SOCK_NAME_1: &str = "/tmp/rust-console-game-p1.sock";
 pub const SOCK_NAME_2: &str = "/tmp/rust-console-game-p2.sock";
 
+// Where we listen on platforms without unix sockets
+pub const TCP_ADDR_1: &str = "127.0.0.1:7771";
+pub const TCP_ADDR_2: &str = "127.0.0.1:7772";
+
+// Environment variable holding the shared secret, if the game requires one
+pub const TOKEN_ENV: &str = "RUST_CONSOLE_GAME_TOKEN";
+
+// Handshake
+// Client sends: version(u8) token_len(u8) token(token_len bytes)
+// Server replies: version(u8) status(u8)
+// and if status is OK, the time between ticks: tick_ms(u32), then the map:
+//   min_x(u32) min_y(u32) max_x(u32) max_y(u32) num_obstacles(u16) then x(u32) y(u32) per obstacle
+// Must match game/src/server.rs handshake
+pub const PROTOCOL_VERSION: u8 = 16;
+pub const HANDSHAKE_OK: u8 = 0;
+pub const HANDSHAKE_BAD_VERSION: u8 = 1;
+pub const HANDSHAKE_BAD_TOKEN: u8 = 2;
+
 // must match the order in which players are added in game/src/lib.rs
 const PLAYER_1_ID: u8 = 0;
 const PLAYER_2_ID: u8 = 1;
 
 // Commands
 // Must match game/src/server.rs into_input_event
+// Each is 8 bytes: cmd(u8) dir(u8) unused(2 bytes) apply_at_tick(u32)
 const MOVE: u8 = 1;
 const FIRE: u8 = 2;
+const SHIELD: u8 = 3;
+const WEAPON: u8 = 4;
+const READY: u8 = 5;
+const REMATCH: u8 = 6;
+const HEARTBEAT: u8 = 7; // apply_at_tick is the frame's tick, see BotOut::heartbeat
+const WALL: u8 = 8;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Player {
     One,
     Two,
 }
 impl Player {
-    fn sock_path(&self) -> &'static str {
+    // Where the game listens for this player's bot by default
+    #[cfg(unix)]
+    pub fn endpoint(&self) -> Endpoint {
+        match self {
+            Player::One => Endpoint::Unix(SOCK_NAME_1.into()),
+            Player::Two => Endpoint::Unix(SOCK_NAME_2.into()),
+        }
+    }
+    #[cfg(not(unix))]
+    pub fn endpoint(&self) -> Endpoint {
         match self {
-            Player::One => SOCK_NAME_1,
-            Player::Two => SOCK_NAME_2,
+            Player::One => Endpoint::Tcp(TCP_ADDR_1.to_string()),
+            Player::Two => Endpoint::Tcp(TCP_ADDR_2.to_string()),
         }
     }
     fn id(&self) -> u8 {
@@ -41,131 +116,584 @@ impl Player {
 }
 
 pub struct BotIn {
-    sock_in: net::UnixStream,
-    buf: [u8; 12], // protocol is units of 12 bytes
+    sock_in: Stream,
+    map: Map,
+    tick_duration: Duration, // time between ticks, as sent in the handshake
+    // each frame starts with tick(u32) state(u8) player_status(9 bytes) x 2 num_entities(u16)
+    header: [u8; FRAME_HEADER_LEN],
+    buf: [u8; ENTITY_LEN], // followed by num_entities units of 15 bytes
+    tick: u32,     // tick of the frame currently being read
+    state: GameState,
+    players: [PlayerStatus; 2],
+    remaining: u16, // entities left to read in current frame
+    recording: Option<BufWriter<File>>,
 }
 
 pub struct BotOut {
-    move_cmd: Vec<u8>,
-    fire_cmd: Vec<u8>,
-    sock_out: net::UnixStream,
-}
-
-pub fn connect(p: Player) -> Result<(BotIn, BotOut), anyhow::Error> {
-    let sp = p.sock_path();
-    let sock_out = match net::UnixStream::connect(sp) {
-        Ok(s) => s,
-        Err(e) => anyhow::bail!("Couldn't connecto to {}. {}", sp, e),
-    };
-    let sock_in = sock_out.try_clone()?;
-    let b_in = BotIn {
-        sock_in,
-        buf: [0u8; 12],
-    };
-    let b_out = BotOut {
-        sock_out,
-        move_cmd: vec![MOVE, 99, 0, 0, 0, 0, 0, 0],
-        fire_cmd: vec![FIRE, 99, 0, 0, 0, 0, 0, 0],
-    };
-    Ok((b_in, b_out))
+    sock_out: Stream,
+}
+
+// One player's connection to the game, as returned by connect
+pub type Connection = (BotIn, BotOut);
+
+// Connect to the game as the given player. `token` is the game's shared
+// secret, if it was started with one.
+// See Connector for other endpoints, retries and timeouts.
+pub fn connect(p: Player, token: Option<&str>) -> Result<(BotIn, BotOut), SDKError> {
+    let c = Connector::new(p);
+    match token {
+        Some(t) => c.token(t).connect(),
+        None => c.connect(),
+    }
+}
+
+// Connect as both players, for self-play or scripted matches in one process.
+// The two connections share nothing, so each half can be moved to its own thread.
+pub fn connect_both(token: Option<&str>) -> Result<(Connection, Connection), SDKError> {
+    let p1 = connect(Player::One, token)?;
+    let p2 = connect(Player::Two, token)?;
+    Ok((p1, p2))
+}
+
+// Returns the map and the time between ticks
+fn handshake(sock: &mut Stream, token: &str) -> Result<(Map, Duration), SDKError> {
+    sock.write_all(&hello_msg(token)?)?;
+    let mut reply = [0u8; 2];
+    sock.read_exact(&mut reply)?;
+    check_handshake_reply(reply)?;
+
+    let mut tick_ms = [0u8; 4];
+    sock.read_exact(&mut tick_ms)?;
+    let tick_duration = Duration::from_millis(u32::from_be_bytes(tick_ms) as u64);
+
+    let mut map_header = [0u8; MAP_HEADER_LEN];
+    sock.read_exact(&mut map_header)?;
+    let (mut map, num_obstacles) = Map::from_network_header(&map_header);
+    let mut obstacles = vec![0u8; num_obstacles * OBSTACLE_LEN];
+    sock.read_exact(&mut obstacles)?;
+    map.add_obstacles(&obstacles);
+    Ok((map, tick_duration))
+}
+
+// The client half of the handshake
+fn hello_msg(token: &str) -> Result<Vec<u8>, SDKError> {
+    if token.len() > u8::MAX as usize {
+        return Err(SDKError::Protocol {
+            expected: format!("token up to {} bytes", u8::MAX),
+            got: format!("{} bytes", token.len()),
+        });
+    }
+    let mut hello = Vec::with_capacity(2 + token.len());
+    hello.push(PROTOCOL_VERSION);
+    hello.push(token.len() as u8);
+    hello.extend_from_slice(token.as_bytes());
+    Ok(hello)
+}
+
+fn check_handshake_reply(reply: [u8; 2]) -> Result<(), SDKError> {
+    match reply[1] {
+        HANDSHAKE_OK => Ok(()),
+        HANDSHAKE_BAD_VERSION => Err(SDKError::HandshakeVersionMismatch {
+            ours: PROTOCOL_VERSION,
+            theirs: reply[0],
+        }),
+        HANDSHAKE_BAD_TOKEN => Err(SDKError::BadToken),
+        n => Err(SDKError::Protocol {
+            expected: "handshake status".to_string(),
+            got: n.to_string(),
+        }),
+    }
 }
 
 impl BotOut {
     // Set bot direction
-    pub fn dir(&mut self, d: Dir) -> Result<(), anyhow::Error> {
-        self.move_cmd[1] = d.as_num();
-        //self.send_cmd(&self.move_cmd)
-        match self.sock_out.write_all(&self.move_cmd) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(anyhow::anyhow!("socket write err: {}", e)),
-        }
+    pub fn dir(&mut self, d: Dir) -> Result<(), SDKError> {
+        self.dir_at(d, 0)
+    }
+
+    // Set bot direction, starting at the given tick or later
+    pub fn dir_at(&mut self, d: Dir, tick: u32) -> Result<(), SDKError> {
+        self.send_cmd(command(MOVE, d, tick))
     }
 
     // Fire in a direction
-    pub fn fire(&mut self, d: Dir) -> Result<(), anyhow::Error> {
-        self.fire_cmd[1] = d.as_num();
-        match self.sock_out.write_all(&self.fire_cmd) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(anyhow::anyhow!("socket write err: {}", e)),
-        }
+    pub fn fire(&mut self, d: Dir) -> Result<(), SDKError> {
+        self.fire_at(d, 0)
+    }
+
+    // Fire in a direction, at the given tick or later
+    pub fn fire_at(&mut self, d: Dir, tick: u32) -> Result<(), SDKError> {
+        self.send_cmd(command(FIRE, d, tick))
+    }
+
+    // Turn our shield on if it's off, off if it's on.
+    // A shield uses energy, and turns itself off when we run out.
+    pub fn toggle_shield(&mut self) -> Result<(), SDKError> {
+        self.send_cmd(command(SHIELD, Dir::None, 0))
+    }
+
+    // Switch between missile and ray
+    pub fn change_weapon(&mut self) -> Result<(), SDKError> {
+        self.send_cmd(command(WEAPON, Dir::None, 0))
+    }
+
+    // Put up a wall behind us, see WALL_LENGTH
+    pub fn drop_wall(&mut self) -> Result<(), SDKError> {
+        self.send_cmd(command(WALL, Dir::None, 0))
+    }
+
+    // Tell the game we are ready for the next round to start.
+    // The round starts once both players are ready.
+    pub fn ready(&mut self) -> Result<(), SDKError> {
+        self.send_cmd(command(READY, Dir::None, 0))
+    }
+
+    // Ask for another match once this one is over.
+    // A new match starts if both players ask.
+    pub fn rematch(&mut self) -> Result<(), SDKError> {
+        self.send_cmd(command(REMATCH, Dir::None, 0))
     }
 
-    //fn send_cmd(&mut self, cmd: &[u8]) -> Result<(), anyhow::Error> {
-    //    match self.sock_out.write(cmd) {
-    //        Ok(_) => Ok(()),
-    //        Err(e) => Err(anyhow::anyhow!("socket write err: {}", e)),
-    //    }
-    //}
+    // Tell the game we've received the frame for tick. It times how long
+    // that took to come back, and shows it as the bot's ping. run_bot sends
+    // one for every frame it's given while playing.
+    pub fn heartbeat(&mut self, tick: u32) -> Result<(), SDKError> {
+        self.send_cmd(command(HEARTBEAT, Dir::None, tick))
+    }
+
+    fn send_cmd(&mut self, cmd: [u8; 8]) -> Result<(), SDKError> {
+        self.sock_out.write_all(&cmd).map_err(write_err)
+    }
+}
+
+// A command as sent over the network
+fn command(cmd: u8, d: Dir, tick: u32) -> [u8; 8] {
+    let mut msg = [cmd, d.as_num(), 0, 0, 0, 0, 0, 0];
+    msg[4..8].copy_from_slice(&tick.to_be_bytes());
+    msg
 }
 
 impl BotIn {
+    fn new(sock_in: Stream, map: Map, tick_duration: Duration) -> BotIn {
+        BotIn {
+            sock_in,
+            map,
+            tick_duration,
+            header: [0u8; FRAME_HEADER_LEN],
+            buf: [0u8; ENTITY_LEN],
+            tick: 0,
+            state: GameState::Waiting,
+            players: [PlayerStatus::default(); 2],
+            remaining: 0,
+            recording: None,
+        }
+    }
+
+    // From now on write everything the game sends us to a file, for replaying
+    // offline with replay::Reader. The map is written first.
+    pub fn record_to(&mut self, path: impl AsRef<Path>) -> Result<(), SDKError> {
+        self.recording = Some(replay::create(path.as_ref(), &self.map, self.tick_duration)?);
+        Ok(())
+    }
+
+    // Read a single entity. Prefer next_frame.
     pub fn get_next_entity(&mut self) -> Result<EntityState, SDKError> {
-        if let Err(e) = self.sock_in.read_exact(&mut self.buf) {
-            match e.kind() {
-                ErrorKind::UnexpectedEof => {
-                    // remote closed connection
-                    return Err(SDKError::Stop);
-                }
-                _ => {
-                    return Err(SDKError::Misc(format!("bot read_exact: {}", e)));
-                }
+        while self.remaining == 0 {
+            self.read_header()?;
+        }
+        read_exact(&mut self.sock_in, &mut self.buf)?;
+        self.remaining -= 1;
+        if let Some(r) = &mut self.recording {
+            r.write_all(&self.buf)?;
+            if self.remaining == 0 {
+                r.flush()?; // end of frame
             }
         }
-        Ok(EntityState::from_network(&self.buf))
+        EntityState::from_network(self.tick, &self.buf)
+    }
+
+    // Read all the entities for the next tick.
+    // If get_next_entity was used part way through a frame, the rest of that frame is skipped.
+    pub fn next_frame(&mut self) -> Result<Frame, SDKError> {
+        while self.remaining > 0 {
+            self.get_next_entity()?;
+        }
+        read_exact(&mut self.sock_in, &mut self.header)?;
+        self.read_frame()
+    }
+
+    // Like next_frame but returns Ok(None) if no frame starts arriving within timeout.
+    // Once a frame has started we wait for all of it.
+    pub fn try_next_frame(&mut self, timeout: Duration) -> Result<Option<Frame>, SDKError> {
+        while self.remaining > 0 {
+            self.get_next_entity()?;
+        }
+        // a zero read timeout is an error, so treat it as "check once, quickly"
+        let timeout = timeout.max(Duration::from_millis(1));
+        self.sock_in.set_read_timeout(Some(timeout))?;
+        let first = self.sock_in.read(&mut self.header);
+        self.sock_in.set_read_timeout(None)?;
+        let n = match first {
+            Ok(0) => return Err(SDKError::Disconnected),
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        read_exact(&mut self.sock_in, &mut self.header[n..])?;
+        self.read_frame().map(Some)
+    }
+
+    // Read the entities following the header that's already in self.header
+    fn read_frame(&mut self) -> Result<Frame, SDKError> {
+        self.parse_header()?;
+        let mut entities = Vec::with_capacity(self.remaining as usize);
+        while self.remaining > 0 {
+            entities.push(self.get_next_entity()?);
+        }
+        Ok(Frame {
+            tick: self.tick,
+            state: self.state,
+            players: self.players,
+            entities,
+        })
+    }
+
+    fn read_header(&mut self) -> Result<(), SDKError> {
+        read_exact(&mut self.sock_in, &mut self.header)?;
+        self.parse_header()
+    }
+
+    fn parse_header(&mut self) -> Result<(), SDKError> {
+        let h = FrameHeader::from_network(&self.header)?;
+        self.tick = h.tick;
+        self.state = h.state;
+        self.players = h.players;
+        self.remaining = h.num_entities;
+        if let Some(r) = &mut self.recording {
+            r.write_all(&self.header)?;
+            if self.remaining == 0 {
+                r.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    // Tick number of the most recent frame. Increases by one every frame while
+    // playing, so a jump means we missed some. Repeats while waiting between rounds.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    // The arena, as sent when we connected
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    // How often the game sends a frame while playing
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    // What the game was doing in the most recent frame
+    pub fn game_state(&self) -> GameState {
+        self.state
+    }
+
+    // Lives, energy, weapon and cooldown of a player as of the most recent frame
+    pub fn player_status(&self, p: Player) -> PlayerStatus {
+        self.players[p.id() as usize]
     }
 }
 
-#[derive(Debug)]
-pub enum SDKError {
-    Stop,
-    Misc(String),
+const FRAME_HEADER_LEN: usize = 25;
+const PLAYER_STATUS_LEN: usize = 9;
+const ENTITY_LEN: usize = 15;
+
+struct FrameHeader {
+    tick: u32,
+    state: GameState,
+    players: [PlayerStatus; 2],
+    num_entities: u16,
 }
 
-impl error::Error for SDKError {}
-impl fmt::Display for SDKError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            SDKError::Stop => write!(f, "stop"),
-            SDKError::Misc(msg) => write!(f, "{}", msg),
+impl FrameHeader {
+    // tick(u32) state(u8) player_status(9 bytes) x 2 num_entities(u16)
+    fn from_network(msg: &[u8; FRAME_HEADER_LEN]) -> Result<FrameHeader, SDKError> {
+        let tick = u32::from_be_bytes([msg[0], msg[1], msg[2], msg[3]]);
+        let status = |at: usize| {
+            let mut s = [0u8; PLAYER_STATUS_LEN];
+            s.copy_from_slice(&msg[at..at + PLAYER_STATUS_LEN]);
+            PlayerStatus::from_network(s)
+        };
+        Ok(FrameHeader {
+            tick,
+            state: GameState::from_num(msg[4]).ok_or_else(|| unknown("game state", msg[4]))?,
+            players: [status(5)?, status(5 + PLAYER_STATUS_LEN)?],
+            num_entities: u16::from_be_bytes([msg[23], msg[24]]),
+        })
+    }
+}
+
+// Entities back to back, as they follow a frame header
+fn entities_from_network(tick: u32, msg: &[u8]) -> Result<Vec<EntityState>, SDKError> {
+    let (entities, rest) = msg.as_chunks::<ENTITY_LEN>();
+    if !rest.is_empty() {
+        return Err(SDKError::Protocol {
+            expected: format!("entities of {} bytes", ENTITY_LEN),
+            got: format!("{} bytes", msg.len()),
+        });
+    }
+    entities.iter().map(|e| EntityState::from_network(tick, e)).collect()
+}
+
+// For a byte the game sent that should be one of our enums, and isn't
+fn unknown(what: &str, n: u8) -> SDKError {
+    SDKError::Protocol {
+        expected: what.to_string(),
+        got: n.to_string(),
+    }
+}
+
+fn read_exact(sock: &mut Stream, buf: &mut [u8]) -> Result<(), SDKError> {
+    sock.read_exact(buf).map_err(read_err)
+}
+
+// The game closing the socket isn't really an error
+fn read_err(e: std::io::Error) -> SDKError {
+    match e.kind() {
+        ErrorKind::UnexpectedEof => SDKError::Disconnected,
+        _ => SDKError::Io(e),
+    }
+}
+
+fn write_err(e: std::io::Error) -> SDKError {
+    match e.kind() {
+        ErrorKind::BrokenPipe | ErrorKind::ConnectionReset => SDKError::Disconnected,
+        _ => SDKError::Io(e),
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerStatus {
+    lives: u8,
+    energy: u16,
+    weapon: Weapon,
+    cooldown: u16,
+    shield_drain: u8,
+    points: u8,
+    energy_hidden: bool,
+}
+impl Default for PlayerStatus {
+    fn default() -> PlayerStatus {
+        PlayerStatus {
+            lives: 0,
+            energy: 0,
+            weapon: Weapon::Missile,
+            cooldown: 0,
+            shield_drain: ENERGY_SHIELD as u8,
+            points: 0,
+            energy_hidden: false,
         }
     }
 }
+impl PlayerStatus {
+    pub fn new(lives: u8, energy: u16, weapon: Weapon) -> PlayerStatus {
+        PlayerStatus {
+            lives,
+            energy,
+            weapon,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_cooldown(mut self, ticks: u16) -> PlayerStatus {
+        self.cooldown = ticks;
+        self
+    }
+
+    pub fn with_shield_drain(mut self, energy: u8) -> PlayerStatus {
+        self.shield_drain = energy;
+        self
+    }
+
+    pub fn with_points(mut self, hits: u8) -> PlayerStatus {
+        self.points = hits;
+        self
+    }
+
+    pub fn with_energy_hidden(mut self, hidden: bool) -> PlayerStatus {
+        self.energy_hidden = hidden;
+        self
+    }
+
+    // msg is lives(u8) energy(u16) weapon(u8) cooldown(u16) shield_drain(u8) points(u8)
+    // energy_hidden(u8)
+    fn from_network(msg: [u8; PLAYER_STATUS_LEN]) -> Result<PlayerStatus, SDKError> {
+        Ok(PlayerStatus {
+            lives: msg[0],
+            energy: u16::from_be_bytes([msg[1], msg[2]]),
+            weapon: Weapon::from_num(msg[3]).ok_or_else(|| unknown("weapon", msg[3]))?,
+            cooldown: u16::from_be_bytes([msg[4], msg[5]]),
+            shield_drain: msg[6],
+            points: msg[7],
+            energy_hidden: msg[8] == 1,
+        })
+    }
+
+    pub fn lives(&self) -> u8 {
+        self.lives
+    }
+
+    pub fn energy(&self) -> u16 {
+        self.energy
+    }
+
+    // The game is set to keep this player's energy from their opponent,
+    // `opponent_energy = false` in its config. Energy and cooldown are both
+    // 0, and mean nothing. Your own are always sent.
+    pub fn is_energy_hidden(&self) -> bool {
+        self.energy_hidden
+    }
+
+    pub fn weapon(&self) -> Weapon {
+        self.weapon
+    }
+
+    // Ticks until a frame shows enough energy to fire the current weapon,
+    // 0 if this one does. Counts the reloads to come and what a raised
+    // shield takes from them. u16::MAX if it never will, say a ray with the
+    // energy system turned off.
+    pub fn cooldown(&self) -> u16 {
+        self.cooldown
+    }
+
+    // Energy a raised shield takes every reload, whether or not it's up now.
+    // ENERGY_SHIELD unless the game's config changes it.
+    pub fn shield_drain(&self) -> u8 {
+        self.shield_drain
+    }
+
+    // Hits this player has landed on the other this match, whatever the
+    // mode. In points mode it's their score.
+    pub fn points(&self) -> u8 {
+        self.points
+    }
+}
 
-#[allow(dead_code)]
 #[derive(Debug)]
 pub struct EntityState {
-    id: u8,
+    tick: u32,
+    id: u16,
     x: u32,
     y: u32,
     dir: Dir,
     velocity: u8,
     has_shield: bool,
+    kind: EntityKind,
+    tier: u8,
 }
 impl EntityState {
-    fn from_network(msg: &[u8]) -> EntityState {
-        //println!("GOT: {:?}", msg);
-        let mut e = EntityState {
-            id: msg[0],
-            dir: Dir::from_num(msg[9]),
-            velocity: msg[10],
-            has_shield: msg[11] == 1,
-            x: 0,
-            y: 0,
-        };
-        // bytes 1..5 (not inclusive) are x position as u32
-        let (x_bytes, rest) = msg[1..].split_at(4);
-        e.x = u32::from_be_bytes(x_bytes.try_into().unwrap());
-        let (y_bytes, _) = rest.split_at(4); // next 4 bytes are y position as u32
-        e.y = u32::from_be_bytes(y_bytes.try_into().unwrap());
+    // Mostly useful for building frames in tests. Tick is set by Frame::new.
+    pub fn new(id: u16, kind: EntityKind, pos: (u32, u32), dir: Dir, speed: u8) -> EntityState {
+        EntityState {
+            tick: 0,
+            id,
+            x: pos.0,
+            y: pos.1,
+            dir,
+            velocity: speed,
+            has_shield: false,
+            kind,
+            tier: 1,
+        }
+    }
+
+    pub fn with_shield(mut self, on: bool) -> EntityState {
+        self.has_shield = on;
+        self
+    }
+
+    pub fn with_tier(mut self, tier: u8) -> EntityState {
+        self.tier = tier;
+        self
+    }
 
-        e
+    // id(u16) x(u32) y(u32) dir(u8) velocity(u8) shield(u8) kind(u8) tier(u8)
+    fn from_network(tick: u32, msg: &[u8; ENTITY_LEN]) -> Result<EntityState, SDKError> {
+        Ok(EntityState {
+            tick,
+            id: u16::from_be_bytes([msg[0], msg[1]]),
+            x: u32::from_be_bytes([msg[2], msg[3], msg[4], msg[5]]),
+            y: u32::from_be_bytes([msg[6], msg[7], msg[8], msg[9]]),
+            dir: Dir::from_num(msg[10]).ok_or_else(|| unknown("direction", msg[10]))?,
+            velocity: msg[11],
+            has_shield: msg[12] == 1,
+            kind: EntityKind::from_num(msg[13]).ok_or_else(|| unknown("entity kind", msg[13]))?,
+            tier: msg[14],
+        })
     }
 
     pub fn is_player(&self, p: Player) -> bool {
-        self.id == p.id()
+        self.id == u16::from(p.id())
+    }
+
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    // Player, missile, obstacle, etc
+    pub fn kind(&self) -> EntityKind {
+        self.kind
     }
 
     pub fn pos(&self) -> (u32, u32) {
         (self.x, self.y)
     }
+
+    // Which way it's facing. A player with speed 0 still has a direction.
+    pub fn dir(&self) -> Dir {
+        self.dir
+    }
+
+    // Squares moved per tick
+    pub fn speed(&self) -> u8 {
+        self.velocity
+    }
+
+    pub fn has_shield(&self) -> bool {
+        self.has_shield
+    }
+
+    // Weapon tier, 1 to MAX_TIER. A player's goes up with each pickup, a
+    // missile or explosion has the tier it was fired at. See rules.rs.
+    pub fn tier(&self) -> u8 {
+        self.tier
+    }
+
+    // Number of moves (no diagonals) to get from here to other
+    pub fn distance_to(&self, other: &EntityState) -> u32 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    // Is this entity moving, and will its next move bring it closer to target (x, y)?
+    pub fn is_moving_toward(&self, target: (u32, u32)) -> bool {
+        if self.velocity == 0 {
+            return false;
+        }
+        let (x, y) = target;
+        match self.dir {
+            Dir::Up => y < self.y,
+            Dir::Down => y > self.y,
+            Dir::Left => x < self.x,
+            Dir::Right => x > self.x,
+            Dir::None => false,
+        }
+    }
+
+    // Tick of the frame this state was sent in
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
 }
@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+use std::convert::TryInto;
+
+// The arena, sent once when we connect
+#[derive(Clone, Debug, Default)]
+pub struct Map {
+    min_x: u32, // playable area, inclusive
+    min_y: u32,
+    max_x: u32, // exclusive
+    max_y: u32,
+    obstacles: HashSet<(u32, u32)>,
+}
+
+pub(crate) const MAP_HEADER_LEN: usize = 18;
+pub(crate) const OBSTACLE_LEN: usize = 8;
+
+impl Map {
+    // top_left is inclusive, bottom_right exclusive, same as bounds()
+    pub fn new(top_left: (u32, u32), bottom_right: (u32, u32), obstacles: &[(u32, u32)]) -> Map {
+        Map {
+            min_x: top_left.0,
+            min_y: top_left.1,
+            max_x: bottom_right.0,
+            max_y: bottom_right.1,
+            obstacles: obstacles.iter().copied().collect(),
+        }
+    }
+
+    // header is min_x(u32) min_y(u32) max_x(u32) max_y(u32) num_obstacles(u16)
+    // returns the map without obstacles, and how many obstacles follow
+    pub(crate) fn from_network_header(msg: &[u8; MAP_HEADER_LEN]) -> (Map, usize) {
+        let n = |i: usize| u32::from_be_bytes(msg[i..i + 4].try_into().unwrap());
+        let m = Map {
+            min_x: n(0),
+            min_y: n(4),
+            max_x: n(8),
+            max_y: n(12),
+            obstacles: HashSet::new(),
+        };
+        (m, u16::from_be_bytes([msg[16], msg[17]]) as usize)
+    }
+
+    // msg is num_obstacles units of x(u32) y(u32)
+    pub(crate) fn add_obstacles(&mut self, msg: &[u8]) {
+        for o in msg.chunks_exact(OBSTACLE_LEN) {
+            let x = u32::from_be_bytes(o[0..4].try_into().unwrap());
+            let y = u32::from_be_bytes(o[4..8].try_into().unwrap());
+            self.obstacles.insert((x, y));
+        }
+    }
+
+    // Same layout as the game sends, see from_network_header and add_obstacles
+    pub(crate) fn to_network(&self) -> Vec<u8> {
+        let mut m = Vec::with_capacity(MAP_HEADER_LEN + self.obstacles.len() * OBSTACLE_LEN);
+        for n in [self.min_x, self.min_y, self.max_x, self.max_y].iter() {
+            m.extend_from_slice(&n.to_be_bytes());
+        }
+        m.extend_from_slice(&(self.obstacles.len() as u16).to_be_bytes());
+        for (x, y) in self.obstacles.iter() {
+            m.extend_from_slice(&x.to_be_bytes());
+            m.extend_from_slice(&y.to_be_bytes());
+        }
+        m
+    }
+
+    // Is (x, y) inside the walls?
+    pub fn in_bounds(&self, x: u32, y: u32) -> bool {
+        self.min_x <= x && x < self.max_x && self.min_y <= y && y < self.max_y
+    }
+
+    // Can't move to (x, y), either a wall or an obstacle
+    pub fn is_blocked(&self, x: u32, y: u32) -> bool {
+        !self.in_bounds(x, y) || self.obstacles.contains(&(x, y))
+    }
+
+    // Top left and bottom right corners of the playable area.
+    // Bottom right is one past the last valid square.
+    pub fn bounds(&self) -> ((u32, u32), (u32, u32)) {
+        ((self.min_x, self.min_y), (self.max_x, self.max_y))
+    }
+
+    pub fn obstacles(&self) -> impl Iterator<Item = &(u32, u32)> {
+        self.obstacles.iter()
+    }
+}
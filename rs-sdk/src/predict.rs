@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+
+use crate::{explosion_radius, Dir, EntityKind, EntityState, Map};
+
+// Squares a missile, ray, beam or explosion may cover during the next `ticks` ticks,
+// and a supply drop's marker, which it will land on. Other kinds of entity
+// return an empty set.
+// The blast is as big as the missile's tier makes it, see explosion_radius.
+//
+// A missile's age isn't sent, so we can't know where it will explode. We
+// assume the worst: it might explode anywhere along its path.
+pub fn danger_zone(e: &EntityState, map: &Map, ticks: u32) -> HashSet<(u32, u32)> {
+    let mut zone = HashSet::new();
+    match e.kind() {
+        // the cluster's four missiles are in the frame once it splits
+        EntityKind::Missile | EntityKind::Cluster => {
+            let radius = explosion_radius(e.tier());
+            let mut p = e.pos();
+            zone.insert(p);
+            zone.extend(explosion_area(p, radius, map));
+            // the second half of the missile is one ahead
+            if let Some(head) = step(p, e.dir(), map) {
+                zone.insert(head);
+            }
+            'ticks: for _ in 0..ticks {
+                for _ in 0..e.speed() {
+                    p = match step(p, e.dir(), map) {
+                        Some(next) => next,
+                        None => break 'ticks, // hit a wall, missile is gone
+                    };
+                    zone.insert(p);
+                }
+                zone.extend(explosion_area(p, radius, map));
+            }
+        }
+        EntityKind::Ray => {
+            // a ray already reaches from where it was fired to the wall,
+            // and then moves along that line
+            let mut p = e.pos();
+            zone.insert(p);
+            while let Some(next) = step(p, e.dir(), map) {
+                zone.insert(next);
+                p = next;
+            }
+        }
+        EntityKind::Beam => {
+            // the whole line, obstacles or not, until it fades
+            let ((min_x, min_y), (max_x, max_y)) = map.bounds();
+            let (x, y) = e.pos();
+            match e.dir() {
+                Dir::Up | Dir::Down => zone.extend((min_y..max_y).map(|y| (x, y))),
+                Dir::Left | Dir::Right => zone.extend((min_x..max_x).map(|x| (x, y))),
+                Dir::None => {
+                    zone.insert((x, y));
+                }
+            }
+        }
+        EntityKind::Explosion => zone.extend(explosion_area(e.pos(), explosion_radius(e.tier()), map)),
+        EntityKind::Marker => {
+            zone.insert(e.pos());
+        }
+        EntityKind::Player | EntityKind::Obstacle | EntityKind::Pickup | EntityKind::Supply | EntityKind::Teleporter => {}
+    }
+    zone
+}
+
+// Squares covered by an explosion of `radius` centered on `center`.
+// Explosions don't go through walls or obstacles. The center is first.
+pub fn explosion_area(center: (u32, u32), radius: u32, map: &Map) -> Vec<(u32, u32)> {
+    let r = radius as i64;
+    let mut v = vec![center];
+    for x in center.0 as i64 - r..=center.0 as i64 + r {
+        for y in center.1 as i64 - r..=center.1 as i64 + r {
+            if x < 0 || y < 0 || (x as u32, y as u32) == center {
+                continue;
+            }
+            if !map.is_blocked(x as u32, y as u32) {
+                v.push((x as u32, y as u32));
+            }
+        }
+    }
+    v
+}
+
+// One square in direction d, or None if that's a wall or obstacle
+fn step(p: (u32, u32), d: Dir, map: &Map) -> Option<(u32, u32)> {
+    let next = match d {
+        Dir::Up => (p.0, p.1.checked_sub(1)?),
+        Dir::Down => (p.0, p.1 + 1),
+        Dir::Left => (p.0.checked_sub(1)?, p.1),
+        Dir::Right => (p.0 + 1, p.1),
+        Dir::None => return None,
+    };
+    if map.is_blocked(next.0, next.1) {
+        None
+    } else {
+        Some(next)
+    }
+}
@@ -0,0 +1,120 @@
+// Recordings of exactly what a bot received, made by BotIn::record_to.
+// Feed the frames back into your bot to debug a decision it made in a live match.
+//
+// File format is a header: magic "RCGB", PROTOCOL_VERSION(u8), then tick_ms(u32)
+// and the map as sent in the handshake, followed by every frame as sent by the game.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::map::{MAP_HEADER_LEN, OBSTACLE_LEN};
+use crate::{
+    EntityState, Frame, FrameHeader, Map, SDKError, ENTITY_LEN, FRAME_HEADER_LEN, PROTOCOL_VERSION,
+};
+
+const MAGIC: &[u8; 4] = b"RCGB";
+
+// Frames haven't changed since this version, which widened entity ids to u16
+const OLDEST_VERSION: u8 = 16;
+
+// Start a recording, BotIn appends the frames
+pub(crate) fn create(
+    path: &Path,
+    map: &Map,
+    tick_duration: Duration,
+) -> io::Result<BufWriter<File>> {
+    let mut w = BufWriter::new(File::create(path)?);
+    w.write_all(MAGIC)?;
+    w.write_all(&[PROTOCOL_VERSION])?;
+    w.write_all(&(tick_duration.as_millis() as u32).to_be_bytes())?;
+    w.write_all(&map.to_network())?;
+    w.flush()?;
+    Ok(w)
+}
+
+pub struct Reader {
+    r: BufReader<File>,
+    map: Map,
+    tick_duration: Duration,
+}
+
+impl Reader {
+    pub fn open(path: impl AsRef<Path>) -> Result<Reader, SDKError> {
+        let mut r = BufReader::new(File::open(path)?);
+        let mut header = [0u8; 5];
+        r.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(SDKError::Protocol {
+                expected: "bot recording".to_string(),
+                got: format!("file starting {:?}", &header[..4]),
+            });
+        }
+        if !(OLDEST_VERSION..=PROTOCOL_VERSION).contains(&header[4]) {
+            return Err(SDKError::HandshakeVersionMismatch {
+                ours: PROTOCOL_VERSION,
+                theirs: header[4],
+            });
+        }
+
+        let mut tick_ms = [0u8; 4];
+        r.read_exact(&mut tick_ms)?;
+        let tick_duration = Duration::from_millis(u32::from_be_bytes(tick_ms) as u64);
+
+        let mut map_header = [0u8; MAP_HEADER_LEN];
+        r.read_exact(&mut map_header)?;
+        let (mut map, num_obstacles) = Map::from_network_header(&map_header);
+        let mut obstacles = vec![0u8; num_obstacles * OBSTACLE_LEN];
+        r.read_exact(&mut obstacles)?;
+        map.add_obstacles(&obstacles);
+        Ok(Reader {
+            r,
+            map,
+            tick_duration,
+        })
+    }
+
+    // The arena, as it was when recording started
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    // Time between ticks in the recorded match
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    // The next recorded frame, or None at the end of the recording
+    pub fn next_frame(&mut self) -> Result<Option<Frame>, SDKError> {
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        match self.r.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let h = FrameHeader::from_network(&header)?;
+
+        let mut entities = Vec::with_capacity(h.num_entities as usize);
+        let mut buf = [0u8; ENTITY_LEN];
+        for _ in 0..h.num_entities {
+            if let Err(e) = self.r.read_exact(&mut buf) {
+                // the bot probably died mid-write
+                return Err(match e.kind() {
+                    ErrorKind::UnexpectedEof => SDKError::Protocol {
+                        expected: format!("{} entities in tick {}", h.num_entities, h.tick),
+                        got: "end of file".to_string(),
+                    },
+                    _ => e.into(),
+                });
+            }
+            entities.push(EntityState::from_network(h.tick, &buf)?);
+        }
+        Ok(Some(Frame {
+            tick: h.tick,
+            state: h.state,
+            players: h.players,
+            entities,
+        }))
+    }
+}
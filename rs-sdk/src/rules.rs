@@ -0,0 +1,54 @@
+// Game rules bots may want to plan with. The game uses these too, so they can't drift.
+
+pub const MAX_ENERGY: u32 = 100;
+pub const ENERGY_MISSILE: u32 = 3; // cost to fire, you need more than this
+pub const ENERGY_RAY: u32 = 25;
+pub const ENERGY_CLUSTER: u32 = 15;
+pub const ENERGY_WALL: u32 = 20;
+pub const ENERGY_SHIELD: u32 = 3; // deducted every ENERGY_EVERY ticks while shielded
+pub const ENERGY_EVERY: u32 = 5; // gain one energy every this many ticks
+pub const ENERGY_PUSH: u32 = 10; // taken from both when shielded players bump into each other
+
+pub const PUSH_DISTANCE: u32 = 2; // squares shielded players bounce back when they bump
+
+// A player can put up a wall this many squares long across the square behind
+// them, the way they last moved. It's an obstacle like any other until it
+// goes, a few seconds later. Bots see it in frames as an EntityKind::Obstacle,
+// it isn't in the Map.
+pub const WALL_LENGTH: u32 = 3;
+
+pub const MISSILE_SPEED: u8 = 2; // squares per tick
+pub const EXPLOSION_RADIUS: u32 = 2; // explosions are a square this far each side of the center
+
+// A cluster missile explodes like a missile, then four missiles fly out of the
+// blast, one each way, for this many ticks before they explode too
+pub const CLUSTER_FLIGHT: u32 = 2;
+
+// Weapon tiers. Players start a round at tier 1, each pickup collected is one
+// more, up to MAX_TIER. Tier 2 missiles are faster and go further, tier 3
+// ones also have a bigger blast.
+pub const MAX_TIER: u8 = 3;
+
+pub fn missile_speed(tier: u8) -> u8 {
+    if tier >= 2 {
+        MISSILE_SPEED + 1
+    } else {
+        MISSILE_SPEED
+    }
+}
+
+// What a weapon pickup gives, sent as its tier
+pub const PICKUP_TIER: u8 = 1; // one weapon tier up
+pub const PICKUP_BEAM: u8 = 2; // a Weapon::Beam, for one shot
+
+// What's in a supply drop, sent as its tier
+pub const SUPPLY_ENERGY: u8 = 1; // fills energy up to MAX_ENERGY
+pub const SUPPLY_LIFE: u8 = 2; // an extra life
+
+pub fn explosion_radius(tier: u8) -> u32 {
+    if tier >= 3 {
+        EXPLOSION_RADIUS + 1
+    } else {
+        EXPLOSION_RADIUS
+    }
+}
@@ -0,0 +1,181 @@
+use std::env;
+use std::time::Duration;
+
+use crate::{BotOut, Connector, Dir, Frame, GameState, Map, Player, SDKError, TOKEN_ENV};
+
+// Implement this and pass it to run_bot to write a bot
+pub trait Bot {
+    // Called once when we connect, with the arena layout
+    fn on_connect(&mut self, _map: &Map) {}
+
+    // Called for every frame the game sends, about every 50ms.
+    // Queue up what you want to do in `out`, it is sent when you return.
+    fn on_frame(&mut self, frame: &Frame, out: &mut Actions);
+
+    // Called when the game changes state, before on_frame for that frame
+    fn on_event(&mut self, _event: Event) {}
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Event {
+    RoundStart,
+    RoundOver, // someone got hit, waiting for next round
+    MatchOver, // someone is out of lives
+}
+
+// Commands a bot wants to send this frame
+#[derive(Default)]
+pub struct Actions {
+    dir: Option<Dir>,
+    fire: Option<Dir>,
+    toggle_shield: bool,
+    change_weapon: bool,
+    drop_wall: bool,
+    tick: u32,               // of the frame being handled
+    tick_duration: Duration, // game time between frames
+}
+
+// Everything a bot asked for in one frame, see Actions::take.
+// Apply them in field order, so a weapon change happens before firing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Intent {
+    pub dir: Option<Dir>,
+    pub toggle_shield: bool,
+    pub change_weapon: bool,
+    pub drop_wall: bool,
+    pub fire: Option<Dir>,
+}
+
+impl Actions {
+    pub fn new(tick_duration: Duration) -> Actions {
+        Actions {
+            tick_duration,
+            ..Default::default()
+        }
+    }
+
+    // Change direction. Moving the opposite way to current direction stops.
+    pub fn dir(&mut self, d: Dir) {
+        self.dir = Some(d);
+    }
+
+    pub fn fire(&mut self, d: Dir) {
+        self.fire = Some(d);
+    }
+
+    // Shield on if it's off, off if it's on
+    pub fn toggle_shield(&mut self) {
+        self.toggle_shield = true;
+    }
+
+    // Switch between missile and ray. Happens before firing.
+    pub fn change_weapon(&mut self) {
+        self.change_weapon = true;
+    }
+
+    // A wall behind us, see WALL_LENGTH. Happens before firing.
+    pub fn drop_wall(&mut self) {
+        self.drop_wall = true;
+    }
+
+    // True once every n ticks of game time, so a bot can pace itself
+    // without sleeping. e.g. `if out.every_n_ticks(4) { out.fire(d) }`
+    pub fn every_n_ticks(&self, n: u32) -> bool {
+        self.tick.is_multiple_of(n)
+    }
+
+    // Tick of the frame being handled
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    // Game time between ticks, as sent by the game when we connected
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    // Called before on_frame by whatever is running the bot
+    pub fn set_tick(&mut self, tick: u32) {
+        self.tick = tick;
+    }
+
+    // What the bot asked for this frame, clearing it.
+    // For running a bot without a connection, e.g. in a headless game.
+    pub fn take(&mut self) -> Intent {
+        Intent {
+            dir: self.dir.take(),
+            fire: self.fire.take(),
+            toggle_shield: std::mem::take(&mut self.toggle_shield),
+            change_weapon: std::mem::take(&mut self.change_weapon),
+            drop_wall: std::mem::take(&mut self.drop_wall),
+        }
+    }
+
+    fn send(&mut self, b_out: &mut BotOut) -> Result<(), SDKError> {
+        let i = self.take();
+        if let Some(d) = i.dir {
+            b_out.dir(d)?;
+        }
+        if i.toggle_shield {
+            b_out.toggle_shield()?;
+        }
+        if i.change_weapon {
+            b_out.change_weapon()?;
+        }
+        if i.drop_wall {
+            b_out.drop_wall()?;
+        }
+        if let Some(d) = i.fire {
+            b_out.fire(d)?;
+        }
+        Ok(())
+    }
+}
+
+// Connect as player p and drive `bot` until the game exits.
+// Between rounds we tell the game we're Ready, and after a match ask for a Rematch.
+// Uses the token in env var TOKEN_ENV if set.
+pub fn run_bot(p: Player, bot: impl Bot) -> Result<(), SDKError> {
+    let c = Connector::new(p);
+    match env::var(TOKEN_ENV) {
+        Ok(token) => run_bot_with(c.token(&token), bot),
+        Err(_) => run_bot_with(c, bot),
+    }
+}
+
+// Like run_bot, but connect with the given Connector
+pub fn run_bot_with(c: Connector, mut bot: impl Bot) -> Result<(), SDKError> {
+    let (mut b_in, mut b_out) = c.connect()?;
+    bot.on_connect(b_in.map());
+    let mut actions = Actions::new(b_in.tick_duration());
+    let mut last_state = GameState::Waiting;
+    loop {
+        let frame = match b_in.next_frame() {
+            Ok(f) => f,
+            Err(SDKError::Disconnected) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let state = frame.game_state();
+        if state != last_state {
+            let ev = match state {
+                GameState::Playing => Event::RoundStart,
+                GameState::Waiting => Event::RoundOver,
+                GameState::GameOver => Event::MatchOver,
+            };
+            bot.on_event(ev);
+            last_state = state;
+        }
+        match state {
+            GameState::Playing => {
+                // before the bot thinks, so the game times only the connection
+                b_out.heartbeat(frame.tick())?;
+                actions.set_tick(frame.tick());
+                bot.on_frame(&frame, &mut actions);
+                actions.send(&mut b_out)?;
+            }
+            GameState::Waiting => b_out.ready()?,
+            GameState::GameOver => b_out.rematch()?,
+        }
+    }
+}
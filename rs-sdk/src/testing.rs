@@ -0,0 +1,272 @@
+// A fake game for unit testing bots. It accepts one connection, does the
+// handshake, sends the frames you push, and records the commands the bot sends.
+//
+//   let mut server = MockServer::start(map)?;
+//   server.push_frame(frame);
+//   server.hang_up();
+//   run_bot_with(server.connector(Player::One), my_bot)?; // returns when frames run out
+//   assert_eq!(server.finish()?, vec![Command::Ready, Command::Fire(Dir::Up, 0)]);
+
+#[cfg(unix)]
+use std::env;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+#[cfg(unix)]
+use std::process;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::transport::{Endpoint, Listener};
+use crate::{
+    Connector, Dir, Frame, Map, Player, FIRE, HANDSHAKE_OK, HEARTBEAT, MOVE, PROTOCOL_VERSION, READY,
+    REMATCH, SHIELD, WALL, WEAPON,
+};
+
+// Time between ticks we tell the bot, same as the game
+const TICK_MS: u32 = 50;
+
+#[cfg(unix)]
+static NEXT_SOCK: AtomicUsize = AtomicUsize::new(0);
+
+// A command received from the bot. The u32 is the tick it should apply at, 0 for now.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Move(Dir, u32),
+    Fire(Dir, u32),
+    ToggleShield,
+    ChangeWeapon,
+    DropWall,
+    Ready,
+    Rematch,
+    Heartbeat(u32), // the frame's tick
+    Unknown(u8),
+}
+
+impl Command {
+    // cmd(u8) dir(u8) unused(2 bytes) apply_at_tick(u32)
+    fn from_network(msg: [u8; 8]) -> Command {
+        let tick = u32::from_be_bytes([msg[4], msg[5], msg[6], msg[7]]);
+        match (msg[0], Dir::from_num(msg[1])) {
+            (MOVE, Some(d)) => Command::Move(d, tick),
+            (FIRE, Some(d)) => Command::Fire(d, tick),
+            (SHIELD, _) => Command::ToggleShield,
+            (WEAPON, _) => Command::ChangeWeapon,
+            (WALL, _) => Command::DropWall,
+            (READY, _) => Command::Ready,
+            (REMATCH, _) => Command::Rematch,
+            (HEARTBEAT, _) => Command::Heartbeat(tick),
+            (n, _) => Command::Unknown(n), // including a move or fire with a bad direction
+        }
+    }
+}
+
+pub struct MockServer {
+    endpoint: Endpoint,
+    frames: Option<Sender<Frame>>,
+    commands: Arc<Mutex<Vec<Command>>>,
+    handle: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl MockServer {
+    // Listen on a fresh temporary endpoint. Bots that connect get this map.
+    pub fn start(map: Map) -> io::Result<MockServer> {
+        let (endpoint, listener) = temp_listener()?;
+        let (tx, rx) = mpsc::channel();
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let cmds = commands.clone();
+        let handle = thread::spawn(move || serve(listener, &map, rx, cmds));
+        Ok(MockServer {
+            endpoint,
+            frames: Some(tx),
+            commands,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    // A Connector pointed at this server
+    pub fn connector(&self, p: Player) -> Connector {
+        Connector::new(p).endpoint(self.endpoint.clone())
+    }
+
+    // Queue a frame. Frames are sent in order once a bot connects.
+    pub fn push_frame(&self, frame: Frame) {
+        if let Some(tx) = &self.frames {
+            // Only fails if the server thread died, finish() will report why
+            let _ = tx.send(frame);
+        }
+    }
+
+    // No more frames. Once the queued ones are sent we close the connection,
+    // which makes run_bot return.
+    pub fn hang_up(&mut self) {
+        self.frames = None;
+    }
+
+    // Commands received so far
+    pub fn commands(&self) -> Vec<Command> {
+        self.commands.lock().unwrap().clone()
+    }
+
+    // Send the remaining frames, hang up, and wait for the bot to close its end.
+    // Returns every command the bot sent.
+    pub fn finish(mut self) -> io::Result<Vec<Command>> {
+        self.hang_up();
+        if let Some(h) = self.handle.take() {
+            h.join()
+                .map_err(|_| io::Error::other("mock server thread panicked"))??;
+        }
+        Ok(self.commands())
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Endpoint::Unix(path) = &self.endpoint {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn temp_listener() -> io::Result<(Endpoint, Listener)> {
+    let name = format!(
+        "rs-sdk-mock-{}-{}.sock",
+        process::id(),
+        NEXT_SOCK.fetch_add(1, Ordering::Relaxed)
+    );
+    let endpoint = Endpoint::Unix(env::temp_dir().join(name));
+    let listener = endpoint.listen()?;
+    Ok((endpoint, listener))
+}
+
+#[cfg(not(unix))]
+fn temp_listener() -> io::Result<(Endpoint, Listener)> {
+    let l = std::net::TcpListener::bind("127.0.0.1:0")?; // any free port
+    let endpoint = Endpoint::Tcp(l.local_addr()?.to_string());
+    Ok((endpoint, Listener::Tcp(l)))
+}
+
+fn serve(
+    listener: Listener,
+    map: &Map,
+    frames: Receiver<Frame>,
+    commands: Arc<Mutex<Vec<Command>>>,
+) -> io::Result<()> {
+    let (mut sock, _) = listener.accept()?;
+
+    // hello is version(u8) token_len(u8) token. We accept any version and token.
+    let mut hello = [0u8; 2];
+    sock.read_exact(&mut hello)?;
+    let mut token = vec![0u8; hello[1] as usize];
+    sock.read_exact(&mut token)?;
+    sock.write_all(&[PROTOCOL_VERSION, HANDSHAKE_OK])?;
+    sock.write_all(&TICK_MS.to_be_bytes())?;
+    sock.write_all(&map.to_network())?;
+
+    let mut sock_in = sock.try_clone()?;
+    let reader = thread::spawn(move || {
+        let mut cmd = [0u8; 8];
+        while sock_in.read_exact(&mut cmd).is_ok() {
+            commands.lock().unwrap().push(Command::from_network(cmd));
+        }
+    });
+
+    // ends when finish() drops the sender
+    for f in frames {
+        if sock.write_all(&frame_to_network(&f)).is_err() {
+            break; // bot went away
+        }
+    }
+    // Only close our side, so we still get any last commands
+    let _ = sock.shutdown(Shutdown::Write);
+    let _ = reader.join();
+    Ok(())
+}
+
+// Same layout the game sends, see FrameHeader and EntityState::from_network
+fn frame_to_network(f: &Frame) -> Vec<u8> {
+    let mut m = Vec::new();
+    m.extend_from_slice(&f.tick.to_be_bytes());
+    m.push(f.state.as_num());
+    for p in f.players.iter() {
+        m.push(p.lives);
+        m.extend_from_slice(&p.energy.to_be_bytes());
+        m.push(p.weapon.as_num());
+        m.extend_from_slice(&p.cooldown.to_be_bytes());
+        m.push(p.shield_drain);
+        m.push(p.points);
+        m.push(if p.energy_hidden { 1 } else { 0 });
+    }
+    m.extend_from_slice(&(f.entities.len() as u16).to_be_bytes());
+    for e in f.entities.iter() {
+        m.extend_from_slice(&e.id.to_be_bytes());
+        m.extend_from_slice(&e.x.to_be_bytes());
+        m.extend_from_slice(&e.y.to_be_bytes());
+        m.push(e.dir.as_num());
+        m.push(e.velocity);
+        m.push(if e.has_shield { 1 } else { 0 });
+        m.push(e.kind.as_num());
+        m.push(e.tier);
+    }
+    m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{run_bot_with, Actions, Bot, EntityKind, EntityState, GameState, PlayerStatus, Weapon};
+
+    // Fires up every frame it plays, once it's seen the map
+    #[derive(Default)]
+    struct Gunner {
+        map: Option<Map>,
+    }
+
+    impl Bot for Gunner {
+        fn on_connect(&mut self, map: &Map) {
+            self.map = Some(map.clone());
+        }
+
+        fn on_frame(&mut self, _frame: &Frame, out: &mut Actions) {
+            if self.map.is_some() {
+                out.fire(Dir::Up);
+            }
+        }
+    }
+
+    fn frame(tick: u32, state: GameState) -> Frame {
+        let status = PlayerStatus::new(3, 50, Weapon::Missile);
+        let me = EntityState::new(0, EntityKind::Player, (5, 5), Dir::None, 0);
+        Frame::new(tick, state, [status, status], vec![me])
+    }
+
+    #[test]
+    fn a_bot_gets_the_frames_and_its_commands_are_recorded() {
+        let mut server = MockServer::start(Map::new((1, 1), (20, 10), &[(3, 3)])).unwrap();
+        server.push_frame(frame(1, GameState::Waiting));
+        server.push_frame(frame(2, GameState::Playing));
+        server.push_frame(frame(3, GameState::Playing));
+        server.push_frame(frame(4, GameState::GameOver));
+        server.hang_up();
+        run_bot_with(server.connector(Player::One), Gunner::default()).unwrap();
+        assert_eq!(
+            server.finish().unwrap(),
+            [
+                Command::Ready,
+                Command::Heartbeat(2),
+                Command::Fire(Dir::Up, 0),
+                Command::Heartbeat(3),
+                Command::Fire(Dir::Up, 0),
+                Command::Rematch,
+            ]
+        );
+    }
+}
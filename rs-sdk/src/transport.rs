@@ -0,0 +1,194 @@
+// The connection between game and bot. Unix domain sockets where we have them,
+// TCP otherwise (Windows), and TCP anywhere if asked for.
+
+use std::fmt::{Display, Formatter};
+#[cfg(unix)]
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::time::Duration;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
+
+// Where the game listens for a player's bot
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Endpoint {
+    #[cfg(unix)]
+    Unix(PathBuf),
+    Tcp(String), // host:port
+}
+
+impl Display for Endpoint {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            #[cfg(unix)]
+            Endpoint::Unix(path) => write!(f, "{}", path.display()),
+            Endpoint::Tcp(addr) => write!(f, "tcp://{}", addr),
+        }
+    }
+}
+
+// The reverse of Display: "tcp://host:port", or else a unix socket path
+impl FromStr for Endpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Endpoint, String> {
+        if let Some(addr) = s.strip_prefix("tcp://") {
+            return Ok(Endpoint::Tcp(addr.to_string()));
+        }
+        #[cfg(unix)]
+        {
+            Ok(Endpoint::Unix(s.into()))
+        }
+        #[cfg(not(unix))]
+        {
+            Err(format!("'{}' is not a tcp://host:port address", s))
+        }
+    }
+}
+
+impl Endpoint {
+    pub fn connect(&self) -> io::Result<Stream> {
+        match self {
+            #[cfg(unix)]
+            Endpoint::Unix(path) => Ok(Stream::Unix(UnixStream::connect(path)?)),
+            Endpoint::Tcp(addr) => {
+                let s = TcpStream::connect(addr.as_str())?;
+                s.set_nodelay(true)?; // commands are tiny and time sensitive
+                Ok(Stream::Tcp(s))
+            }
+        }
+    }
+
+    // Like connect, but give up after timeout. Unix sockets connect
+    // immediately or not at all, so the timeout only applies to TCP.
+    pub fn connect_timeout(&self, timeout: Duration) -> io::Result<Stream> {
+        match self {
+            #[cfg(unix)]
+            Endpoint::Unix(_) => self.connect(),
+            Endpoint::Tcp(addr) => {
+                let mut last_err = io::Error::new(io::ErrorKind::NotFound, "no address");
+                for a in addr.to_socket_addrs()? {
+                    match TcpStream::connect_timeout(&a, timeout) {
+                        Ok(s) => {
+                            s.set_nodelay(true)?;
+                            return Ok(Stream::Tcp(s));
+                        }
+                        Err(e) => last_err = e,
+                    }
+                }
+                Err(last_err)
+            }
+        }
+    }
+
+    pub fn listen(&self) -> io::Result<Listener> {
+        match self {
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                if path.exists() {
+                    fs::remove_file(path)?; // left over from a previous game
+                }
+                Ok(Listener::Unix(UnixListener::bind(path)?))
+            }
+            Endpoint::Tcp(addr) => Ok(Listener::Tcp(TcpListener::bind(addr.as_str())?)),
+        }
+    }
+}
+
+pub enum Listener {
+    #[cfg(unix)]
+    Unix(UnixListener),
+    Tcp(TcpListener),
+}
+
+impl Listener {
+    // Wait for a connection. Returns the stream and a description of the peer.
+    pub fn accept(&self) -> io::Result<(Stream, String)> {
+        match self {
+            #[cfg(unix)]
+            Listener::Unix(l) => {
+                let (s, addr) = l.accept()?;
+                Ok((Stream::Unix(s), format!("{:?}", addr)))
+            }
+            Listener::Tcp(l) => {
+                let (s, addr) = l.accept()?;
+                s.set_nodelay(true)?;
+                Ok((Stream::Tcp(s), addr.to_string()))
+            }
+        }
+    }
+}
+
+pub enum Stream {
+    #[cfg(unix)]
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+impl Stream {
+    pub fn try_clone(&self) -> io::Result<Stream> {
+        match self {
+            #[cfg(unix)]
+            Stream::Unix(s) => Ok(Stream::Unix(s.try_clone()?)),
+            Stream::Tcp(s) => Ok(Stream::Tcp(s.try_clone()?)),
+        }
+    }
+
+    // None means reads block forever
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Stream::Unix(s) => s.set_read_timeout(dur),
+            Stream::Tcp(s) => s.set_read_timeout(dur),
+        }
+    }
+
+    // None means writes block forever
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Stream::Unix(s) => s.set_write_timeout(dur),
+            Stream::Tcp(s) => s.set_write_timeout(dur),
+        }
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Stream::Unix(s) => s.shutdown(how),
+            Stream::Tcp(s) => s.shutdown(how),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Stream::Unix(s) => s.read(buf),
+            Stream::Tcp(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Stream::Unix(s) => s.write(buf),
+            Stream::Tcp(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Stream::Unix(s) => s.flush(),
+            Stream::Tcp(s) => s.flush(),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Weapon {
+    Missile,
+    Ray,
+    Beam,    // a super weapon, from a rare pickup. Good for one shot, see is_super.
+    Cluster, // a missile which splits into four when it explodes
+}
+
+// In as_num order
+const WEAPONS: [Weapon; 4] = [Weapon::Missile, Weapon::Ray, Weapon::Beam, Weapon::Cluster];
+
+impl Weapon {
+    pub fn name(&self) -> String {
+        match self {
+            Weapon::Missile => "Missile".to_string(),
+            Weapon::Ray => "Ray".to_string(),
+            Weapon::Beam => "Beam".to_string(),
+            Weapon::Cluster => "Cluster".to_string(),
+        }
+    }
+    pub fn next(&mut self) {
+        *self = match self {
+            Weapon::Missile => Weapon::Ray,
+            Weapon::Ray => Weapon::Cluster,
+            Weapon::Cluster => Weapon::Missile,
+            // can't be put away, only fired
+            Weapon::Beam => Weapon::Beam,
+        }
+    }
+
+    // Good for one shot, after which the player has back the weapon they had
+    // before picking it up
+    pub fn is_super(&self) -> bool {
+        *self == Weapon::Beam
+    }
+
+    // None if n isn't one of ours, see as_num
+    pub fn from_num(n: u8) -> Option<Weapon> {
+        WEAPONS.get(n as usize).copied()
+    }
+
+    pub fn as_num(&self) -> u8 {
+        match self {
+            Weapon::Missile => 0,
+            Weapon::Ray => 1,
+            Weapon::Beam => 2,
+            Weapon::Cluster => 3,
+        }
+    }
+}
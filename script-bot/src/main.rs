@@ -0,0 +1,30 @@
+use std::env;
+use std::path::PathBuf;
+
+use rs_sdk::{run_bot, Player};
+
+mod script;
+use script::ScriptBot;
+
+const USAGE: &str = r#"Usage: script-bot 1|2 strategy.lua
+    1 to be player 1, 2 to be player 2.
+    strategy.lua: the bot's strategy, reloaded whenever it changes. See strategies/chase.lua.
+    If the game needs a token, set it in env var RUST_CONSOLE_GAME_TOKEN.
+"#;
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (player, path) = match args.as_slice() {
+        [p, path] => (p, PathBuf::from(path)),
+        _ => anyhow::bail!("{}", USAGE),
+    };
+    let (player, opponent) = match player.as_str() {
+        "2" => (Player::Two, Player::One),
+        _ => (Player::One, Player::Two),
+    };
+    if !path.is_file() {
+        anyhow::bail!("{} not found", path.display());
+    }
+    run_bot(player, ScriptBot::new(player, opponent, path))?;
+    Ok(())
+}
@@ -0,0 +1,235 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use mlua::{Function, Lua, Table, Value};
+use rs_sdk::{Actions, Bot, Dir, EntityKind, EntityState, Event, Frame, Map, Player, Weapon};
+
+// A Bot whose strategy is a Lua script. The script defines
+//
+//   function on_frame(frame) ... return { dir = "up", fire = "left" } end
+//   function on_event(name) ... end  -- optional, "round_start", "round_over" or "match_over"
+//
+// and can call map.is_blocked(x, y). See strategies/chase.lua for what's in `frame`.
+// The script is reloaded whenever the file changes. If it fails to load
+// we keep running the last version that did.
+pub struct ScriptBot {
+    me: Player,
+    opponent: Player,
+    path: PathBuf,
+    map: Map,
+    lua: Option<Lua>,
+    loaded_at: Option<SystemTime>, // modified time of the file when we last loaded it
+    last_err: Option<String>,      // so we only print each error once
+}
+
+impl ScriptBot {
+    pub fn new(me: Player, opponent: Player, path: PathBuf) -> ScriptBot {
+        ScriptBot {
+            me,
+            opponent,
+            path,
+            map: Map::default(),
+            lua: None,
+            loaded_at: None,
+            last_err: None,
+        }
+    }
+
+    // Load the script if it's new or has changed since we last did
+    fn reload_if_changed(&mut self) {
+        let modified = match fs::metadata(&self.path).and_then(|m| m.modified()) {
+            Ok(m) => m,
+            Err(e) => return self.report(format!("{}: {}", self.path.display(), e)),
+        };
+        if self.loaded_at == Some(modified) {
+            return;
+        }
+        self.loaded_at = Some(modified);
+        match self.load() {
+            Ok(lua) => {
+                eprintln!("Loaded {}", self.path.display());
+                self.lua = Some(lua);
+                self.last_err = None;
+            }
+            Err(e) => self.report(e.to_string()),
+        }
+    }
+
+    // A fresh Lua with our globals and the script run
+    fn load(&self) -> Result<Lua, mlua::Error> {
+        let src = fs::read_to_string(&self.path)?;
+        let lua = Lua::new();
+        lua.globals().set("map", self.map_table(&lua)?)?;
+        lua.load(&src)
+            .set_name(self.path.display().to_string())
+            .exec()?;
+        Ok(lua)
+    }
+
+    fn map_table<'lua>(&self, lua: &'lua Lua) -> Result<Table<'lua>, mlua::Error> {
+        let t = lua.create_table()?;
+        let ((min_x, min_y), (max_x, max_y)) = self.map.bounds();
+        t.set("min_x", min_x)?;
+        t.set("min_y", min_y)?;
+        t.set("max_x", max_x)?;
+        t.set("max_y", max_y)?;
+        let map = self.map.clone();
+        let is_blocked = lua.create_function(move |_, (x, y): (u32, u32)| Ok(map.is_blocked(x, y)))?;
+        t.set("is_blocked", is_blocked)?;
+        Ok(t)
+    }
+
+    fn report(&mut self, err: String) {
+        if self.last_err.as_ref() != Some(&err) {
+            eprintln!("{}", err);
+            self.last_err = Some(err);
+        }
+    }
+
+    fn call_on_frame(&self, lua: &Lua, frame: &Frame, out: &mut Actions) -> Result<(), mlua::Error> {
+        let on_frame: Function = match lua.globals().get("on_frame")? {
+            Some(f) => f,
+            None => return Err(mlua::Error::runtime("script has no on_frame function")),
+        };
+        let ret: Value = on_frame.call(self.frame_table(lua, frame)?)?;
+        let t = match ret {
+            Value::Table(t) => t,
+            Value::Nil => return Ok(()), // nothing to do this frame
+            _ => return Err(mlua::Error::runtime("on_frame must return a table or nil")),
+        };
+        if let Some(d) = t.get::<_, Option<String>>("dir")? {
+            out.dir(parse_dir(&d)?);
+        }
+        if t.get::<_, Option<bool>>("shield")?.unwrap_or(false) {
+            out.toggle_shield();
+        }
+        if t.get::<_, Option<bool>>("weapon")?.unwrap_or(false) {
+            out.change_weapon();
+        }
+        if t.get::<_, Option<bool>>("wall")?.unwrap_or(false) {
+            out.drop_wall();
+        }
+        if let Some(d) = t.get::<_, Option<String>>("fire")? {
+            out.fire(parse_dir(&d)?);
+        }
+        Ok(())
+    }
+
+    fn frame_table<'lua>(&self, lua: &'lua Lua, frame: &Frame) -> Result<Table<'lua>, mlua::Error> {
+        let t = lua.create_table()?;
+        t.set("tick", frame.tick())?;
+        for (name, p) in [("me", self.me), ("opponent", self.opponent)] {
+            if let Some(e) = frame.player(p) {
+                let pt = entity_table(lua, e)?;
+                let status = frame.player_status(p);
+                pt.set("lives", status.lives())?;
+                pt.set("energy", status.energy())?;
+                pt.set("weapon", weapon_name(status.weapon()))?;
+                pt.set("cooldown", status.cooldown())?;
+                pt.set("shield_drain", status.shield_drain())?;
+                pt.set("points", status.points())?;
+                pt.set("energy_hidden", status.is_energy_hidden())?;
+                t.set(name, pt)?;
+            }
+        }
+        for (name, kind) in [
+            ("missiles", EntityKind::Missile),
+            ("rays", EntityKind::Ray),
+            ("explosions", EntityKind::Explosion),
+            ("pickups", EntityKind::Pickup),
+            ("markers", EntityKind::Marker),
+            ("supplies", EntityKind::Supply),
+            ("teleporters", EntityKind::Teleporter),
+            ("beams", EntityKind::Beam),
+            ("clusters", EntityKind::Cluster),
+        ] {
+            let list = lua.create_table()?;
+            for (i, e) in frame.entities_of(kind).enumerate() {
+                list.set(i + 1, entity_table(lua, e)?)?;
+            }
+            t.set(name, list)?;
+        }
+        Ok(t)
+    }
+}
+
+impl Bot for ScriptBot {
+    fn on_connect(&mut self, map: &Map) {
+        self.map = map.clone();
+        self.loaded_at = None; // load again so the script sees the map
+        self.reload_if_changed();
+    }
+
+    fn on_frame(&mut self, frame: &Frame, out: &mut Actions) {
+        self.reload_if_changed();
+        let res = match &self.lua {
+            Some(lua) => self.call_on_frame(lua, frame, out),
+            None => return,
+        };
+        if let Err(e) = res {
+            self.report(e.to_string());
+        }
+    }
+
+    fn on_event(&mut self, event: Event) {
+        let name = match event {
+            Event::RoundStart => "round_start",
+            Event::RoundOver => "round_over",
+            Event::MatchOver => "match_over",
+        };
+        let res = match &self.lua {
+            Some(lua) => match lua.globals().get::<_, Option<Function>>("on_event") {
+                Ok(Some(f)) => f.call::<_, ()>(name),
+                Ok(None) => Ok(()),
+                Err(e) => Err(e),
+            },
+            None => return,
+        };
+        if let Err(e) = res {
+            self.report(e.to_string());
+        }
+    }
+}
+
+fn entity_table<'lua>(lua: &'lua Lua, e: &EntityState) -> Result<Table<'lua>, mlua::Error> {
+    let t = lua.create_table()?;
+    let (x, y) = e.pos();
+    t.set("x", x)?;
+    t.set("y", y)?;
+    t.set("dir", dir_name(e.dir()))?;
+    t.set("speed", e.speed())?;
+    t.set("shield", e.has_shield())?;
+    t.set("tier", e.tier())?;
+    Ok(t)
+}
+
+fn dir_name(d: Dir) -> &'static str {
+    match d {
+        Dir::None => "none",
+        Dir::Up => "up",
+        Dir::Down => "down",
+        Dir::Left => "left",
+        Dir::Right => "right",
+    }
+}
+
+fn parse_dir(s: &str) -> Result<Dir, mlua::Error> {
+    match s {
+        "none" => Ok(Dir::None),
+        "up" => Ok(Dir::Up),
+        "down" => Ok(Dir::Down),
+        "left" => Ok(Dir::Left),
+        "right" => Ok(Dir::Right),
+        _ => Err(mlua::Error::runtime(format!("unknown direction '{}'", s))),
+    }
+}
+
+fn weapon_name(w: Weapon) -> &'static str {
+    match w {
+        Weapon::Missile => "missile",
+        Weapon::Ray => "ray",
+        Weapon::Beam => "beam",
+        Weapon::Cluster => "cluster",
+    }
+}
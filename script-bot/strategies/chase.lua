@@ -0,0 +1,52 @@
+-- Chase the opponent and fire when lined up. Edit and save while the bot
+-- is running and it picks up the change on the next frame.
+--
+-- on_frame gets a table:
+--   frame.tick
+--   frame.me, frame.opponent: x, y, dir, speed, shield, tier, lives, energy,
+--     weapon ("missile", "ray", "cluster" or "beam"), cooldown (ticks until there's energy to fire,
+--     0 now), shield_drain (energy a shield takes per reload), points (hits landed this match),
+--     energy_hidden (the game keeps the opponent's energy and cooldown from you, both are 0)
+--   frame.missiles, frame.rays, frame.explosions, frame.pickups, frame.markers, frame.supplies,
+--     frame.teleporters, frame.beams, frame.clusters: lists of x, y, dir, speed, tier. A marker is
+--     where a supply drop is about to land. A teleporter sends whatever moves onto it to the
+--     opposite corner. A pickup of tier 2 is a beam, fired once through anything, then you're back
+--     to what you had. A cluster is a missile which splits into four missiles when it explodes.
+-- and returns nil to do nothing, or a table with any of:
+--   dir = "up" | "down" | "left" | "right" | "none"   change direction
+--   fire = "up" | "down" | "left" | "right"           fire current weapon
+--   shield = true                                     toggle shield
+--   weapon = true                                     switch missile / ray / cluster, before firing. Not a beam.
+--   wall = true                                       put up a wall behind you, three squares across
+-- Directions are the same as in the SDK: moving the opposite way stops you.
+--
+-- map has min_x, min_y (inclusive), max_x, max_y (exclusive) and is_blocked(x, y).
+
+function on_frame(frame)
+  local me, op = frame.me, frame.opponent
+  if me == nil or op == nil then
+    return nil
+  end
+
+  local out = {}
+  if me.x == op.x then
+    out.fire = op.y < me.y and "up" or "down"
+  elseif me.y == op.y then
+    out.fire = op.x < me.x and "left" or "right"
+  end
+
+  local want
+  if math.abs(me.x - op.x) > math.abs(me.y - op.y) then
+    want = op.x < me.x and "left" or "right"
+  else
+    want = op.y < me.y and "up" or "down"
+  end
+  if want ~= me.dir then
+    out.dir = want
+  end
+  return out
+end
+
+function on_event(name)
+  print("event: " .. name)
+end